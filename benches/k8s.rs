@@ -0,0 +1,74 @@
+//! Benchmarks for resolving and emitting the Kubernetes spec.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use paperclip::v2::{
+    self,
+    codegen::{DefaultEmitter, Emitter, EmitterState},
+    graph::SchemaGraph,
+    models::{DefaultSchema, ResolvableApi},
+    Schema,
+};
+
+use std::{fs, path::PathBuf};
+
+fn k8s_spec() -> Vec<u8> {
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/k8s-v1.16.0-alpha.0-openapi-v2.json");
+    fs::read(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e))
+}
+
+fn parse(spec: &[u8]) -> ResolvableApi<DefaultSchema> {
+    v2::from_reader(spec).expect("deserializing spec")
+}
+
+fn k8s(c: &mut Criterion) {
+    let spec = k8s_spec();
+    let api = parse(&spec).resolve().expect("resolution");
+    let graph = SchemaGraph::new(&api);
+
+    let mut group = c.benchmark_group("k8s");
+    group.sample_size(10);
+
+    group.bench_function("parse", |b| b.iter(|| parse(&spec)));
+    group.bench_function("resolve", |b| {
+        b.iter_batched(
+            || parse(&spec),
+            |api| api.resolve().expect("resolution"),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("schema_graph", |b| b.iter(|| SchemaGraph::new(&api)));
+
+    group.bench_function("contains_any/schema", |b| {
+        b.iter(|| {
+            api.definitions
+                .values()
+                .filter(|s| s.read().unwrap().contains_any())
+                .count()
+        })
+    });
+    group.bench_function("contains_any/graph", |b| {
+        b.iter(|| {
+            graph
+                .definitions()
+                .values()
+                .filter(|id| graph.contains_any(**id))
+                .count()
+        })
+    });
+
+    let out = std::env::temp_dir().join("paperclip-bench-k8s");
+    group.bench_function("emit", |b| {
+        b.iter(|| {
+            let mut state = EmitterState::default();
+            state.working_dir = out.clone();
+            DefaultEmitter::from(state).generate(&api).expect("codegen")
+        })
+    });
+
+    group.finish();
+    let _ = fs::remove_dir_all(out);
+}
+
+criterion_group!(benches, k8s);
+criterion_main!(benches);
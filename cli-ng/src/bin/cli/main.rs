@@ -10,7 +10,8 @@ mod error;
 use error::Error;
 
 /// Deserialize the schema from the given reader. Currently, this only supports
-/// JSON and YAML formats.
+/// JSON and YAML formats. OpenAPI 3.1 specs are downgraded to v3.0 (with a
+/// warning for every construct that couldn't be carried across).
 fn from_reader_v3<R>(mut reader: R) -> Result<openapiv3::OpenAPI, Error>
 where
     R: Read,
@@ -21,11 +22,18 @@ where
     }
     let reader = buf.as_ref().chain(reader);
 
-    Ok(if buf[0] == b'{' {
-        serde_json::from_reader::<_, openapiv3::OpenAPI>(reader)?
+    let doc = if buf[0] == b'{' {
+        serde_json::from_reader::<_, serde_json::Value>(reader)?
     } else {
-        serde_yaml::from_reader::<_, openapiv3::OpenAPI>(reader)?
-    })
+        serde_yaml::from_reader::<_, serde_json::Value>(reader)?
+    };
+
+    let (api, report) = paperclip_core::v3::openapiv3_from_value(doc)?;
+    for issue in &report.issues {
+        eprintln!("warning: {}", issue);
+    }
+
+    Ok(api)
 }
 fn parse_spec_v3(s: &str) -> Result<openapiv3::OpenAPI, Error> {
     let fd = File::open(s)?;
@@ -2,4 +2,5 @@ mod v3;
 
 pub mod v3_03 {
     pub use super::v3::{OpenApiV3, PackageInfo};
+    pub use paperclip_core::v3::v31;
 }
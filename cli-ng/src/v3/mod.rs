@@ -14,7 +14,8 @@ use itertools::Itertools;
 use ramhorns::Template;
 use ramhorns_derive::Content;
 
-use log::{debug, trace};
+use log::{debug, trace, warn};
+use paperclip_core::v3::v31;
 
 /// OpenApiV3 code generator.
 #[derive(Debug)]
@@ -55,6 +56,27 @@ impl OpenApiV3 {
     }
 }
 
+impl OpenApiV3 {
+    /// Creates a new OpenApi V3 Generator for an OpenAPI 3.1 spec.
+    ///
+    /// The spec is downgraded to v3.0 first, with a warning for every construct
+    /// which couldn't be carried across (like webhooks).
+    pub fn new_v31(
+        api: v31::OpenApi,
+        tpl_path: Option<std::path::PathBuf>,
+        output_path: Option<std::path::PathBuf>,
+        package_info: PackageInfo,
+    ) -> Result<Self, std::io::Error> {
+        let (api, report) = paperclip_core::v3::openapiv31_to_v3(&api)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        for issue in &report.issues {
+            warn!("{}", issue);
+        }
+
+        Self::new(api, tpl_path, output_path, package_info)
+    }
+}
+
 #[derive(Debug)]
 pub struct PackageInfo {
     pub name: String,
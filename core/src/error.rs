@@ -2,6 +2,8 @@
 use crate::v2::models::{DataType, ParameterIn};
 use thiserror::Error;
 
+use std::{fmt, path::PathBuf};
+
 /// Errors related to spec validation.
 #[derive(Debug, Error)]
 pub enum ValidationError {
@@ -14,6 +16,15 @@ pub enum ValidationError {
     /// The specified reference is missing in the spec.
     #[error("Reference missing in spec: {}", _0)]
     MissingReference(String),
+    /// The JSON pointer in `$ref` field doesn't point to a schema.
+    ///
+    /// Pointers can only refer to definitions, the schemas of parameters and
+    /// responses, and the schemas nested in those.
+    #[error(
+        "JSON pointer {:?} is not supported. Only pointers to schemas are supported.",
+        _0
+    )]
+    UnsupportedPointer(String),
     /// If a parameter specifies body, then schema must be specified.
     #[error(
         "Parameter {:?} in path {:?} is a body but the schema is missing",
@@ -35,4 +46,87 @@ pub enum ValidationError {
         _3
     )]
     InvalidParameterType(String, String, Option<DataType>, ParameterIn),
+    /// Some validation error along with its location in the spec.
+    #[error("{}\n{}", _0, _1.snippet())]
+    Located(Box<ValidationError>, Span),
+}
+
+impl ValidationError {
+    /// Attaches the given location to this error (replacing the existing
+    /// location, if any).
+    pub fn with_span(self, span: Span) -> Self {
+        match self {
+            ValidationError::Located(err, _) => ValidationError::Located(err, span),
+            err => ValidationError::Located(Box::new(err), span),
+        }
+    }
+
+    /// Location of this error in the spec (if it's known).
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ValidationError::Located(_, span) => Some(span),
+            _ => None,
+        }
+    }
+
+    /// The actual error (i.e., without the location).
+    pub fn kind(&self) -> &ValidationError {
+        match self {
+            ValidationError::Located(err, _) => err.kind(),
+            err => err,
+        }
+    }
+}
+
+/// Location of some node in a spec document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    /// Path to the document (if it was loaded from a file).
+    pub file: Option<PathBuf>,
+    /// Line number (starting from 1).
+    pub line: usize,
+    /// Column number (starting from 1).
+    pub column: usize,
+    /// Contents of the line, used for rendering snippets.
+    pub text: String,
+}
+
+impl Span {
+    /// Renders the location along with the line it points to (like the
+    /// snippets in compiler diagnostics).
+    ///
+    /// ```text
+    ///   --> spec.yaml:12:15
+    ///    |
+    /// 12 |         $ref: "#/definitions/Pet"
+    ///    |               ^
+    /// ```
+    pub fn snippet(&self) -> String {
+        let line = self.line.to_string();
+        let pad = " ".repeat(line.len());
+        let marker = self
+            .text
+            .chars()
+            .take(self.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        format!(
+            "{pad}--> {span}\n{pad} |\n{line} | {text}\n{pad} | {marker}^",
+            pad = pad,
+            span = self,
+            line = line,
+            text = self.text,
+            marker = marker,
+        )
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.file {
+            write!(f, "{}:", path.display())?;
+        }
+
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
@@ -18,7 +18,7 @@ pub mod v2;
 // #[cfg(feature = "v3")]
 pub mod v3;
 
-pub use self::error::ValidationError;
+pub use self::error::{Span, ValidationError};
 
 #[cfg(all(feature = "actix2", feature = "actix3"))]
 compile_error!("feature \"actix2\" and feature \"actix3\" cannot be enabled at the same time");
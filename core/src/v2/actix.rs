@@ -319,6 +319,10 @@ impl<T: Apiv2Schema> Apiv2Schema for Json<T> {
     fn raw_schema() -> DefaultSchemaRaw {
         T::raw_schema()
     }
+
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
 }
 
 impl<T> OperationModifier for Json<T>
@@ -380,6 +384,10 @@ impl<T: Apiv2Schema> Apiv2Schema for ValidatedJson<T> {
     fn raw_schema() -> DefaultSchemaRaw {
         T::raw_schema()
     }
+
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
 }
 
 #[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
@@ -504,6 +512,10 @@ impl<T: Apiv2Schema> Apiv2Schema for Form<T> {
     fn raw_schema() -> DefaultSchemaRaw {
         T::raw_schema()
     }
+
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
 }
 
 impl_param_extractor!(Path<T> => Path);
@@ -732,6 +744,8 @@ where
 
         break;
     }
+
+    map.extend(T::subtype_definitions());
 }
 
 fn update_parameter<T>(op: &mut DefaultOperationRaw)
@@ -752,7 +766,7 @@ where
         let mut security_map = BTreeMap::new();
         let scopes = scheme.scopes.keys().map(String::clone).collect();
         security_map.insert(name, scopes);
-        op.security.push(security_map);
+        op.security.get_or_insert_with(Vec::new).push(security_map);
     }
 }
 
@@ -842,6 +856,10 @@ macro_rules! json_with_status {
             fn raw_schema() -> DefaultSchemaRaw {
                 T::raw_schema()
             }
+
+            fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+                T::subtype_definitions()
+            }
         }
 
         impl<T> OperationModifier for $name<T>
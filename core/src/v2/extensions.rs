@@ -38,11 +38,33 @@ pub static YAML_CODER: Lazy<Arc<Coder>> = Lazy::new(|| {
         builtin: true,
     })
 });
+/// Media range for XML.
+pub static XML_MIME: Lazy<MediaRange> =
+    Lazy::new(|| MediaRange("application/xml".parse().expect("parsing mime")));
+/// Default coder for XML (based on `serde-xml-rs`).
+pub static XML_CODER: Lazy<Arc<Coder>> = Lazy::new(|| {
+    Arc::new(Coder {
+        encoder_path: "serde_xml_rs::to_writer".into(),
+        decoder_path: "serde_xml_rs::from_reader".into(),
+        any_value: "serde_json::Value".into(),
+        error_path: "serde_xml_rs::Error".into(),
+        prefer: false,
+        builtin: true,
+    })
+});
 
 /// Wrapper for `mime::MediaRange` to support `BTree{Set, Map}`.
 #[derive(Debug, Clone)]
 pub struct MediaRange(pub mime::Mime);
 
+impl MediaRange {
+    /// Returns whether this is an XML media range (like `application/xml`,
+    /// `text/xml` or `application/atom+xml`).
+    pub fn is_xml(&self) -> bool {
+        self.0.subtype() == mime::XML || self.0.suffix() == Some(mime::XML)
+    }
+}
+
 #[cfg(feature = "codegen")]
 impl MediaRange {
     /// Implementation from https://github.com/hyperium/mime/blob/65ea9c3d0cad4cb548b41124050c545120134035/src/range.rs#L155
@@ -211,7 +233,8 @@ impl<'de> Deserialize<'de> for Coders {
 /// Method used by openapiv3 crate.
 /// Works when deserializing but one could still add keys that don't start with "x-".
 /// todo: add own extensions map type that enforces "x-".
-pub(crate) fn deserialize_extensions<'de, D>(
+#[doc(hidden)]
+pub fn deserialize_extensions<'de, D>(
     deserializer: D,
 ) -> Result<BTreeMap<String, serde_json::Value>, D::Error>
 where
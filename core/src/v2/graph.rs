@@ -0,0 +1,427 @@
+//! Index-based graph of the schemas in a v2 spec.
+//!
+//! Schemas in a [`ResolvableApi`](../models/type.ResolvableApi.html) are shared
+//! through `Arc<RwLock<_>>`, which means that every traversal has to take locks
+//! and keep track of the nodes it has visited (to avoid going around in cycles).
+//! [`SchemaGraph`](struct.SchemaGraph.html) visits each schema exactly once and
+//! stores it in an arena, so that schemas can be referred to by their
+//! [`SchemaId`](struct.SchemaId.html) and traversed without locking. Metadata
+//! which requires walking the whole graph (like whether a schema is part of
+//! a cycle or whether it contains `Any` types) is computed once while building it.
+//!
+//! The graph is built through the [`Schema`](../schema/trait.Schema.html) trait,
+//! so it works for custom `#[api_v2_schema]` structs as well. Use
+//! [`SchemaGraph::id_of`](struct.SchemaGraph.html#method.id_of) for going from a
+//! schema borrowed from the spec (along with its custom fields) to its node.
+
+use super::{
+    models::{DataType, DataTypeFormat, Either, ResolvableApi},
+    schema::Schema,
+};
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+/// Handle for a schema in a [`SchemaGraph`](struct.SchemaGraph.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SchemaId(usize);
+
+impl SchemaId {
+    /// Index of this schema in the graph's arena.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for SchemaId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Immutable snapshot of a schema, with its children replaced by their IDs.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaNode {
+    /// Name of this schema (set by the resolver for definitions).
+    pub name: Option<String>,
+    /// Reference to some other schema, if any (`$ref` field).
+    pub reference: Option<String>,
+    /// Description for this schema, if any.
+    pub description: Option<String>,
+    /// Data type of this schema, if any.
+    pub data_type: Option<DataType>,
+    /// Data type format used by this schema, if any.
+    pub format: Option<DataTypeFormat>,
+    /// Properties of this schema (if it's an object).
+    pub properties: BTreeMap<String, SchemaId>,
+    /// Required properties of this schema.
+    pub required: BTreeSet<String>,
+    /// Schema of the array items, if any.
+    pub items: Option<SchemaId>,
+    /// Schemas which this schema is composed of (`allOf` field).
+    pub all_of: Vec<SchemaId>,
+    /// Value schema for maps, if any.
+    pub additional_properties: Option<Either<bool, SchemaId>>,
+    /// Discriminator field, if any.
+    pub discriminator: Option<String>,
+    /// Schemas extending this schema (set by the resolver for discriminators).
+    pub subtypes: Vec<SchemaId>,
+    /// Enum variants in this schema.
+    pub enum_variants: Vec<serde_json::Value>,
+    /// Whether the resolver has marked this schema as cyclic (i.e., it
+    /// needs to be boxed by whatever refers to it).
+    pub cyclic: bool,
+}
+
+/// Arena of schemas reachable from the definitions, parameters and
+/// responses of a spec.
+#[derive(Debug)]
+pub struct SchemaGraph<S> {
+    nodes: Vec<SchemaNode>,
+    /// Schemas which the nodes were built from (by ID). These are kept alive
+    /// for as long as the graph, so that their addresses (in `index`) can't
+    /// be reused by other schemas.
+    schemas: Vec<Arc<RwLock<S>>>,
+    /// Node IDs by the address of the schema they were built from.
+    index: HashMap<usize, SchemaId>,
+    definitions: BTreeMap<String, SchemaId>,
+    in_cycle: Vec<bool>,
+    contains_any: Vec<bool>,
+}
+
+impl<S> Default for SchemaGraph<S> {
+    fn default() -> Self {
+        SchemaGraph {
+            nodes: vec![],
+            schemas: vec![],
+            index: HashMap::new(),
+            definitions: BTreeMap::new(),
+            in_cycle: vec![],
+            contains_any: vec![],
+        }
+    }
+}
+
+impl<S> Clone for SchemaGraph<S> {
+    fn clone(&self) -> Self {
+        SchemaGraph {
+            nodes: self.nodes.clone(),
+            schemas: self.schemas.clone(),
+            index: self.index.clone(),
+            definitions: self.definitions.clone(),
+            in_cycle: self.in_cycle.clone(),
+            contains_any: self.contains_any.clone(),
+        }
+    }
+}
+
+impl<S: Schema> SchemaGraph<S> {
+    /// Builds the graph for the given (ideally resolved) spec.
+    pub fn new(api: &ResolvableApi<S>) -> Self {
+        let mut builder = Builder {
+            graph: SchemaGraph::default(),
+            locks: HashMap::new(),
+            pending: vec![],
+        };
+
+        for (name, schema) in &api.definitions {
+            let id = builder.add(schema);
+            builder.graph.definitions.insert(name.clone(), id);
+        }
+
+        let params = api.parameters.values().chain(
+            api.paths
+                .values()
+                .flat_map(|map| {
+                    map.methods
+                        .values()
+                        .flat_map(|op| op.parameters.iter())
+                        .chain(map.parameters.iter())
+                })
+                .filter_map(|p| p.right()),
+        );
+        for p in params {
+            if let Some(s) = p.read().unwrap().schema.as_ref() {
+                builder.add(s);
+            }
+        }
+
+        let responses = api.responses.values().chain(
+            api.paths
+                .values()
+                .flat_map(|map| map.methods.values())
+                .flat_map(|op| op.responses.values())
+                .filter_map(|r| r.right()),
+        );
+        for r in responses {
+            if let Some(s) = r.read().unwrap().schema.as_ref() {
+                builder.add(s);
+            }
+        }
+
+        builder.finish()
+    }
+
+    /// Number of schemas in this graph.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether this graph has no schemas.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the node for the given ID.
+    ///
+    /// **NOTE:** This panics if the ID doesn't belong to this graph.
+    pub fn node(&self, id: SchemaId) -> &SchemaNode {
+        &self.nodes[id.0]
+    }
+
+    /// Iterates over all the nodes in this graph.
+    pub fn nodes(&self) -> impl Iterator<Item = (SchemaId, &SchemaNode)> {
+        self.nodes.iter().enumerate().map(|(i, n)| (SchemaId(i), n))
+    }
+
+    /// Returns the schema which the given node was built from.
+    ///
+    /// **NOTE:** This panics if the ID doesn't belong to this graph.
+    pub fn schema(&self, id: SchemaId) -> &Arc<RwLock<S>> {
+        &self.schemas[id.0]
+    }
+
+    /// Returns the ID of the global definition with the given name.
+    pub fn definition(&self, name: &str) -> Option<SchemaId> {
+        self.definitions.get(name).copied()
+    }
+
+    /// Returns the global definitions and their IDs.
+    pub fn definitions(&self) -> &BTreeMap<String, SchemaId> {
+        &self.definitions
+    }
+
+    /// Returns the ID of the node built from the given schema. This is useful
+    /// for going from a schema (borrowed from the spec) to its node.
+    ///
+    /// **NOTE:** The graph is a snapshot of the spec. It holds on to the schemas
+    /// it was built from (so this never returns the ID of some other schema),
+    /// but changes made to them after building the graph aren't reflected in it.
+    pub fn id_of(&self, schema: &S) -> Option<SchemaId> {
+        self.index.get(&(schema as *const S as usize)).copied()
+    }
+
+    /// Whether the given schema is part of a reference cycle.
+    pub fn in_cycle(&self, id: SchemaId) -> bool {
+        self.in_cycle[id.0]
+    }
+
+    /// Whether the given schema "is" or "has" `Any` type. This is the same as
+    /// [`Schema::contains_any`](../schema/trait.Schema.html#method.contains_any).
+    pub fn contains_any(&self, id: SchemaId) -> bool {
+        self.contains_any[id.0]
+    }
+
+    /// Returns the IDs of the schemas directly referred by the given schema.
+    pub fn children(&self, id: SchemaId) -> impl Iterator<Item = SchemaId> + '_ {
+        let node = &self.nodes[id.0];
+        node.properties
+            .values()
+            .copied()
+            .chain(node.items)
+            .chain(node.all_of.iter().copied())
+            .chain(node.additional_properties.as_ref().and_then(|e| match e {
+                Either::Right(id) => Some(*id),
+                Either::Left(_) => None,
+            }))
+    }
+
+    /// Marks the schemas which are part of some cycle (i.e., they belong
+    /// to a strongly connected component with more than one node or they
+    /// refer to themselves) using Tarjan's algorithm.
+    fn find_cycles(&mut self) {
+        const UNVISITED: usize = usize::MAX;
+
+        let len = self.nodes.len();
+        let mut index = vec![UNVISITED; len];
+        let mut low = vec![0; len];
+        let mut on_stack = vec![false; len];
+        let mut stack = vec![];
+        let mut next = 0;
+        self.in_cycle = vec![false; len];
+
+        for root in 0..len {
+            if index[root] != UNVISITED {
+                continue;
+            }
+
+            // Iterative DFS - each frame holds the node and its children.
+            let mut frames = vec![(root, self.children(SchemaId(root)).collect::<Vec<_>>())];
+            index[root] = next;
+            low[root] = next;
+            next += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((node, children)) = frames.last_mut() {
+                let node = *node;
+                if let Some(child) = children.pop() {
+                    let child = child.0;
+                    if child == node {
+                        self.in_cycle[node] = true;
+                    }
+
+                    if index[child] == UNVISITED {
+                        index[child] = next;
+                        low[child] = next;
+                        next += 1;
+                        stack.push(child);
+                        on_stack[child] = true;
+                        frames.push((child, self.children(SchemaId(child)).collect()));
+                    } else if on_stack[child] {
+                        low[node] = low[node].min(index[child]);
+                    }
+
+                    continue;
+                }
+
+                frames.pop();
+                if let Some((parent, _)) = frames.last() {
+                    low[*parent] = low[*parent].min(low[node]);
+                }
+
+                if low[node] == index[node] {
+                    let mut component = vec![];
+                    while let Some(n) = stack.pop() {
+                        on_stack[n] = false;
+                        component.push(n);
+                        if n == node {
+                            break;
+                        }
+                    }
+
+                    if component.len() > 1 {
+                        for n in component {
+                            self.in_cycle[n] = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the schemas which contain `Any` types. References which lead
+    /// back to a schema don't add anything, so this is the least fixpoint
+    /// of the rules in `Schema::contains_any`.
+    fn find_any(&mut self) {
+        let len = self.nodes.len();
+        let mut schema_any = vec![false; len];
+        let mut fields_any = vec![false; len];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..len {
+                let node = &self.nodes[i];
+                let fields = node.properties.values().any(|p| schema_any[p.0])
+                    || node.items.map(|s| schema_any[s.0]).unwrap_or(false)
+                    || node.all_of.iter().any(|m| {
+                        let member = &self.nodes[m.0];
+                        // Members are allowed to skip the type if they only add properties.
+                        if member.data_type.is_none() && !member.properties.is_empty() {
+                            fields_any[m.0]
+                        } else {
+                            schema_any[m.0]
+                        }
+                    })
+                    || match node.additional_properties {
+                        Some(Either::Left(allowed)) => allowed,
+                        Some(Either::Right(s)) => schema_any[s.0],
+                        None => false,
+                    };
+                let schema = (node.data_type.is_none() && node.all_of.is_empty()) || fields;
+
+                if fields != fields_any[i] || schema != schema_any[i] {
+                    fields_any[i] = fields;
+                    schema_any[i] = schema;
+                    changed = true;
+                }
+            }
+        }
+
+        self.contains_any = schema_any;
+    }
+}
+
+/// Collects schemas into the graph (one lock per schema).
+struct Builder<S> {
+    graph: SchemaGraph<S>,
+    /// Node IDs by the address of the lock holding the schema.
+    locks: HashMap<*const RwLock<S>, SchemaId>,
+    pending: Vec<(SchemaId, Arc<RwLock<S>>)>,
+}
+
+impl<S: Schema> Builder<S> {
+    /// Adds the given schema (and everything reachable from it) to the graph.
+    fn add(&mut self, schema: &Arc<RwLock<S>>) -> SchemaId {
+        let root = self.id(schema);
+        while let Some((id, lock)) = self.pending.pop() {
+            let s = lock.read().unwrap();
+            self.graph.index.insert(&*s as *const S as usize, id);
+
+            let node = SchemaNode {
+                name: s.name().map(String::from),
+                reference: s.reference().map(String::from),
+                description: s.description().map(String::from),
+                data_type: s.data_type(),
+                format: s.format().cloned(),
+                properties: s
+                    .properties()
+                    .map(|p| p.iter().map(|(k, v)| (k.clone(), self.id(v))).collect())
+                    .unwrap_or_default(),
+                required: s.required_properties().cloned().unwrap_or_default(),
+                items: s.items().map(|i| self.id(i)),
+                all_of: s
+                    .all_of()
+                    .map(|m| m.iter().map(|s| self.id(s)).collect())
+                    .unwrap_or_default(),
+                additional_properties: s.additional_properties().map(|e| match e {
+                    Either::Left(allowed) => Either::Left(*allowed),
+                    Either::Right(s) => Either::Right(self.id(s)),
+                }),
+                discriminator: s.discriminator().map(String::from),
+                subtypes: s.subtypes().iter().map(|s| self.id(s)).collect(),
+                enum_variants: s.enum_variants().map(|v| v.to_vec()).unwrap_or_default(),
+                cyclic: s.is_cyclic(),
+            };
+
+            self.graph.nodes[id.0] = node;
+        }
+
+        root
+    }
+
+    /// Returns the ID for the given schema, allocating a node (which
+    /// will be filled later) if we haven't seen it already.
+    fn id(&mut self, schema: &Arc<RwLock<S>>) -> SchemaId {
+        if let Some(id) = self.locks.get(&Arc::as_ptr(schema)) {
+            return *id;
+        }
+
+        let id = SchemaId(self.graph.nodes.len());
+        self.graph.nodes.push(SchemaNode::default());
+        self.locks.insert(Arc::as_ptr(schema), id);
+        self.graph.schemas.push(Arc::clone(schema));
+        self.pending.push((id, Arc::clone(schema)));
+        id
+    }
+
+    fn finish(mut self) -> SchemaGraph<S> {
+        self.graph.find_cycles();
+        self.graph.find_any();
+        self.graph
+    }
+}
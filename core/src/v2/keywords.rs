@@ -0,0 +1,26 @@
+//! Rewrites of schema keywords which changed in JSON Schema (draft 2020-12),
+//! shared by the JSON Schema exporter and the OpenAPI 3.1 conversion.
+//!
+//! **NOTE:** This is not a part of the public API.
+
+use serde_json::{Map, Value};
+
+/// Replaces `example` with `examples` and turns the boolean `exclusiveMinimum`
+/// and `exclusiveMaximum` flags into numeric bounds (in place of `minimum`
+/// and `maximum` respectively).
+pub fn upgrade(schema: &mut Map<String, Value>) {
+    if let Some(example) = schema.remove("example") {
+        schema.insert("examples".into(), Value::Array(vec![example]));
+    }
+
+    for (flag, bound) in &[
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        if schema.remove(*flag) == Some(Value::Bool(true)) {
+            if let Some(value) = schema.remove(*bound) {
+                schema.insert((*flag).into(), value);
+            }
+        }
+    }
+}
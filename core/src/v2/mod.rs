@@ -4,10 +4,16 @@
 #[cfg(feature = "actix-base")]
 mod actix;
 mod extensions;
+pub mod graph;
+#[doc(hidden)]
+pub mod keywords;
 pub mod models;
+#[doc(hidden)]
+pub mod pointer;
 #[cfg(feature = "codegen")]
 mod resolver;
 pub mod schema;
+pub mod source_map;
 
 #[cfg(feature = "actix-base")]
 pub use self::actix::{
@@ -44,7 +50,13 @@ impl<S: Schema + Default> ResolvableApi<S> {
             self.parameters,
             self.responses,
         ));
-        resolver.resolve()?;
+        if let Err(e) = resolver.resolve() {
+            return Err(match &self.source_map {
+                Some(map) => map.annotate(e),
+                None => e,
+            });
+        }
+
         Ok(ResolvableApi {
             swagger: self.swagger,
             info: self.info,
@@ -60,6 +72,7 @@ impl<S: Schema + Default> ResolvableApi<S> {
             parameters: resolver.params,
             responses: resolver.resp,
             spec_format: self.spec_format,
+            source_map: self.source_map,
             external_docs: self.external_docs,
             security: self.security,
             security_definitions: self.security_definitions,
@@ -1,15 +1,20 @@
 //! Models used by OpenAPI v2.
 
+#[doc(hidden)]
+pub use super::extensions::deserialize_extensions;
 pub use super::extensions::{
-    Coder, Coders, MediaRange, JSON_CODER, JSON_MIME, YAML_CODER, YAML_MIME,
+    Coder, Coders, MediaRange, JSON_CODER, JSON_MIME, XML_CODER, XML_MIME, YAML_CODER, YAML_MIME,
 };
 
-use super::schema::Schema;
+use super::{schema::Schema, source_map::SourceMap};
 use crate::error::ValidationError;
 use once_cell::sync::Lazy;
 use paperclip_macros::api_v2_schema_struct;
 use regex::{Captures, Regex};
-use serde::ser::{SerializeMap, Serializer};
+use serde::{
+    de::{Deserialize, Deserializer},
+    ser::{Serialize, SerializeMap, Serializer},
+};
 
 #[cfg(feature = "actix-base")]
 use actix_web::http::Method;
@@ -31,8 +36,9 @@ static PATH_TEMPLATE_REGEX: Lazy<Regex> =
 const SPECIAL_HEADERS: &[&str] = &["content-type", "accept", "authorization"];
 
 /// OpenAPI version.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
 pub enum Version {
+    #[default]
     #[serde(rename = "2.0")]
     V2,
 }
@@ -62,8 +68,10 @@ impl DataType {
 }
 
 /// Supported data type formats.
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
-#[serde(rename_all = "lowercase")]
+///
+/// Formats which aren't known to us (say, `int8`, `email` or `decimal`) are
+/// preserved as-is in `Other`.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataTypeFormat {
     Int32,
     Int64,
@@ -72,7 +80,6 @@ pub enum DataTypeFormat {
     Byte,
     Binary,
     Date,
-    #[serde(rename = "date-time")]
     DateTime,
     Password,
     Url,
@@ -80,13 +87,12 @@ pub enum DataTypeFormat {
     Ip,
     IpV4,
     IpV6,
-    #[serde(other)]
-    Other,
+    Other(String),
 }
 
-#[allow(clippy::to_string_trait_impl)]
-impl ToString for DataTypeFormat {
-    fn to_string(&self) -> String {
+impl DataTypeFormat {
+    /// Returns the format as it appears in the spec.
+    pub fn as_str(&self) -> &str {
         match self {
             DataTypeFormat::Int32 => "int32",
             DataTypeFormat::Int64 => "int64",
@@ -95,17 +101,79 @@ impl ToString for DataTypeFormat {
             DataTypeFormat::Byte => "byte",
             DataTypeFormat::Binary => "binary",
             DataTypeFormat::Date => "date",
-            DataTypeFormat::DateTime => "datetime",
+            DataTypeFormat::DateTime => "date-time",
             DataTypeFormat::Password => "password",
             DataTypeFormat::Url => "url",
             DataTypeFormat::Uuid => "uuid",
             DataTypeFormat::Ip => "ip",
             DataTypeFormat::IpV4 => "ipv4",
             DataTypeFormat::IpV6 => "ipv6",
-            // would be nice if Other was Other(String)
-            DataTypeFormat::Other => "other",
+            DataTypeFormat::Other(format) => format,
         }
-        .to_string()
+    }
+}
+
+impl From<&str> for DataTypeFormat {
+    fn from(format: &str) -> Self {
+        match format {
+            "int32" => DataTypeFormat::Int32,
+            "int64" => DataTypeFormat::Int64,
+            "float" => DataTypeFormat::Float,
+            "double" => DataTypeFormat::Double,
+            "byte" => DataTypeFormat::Byte,
+            "binary" => DataTypeFormat::Binary,
+            "date" => DataTypeFormat::Date,
+            "date-time" => DataTypeFormat::DateTime,
+            "password" => DataTypeFormat::Password,
+            "url" => DataTypeFormat::Url,
+            "uuid" => DataTypeFormat::Uuid,
+            "ip" => DataTypeFormat::Ip,
+            "ipv4" => DataTypeFormat::IpV4,
+            "ipv6" => DataTypeFormat::IpV6,
+            other => DataTypeFormat::Other(other.into()),
+        }
+    }
+}
+
+impl Serialize for DataTypeFormat {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DataTypeFormat {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = DataTypeFormat;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a data type format")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<DataTypeFormat, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(value.into())
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for DataTypeFormat {
+    fn to_string(&self) -> String {
+        self.as_str().to_string()
     }
 }
 
@@ -126,7 +194,7 @@ impl From<DataTypeFormat> for DataType {
             DataTypeFormat::Ip => Self::String,
             DataTypeFormat::IpV4 => Self::String,
             DataTypeFormat::IpV6 => Self::String,
-            DataTypeFormat::Other => Self::Object,
+            DataTypeFormat::Other(_) => Self::Object,
         }
     }
 }
@@ -242,6 +310,10 @@ pub struct Api<P, R, S> {
     /// the spec was provided and we need to use this as the fallback encoding.
     #[serde(skip)]
     pub spec_format: SpecFormat,
+    /// Source map of the document from which this spec was loaded (if any).
+    /// This is set by the loader and used for locating errors in the spec.
+    #[serde(skip)]
+    pub source_map: Option<Arc<SourceMap>>,
     pub info: Info,
 
     #[serde(
@@ -253,8 +325,9 @@ pub struct Api<P, R, S> {
 }
 
 /// The format used by spec (JSON/YAML).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum SpecFormat {
+    #[default]
     Json,
     Yaml,
 }
@@ -422,6 +495,50 @@ pub struct ExternalDocs {
     pub url: String,
 }
 
+/// XML object for fine-tuning the XML representation of a schema.
+///
+/// <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#xml-object>
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Xml {
+    /// Name of the element (or attribute) for this schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// URI of the namespace definition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Prefix to be used for the name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Whether this property is an attribute (instead of an element).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<bool>,
+    /// Whether the items of this array are wrapped in an element.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrapped: Option<bool>,
+}
+
+impl Xml {
+    /// Returns the qualified name (i.e., with the prefix, if any) for the
+    /// element or attribute, or `None` if this object doesn't rename it.
+    pub fn qualified_name(&self) -> Option<String> {
+        let name = self.name.as_ref()?;
+        Some(match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, name),
+            None => name.clone(),
+        })
+    }
+
+    /// Whether this property is an attribute.
+    pub fn is_attribute(&self) -> bool {
+        self.attribute.unwrap_or_default()
+    }
+
+    /// Whether the items of this array are wrapped in an element.
+    pub fn is_wrapped(&self) -> bool {
+        self.wrapped.unwrap_or_default()
+    }
+}
+
 /// Path item that can be traversed and resolved for codegen.
 pub type ResolvablePathItem<S> = PathItem<ResolvableParameter<S>, ResolvableResponse<S>>;
 
@@ -432,11 +549,23 @@ pub type DefaultPathItemRaw = PathItem<DefaultParameterRaw, DefaultResponseRaw>;
 ///
 /// <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#pathItemObject>
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: Deserialize<'de>, R: Deserialize<'de>"))]
 pub struct PathItem<P, R> {
-    #[serde(flatten, default = "BTreeMap::default")]
+    #[serde(
+        flatten,
+        default = "BTreeMap::default",
+        deserialize_with = "deserialize_methods"
+    )]
     pub methods: BTreeMap<HttpMethod, Operation<P, R>>,
     #[serde(default = "Vec::default", skip_serializing_if = "Vec::is_empty")]
     pub parameters: Vec<Either<Reference, P>>,
+    /// Inline extensions to this object.
+    #[serde(
+        flatten,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "crate::v2::extensions::deserialize_extensions"
+    )]
+    pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
 impl<S> PathItem<Parameter<S>, Response<S>> {
@@ -542,6 +671,13 @@ pub struct Parameter<S> {
     pub multiple_of: Option<f32>,
     #[serde(default, rename = "enum", skip_serializing_if = "Vec::is_empty")]
     pub enum_: Vec<serde_json::Value>,
+    /// Inline extensions to this object.
+    #[serde(
+        flatten,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "crate::v2::extensions::deserialize_extensions"
+    )]
+    pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
 /// Items object.
@@ -675,9 +811,10 @@ pub enum ParameterIn {
 }
 
 /// Possible formats for array values in parameter.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
 #[serde(rename_all = "lowercase")]
 pub enum CollectionFormat {
+    #[default]
     Csv,
     Ssv,
     Tsv,
@@ -710,8 +847,9 @@ pub struct Operation<P, R> {
     pub consumes: Option<BTreeSet<MediaRange>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub produces: Option<BTreeSet<MediaRange>>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub security: Vec<BTreeMap<String, Vec<String>>>,
+    // *NOTE:* Same as above - an empty list removes the global requirements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<Vec<BTreeMap<String, Vec<String>>>>,
     #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
     pub schemes: BTreeSet<OperationProtocol>,
     // FIXME: Validate using `http::status::StatusCode::from_u16`
@@ -722,6 +860,13 @@ pub struct Operation<P, R> {
     pub deprecated: bool,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
+    /// Inline extensions to this object.
+    #[serde(
+        flatten,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "crate::v2::extensions::deserialize_extensions"
+    )]
+    pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
 impl<S> Operation<Parameter<S>, Response<S>> {
@@ -799,6 +944,13 @@ pub struct Response<S> {
     pub schema: Option<S>,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub headers: BTreeMap<String, Header>,
+    /// Inline extensions to this object.
+    #[serde(
+        flatten,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "crate::v2::extensions::deserialize_extensions"
+    )]
+    pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
 /// Header object.
@@ -814,7 +966,7 @@ pub struct Header {
     pub format: Option<DataTypeFormat>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Items>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "collectionFormat", skip_serializing_if = "Option::is_none")]
     pub collection_format: Option<CollectionFormat>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<serde_json::Value>,
@@ -845,7 +997,7 @@ pub struct Header {
 }
 
 /// The HTTP method used for an operation.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Ord, PartialOrd)]
 #[serde(rename_all = "lowercase")]
 pub enum HttpMethod {
     Get,
@@ -941,12 +1093,6 @@ where
 
 /* Common trait impls */
 
-impl Default for SpecFormat {
-    fn default() -> Self {
-        SpecFormat::Json
-    }
-}
-
 #[cfg(feature = "actix-base")]
 impl From<&Method> for HttpMethod {
     fn from(method: &Method) -> HttpMethod {
@@ -1032,18 +1178,6 @@ impl Display for HttpMethod {
     }
 }
 
-impl Default for Version {
-    fn default() -> Self {
-        Version::V2
-    }
-}
-
-impl Default for CollectionFormat {
-    fn default() -> Self {
-        CollectionFormat::Csv
-    }
-}
-
 /// **NOTE:** This is just a stub. This is usually set explicitly.
 impl Default for ParameterIn {
     fn default() -> Self {
@@ -1057,3 +1191,19 @@ impl Default for ParameterIn {
 fn is_false(val: &bool) -> bool {
     !*val
 }
+
+/// Deserializes the operations in a path item, skipping other keys
+/// (like `parameters` and vendor extensions).
+fn deserialize_methods<'de, D, P, R>(
+    deserializer: D,
+) -> Result<BTreeMap<HttpMethod, Operation<P, R>>, D::Error>
+where
+    D: Deserializer<'de>,
+    P: Deserialize<'de>,
+    R: Deserialize<'de>,
+{
+    deserializer.deserialize_map(super::extensions::PredicateVisitor(
+        |_: &HttpMethod| true,
+        std::marker::PhantomData,
+    ))
+}
@@ -0,0 +1,73 @@
+//! Helpers for [JSON pointers](https://tools.ietf.org/html/rfc6901) in local
+//! references (i.e., `$ref` URIs like `#/definitions/Pet`).
+//!
+//! **NOTE:** These are used across paperclip crates, so that references are
+//! parsed the same way everywhere. They're not a part of the public API.
+
+/// Prefix of references to global definitions.
+pub const DEF_REF_PREFIX: &str = "#/definitions/";
+/// Prefix of references to global parameters.
+pub const PARAM_REF_PREFIX: &str = "#/parameters/";
+/// Prefix of references to global responses.
+pub const RESP_REF_PREFIX: &str = "#/responses/";
+/// Maximum number of references followed while evaluating a JSON pointer.
+pub const MAX_REF_DEPTH: usize = 32;
+
+/// Returns the (percent-decoded) JSON pointer in the given URI fragment (`#/...`).
+pub fn fragment(uri: &str) -> Option<String> {
+    percent_decode(uri.strip_prefix('#')?)
+}
+
+/// Splits the JSON pointer in the given URI fragment into its (unescaped)
+/// reference tokens.
+pub fn segments(uri: &str) -> Option<Vec<String>> {
+    let pointer = fragment(uri)?;
+    let pointer = pointer.strip_prefix('/')?;
+    Some(pointer.split('/').map(unescape).collect())
+}
+
+/// Returns the name of the object referred by the given URI in the section
+/// with the given prefix (say, `Pet` for `#/definitions/Pet`).
+pub fn name(uri: &str, prefix: &str) -> Option<String> {
+    let name = fragment(uri)?;
+    let name = name.strip_prefix(prefix.strip_prefix('#')?)?;
+    Some(unescape(name))
+}
+
+/// Same as [`name`](fn.name.html), but for deep references (like
+/// `#/definitions/Pod/properties/spec`), this returns the name of the root
+/// object (`Pod`).
+pub fn root_name(uri: &str, prefix: &str) -> Option<String> {
+    let name = fragment(uri)?;
+    let name = name.strip_prefix(prefix.strip_prefix('#')?)?;
+    name.split('/').next().map(unescape)
+}
+
+/// Unescapes `~1` and `~0` in a reference token.
+pub fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Escapes `~` and `/` in a reference token.
+pub fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Decodes percent-encoded octets in a URI fragment.
+pub fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
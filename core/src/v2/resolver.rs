@@ -3,18 +3,35 @@ use super::{
         Either, HttpMethod, Reference, Resolvable, ResolvableParameter, ResolvablePathItem,
         ResolvableResponse,
     },
+    pointer::{self, DEF_REF_PREFIX, MAX_REF_DEPTH, PARAM_REF_PREFIX, RESP_REF_PREFIX},
     Schema,
 };
 use crate::error::ValidationError;
 use heck::ToPascalCase;
 
-use std::{cell::RefCell, collections::BTreeMap, mem};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    mem,
+    sync::{Arc, RwLock},
+};
 
 // FIXME: The resolver is not in its best. It "just" works atm.
 
-const DEF_REF_PREFIX: &str = "#/definitions/";
-const PARAM_REF_PREFIX: &str = "#/parameters/";
-const RESP_REF_PREFIX: &str = "#/responses/";
+/// Pointer segments which don't add anything to the names of definitions
+/// we create for JSON pointer targets.
+const STRUCTURAL_SEGMENTS: &[&str] = &[
+    "definitions",
+    "parameters",
+    "responses",
+    "paths",
+    "properties",
+    "schema",
+];
+
+/// Operation keys in path items (used to tell missing operations apart
+/// from unsupported pointers).
+const HTTP_METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch"];
 
 type DefinitionsMap<S> = BTreeMap<String, Resolvable<S>>;
 type OperationsMap<S> = BTreeMap<String, ResolvablePathItem<S>>;
@@ -28,6 +45,9 @@ type ResponsesMap<S> = BTreeMap<String, ResolvableResponse<S>>;
 pub(crate) struct Resolver<S> {
     /// List of definitions that must be marked as cyclic while resolving a definition.
     cyclic_defs: RefCell<Vec<Resolvable<S>>>,
+    /// Schemas for references which are JSON pointers to nodes other than
+    /// the global definitions (for example, `#/definitions/Pod/properties/spec`).
+    pointer_defs: BTreeMap<String, Resolvable<S>>,
     /// Globally defined object definitions.
     pub defs: DefinitionsMap<S>,
     /// Paths and the corresponding operations.
@@ -56,6 +76,7 @@ impl<S>
     ) -> Self {
         Resolver {
             cyclic_defs: vec![].into(),
+            pointer_defs: BTreeMap::new(),
             defs,
             paths,
             params,
@@ -70,21 +91,30 @@ where
 {
     /// Visit definitions and resolve them!
     pub fn resolve(&mut self) -> Result<(), ValidationError> {
-        // Resolve path operations first. We may encounter anonymous
+        // Check path operations first. We may encounter anonymous
         // definitions along the way, which we'll insert into `self.defs`
         // and we'll have to resolve them anyway.
         let mut paths = mem::take(&mut self.paths);
         paths.iter_mut().try_for_each(|(path, map)| {
             log::trace!("Checking path: {}", path);
-            self.resolve_operations(path, map)
+            self.hoist_operations(path, map)
         })?;
         self.paths = paths;
 
+        // Find the targets of JSON pointers before we start locking schemas
+        // for resolution (the targets may live inside those schemas).
+        self.collect_pointer_defs()?;
+
         // Set the names of all schemas.
         for (name, schema) in &self.defs {
             schema.write().unwrap().set_name(name);
         }
 
+        for (path, map) in &self.paths {
+            log::trace!("Resolving path: {}", path);
+            self.resolve_operations(map)?;
+        }
+
         for (name, schema) in &self.defs {
             log::trace!("Entering: {}", name);
             self.resolve_definitions_no_root_ref(schema)?;
@@ -98,9 +128,49 @@ where
             }
         }
 
+        self.collect_subtypes();
         Ok(())
     }
 
+    /// Find the definitions which extend some definition with a discriminator
+    /// (through `allOf` references, either directly or through other definitions)
+    /// and add them as its subtypes.
+    fn collect_subtypes(&self) {
+        for (name, schema) in &self.defs {
+            let mut bases = vec![];
+            let mut pending = vec![Arc::clone(schema)];
+            while let Some(def) = pending.pop() {
+                let members = def
+                    .read()
+                    .unwrap()
+                    .all_of()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|m| matches!(m, Resolvable::Resolved { .. }))
+                    .map(|m| Arc::clone(m))
+                    .collect::<Vec<_>>();
+
+                for member in members {
+                    if Arc::ptr_eq(&member, schema) || bases.iter().any(|b| Arc::ptr_eq(b, &member))
+                    {
+                        continue;
+                    }
+
+                    bases.push(Arc::clone(&member));
+                    pending.push(member);
+                }
+            }
+
+            for base in &bases {
+                let mut base = base.write().unwrap();
+                if base.discriminator().is_some() {
+                    log::trace!("Adding subtype {:?} to {:?}", name, base.name());
+                    base.add_subtype(schema);
+                }
+            }
+        }
+    }
+
     /// We've passed some definition. Resolve it assuming that it doesn't
     /// contain any reference.
     fn resolve_definitions_no_root_ref(
@@ -126,6 +196,13 @@ where
             })?;
         }
 
+        if let Some(members) = schema.all_of_mut() {
+            members.iter_mut().try_for_each(|s| {
+                log::trace!("Resolving allOf member");
+                self.resolve_definitions(s)
+            })?;
+        }
+
         if let Some(props) = schema
             .additional_properties_mut()
             .and_then(|s| s.right_mut())
@@ -139,6 +216,12 @@ where
     /// Resolve the given definition. If it contains a reference, find and assign it,
     /// otherwise traverse further.
     fn resolve_definitions(&self, schema: &mut Resolvable<S>) -> Result<(), ValidationError> {
+        // Schemas shared through JSON pointers are visited once for every place
+        // they're in, so their references may have been resolved already.
+        if let Resolvable::Resolved { .. } = schema {
+            return self.resolve_definitions_no_root_ref(schema);
+        }
+
         let ref_def = {
             let s = match schema.try_read().ok() {
                 Some(s) => s,
@@ -156,27 +239,25 @@ where
             }
         };
 
-        if let Some(new) = ref_def {
-            *schema = match schema {
-                Resolvable::Raw(old) => Resolvable::Resolved {
-                    old: old.clone(),
-                    new: (*new).clone(),
-                },
-                _ => unimplemented!("schema already resolved?"),
+        if let (Some(new), Resolvable::Raw(old)) = (ref_def, &*schema) {
+            *schema = Resolvable::Resolved {
+                old: old.clone(),
+                new: (*new).clone(),
             };
         }
 
         self.resolve_definitions_no_root_ref(&*schema)
     }
 
-    /// Resolve a given operation.
-    fn resolve_operations(
+    /// Replace parameter and response references in the given operations
+    /// and hoist their anonymous schemas into global definitions.
+    fn hoist_operations(
         &mut self,
         path: &str,
         map: &mut ResolvablePathItem<S>,
     ) -> Result<(), ValidationError> {
         for (&method, op) in &mut map.methods {
-            self.resolve_parameters(Some(method), path, &mut op.parameters)?;
+            self.hoist_parameters(Some(method), path, &mut op.parameters)?;
             for resp in op.responses.values_mut() {
                 let ref_resp = if let Some(r) = resp.left() {
                     log::trace!("Resolving response {}", r.reference);
@@ -190,20 +271,16 @@ where
                 }
 
                 let mut response = resp.write().unwrap();
-                self.resolve_operation_schema(
-                    &mut response.schema,
-                    Some(method),
-                    path,
-                    "Response",
-                )?;
+                self.hoist_operation_schema(&mut response.schema, Some(method), path, "Response");
             }
         }
 
-        self.resolve_parameters(None, path, &mut map.parameters)
+        self.hoist_parameters(None, path, &mut map.parameters)
     }
 
-    /// Resolve the given bunch of parameters.
-    fn resolve_parameters(
+    /// Replace references in the given bunch of parameters and hoist their
+    /// anonymous body schemas.
+    fn hoist_parameters(
         &mut self,
         method: Option<HttpMethod>,
         path: &str,
@@ -222,23 +299,24 @@ where
             }
 
             let mut param = p.write().unwrap();
-            self.resolve_operation_schema(&mut param.schema, method, path, "Body")?;
+            self.hoist_operation_schema(&mut param.schema, method, path, "Body");
         }
 
         Ok(())
     }
 
-    /// Resolves request/response schema in operation.
-    fn resolve_operation_schema(
+    /// Adds the request/response schema in operation to global definitions
+    /// (if it's anonymous).
+    fn hoist_operation_schema(
         &mut self,
         s: &mut Option<Resolvable<S>>,
         method: Option<HttpMethod>,
         path: &str,
         suffix: &str,
-    ) -> Result<(), ValidationError> {
+    ) {
         let schema = match s.as_mut() {
             Some(s) => s,
-            _ => return Ok(()),
+            _ => return,
         };
 
         match schema {
@@ -254,22 +332,264 @@ where
             }
             _ => (),
         }
+    }
+
+    /// Resolve the request/response schemas in the given operations.
+    fn resolve_operations(&self, map: &ResolvablePathItem<S>) -> Result<(), ValidationError> {
+        let params = map
+            .methods
+            .values()
+            .flat_map(|op| op.parameters.iter())
+            .chain(map.parameters.iter());
+        for param in params {
+            if let Some(schema) = param.write().unwrap().schema.as_mut() {
+                self.resolve_definitions(schema)?;
+            }
+        }
+
+        for resp in map.methods.values().flat_map(|op| op.responses.values()) {
+            if let Some(schema) = resp.write().unwrap().schema.as_mut() {
+                self.resolve_definitions(schema)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Walks through all schemas, evaluates references which are JSON pointers
+    /// to nodes other than global definitions and adds their targets to the
+    /// global definitions (if they're not there already), so that all references
+    /// to a node share the same schema.
+    fn collect_pointer_defs(&mut self) -> Result<(), ValidationError> {
+        let mut refs = vec![];
+        for schema in self.defs.values() {
+            collect_references(schema, &mut refs);
+        }
+
+        let params = self
+            .paths
+            .values()
+            .flat_map(|map| {
+                map.methods
+                    .values()
+                    .flat_map(|op| op.parameters.iter())
+                    .chain(map.parameters.iter())
+                    .filter_map(|p| p.right())
+            })
+            .chain(self.params.values());
+        for param in params {
+            if let Some(schema) = param.read().unwrap().schema.as_ref() {
+                collect_references(schema, &mut refs);
+            }
+        }
+
+        let responses = self
+            .paths
+            .values()
+            .flat_map(|map| map.methods.values().flat_map(|op| op.responses.values()))
+            .filter_map(|r| r.right())
+            .chain(self.resp.values());
+        for resp in responses {
+            if let Some(schema) = resp.read().unwrap().schema.as_ref() {
+                collect_references(schema, &mut refs);
+            }
+        }
+
+        for uri in refs {
+            if self.pointer_defs.contains_key(&uri) {
+                continue;
+            }
+
+            log::trace!("Evaluating JSON pointer {}", uri);
+            let target = self.find_pointer_target(&uri, 0)?;
+            let existing = self
+                .defs
+                .values()
+                .find(|d| Arc::ptr_eq(d, &target))
+                .cloned();
+            let def = match existing {
+                Some(d) => d,
+                None => {
+                    let def = Resolvable::Raw(target);
+                    let name = self.pointer_def_name(&uri);
+                    log::debug!("Adding definition {} for {}", name, uri);
+                    self.defs.insert(name, def.clone());
+                    def
+                }
+            };
+
+            self.pointer_defs.insert(uri, def);
+        }
 
-        self.resolve_definitions(schema)?;
         Ok(())
     }
 
+    /// Evaluates the given JSON pointer (from `$ref` field) and returns the
+    /// schema it points to. References found along the way are followed.
+    ///
+    /// Pointers are evaluated against the parsed spec (since the targets are
+    /// shared with the places they're defined in), so only pointers to schemas
+    /// are supported. They should start at one of
+    ///
+    /// - `#/definitions/{name}`
+    /// - `#/{parameters,responses}/{name}/schema`
+    /// - `#/paths/{path}/parameters/{index}/schema`
+    /// - `#/paths/{path}/{method}/parameters/{index}/schema`
+    /// - `#/paths/{path}/{method}/responses/{code}/schema`
+    ///
+    /// followed by any number of `properties/{name}`, `items`, `allOf/{index}`
+    /// and `additionalProperties` segments. Other pointers (say, to the `info`
+    /// object or an extension) are rejected with `UnsupportedPointer`.
+    fn find_pointer_target(
+        &self,
+        uri: &str,
+        depth: usize,
+    ) -> Result<Arc<RwLock<S>>, ValidationError> {
+        if depth > MAX_REF_DEPTH {
+            return Err(ValidationError::InvalidRefUri(uri.into()));
+        }
+
+        let segments =
+            pointer::segments(uri).ok_or_else(|| ValidationError::InvalidRefUri(uri.into()))?;
+        let missing = || ValidationError::MissingReference(uri.into());
+        let unsupported = || ValidationError::UnsupportedPointer(uri.into());
+        let mut iter = segments.iter().map(String::as_str);
+
+        let mut current = match (iter.next(), iter.next()) {
+            (Some("definitions"), Some(name)) => {
+                let def = self.defs.get(name).ok_or_else(missing)?;
+                Arc::clone(def)
+            }
+            (Some("parameters"), Some(name)) => {
+                let param = self.params.get(name).ok_or_else(missing)?;
+                parameter_schema(param, iter.next(), uri)?
+            }
+            (Some("responses"), Some(name)) => {
+                let resp = self.resp.get(name).ok_or_else(missing)?;
+                response_schema(resp, iter.next(), uri)?
+            }
+            (Some("paths"), Some(path)) => {
+                let map = self.paths.get(path).ok_or_else(missing)?;
+                match iter.next() {
+                    Some("parameters") => {
+                        let param = iter
+                            .next()
+                            .and_then(|i| i.parse::<usize>().ok())
+                            .and_then(|i| map.parameters.get(i))
+                            .ok_or_else(missing)?
+                            .right()
+                            .ok_or_else(unsupported)?;
+                        parameter_schema(param, iter.next(), uri)?
+                    }
+                    Some(method) => {
+                        let op = map
+                            .methods
+                            .iter()
+                            .find(|(m, _)| m.to_string().to_lowercase() == method)
+                            .map(|(_, op)| op)
+                            .ok_or_else(|| {
+                                if HTTP_METHODS.contains(&method) {
+                                    missing()
+                                } else {
+                                    unsupported()
+                                }
+                            })?;
+                        match iter.next() {
+                            Some("parameters") => {
+                                let param = iter
+                                    .next()
+                                    .and_then(|i| i.parse::<usize>().ok())
+                                    .and_then(|i| op.parameters.get(i))
+                                    .ok_or_else(missing)?
+                                    .right()
+                                    .ok_or_else(unsupported)?;
+                                parameter_schema(param, iter.next(), uri)?
+                            }
+                            Some("responses") => {
+                                let resp = iter
+                                    .next()
+                                    .and_then(|c| op.responses.get(c))
+                                    .ok_or_else(missing)?
+                                    .right()
+                                    .ok_or_else(unsupported)?;
+                                response_schema(resp, iter.next(), uri)?
+                            }
+                            _ => return Err(unsupported()),
+                        }
+                    }
+                    None => return Err(unsupported()),
+                }
+            }
+            _ => return Err(unsupported()),
+        };
+
+        loop {
+            let reference = current.read().unwrap().reference().map(String::from);
+            if let Some(uri) = reference {
+                current = self.find_pointer_target(&uri, depth + 1)?;
+            }
+
+            let segment = match iter.next() {
+                Some(s) => s,
+                None => return Ok(current),
+            };
+
+            let next = {
+                let schema = current.read().unwrap();
+                match segment {
+                    "properties" => iter
+                        .next()
+                        .and_then(|p| schema.properties().and_then(|m| m.get(p))),
+                    "items" => schema.items(),
+                    "allOf" => iter
+                        .next()
+                        .and_then(|i| i.parse::<usize>().ok())
+                        .and_then(|i| schema.all_of().and_then(|m| m.get(i))),
+                    "additionalProperties" => {
+                        schema.additional_properties().and_then(|s| s.right())
+                    }
+                    _ => return Err(unsupported()),
+                }
+                .map(|s| Arc::clone(s))
+                .ok_or_else(missing)?
+            };
+
+            current = next;
+        }
+    }
+
+    /// Returns an unused name for the definition created for the given JSON pointer.
+    fn pointer_def_name(&self, uri: &str) -> String {
+        let base = pointer::segments(uri)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| !STRUCTURAL_SEGMENTS.contains(&s.as_str()))
+            .collect::<Vec<_>>()
+            .join("_")
+            .to_pascal_case();
+
+        let mut name = base.clone();
+        let mut idx = 1;
+        while self.defs.contains_key(&name) {
+            idx += 1;
+            name = format!("{}{}", base, idx);
+        }
+
+        name
+    }
+
     /// Given a name (from `$ref` field), get a reference to the definition.
     fn resolve_definition_reference(&self, name: &str) -> Result<Resolvable<S>, ValidationError> {
-        if !name.starts_with(DEF_REF_PREFIX) {
-            return Err(ValidationError::InvalidRefUri(name.into()));
+        if let Some(schema) = self.pointer_defs.get(name) {
+            return Ok(schema.clone());
         }
 
-        let name = &name[DEF_REF_PREFIX.len()..];
+        let name = pointer::name(name, DEF_REF_PREFIX)
+            .ok_or_else(|| ValidationError::InvalidRefUri(name.into()))?;
         let schema = self
             .defs
-            .get(name)
-            .ok_or_else(|| ValidationError::MissingReference(name.into()))?;
+            .get(&name)
+            .ok_or_else(|| ValidationError::MissingReference(name.clone()))?;
         Ok(schema.clone())
     }
 
@@ -278,15 +598,12 @@ where
         &self,
         name: &str,
     ) -> Result<ResolvableParameter<S>, ValidationError> {
-        if !name.starts_with(PARAM_REF_PREFIX) {
-            return Err(ValidationError::InvalidRefUri(name.into()));
-        }
-
-        let name = &name[PARAM_REF_PREFIX.len()..];
+        let name = pointer::name(name, PARAM_REF_PREFIX)
+            .ok_or_else(|| ValidationError::InvalidRefUri(name.into()))?;
         let param = self
             .params
-            .get(name)
-            .ok_or_else(|| ValidationError::MissingReference(name.into()))?;
+            .get(&name)
+            .ok_or_else(|| ValidationError::MissingReference(name.clone()))?;
         Ok(param.clone())
     }
 
@@ -295,15 +612,83 @@ where
         &self,
         name: &str,
     ) -> Result<ResolvableResponse<S>, ValidationError> {
-        if !name.starts_with(RESP_REF_PREFIX) {
-            return Err(ValidationError::InvalidRefUri(name.into()));
-        }
-
-        let name = &name[RESP_REF_PREFIX.len()..];
+        let name = pointer::name(name, RESP_REF_PREFIX)
+            .ok_or_else(|| ValidationError::InvalidRefUri(name.into()))?;
         let resp = self
             .resp
-            .get(name)
-            .ok_or_else(|| ValidationError::MissingReference(name.into()))?;
+            .get(&name)
+            .ok_or_else(|| ValidationError::MissingReference(name.clone()))?;
         Ok(resp.clone())
     }
 }
+
+/// Collects references (in the given schema and its children) which can't be
+/// resolved by looking up a global definition by name.
+fn collect_references<S: Schema>(schema: &Resolvable<S>, refs: &mut Vec<String>) {
+    let schema = schema.read().unwrap();
+    if let Some(uri) = schema.reference() {
+        let is_def_name = uri
+            .strip_prefix(DEF_REF_PREFIX)
+            .map(|n| !n.contains('/'))
+            .unwrap_or(false);
+        if !is_def_name {
+            refs.push(uri.into());
+        }
+    }
+
+    if let Some(s) = schema.items() {
+        collect_references(s, refs);
+    }
+
+    if let Some(props) = schema.properties() {
+        for s in props.values() {
+            collect_references(s, refs);
+        }
+    }
+
+    if let Some(members) = schema.all_of() {
+        for s in members {
+            collect_references(s, refs);
+        }
+    }
+
+    if let Some(s) = schema.additional_properties().and_then(|s| s.right()) {
+        collect_references(s, refs);
+    }
+}
+
+/// Returns the schema of the given parameter, if the pointer segment is `schema`.
+fn parameter_schema<S>(
+    param: &ResolvableParameter<S>,
+    segment: Option<&str>,
+    uri: &str,
+) -> Result<Arc<RwLock<S>>, ValidationError> {
+    match segment {
+        Some("schema") => param
+            .read()
+            .unwrap()
+            .schema
+            .as_deref()
+            .cloned()
+            .ok_or_else(|| ValidationError::MissingReference(uri.into())),
+        _ => Err(ValidationError::UnsupportedPointer(uri.into())),
+    }
+}
+
+/// Returns the schema of the given response, if the pointer segment is `schema`.
+fn response_schema<S>(
+    resp: &ResolvableResponse<S>,
+    segment: Option<&str>,
+    uri: &str,
+) -> Result<Arc<RwLock<S>>, ValidationError> {
+    match segment {
+        Some("schema") => resp
+            .read()
+            .unwrap()
+            .schema
+            .as_deref()
+            .cloned()
+            .ok_or_else(|| ValidationError::MissingReference(uri.into())),
+        _ => Err(ValidationError::UnsupportedPointer(uri.into())),
+    }
+}
@@ -2,10 +2,13 @@
 
 use super::models::{
     DataType, DataTypeFormat, DefaultOperationRaw, DefaultSchemaRaw, Either, Resolvable,
-    SecurityScheme,
+    SecurityScheme, Xml,
 };
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, RwLock},
+};
 
 /// Interface for the [`Schema`](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#schemaObject) object.
 ///
@@ -47,6 +50,92 @@ pub trait Schema: Sized {
     /// Returns the required properties (if any) for this object.
     fn required_properties(&self) -> Option<&BTreeSet<String>>;
 
+    /// Schemas which this schema is composed of, if any (`allOf` field).
+    fn all_of(&self) -> Option<&[Resolvable<Self>]> {
+        None
+    }
+
+    /// Mutable access to `allOf` field.
+    fn all_of_mut(&mut self) -> Option<&mut [Resolvable<Self>]> {
+        None
+    }
+
+    /// Name of the property used for discriminating between the subtypes
+    /// of this schema, if any (`discriminator` field).
+    fn discriminator(&self) -> Option<&str> {
+        None
+    }
+
+    /// XML representation of this schema (if specified).
+    fn xml(&self) -> Option<&Xml> {
+        None
+    }
+
+    /// Example value for this schema, if any (`example` field).
+    fn example(&self) -> Option<&serde_json::Value> {
+        None
+    }
+
+    /// Default value for this schema, if any (`default` field).
+    fn default_value(&self) -> Option<&serde_json::Value> {
+        None
+    }
+
+    /// Inclusive upper bound for numbers, if any (`maximum` field).
+    fn maximum(&self) -> Option<f32> {
+        None
+    }
+
+    /// Inclusive lower bound for numbers, if any (`minimum` field).
+    fn minimum(&self) -> Option<f32> {
+        None
+    }
+
+    /// Whether `maximum` is an exclusive bound (`exclusiveMaximum` field).
+    fn exclusive_maximum(&self) -> bool {
+        false
+    }
+
+    /// Whether `minimum` is an exclusive bound (`exclusiveMinimum` field).
+    fn exclusive_minimum(&self) -> bool {
+        false
+    }
+
+    /// Number which numeric values should be a multiple of (`multipleOf` field).
+    fn multiple_of(&self) -> Option<f32> {
+        None
+    }
+
+    /// Maximum length of strings, if any (`maxLength` field).
+    fn max_length(&self) -> Option<u32> {
+        None
+    }
+
+    /// Minimum length of strings, if any (`minLength` field).
+    fn min_length(&self) -> Option<u32> {
+        None
+    }
+
+    /// Regular expression which strings should match, if any (`pattern` field).
+    fn pattern(&self) -> Option<&str> {
+        None
+    }
+
+    /// Maximum number of items in arrays, if any (`maxItems` field).
+    fn max_items(&self) -> Option<u32> {
+        None
+    }
+
+    /// Minimum number of items in arrays, if any (`minItems` field).
+    fn min_items(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether the items in arrays should be unique (`uniqueItems` field).
+    fn unique_items(&self) -> bool {
+        false
+    }
+
     /// Enum variants in this schema (if any). It's `serde_json::Value`
     /// because:
     ///
@@ -67,6 +156,18 @@ pub trait Schema: Sized {
     /// Set whether this definition is cyclic. This is done by the resolver.
     fn set_cyclic(&mut self, cyclic: bool);
 
+    /// Add a schema which extends this schema (through `allOf`). This is done
+    /// by the resolver for schemas with a discriminator.
+    fn add_subtype(&mut self, _subtype: &Arc<RwLock<Self>>) {}
+
+    /// Returns the schemas extending this schema.
+    ///
+    /// **NOTE:** This is not part of the schema object, but it's
+    /// set by the resolver using `add_subtype` for codegen.
+    fn subtypes(&self) -> Vec<Arc<RwLock<Self>>> {
+        vec![]
+    }
+
     /// Returns whether this definition is cyclic.
     ///
     /// **NOTE:** This is not part of the schema object, but it's
@@ -83,11 +184,15 @@ pub trait Schema: Sized {
     fn set_name(&mut self, name: &str);
 }
 
-fn _schema_contains_any<'a, S: Schema>(schema: &'a S, mut nodes: Vec<&'a str>) -> bool {
-    if schema.data_type().is_none() {
+fn _schema_contains_any<'a, S: Schema>(schema: &'a S, nodes: Vec<&'a str>) -> bool {
+    if schema.data_type().is_none() && schema.all_of().is_none() {
         return true;
     }
 
+    _fields_contain_any(schema, nodes)
+}
+
+fn _fields_contain_any<'a, S: Schema>(schema: &'a S, mut nodes: Vec<&'a str>) -> bool {
     if let Some(name) = schema.name() {
         if nodes.contains(&name) {
             return false; // We've encountered a cycle.
@@ -107,6 +212,20 @@ fn _schema_contains_any<'a, S: Schema>(schema: &'a S, mut nodes: Vec<&'a str>) -
             .items()
             .map(|s| _schema_contains_any(&*s.read().unwrap(), nodes.clone()))
             .unwrap_or(false)
+        || schema
+            .all_of()
+            .map(|m| {
+                m.iter().any(|s| {
+                    let s = &*s.read().unwrap();
+                    // Members are allowed to skip the type if they only add properties.
+                    if s.data_type().is_none() && s.properties().is_some() {
+                        _fields_contain_any(s, nodes.clone())
+                    } else {
+                        _schema_contains_any(s, nodes.clone())
+                    }
+                })
+            })
+            .unwrap_or(false)
         || schema
             .additional_properties()
             .map(|e| match e {
@@ -271,17 +390,21 @@ impl_type_simple!(
 );
 #[cfg(feature = "jiff01")]
 impl_type_simple!(jiff::Timestamp, DataType::String, DataTypeFormat::DateTime);
+// `Zoned` values have a time zone annotation (RFC 9557), so they're not RFC 3339
+// `date-time`s. `zoned-date-time` isn't a standard format, but it's how we
+// identify them (2024-06-19T15:22:45-04:00[America/New_York]).
 #[cfg(feature = "jiff01")]
 impl_type_simple!(
     jiff::Zoned,
     DataType::String,
-    DataTypeFormat::Other //RFC 8536
+    DataTypeFormat::Other("zoned-date-time".into())
 );
+// Civil datetimes don't have an offset, so they're not `date-time`s either.
 #[cfg(feature = "jiff01")]
 impl_type_simple!(
     jiff::civil::DateTime,
     DataType::String,
-    DataTypeFormat::Other //2024-06-19T15:22:45
+    DataTypeFormat::Other("date-time-local".into()) //2024-06-19T15:22:45
 );
 #[cfg(feature = "jiff01")]
 impl_type_simple!(jiff::civil::Date, DataType::String, DataTypeFormat::Date);
@@ -289,13 +412,13 @@ impl_type_simple!(jiff::civil::Date, DataType::String, DataTypeFormat::Date);
 impl_type_simple!(
     jiff::civil::Time,
     DataType::String,
-    DataTypeFormat::Other //15:22:45
+    DataTypeFormat::Other("time".into()) //15:22:45
 );
 #[cfg(feature = "jiff01")]
 impl_type_simple!(
     jiff::Span,
     DataType::String,
-    DataTypeFormat::Other //ISO 8601
+    DataTypeFormat::Other("duration".into()) //ISO 8601
 );
 #[cfg(feature = "chrono")]
 impl_type_simple!(
@@ -408,6 +531,13 @@ pub trait Apiv2Schema {
         def
     }
 
+    /// Returns the definitions of the subtypes of this object (if it's
+    /// polymorphic), keyed by their names. These refer to the definition
+    /// of this object through `allOf`.
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        BTreeMap::new()
+    }
+
     /// Returns the security scheme for this object.
     fn security_scheme() -> Option<SecurityScheme> {
         None
@@ -470,6 +600,10 @@ impl<T: Apiv2Schema> Apiv2Schema for Option<T> {
         T::raw_schema()
     }
 
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
+
     fn security_scheme() -> Option<SecurityScheme> {
         T::security_scheme()
     }
@@ -507,6 +641,10 @@ impl<T: Apiv2Schema, E> Apiv2Schema for Result<T, E> {
         T::raw_schema()
     }
 
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
+
     fn security_scheme() -> Option<SecurityScheme> {
         T::security_scheme()
     }
@@ -525,6 +663,10 @@ impl<T: Apiv2Schema + Clone> Apiv2Schema for std::borrow::Cow<'_, T> {
         T::raw_schema()
     }
 
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
+
     fn security_scheme() -> Option<SecurityScheme> {
         T::security_scheme()
     }
@@ -538,6 +680,10 @@ impl<T: Apiv2Schema> Apiv2Schema for &[T] {
     fn raw_schema() -> DefaultSchemaRaw {
         Vec::<T>::raw_schema()
     }
+
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
 }
 
 impl<T: Apiv2Schema, const N: usize> Apiv2Schema for [T; N] {
@@ -548,6 +694,10 @@ impl<T: Apiv2Schema, const N: usize> Apiv2Schema for [T; N] {
             ..Default::default()
         }
     }
+
+    fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        T::subtype_definitions()
+    }
 }
 
 macro_rules! impl_schema_array {
@@ -560,6 +710,10 @@ macro_rules! impl_schema_array {
                     ..Default::default()
                 }
             }
+
+            fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+                T::subtype_definitions()
+            }
         }
     };
 }
@@ -574,6 +728,10 @@ macro_rules! impl_schema_map {
                     ..Default::default()
                 }
             }
+
+            fn subtype_definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+                V::subtype_definitions()
+            }
         }
     };
 }
@@ -611,6 +769,13 @@ pub trait Apiv2Operation {
     fn is_visible() -> bool {
         true
     }
+
+    /// Returns the native OpenAPI v3 operation (and its components), if this
+    /// operation was generated by `api_v3_operation`.
+    #[cfg(feature = "v3")]
+    fn v3_operation() -> Option<(openapiv3::Operation, openapiv3::Components)> {
+        None
+    }
 }
 
 /// Represents a OpenAPI v2 error convertible. This is auto-implemented by
@@ -0,0 +1,638 @@
+//! Source maps for locating nodes (and errors) in spec documents.
+//!
+//! A [`SourceMap`](struct.SourceMap.html) maps the JSON paths of nodes in a
+//! document (like `$.paths['/pets'].get`) to the line and column at which
+//! they appear. Objects and arrays are located by their keys (or items),
+//! so that the location of `$.definitions.Pet` is where the `Pet` key starts.
+//!
+//! The YAML scanner only understands the layouts found in specs (block
+//! mappings and sequences, flow collections, quoted and block scalars).
+//! Anchors and aliases are not followed, and nodes which can't be located
+//! fall back to the location of their nearest ancestor.
+
+use super::{models::SpecFormat, pointer};
+use crate::error::{Span, ValidationError};
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Keys whose (scalar) values are recorded for locating errors.
+const RECORDED_KEYS: &[&str] = &["$ref", "name"];
+
+/// Source map of a spec document.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    /// Path to the document (if it was loaded from a file).
+    file: Option<PathBuf>,
+    /// Lines in the document.
+    lines: Vec<String>,
+    /// Location (line, column) of nodes by their JSON paths.
+    positions: HashMap<String, (usize, usize)>,
+    /// Values of recorded keys (along with the JSON paths of those keys)
+    /// in the order in which they appear in the document.
+    values: Vec<(String, String)>,
+}
+
+impl SourceMap {
+    /// Builds the source map for the given document.
+    pub fn new(text: &str, format: SpecFormat) -> Self {
+        let mut map = SourceMap {
+            file: None,
+            lines: text.lines().map(String::from).collect(),
+            positions: HashMap::new(),
+            values: vec![],
+        };
+
+        match format {
+            SpecFormat::Json => {
+                let mut scanner = FlowScanner::new(text, 0, &mut map);
+                scanner.value("$", None);
+            }
+            SpecFormat::Yaml => scan_yaml(text, &mut map),
+        }
+
+        map
+    }
+
+    /// Sets the path to the document (used in spans).
+    pub fn with_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Path to the document (if it was loaded from a file).
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the span of the node at the given JSON path. If the node
+    /// can't be located, then this returns the span of its nearest ancestor.
+    pub fn span(&self, path: &str) -> Option<Span> {
+        let mut path = path;
+        loop {
+            if let Some(&(line, column)) = self.positions.get(path) {
+                return Some(self.span_at(line, column));
+            }
+
+            path = parent_path(path)?;
+        }
+    }
+
+    /// Returns the span for the given line and column (both starting from 1).
+    pub fn span_at(&self, line: usize, column: usize) -> Span {
+        Span {
+            file: self.file.clone(),
+            line,
+            column,
+            text: line
+                .checked_sub(1)
+                .and_then(|i| self.lines.get(i))
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Finds the location of the node responsible for the given error.
+    pub fn locate(&self, err: &ValidationError) -> Option<Span> {
+        match err {
+            ValidationError::InvalidRefUri(uri)
+            | ValidationError::MissingReference(uri)
+            | ValidationError::UnsupportedPointer(uri) => {
+                // Errors may have either the URI or the (unescaped) name in the URI.
+                self.values
+                    .iter()
+                    .filter(|(path, _)| path.ends_with("['$ref']"))
+                    .find(|(_, value)| {
+                        value == uri
+                            || pointer::segments(value).and_then(|mut s| s.pop()).as_ref()
+                                == Some(uri)
+                    })
+                    .and_then(|(path, _)| self.span(path))
+            }
+            ValidationError::MissingSchemaForBodyParameter(name, path)
+            | ValidationError::InvalidHeader(name, path)
+            | ValidationError::InvalidParameterType(name, path, _, _) => {
+                let path_item = json_path("$.paths", path);
+                self.values
+                    .iter()
+                    .find(|(p, value)| {
+                        value == name && p.starts_with(&path_item) && p.ends_with(".name")
+                    })
+                    .and_then(|(p, _)| self.span(parent_path(p)?))
+                    .or_else(|| self.span(&path_item))
+            }
+            ValidationError::Located(_, span) => Some(span.clone()),
+        }
+    }
+
+    /// Attaches the location of the node responsible for the given error
+    /// (if it can be found) to that error.
+    pub fn annotate(&self, err: ValidationError) -> ValidationError {
+        match self.locate(&err) {
+            Some(span) if err.span().is_none() => err.with_span(span),
+            _ => err,
+        }
+    }
+
+    /// Records the location of the node at the given path. If a node
+    /// shows up more than once, then the first one wins.
+    fn record(&mut self, path: &str, line: usize, column: usize) {
+        if !self.positions.contains_key(path) {
+            self.positions.insert(path.into(), (line, column));
+        }
+    }
+
+    /// Records the value of the given key (if it's one of the recorded keys).
+    fn record_value(&mut self, path: &str, key: Option<&str>, value: &str) {
+        if matches!(key, Some(k) if RECORDED_KEYS.contains(&k)) {
+            self.values.push((path.into(), value.into()));
+        }
+    }
+}
+
+/// Returns the JSON path (like `$.paths['/pets'].get`) of the given key in
+/// the node at the given path.
+pub fn json_path(parent: &str, key: &str) -> String {
+    let is_ident = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if is_ident {
+        format!("{}.{}", parent, key)
+    } else {
+        format!(
+            "{}['{}']",
+            parent,
+            key.replace('\\', "\\\\").replace('\'', "\\'")
+        )
+    }
+}
+
+/// Returns the path of the parent node (if any) for the given JSON path.
+fn parent_path(path: &str) -> Option<&str> {
+    if path.ends_with(']') {
+        // Look for the opening bracket which isn't inside a quoted key.
+        let mut quoted = false;
+        let mut escaped = false;
+        for (i, c) in path.char_indices().rev().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\'' => {
+                    // Quotes in keys are escaped, so check the preceding backslashes.
+                    let slashes = path[..i].chars().rev().take_while(|&c| c == '\\').count();
+                    if slashes % 2 == 1 {
+                        escaped = true;
+                    } else {
+                        quoted = !quoted;
+                    }
+                }
+                '[' if !quoted => return Some(&path[..i]),
+                _ => (),
+            }
+        }
+
+        None
+    } else {
+        path.rfind('.').map(|i| &path[..i])
+    }
+}
+
+/// Returns the path of the item at the given index in the array at the given path.
+fn index_path(parent: &str, index: usize) -> String {
+    format!("{}[{}]", parent, index)
+}
+
+/// Scanner for JSON documents and YAML flow collections.
+struct FlowScanner<'a, 'm> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    map: &'m mut SourceMap,
+}
+
+impl<'a, 'm> FlowScanner<'a, 'm> {
+    /// Creates a scanner which begins at the given byte offset in the text.
+    fn new(text: &'a str, pos: usize, map: &'m mut SourceMap) -> Self {
+        let line = text[..pos].matches('\n').count() + 1;
+        let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        FlowScanner {
+            text,
+            pos,
+            line,
+            line_start,
+            map,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = self.pos;
+        }
+
+        Some(c)
+    }
+
+    fn column(&self) -> usize {
+        self.text[self.line_start..self.pos].chars().count() + 1
+    }
+
+    /// Skips whitespace and (YAML) comments.
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while !matches!(self.peek(), None | Some('\n')) {
+                    self.bump();
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Scans the value at the given path (of the given key) and records
+    /// the locations of its children.
+    fn value(&mut self, path: &str, key: Option<&str>) {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                loop {
+                    self.skip_whitespace();
+                    if matches!(self.peek(), None | Some('}')) {
+                        self.bump();
+                        break;
+                    }
+
+                    let (line, column) = (self.line, self.column());
+                    let start = self.pos;
+                    let name = self.scalar();
+                    let child = json_path(path, &name);
+                    self.map.record(&child, line, column);
+
+                    self.skip_whitespace();
+                    if self.peek() == Some(':') {
+                        self.bump();
+                        self.value(&child, Some(&name));
+                    }
+
+                    if !self.separator('}') || self.pos == start {
+                        break;
+                    }
+                }
+            }
+            Some('[') => {
+                self.bump();
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    if matches!(self.peek(), None | Some(']')) {
+                        self.bump();
+                        break;
+                    }
+
+                    let start = self.pos;
+                    let child = index_path(path, index);
+                    self.map.record(&child, self.line, self.column());
+                    self.value(&child, None);
+                    index += 1;
+
+                    if !self.separator(']') || self.pos == start {
+                        break;
+                    }
+                }
+            }
+            Some(_) => {
+                let value = self.scalar();
+                self.map.record_value(path, key, &value);
+            }
+            None => (),
+        }
+    }
+
+    /// Consumes the separator after an entry. Returns `false` if there are no
+    /// more entries in the collection.
+    fn separator(&mut self, end: char) -> bool {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(',') => {
+                self.bump();
+                true
+            }
+            Some(c) if c == end => {
+                self.bump();
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Scans a quoted or a plain scalar and returns its value.
+    fn scalar(&mut self) -> String {
+        match self.peek() {
+            Some(q @ '"') | Some(q @ '\'') => {
+                self.bump();
+                let mut value = String::new();
+                while let Some(c) = self.bump() {
+                    match c {
+                        '\\' if q == '"' => {
+                            if let Some(c) = self.bump() {
+                                value.push(c);
+                            }
+                        }
+                        '\'' if q == '\'' && self.peek() == Some('\'') => {
+                            self.bump();
+                            value.push('\'');
+                        }
+                        _ if c == q => break,
+                        _ => value.push(c),
+                    }
+                }
+
+                value
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    let rest = &self.text[self.pos + c.len_utf8()..];
+                    let ends_key = c == ':'
+                        && !matches!(rest.chars().next(), Some(n) if !n.is_whitespace() && !",[]{}".contains(n));
+                    let is_comment = c == '#'
+                        && !matches!(self.text[..self.pos].chars().last(), Some(p) if !p.is_whitespace());
+                    if ends_key || is_comment || ",[]{}\n".contains(c) {
+                        break;
+                    }
+
+                    self.bump();
+                }
+
+                self.text[start..self.pos].trim().into()
+            }
+        }
+    }
+}
+
+/// Collection in a YAML document (which is being scanned).
+struct Block {
+    /// Column (starting from 0) at which the entries of this collection begin.
+    indent: usize,
+    /// JSON path of this collection.
+    path: String,
+    /// Index of the next item (if this is a sequence).
+    next_index: Option<usize>,
+}
+
+/// Scans the given YAML document and records the locations of nodes.
+fn scan_yaml(text: &str, map: &mut SourceMap) {
+    let mut blocks = vec![Block {
+        indent: 0,
+        path: "$".into(),
+        next_index: None,
+    }];
+    // Key (path and column) whose value begins in the following lines.
+    let mut pending: Option<(String, usize)> = None;
+    // Lines indented beyond this column belong to a multi-line scalar.
+    let mut continued: Option<usize> = None;
+    // Flow collections may span multiple lines (skip until this offset).
+    let mut skip_until = 0;
+
+    let mut offset = 0;
+    for (i, raw) in text.split('\n').enumerate() {
+        let start = offset;
+        offset += raw.len() + 1;
+        if start < skip_until {
+            continue;
+        }
+
+        let line = raw.trim_end_matches('\r');
+        let content = line.trim_start_matches(' ');
+        let indent = line.len() - content.len();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        if let Some(col) = continued {
+            if indent > col {
+                continue;
+            }
+
+            continued = None;
+        }
+
+        if content.starts_with("---") || content.starts_with("...") || content.starts_with('%') {
+            continue;
+        }
+
+        let is_item = is_sequence_item(content);
+        if let Some((path, col)) = pending.take() {
+            if indent > col || (indent == col && is_item) {
+                blocks.push(Block {
+                    indent,
+                    path,
+                    next_index: if is_item { Some(0) } else { None },
+                });
+            }
+        }
+
+        while blocks.len() > 1 {
+            let top = blocks.last().expect("non-empty blocks");
+            if top.indent > indent || (top.indent == indent && top.next_index.is_some() && !is_item)
+            {
+                blocks.pop();
+            } else {
+                break;
+            }
+        }
+
+        let mut scanner = LineScanner {
+            text,
+            line_offset: start,
+            line_number: i + 1,
+            line,
+            blocks: &mut blocks,
+            map,
+        };
+        match scanner.entry(indent) {
+            LineEnd::Pending(path, col) => pending = Some((path, col)),
+            LineEnd::Continued(col) => continued = Some(col),
+            LineEnd::SkipUntil(pos) => skip_until = pos,
+        }
+    }
+}
+
+/// Checks whether the given line content begins a sequence item.
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// What follows the scanned line.
+enum LineEnd {
+    /// The value of the key (at the given path and column) is in the following lines.
+    Pending(String, usize),
+    /// Lines indented beyond the given column continue a scalar.
+    Continued(usize),
+    /// A flow collection ended at the given offset (beyond this line).
+    SkipUntil(usize),
+}
+
+/// Scanner for the entries in a single line of a YAML document.
+struct LineScanner<'a, 'b> {
+    text: &'a str,
+    line_offset: usize,
+    line_number: usize,
+    line: &'a str,
+    blocks: &'b mut Vec<Block>,
+    map: &'b mut SourceMap,
+}
+
+impl LineScanner<'_, '_> {
+    fn column(&self, col: usize) -> usize {
+        self.line[..col].chars().count() + 1
+    }
+
+    /// Scans the entry beginning at the given column (byte offset in the line).
+    fn entry(&mut self, col: usize) -> LineEnd {
+        let content = &self.line[col..];
+        let block = self.blocks.last_mut().expect("non-empty blocks");
+        let item = match block.next_index.as_mut() {
+            Some(index) if is_sequence_item(content) => {
+                *index += 1;
+                Some(index_path(&block.path, *index - 1))
+            }
+            _ => None,
+        };
+
+        if let Some(path) = item {
+            self.map.record(&path, self.line_number, self.column(col));
+
+            let rest = content[1..].trim_start_matches(' ');
+            let rest_col = self.line.len() - rest.len();
+            if rest.is_empty() || rest.starts_with('#') {
+                return LineEnd::Pending(path, col);
+            }
+
+            if is_sequence_item(rest) || split_key(rest).is_some() {
+                self.blocks.push(Block {
+                    indent: rest_col,
+                    path,
+                    next_index: if is_sequence_item(rest) {
+                        Some(0)
+                    } else {
+                        None
+                    },
+                });
+                return self.entry(rest_col);
+            }
+
+            return self.value(&path, None, rest_col, col);
+        }
+
+        let (key, value_col) = match split_key(content) {
+            Some((key, value_offset)) => (key, col + value_offset),
+            // Not an entry we understand (say, a complex key).
+            None => return LineEnd::Continued(col),
+        };
+
+        let path = json_path(&self.blocks.last().expect("non-empty blocks").path, &key);
+        self.map.record(&path, self.line_number, self.column(col));
+        self.value(&path, Some(&key), value_col, col)
+    }
+
+    /// Scans the value (at the given path) beginning at the given column.
+    fn value(&mut self, path: &str, key: Option<&str>, col: usize, parent: usize) -> LineEnd {
+        let mut rest = self.line[col..].trim_start_matches(' ');
+        // Skip anchors and tags.
+        while rest.starts_with('&') || rest.starts_with('!') {
+            rest = rest
+                .split_once(' ')
+                .map_or("", |(_, r)| r)
+                .trim_start_matches(' ');
+        }
+
+        if rest.is_empty() || rest.starts_with('#') {
+            return LineEnd::Pending(path.into(), parent);
+        }
+
+        if rest.starts_with('|') || rest.starts_with('>') {
+            return LineEnd::Continued(parent);
+        }
+
+        let offset = self.line_offset + self.line.len() - rest.len();
+        let mut scanner = FlowScanner::new(self.text, offset, self.map);
+        if rest.starts_with('{') || rest.starts_with('[') {
+            scanner.value(path, key);
+            let end = scanner.pos;
+            return if end > self.line_offset + self.line.len() {
+                LineEnd::SkipUntil(end)
+            } else {
+                LineEnd::Continued(parent)
+            };
+        }
+
+        let value = scanner.scalar();
+        self.map.record_value(path, key, &value);
+        LineEnd::Continued(parent)
+    }
+}
+
+/// Splits the given mapping entry into its key and the offset at which
+/// its value begins.
+fn split_key(content: &str) -> Option<(String, usize)> {
+    let (key, rest) = match content.chars().next()? {
+        q @ '"' | q @ '\'' => {
+            let mut key = String::new();
+            let mut chars = content.char_indices().skip(1).peekable();
+            let mut end = None;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' if q == '"' => {
+                        if let Some((_, c)) = chars.next() {
+                            key.push(c);
+                        }
+                    }
+                    '\'' if q == '\'' && chars.peek().map(|&(_, c)| c) == Some('\'') => {
+                        chars.next();
+                        key.push('\'');
+                    }
+                    _ if c == q => {
+                        end = Some(i + 1);
+                        break;
+                    }
+                    _ => key.push(c),
+                }
+            }
+
+            let rest = &content[end?..];
+            (key, rest.trim_start_matches(' '))
+        }
+        '[' | '{' | '-' | '?' | '#' | '|' | '>' | '*' => return None,
+        _ => {
+            let end = content
+                .match_indices(':')
+                .map(|(i, _)| i)
+                .find(|&i| matches!(content[i + 1..].chars().next(), None | Some(' ')))?;
+            if content[..end].contains(" #") {
+                return None;
+            }
+
+            (content[..end].trim_end().into(), &content[end..])
+        }
+    };
+
+    let value = rest.strip_prefix(':')?;
+    if !(value.is_empty() || value.starts_with(' ')) {
+        return None;
+    }
+
+    Some((key, content.len() - value.len()))
+}
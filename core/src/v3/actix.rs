@@ -0,0 +1,705 @@
+//! Actix-specific traits and types for generating native OpenAPI v3 operations.
+
+#[cfg(feature = "actix3-validator")]
+extern crate actix_web_validator2 as actix_web_validator;
+#[cfg(feature = "actix4-validator")]
+extern crate actix_web_validator3 as actix_web_validator;
+
+use super::{
+    schema::{schema_of, Apiv3Operation, Apiv3Schema},
+    v2,
+};
+use crate::v2::{
+    schema::{Apiv2Errors, Apiv2Operation, Apiv2Schema},
+    AcceptedJson, CreatedJson, NoContent, ResponderWrapper,
+};
+#[cfg(any(feature = "actix3", feature = "actix4"))]
+use actix_web::web::ReqData;
+#[cfg(feature = "actix4")]
+use actix_web::HttpResponse;
+use actix_web::{
+    http::StatusCode,
+    web::{Bytes, Data, Form, Json, Path, Payload, Query},
+    HttpRequest, Responder,
+};
+#[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
+use actix_web_validator::{
+    Json as ValidatedJson, Path as ValidatedPath, QsQuery as ValidatedQsQuery,
+    Query as ValidatedQuery,
+};
+use openapiv3::{
+    Components, CookieStyle, Link, LinkOperation, MediaType, Operation, Parameter, ParameterData,
+    ParameterSchemaOrContent, PathStyle, QueryStyle, ReferenceOr, RequestBody, Response, Schema,
+    SchemaKind, SecurityScheme, StringType, Type,
+};
+use pin_project_lite::pin_project;
+use serde::Serialize;
+#[cfg(feature = "serde_qs")]
+use serde_qs::actix::QsQuery;
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Actix-specific trait for indicating that this entity can modify a native v3
+/// operation and/or update the map of component schemas.
+pub trait OperationModifier {
+    /// Update the parameters or the request body in the given operation (if needed).
+    fn update_parameter(_op: &mut Operation) {}
+
+    /// Update the responses in the given operation (if needed).
+    fn update_response(_op: &mut Operation) {}
+
+    /// Update the component schemas (if needed).
+    fn update_schemas(_map: &mut BTreeMap<String, Schema>) {}
+
+    /// Update the security requirements in the given operation (if needed).
+    fn update_security(_op: &mut Operation) {}
+
+    /// Update the security schemes (if needed). Schemes are collected in their v2
+    /// form so that the scopes of related security types can be merged.
+    fn update_security_schemes(_map: &mut BTreeMap<String, v2::SecurityScheme>) {}
+}
+
+impl<T: OperationModifier> OperationModifier for Option<T> {
+    fn update_parameter(op: &mut Operation) {
+        T::update_parameter(op);
+    }
+
+    fn update_response(op: &mut Operation) {
+        T::update_response(op);
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+
+    fn update_security(op: &mut Operation) {
+        T::update_security(op);
+    }
+
+    fn update_security_schemes(map: &mut BTreeMap<String, v2::SecurityScheme>) {
+        T::update_security_schemes(map);
+    }
+}
+
+/// Errors are described with `#[api_v2_errors]`, so their responses and
+/// schemas are converted from v2.
+impl<T: OperationModifier, E: Apiv2Errors> OperationModifier for Result<T, E> {
+    fn update_parameter(op: &mut Operation) {
+        T::update_parameter(op);
+    }
+
+    fn update_response(op: &mut Operation) {
+        T::update_response(op);
+
+        let mut v2_op = v2::DefaultOperationRaw::default();
+        E::update_error_definitions(&mut v2_op);
+        for (code, response) in v2_op.responses {
+            if let (Ok(code), v2::Either::Right(response)) = (code.parse(), response) {
+                op.responses
+                    .responses
+                    .insert(openapiv3::StatusCode::Code(code), response.into());
+            }
+        }
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+
+        let mut definitions = BTreeMap::new();
+        E::update_definitions(&mut definitions);
+        for (name, schema) in definitions {
+            if let ReferenceOr::Item(schema) = schema.into() {
+                map.entry(name).or_insert(schema);
+            }
+        }
+    }
+
+    fn update_security(op: &mut Operation) {
+        T::update_security(op);
+    }
+
+    fn update_security_schemes(map: &mut BTreeMap<String, v2::SecurityScheme>) {
+        T::update_security_schemes(map);
+    }
+}
+
+// We don't know what we should do with these abstractions
+// as they could be anything.
+impl<T> OperationModifier for Data<T> {}
+#[cfg(any(feature = "actix3", feature = "actix4"))]
+impl<T: Clone> OperationModifier for ReqData<T> {}
+impl<T: Responder> OperationModifier for ResponderWrapper<T> {}
+#[cfg(feature = "actix4")]
+impl OperationModifier for actix_web::dev::Response<actix_web::body::BoxBody> {}
+
+macro_rules! impl_empty({ $($ty:ty),+ } => {
+    $(
+        impl OperationModifier for $ty {}
+    )+
+});
+
+impl_empty!(HttpRequest, actix_web::HttpResponse, Bytes, Payload);
+impl_empty!(&str, char, String, bool, f32, f64);
+impl_empty!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+#[cfg(feature = "chrono")]
+impl_empty!(chrono::NaiveDateTime);
+#[cfg(feature = "chrono")]
+impl<T: chrono::TimeZone> OperationModifier for chrono::DateTime<T> {}
+#[cfg(feature = "jiff01")]
+impl_empty!(jiff::Timestamp, jiff::Zoned);
+#[cfg(feature = "rust_decimal")]
+impl_empty!(rust_decimal::Decimal);
+#[cfg(feature = "url")]
+impl_empty!(url::Url);
+#[cfg(feature = "uuid0")]
+impl_empty!(uuid0_dep::Uuid);
+#[cfg(feature = "uuid1")]
+impl_empty!(uuid1_dep::Uuid);
+#[cfg(feature = "actix-session")]
+impl_empty!(actix_session::Session);
+#[cfg(feature = "actix-identity")]
+impl_empty!(actix_identity::Identity);
+#[cfg(feature = "actix-files")]
+impl_empty!(actix_files::NamedFile);
+
+#[cfg(feature = "actix-multipart")]
+impl OperationModifier for actix_multipart::Multipart {
+    fn update_parameter(op: &mut Operation) {
+        let schema = schema_of(Type::Object(Default::default()));
+        set_request_body(op, "multipart/form-data", ReferenceOr::Item(schema), true);
+    }
+}
+
+impl<T: Apiv3Schema> OperationModifier for Json<T> {
+    fn update_parameter(op: &mut Operation) {
+        let media = v2::SpecFormat::Json.mime().0.to_string();
+        set_request_body(op, &media, T::schema_with_ref(), T::required());
+    }
+
+    fn update_response(op: &mut Operation) {
+        // TODO: Support configuring other 2xx codes using macro attribute.
+        set_response(op, StatusCode::OK, Some(T::schema_with_ref()));
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+}
+
+#[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
+impl<T: Apiv3Schema> OperationModifier for ValidatedJson<T> {
+    fn update_parameter(op: &mut Operation) {
+        Json::<T>::update_parameter(op);
+    }
+
+    fn update_response(op: &mut Operation) {
+        Json::<T>::update_response(op);
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+}
+
+/// Forms are objects in request bodies, so they can refer to the component schemas.
+impl<T: Apiv3Schema> OperationModifier for Form<T> {
+    fn update_parameter(op: &mut Operation) {
+        set_request_body(
+            op,
+            "application/x-www-form-urlencoded",
+            T::schema_with_ref(),
+            true,
+        );
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+}
+
+macro_rules! json_with_status {
+    ($name:ident => $status:expr) => {
+        impl<T> OperationModifier for $name<T>
+        where
+            T: Serialize + Apiv2Schema + Apiv3Schema,
+        {
+            fn update_response(op: &mut Operation) {
+                let schema = <T as Apiv3Schema>::schema_with_ref();
+                set_response(op, $status, Some(schema));
+            }
+
+            fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+                T::update_schemas(map);
+            }
+        }
+    };
+}
+
+json_with_status!(CreatedJson => StatusCode::CREATED);
+json_with_status!(AcceptedJson => StatusCode::ACCEPTED);
+
+impl OperationModifier for NoContent {
+    fn update_response(op: &mut Operation) {
+        set_response(op, StatusCode::NO_CONTENT, None);
+    }
+}
+
+/// Location of the parameters extracted from a type.
+#[derive(Clone, Copy, PartialEq)]
+enum ParameterIn {
+    Path,
+    Query,
+}
+
+macro_rules! impl_param_extractor ({ $ty:ty => $container:ident } => {
+    impl<T: Apiv3Schema> OperationModifier for $ty {
+        fn update_parameter(op: &mut Operation) {
+            update_parameters::<T>(op, ParameterIn::$container);
+        }
+
+        // These don't require updating the schemas, as we use them only
+        // to get their properties.
+    }
+});
+
+impl_param_extractor!(Path<T> => Path);
+impl_param_extractor!(Query<T> => Query);
+#[cfg(feature = "serde_qs")]
+impl_param_extractor!(QsQuery<T> => Query);
+#[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
+impl_param_extractor!(ValidatedPath<T> => Path);
+#[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
+impl_param_extractor!(ValidatedQuery<T> => Query);
+#[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
+impl_param_extractor!(ValidatedQsQuery<T> => Query);
+
+macro_rules! impl_path_tuple ({ $($ty:ident),+ } => {
+    impl<$($ty: Apiv3Schema,)+> OperationModifier for Path<($($ty,)+)> {
+        fn update_parameter(op: &mut Operation) {
+            $(
+                update_parameters::<$ty>(op, ParameterIn::Path);
+            )+
+        }
+    }
+
+    #[cfg(any(feature = "actix4-validator", feature = "actix3-validator"))]
+    impl<$($ty: Apiv3Schema,)+> OperationModifier for ValidatedPath<($($ty,)+)> {
+        fn update_parameter(op: &mut Operation) {
+            Path::<($($ty,)+)>::update_parameter(op);
+        }
+    }
+});
+
+impl_path_tuple!(A);
+impl_path_tuple!(A, B);
+impl_path_tuple!(A, B, C);
+impl_path_tuple!(A, B, C, D);
+impl_path_tuple!(A, B, C, D, E);
+impl_path_tuple!(A, B, C, D, E, F);
+impl_path_tuple!(A, B, C, D, E, F, G);
+impl_path_tuple!(A, B, C, D, E, F, G, H);
+impl_path_tuple!(A, B, C, D, E, F, G, H, I);
+impl_path_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_path_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_path_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_path_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+
+/// Adds the properties of the given type as parameters. Path parameters are added
+/// without names, because we don't know them in this context. We'll get them when
+/// we add services (using `set_parameter_names_from_path_template`).
+fn update_parameters<T: Apiv3Schema>(op: &mut Operation, location: ParameterIn) {
+    let schema = T::raw_schema();
+    match schema.schema_kind {
+        SchemaKind::Type(Type::Object(object)) => {
+            for (name, property) in object.properties {
+                let required = location == ParameterIn::Path || object.required.contains(&name);
+                let mut property = match property {
+                    ReferenceOr::Item(s) => ReferenceOr::Item(*s),
+                    ReferenceOr::Reference { reference } => ReferenceOr::Reference { reference },
+                };
+                let description = match &mut property {
+                    ReferenceOr::Item(s) => s.schema_data.description.take(),
+                    _ => None,
+                };
+                let name = if location == ParameterIn::Path {
+                    String::new()
+                } else {
+                    name
+                };
+
+                op.parameters.push(ReferenceOr::Item(parameter(
+                    location,
+                    parameter_data(name, description, required, property),
+                )));
+            }
+        }
+        // If it's not an object and if it's a path parameter,
+        // then add a parameter whose name will be overridden later.
+        _ if location == ParameterIn::Path => {
+            let mut schema = schema;
+            let description = schema.schema_data.description.take();
+            op.parameters.push(ReferenceOr::Item(parameter(
+                location,
+                parameter_data(String::new(), description, true, ReferenceOr::Item(schema)),
+            )));
+        }
+        _ => (),
+    }
+}
+
+fn parameter_data(
+    name: String,
+    description: Option<String>,
+    required: bool,
+    schema: ReferenceOr<Schema>,
+) -> ParameterData {
+    ParameterData {
+        name,
+        description,
+        required,
+        deprecated: None,
+        format: ParameterSchemaOrContent::Schema(schema),
+        example: None,
+        examples: Default::default(),
+        explode: None,
+        extensions: Default::default(),
+    }
+}
+
+fn parameter(location: ParameterIn, parameter_data: ParameterData) -> Parameter {
+    match location {
+        ParameterIn::Path => Parameter::Path {
+            parameter_data,
+            style: PathStyle::Simple,
+        },
+        ParameterIn::Query => Parameter::Query {
+            parameter_data,
+            allow_reserved: false,
+            style: QueryStyle::Form,
+            allow_empty_value: None,
+        },
+    }
+}
+
+fn set_request_body(op: &mut Operation, media: &str, schema: ReferenceOr<Schema>, required: bool) {
+    let body = op
+        .request_body
+        .get_or_insert_with(|| ReferenceOr::Item(RequestBody::default()));
+    if let ReferenceOr::Item(body) = body {
+        body.required = required;
+        body.content.insert(
+            media.into(),
+            MediaType {
+                schema: Some(schema),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+fn set_response(op: &mut Operation, status: StatusCode, schema: Option<ReferenceOr<Schema>>) {
+    let mut response = Response {
+        description: status.canonical_reason().unwrap_or_default().into(),
+        ..Default::default()
+    };
+    if let Some(schema) = schema {
+        response.content.insert(
+            v2::SpecFormat::Json.mime().0.to_string(),
+            MediaType {
+                schema: Some(schema),
+                ..Default::default()
+            },
+        );
+    }
+
+    op.responses.responses.insert(
+        openapiv3::StatusCode::Code(status.as_u16()),
+        ReferenceOr::Item(response),
+    );
+}
+
+/* Helpers used by the `api_v3_operation` macro and the derives. */
+
+/// Adds the header parameters of the given (v2) header type to the operation.
+pub fn update_parameter<T: Apiv2Schema>(op: &mut Operation) {
+    for param in T::header_parameter_schema() {
+        let schema = v2::DefaultSchemaRaw {
+            data_type: param.data_type,
+            format: param.format.clone(),
+            maximum: param.maximum,
+            minimum: param.minimum,
+            ..Default::default()
+        };
+        op.parameters.push(ReferenceOr::Item(Parameter::Header {
+            parameter_data: parameter_data(
+                param.name,
+                param.description,
+                param.required,
+                schema.into(),
+            ),
+            style: Default::default(),
+        }));
+    }
+}
+
+/// Adds the security requirement of the given (v2) security type to the operation.
+pub fn update_security<T: Apiv2Schema>(op: &mut Operation) {
+    if let (Some(name), Some(scheme)) = (T::name(), T::security_scheme()) {
+        let scopes = scheme.scopes.keys().cloned().collect();
+        op.security
+            .get_or_insert_with(Vec::new)
+            .push(std::iter::once((name, scopes)).collect());
+    }
+}
+
+/// Merges the security scheme of the given (v2) security type into the map.
+pub fn update_security_schemes<T: Apiv2Schema>(map: &mut BTreeMap<String, v2::SecurityScheme>) {
+    if let (Some(name), Some(new)) = (T::name(), T::security_scheme()) {
+        new.update_definitions(&name, map);
+    }
+}
+
+/// Builds the components of an operation from the collected schemas and security schemes.
+pub fn components(
+    schemas: BTreeMap<String, Schema>,
+    security_schemes: BTreeMap<String, v2::SecurityScheme>,
+) -> Components {
+    Components {
+        schemas: schemas
+            .into_iter()
+            .map(|(k, v)| (k, ReferenceOr::Item(v)))
+            .collect(),
+        security_schemes: security_schemes
+            .into_iter()
+            .map(|(k, v)| (k, ReferenceOr::Item(SecurityScheme::from(v))))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Serves the request body with the given media types (instead of the ones
+/// added by the extractors).
+pub fn set_request_content_types(op: &mut Operation, types: &[&str]) {
+    if let Some(ReferenceOr::Item(body)) = &mut op.request_body {
+        let media = body.content.values().next().cloned().unwrap_or_default();
+        body.content = types
+            .iter()
+            .map(|t| (t.to_string(), media.clone()))
+            .collect();
+    }
+}
+
+/// Serves the responses with the given media types (instead of JSON).
+pub fn set_response_content_types(op: &mut Operation, types: &[&str]) {
+    let responses = op.responses.responses.values_mut();
+    for response in responses.chain(op.responses.default.as_mut()) {
+        if let ReferenceOr::Item(response) = response {
+            if let Some(media) = response.content.values().next().cloned() {
+                response.content = types
+                    .iter()
+                    .map(|t| (t.to_string(), media.clone()))
+                    .collect();
+            }
+        }
+    }
+}
+
+/// Sets the description of the request body (if the operation has one).
+pub fn set_request_body_description(op: &mut Operation, description: &str) {
+    if let Some(ReferenceOr::Item(body)) = &mut op.request_body {
+        body.description = Some(description.into());
+    }
+}
+
+/// Adds a string cookie parameter to the operation.
+pub fn add_cookie_parameter(
+    op: &mut Operation,
+    name: &str,
+    description: Option<&str>,
+    required: bool,
+) {
+    let schema = schema_of(Type::String(StringType::default()));
+    op.parameters.push(ReferenceOr::Item(Parameter::Cookie {
+        parameter_data: parameter_data(
+            name.into(),
+            description.map(Into::into),
+            required,
+            ReferenceOr::Item(schema),
+        ),
+        style: CookieStyle::Form,
+    }));
+}
+
+/// Adds a link (to the operation with the given ID) to the response with the given
+/// status code. Parameters map parameter names to runtime expressions.
+pub fn add_response_link(
+    op: &mut Operation,
+    status: u16,
+    name: &str,
+    operation_id: &str,
+    parameters: &[(&str, &str)],
+) {
+    let response = op
+        .responses
+        .responses
+        .entry(openapiv3::StatusCode::Code(status))
+        .or_insert_with(|| {
+            ReferenceOr::Item(Response {
+                description: StatusCode::from_u16(status)
+                    .ok()
+                    .and_then(|s| s.canonical_reason())
+                    .unwrap_or_default()
+                    .into(),
+                ..Default::default()
+            })
+        });
+
+    if let ReferenceOr::Item(response) = response {
+        response.links.insert(
+            name.into(),
+            ReferenceOr::Item(Link {
+                description: None,
+                operation: LinkOperation::OperationId(operation_id.into()),
+                request_body: None,
+                parameters: parameters
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), serde_json::Value::from(*v)))
+                    .collect(),
+                server: None,
+                extensions: Default::default(),
+            }),
+        );
+    }
+}
+
+/// Overwrites the names of path parameters in this operation using the
+/// given path template.
+pub fn set_parameter_names_from_path_template(op: &mut Operation, path: &str) {
+    let mut names = vec![];
+    v2::DefaultApiRaw::path_parameters_map(path, |name| {
+        let exists = op.parameters.iter().any(|p| match p {
+            ReferenceOr::Item(Parameter::Path { parameter_data, .. }) => {
+                parameter_data.name == name
+            }
+            _ => false,
+        });
+        if !exists {
+            names.push(name.to_owned());
+        }
+
+        ":".into()
+    });
+
+    for p in op.parameters.iter_mut().rev() {
+        if let ReferenceOr::Item(Parameter::Path { parameter_data, .. }) = p {
+            if parameter_data.name.is_empty() {
+                if let Some(n) = names.pop() {
+                    parameter_data.name = n;
+                }
+            }
+        }
+    }
+}
+
+// Wrapper for all response types from `#[api_v3_operation]` handlers. Like its
+// v2 counterpart, this holds the actual value returned by the handler and a unit
+// struct (autogenerated by the plugin) which is used for generating operation
+// information.
+pin_project! {
+    pub struct ResponseWrapper<T, H> {
+        #[pin]
+        pub responder: T,
+        pub operations: H,
+    }
+}
+
+#[cfg(feature = "actix4")]
+impl<T: Responder, H> Responder for ResponseWrapper<T, H> {
+    type Body = T::Body;
+
+    #[inline]
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        self.responder.respond_to(req)
+    }
+}
+
+#[cfg(not(feature = "actix4"))]
+impl<T: Responder, H> Responder for ResponseWrapper<T, H> {
+    type Error = <T as Responder>::Error;
+    type Future = <T as Responder>::Future;
+
+    #[inline]
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        self.responder.respond_to(req)
+    }
+}
+
+impl<F, T, H> Future for ResponseWrapper<F, H>
+where
+    F: Future<Output = T>,
+    T: OperationModifier + Responder,
+    H: Apiv3Operation,
+{
+    type Output = T;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.as_mut().project();
+        this.responder.poll(ctx)
+    }
+}
+
+/// Native operations are also downgraded for the v2 spec, so that
+/// they can be mounted alongside the v2 operations.
+impl<F, T, H> Apiv2Operation for ResponseWrapper<F, H>
+where
+    F: Future<Output = T>,
+    T: OperationModifier + Responder,
+    H: Apiv3Operation,
+{
+    fn operation() -> v2::DefaultOperationRaw {
+        // The constructs which don't exist in v2 are only logged (once per
+        // operation, and not for its definitions), since the v2 spec can't
+        // fail here.
+        let op = H::operation();
+        let id = op.operation_id.clone().unwrap_or_default();
+        match super::operation_to_v2(op, H::components()) {
+            Ok((op, _, _, report)) => {
+                for issue in &report.issues {
+                    log::warn!("Downgrading v3 operation {:?}: {}", id, issue);
+                }
+
+                op
+            }
+            Err(e) => {
+                log::error!("Failed to downgrade v3 operation {:?}: {}", id, e);
+                Default::default()
+            }
+        }
+    }
+
+    fn security_definitions() -> BTreeMap<String, v2::SecurityScheme> {
+        super::operation_to_v2(H::operation(), H::components())
+            .map(|(_, _, security, _)| security)
+            .unwrap_or_default()
+    }
+
+    fn definitions() -> BTreeMap<String, v2::DefaultSchemaRaw> {
+        super::operation_to_v2(H::operation(), H::components())
+            .map(|(_, definitions, _, _)| definitions)
+            .unwrap_or_default()
+    }
+
+    fn is_visible() -> bool {
+        H::is_visible()
+    }
+
+    fn v3_operation() -> Option<(Operation, Components)> {
+        Some((H::operation(), H::components()))
+    }
+}
@@ -0,0 +1,1089 @@
+//! Conversion of OpenAPI v3 documents to v2.
+//!
+//! The conversion works on the serialized v3 document, so that it can follow
+//! (local) references while inlining the objects which don't have a v2
+//! counterpart (request bodies and headers).
+
+use super::report::ConversionReport;
+use crate::v2::{
+    models as v2,
+    pointer::{self, MAX_REF_DEPTH},
+    source_map::json_path,
+};
+use serde_json::{json, Map, Value};
+
+use std::collections::BTreeSet;
+
+const REF_PREFIXES: &[(&str, &str)] = &[
+    ("#/components/schemas/", "#/definitions/"),
+    ("#/components/parameters/", "#/parameters/"),
+    ("#/components/responses/", "#/responses/"),
+];
+
+const FORM_MEDIA_TYPES: &[&str] = &["application/x-www-form-urlencoded", "multipart/form-data"];
+
+/// Keywords of primitive schemas which are allowed in v2 parameters, headers and items.
+const PRIMITIVE_KEYS: &[&str] = &[
+    "type",
+    "format",
+    "default",
+    "enum",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "multipleOf",
+];
+
+/// Schema keywords which don't exist in v2 (they're kept as vendor extensions).
+const V3_ONLY_KEYS: &[&str] = &["writeOnly", "deprecated", "not"];
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch"];
+
+pub(super) fn downgrade(
+    v3: &openapiv3::OpenAPI,
+) -> Result<(v2::DefaultApiRaw, ConversionReport), serde_json::Error> {
+    let doc = serde_json::to_value(v3)?;
+    let mut converter = Downgrade {
+        doc: &doc,
+        report: ConversionReport::default(),
+        dropped_params: BTreeSet::new(),
+        dropped_schemes: BTreeSet::new(),
+    };
+
+    let api = converter.api();
+    Ok((serde_json::from_value(api)?, converter.report))
+}
+
+struct Downgrade<'a> {
+    doc: &'a Value,
+    report: ConversionReport,
+    /// Component parameters which couldn't be converted (references to them are dropped too).
+    dropped_params: BTreeSet<String>,
+    /// Security schemes which couldn't be converted (requirements for them are dropped too).
+    dropped_schemes: BTreeSet<String>,
+}
+
+impl<'a> Downgrade<'a> {
+    fn api(&mut self) -> Value {
+        let doc = self.doc;
+        let mut api = Map::new();
+        api.insert("swagger".into(), "2.0".into());
+        for key in &["info", "tags", "externalDocs"] {
+            if let Some(value) = doc.get(*key) {
+                api.insert((*key).into(), value.clone());
+            }
+        }
+
+        copy_extensions(doc, &mut api);
+        self.servers(&doc["servers"], &mut api);
+
+        let components = &doc["components"];
+        let schemes = self.security_schemes(&components["securitySchemes"]);
+        if !schemes.is_empty() {
+            api.insert("securityDefinitions".into(), Value::Object(schemes));
+        }
+
+        if let Some(security) = doc.get("security") {
+            api.insert("security".into(), self.security(security));
+        }
+
+        let mut definitions = Map::new();
+        for (name, schema) in entries(&components["schemas"]) {
+            let path = json_path("$.components.schemas", name);
+            definitions.insert(name.clone(), self.schema(schema, &path));
+        }
+
+        let mut parameters = Map::new();
+        for (name, param) in entries(&components["parameters"]) {
+            let path = json_path("$.components.parameters", name);
+            match self.parameter(param, &path) {
+                Some(p) => {
+                    parameters.insert(name.clone(), p);
+                }
+                None => {
+                    self.dropped_params.insert(name.clone());
+                }
+            }
+        }
+
+        let mut responses = Map::new();
+        for (name, response) in entries(&components["responses"]) {
+            let path = json_path("$.components.responses", name);
+            responses.insert(name.clone(), self.response(response, &path).0);
+        }
+
+        // Request bodies and headers are inlined wherever they're used.
+        for key in &["examples", "links", "callbacks"] {
+            if entries(&components[*key]).next().is_some() {
+                let path = json_path("$.components", key);
+                self.report.drop(
+                    &path,
+                    format!("`{}` components aren't supported in v2", key),
+                );
+            }
+        }
+
+        let mut paths = Map::new();
+        for (template, item) in entries(&doc["paths"]) {
+            if template.starts_with("x-") {
+                continue;
+            }
+
+            let path = json_path("$.paths", template);
+            if let Some(item) = self.path_item(item, &path) {
+                paths.insert(template.clone(), item);
+            }
+        }
+
+        api.insert("definitions".into(), Value::Object(definitions));
+        api.insert("parameters".into(), Value::Object(parameters));
+        api.insert("responses".into(), Value::Object(responses));
+        api.insert("paths".into(), Value::Object(paths));
+        Value::Object(api)
+    }
+
+    /// Sets the host, base path and schemes based on the servers. v2 has a
+    /// single host and base path, so only the servers matching the first
+    /// one are kept.
+    fn servers(&mut self, servers: &Value, api: &mut Map<String, Value>) {
+        let mut target = None;
+        let mut schemes = BTreeSet::new();
+        for (i, server) in members(servers).enumerate() {
+            let path = format!("$.servers[{}]", i);
+            let url = self.server_url(server, &path);
+            let (scheme, host, base) = split_url(&url);
+            match &target {
+                None => target = Some((host, base)),
+                Some(t) if *t == (host, base) => (),
+                Some(_) => {
+                    self.report.drop(
+                        &path,
+                        format!(
+                            "server {:?} (v2 specs have a single host and base path)",
+                            url
+                        ),
+                    );
+                    continue;
+                }
+            }
+
+            match scheme {
+                Some(s) if ["http", "https", "ws", "wss"].contains(&s.as_str()) => {
+                    schemes.insert(s);
+                }
+                Some(s) => self
+                    .report
+                    .drop(&path, format!("scheme {:?} isn't supported in v2", s)),
+                None => (),
+            }
+        }
+
+        if let Some((host, base)) = target {
+            if let Some(host) = host {
+                api.insert("host".into(), host.into());
+            }
+
+            if let Some(base) = base {
+                api.insert("basePath".into(), base.into());
+            }
+        }
+
+        if !schemes.is_empty() {
+            api.insert(
+                "schemes".into(),
+                schemes.into_iter().map(Value::String).collect(),
+            );
+        }
+    }
+
+    /// Returns the URL of the server with its variables replaced by their defaults.
+    fn server_url(&mut self, server: &Value, path: &str) -> String {
+        let mut url = server["url"].as_str().unwrap_or_default().to_owned();
+        let mut has_variables = false;
+        for (name, var) in entries(&server["variables"]) {
+            let default = var["default"].as_str().unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", name), default);
+            has_variables = true;
+        }
+
+        if has_variables {
+            self.report.approximate(
+                &json_path(path, "variables"),
+                "server variables are replaced by their default values".into(),
+            );
+        }
+
+        url
+    }
+
+    fn security_schemes(&mut self, schemes: &'a Value) -> Map<String, Value> {
+        let mut definitions = Map::new();
+        for (name, scheme) in entries(schemes) {
+            let path = json_path("$.components.securitySchemes", name);
+            match self.security_scheme(self.resolve(scheme), &path) {
+                Some(s) => {
+                    definitions.insert(name.clone(), s);
+                }
+                None => {
+                    self.dropped_schemes.insert(name.clone());
+                }
+            }
+        }
+
+        definitions
+    }
+
+    fn security_scheme(&mut self, scheme: &Value, path: &str) -> Option<Value> {
+        let mut out = Map::new();
+        match scheme["type"].as_str().unwrap_or_default() {
+            "apiKey" => match scheme["in"].as_str() {
+                Some(loc) if loc == "query" || loc == "header" => {
+                    out.insert("type".into(), "apiKey".into());
+                    out.insert("name".into(), scheme["name"].clone());
+                    out.insert("in".into(), loc.into());
+                }
+                _ => {
+                    self.report
+                        .drop(path, "cookie API keys aren't supported in v2".into());
+                    return None;
+                }
+            },
+            "http" => match scheme["scheme"].as_str().map(str::to_lowercase).as_deref() {
+                Some("basic") => {
+                    out.insert("type".into(), "basic".into());
+                }
+                Some("bearer") => {
+                    out.insert("type".into(), "apiKey".into());
+                    out.insert("name".into(), "Authorization".into());
+                    out.insert("in".into(), "header".into());
+                    self.report.approximate(
+                        path,
+                        "bearer authentication is represented as an API key in the `Authorization` header".into(),
+                    );
+                }
+                s => {
+                    self.report.drop(
+                        path,
+                        format!(
+                            "HTTP authentication scheme {:?} isn't supported in v2",
+                            s.unwrap_or_default()
+                        ),
+                    );
+                    return None;
+                }
+            },
+            "oauth2" => {
+                let flows = &scheme["flows"];
+                let mut found = [
+                    ("implicit", "implicit"),
+                    ("password", "password"),
+                    ("clientCredentials", "application"),
+                    ("authorizationCode", "accessCode"),
+                ]
+                .iter()
+                .filter(|(key, _)| flows.get(*key).is_some());
+
+                let &(key, flow) = match found.next() {
+                    Some(f) => f,
+                    None => {
+                        self.report
+                            .drop(path, "OAuth2 scheme doesn't have any flows".into());
+                        return None;
+                    }
+                };
+
+                let others: Vec<_> = found.map(|(k, _)| *k).collect();
+                if !others.is_empty() {
+                    self.report.drop(
+                        &json_path(path, "flows"),
+                        format!(
+                            "v2 schemes have a single flow, so only `{}` is kept (and not {})",
+                            key,
+                            others.join(", ")
+                        ),
+                    );
+                }
+
+                let data = &flows[key];
+                out.insert("type".into(), "oauth2".into());
+                out.insert("flow".into(), flow.into());
+                for url in &["authorizationUrl", "tokenUrl"] {
+                    if let Some(u) = data.get(*url) {
+                        out.insert((*url).into(), u.clone());
+                    }
+                }
+
+                out.insert(
+                    "scopes".into(),
+                    data.get("scopes").cloned().unwrap_or_else(|| json!({})),
+                );
+            }
+            other => {
+                self.report.drop(
+                    path,
+                    format!("`{}` security schemes aren't supported in v2", other),
+                );
+                return None;
+            }
+        }
+
+        if let Some(d) = scheme.get("description") {
+            out.insert("description".into(), d.clone());
+        }
+
+        Some(Value::Object(out))
+    }
+
+    /// Converts security requirements, leaving out the schemes which were dropped.
+    fn security(&self, requirements: &Value) -> Value {
+        members(requirements)
+            .filter_map(|req| {
+                let kept: Map<_, _> = entries(req)
+                    .filter(|(name, _)| !self.dropped_schemes.contains(*name))
+                    .map(|(name, scopes)| (name.clone(), scopes.clone()))
+                    .collect();
+                // An empty requirement means that security is optional.
+                if kept.is_empty() && entries(req).next().is_some() {
+                    None
+                } else {
+                    Some(Value::Object(kept))
+                }
+            })
+            .collect()
+    }
+
+    fn path_item(&mut self, item: &'a Value, path: &str) -> Option<Value> {
+        let item = self.resolve(item);
+        if item.get("$ref").is_some() {
+            self.report.drop(
+                path,
+                "external path item references aren't supported".into(),
+            );
+            return None;
+        }
+
+        let mut out = Map::new();
+        copy_extensions(item, &mut out);
+        for key in &["summary", "description"] {
+            if item.get(*key).is_some() {
+                self.report.drop(
+                    &json_path(path, key),
+                    format!("path items don't have a `{}` in v2", key),
+                );
+            }
+        }
+
+        if members(&item["servers"]).next().is_some() {
+            self.report.drop(
+                &json_path(path, "servers"),
+                "servers for paths aren't supported in v2".into(),
+            );
+        }
+
+        let params = self.parameters(&item["parameters"], &json_path(path, "parameters"));
+        if !params.is_empty() {
+            out.insert("parameters".into(), Value::Array(params));
+        }
+
+        for method in METHODS {
+            if let Some(op) = item.get(*method) {
+                let op = self.operation(op, &json_path(path, method));
+                out.insert((*method).into(), op);
+            }
+        }
+
+        if item.get("trace").is_some() {
+            self.report.drop(
+                &json_path(path, "trace"),
+                "TRACE operations aren't supported in v2".into(),
+            );
+        }
+
+        Some(Value::Object(out))
+    }
+
+    fn operation(&mut self, op: &'a Value, path: &str) -> Value {
+        let mut out = Map::new();
+        for key in &[
+            "tags",
+            "summary",
+            "description",
+            "operationId",
+            "deprecated",
+        ] {
+            if let Some(value) = op.get(*key) {
+                out.insert((*key).into(), value.clone());
+            }
+        }
+
+        copy_extensions(op, &mut out);
+        let mut params = self.parameters(&op["parameters"], &json_path(path, "parameters"));
+        if let Some(body) = op.get("requestBody") {
+            let name = op["x-codegen-request-body-name"].as_str().unwrap_or("body");
+            let (body_params, consumes) =
+                self.request_body(body, name, &json_path(path, "requestBody"));
+            params.extend(body_params);
+            if !consumes.is_empty() {
+                out.insert("consumes".into(), self.media_ranges(consumes, path));
+            }
+        }
+
+        if !params.is_empty() {
+            out.insert("parameters".into(), Value::Array(params));
+        }
+
+        let mut responses = Map::new();
+        let mut produces = vec![];
+        for (code, response) in entries(&op["responses"]) {
+            if code.starts_with("x-") {
+                continue;
+            }
+
+            let resp_path = json_path(&json_path(path, "responses"), code);
+            if code != "default" && code.parse::<u16>().is_err() {
+                self.report.drop(
+                    &resp_path,
+                    format!("status code range {:?} isn't supported in v2", code),
+                );
+                continue;
+            }
+
+            let (response, media) = self.response(response, &resp_path);
+            produces.extend(media);
+            responses.insert(code.clone(), response);
+        }
+
+        out.insert("responses".into(), Value::Object(responses));
+        if !produces.is_empty() {
+            out.insert("produces".into(), self.media_ranges(produces, path));
+        }
+
+        if let Some(security) = op.get("security") {
+            out.insert("security".into(), self.security(security));
+        }
+
+        for (key, what) in &[
+            (
+                "externalDocs",
+                "external docs for operations aren't supported",
+            ),
+            ("callbacks", "callbacks aren't supported in v2"),
+            ("servers", "servers for operations aren't supported in v2"),
+        ] {
+            let present = match op.get(*key) {
+                Some(Value::Array(v)) => !v.is_empty(),
+                Some(Value::Object(v)) => !v.is_empty(),
+                Some(_) => true,
+                None => false,
+            };
+
+            if present {
+                self.report.drop(&json_path(path, key), (*what).into());
+            }
+        }
+
+        Value::Object(out)
+    }
+
+    fn parameters(&mut self, params: &'a Value, path: &str) -> Vec<Value> {
+        members(params)
+            .enumerate()
+            .filter_map(|(i, p)| self.parameter(p, &format!("{}[{}]", path, i)))
+            .collect()
+    }
+
+    fn parameter(&mut self, param: &'a Value, path: &str) -> Option<Value> {
+        if let Some(r) = param.get("$ref").and_then(Value::as_str) {
+            let dropped = pointer::name(r, "#/components/parameters/")
+                .filter(|name| self.dropped_params.contains(name))
+                .is_some();
+            return if dropped {
+                None
+            } else {
+                Some(json!({ "$ref": rewrite_ref(r) }))
+            };
+        }
+
+        let name = param["name"].as_str().unwrap_or_default();
+        let location = param["in"].as_str().unwrap_or_default();
+        if location == "cookie" {
+            self.report.drop(
+                path,
+                format!("cookie parameter {:?} isn't supported in v2", name),
+            );
+            return None;
+        }
+
+        let mut out = Map::new();
+        for key in &["name", "in", "description", "required", "allowEmptyValue"] {
+            if let Some(value) = param.get(*key) {
+                out.insert((*key).into(), value.clone());
+            }
+        }
+
+        copy_extensions(param, &mut out);
+        if param.get("deprecated") == Some(&Value::Bool(true)) {
+            out.insert("x-deprecated".into(), true.into());
+            self.report.approximate(
+                &json_path(path, "deprecated"),
+                "`deprecated` is kept as `x-deprecated`".into(),
+            );
+        }
+
+        if let Some(example) = param.get("example") {
+            out.insert("x-example".into(), example.clone());
+            self.report.approximate(
+                &json_path(path, "example"),
+                "`example` is kept as `x-example`".into(),
+            );
+        }
+
+        if entries(&param["examples"]).next().is_some() {
+            self.report.drop(
+                &json_path(path, "examples"),
+                "parameter examples aren't supported in v2".into(),
+            );
+        }
+
+        let content_path = json_path(path, "content");
+        let schema = match (param.get("schema"), entries(&param["content"]).next()) {
+            (Some(schema), _) => Some((schema, json_path(path, "schema"))),
+            (None, Some((media, content))) => {
+                self.report.approximate(
+                    &content_path,
+                    format!(
+                        "parameter content is represented by the schema for {:?}",
+                        media
+                    ),
+                );
+                content
+                    .get("schema")
+                    .map(|s| (s, json_path(&json_path(&content_path, media), "schema")))
+            }
+            _ => None,
+        };
+
+        match schema {
+            Some((schema, schema_path)) => self.primitive(schema, &schema_path, false, &mut out),
+            None => {
+                out.insert("type".into(), "string".into());
+                self.report.approximate(
+                    path,
+                    "parameter without a schema is represented as a string".into(),
+                );
+            }
+        }
+
+        if out.get("type").and_then(Value::as_str) == Some("array") {
+            let style = param["style"].as_str().unwrap_or(if location == "query" {
+                "form"
+            } else {
+                "simple"
+            });
+            let explode = param["explode"].as_bool().unwrap_or(style == "form");
+            let format = match (style, explode) {
+                ("form", true) => "multi",
+                ("form", false) | ("simple", _) => "csv",
+                ("spaceDelimited", _) => "ssv",
+                ("pipeDelimited", _) => "pipes",
+                (other, _) => {
+                    self.report.approximate(
+                        &json_path(path, "style"),
+                        format!("style {:?} is represented as `csv`", other),
+                    );
+                    "csv"
+                }
+            };
+
+            // `csv` is the default in v2.
+            if format != "csv" {
+                out.insert("collectionFormat".into(), format.into());
+            }
+        }
+
+        Some(Value::Object(out))
+    }
+
+    /// Converts the request body into `body` or `formData` parameters and
+    /// returns them along with the media types for `consumes`. The body
+    /// parameter gets the given name.
+    fn request_body(
+        &mut self,
+        body: &'a Value,
+        name: &str,
+        path: &str,
+    ) -> (Vec<Value>, Vec<String>) {
+        let body = self.resolve(body);
+        let content: Vec<_> = entries(&body["content"]).collect();
+        let (form, other): (Vec<_>, Vec<_>) = content
+            .into_iter()
+            .partition(|(media, _)| FORM_MEDIA_TYPES.iter().any(|f| media.starts_with(f)));
+        let content_path = json_path(path, "content");
+        let required = body.get("required") == Some(&Value::Bool(true));
+
+        if !other.is_empty() {
+            if !form.is_empty() {
+                let media: Vec<_> = form.iter().map(|(m, _)| m.as_str()).collect();
+                self.report.drop(
+                    &content_path,
+                    format!(
+                        "form media types ({}) can't be combined with a body parameter in v2",
+                        media.join(", ")
+                    ),
+                );
+            }
+
+            let (media, chosen) = preferred(&other);
+            self.check_schemas(&other, media, &content_path);
+            let mut param = Map::new();
+            param.insert("name".into(), name.into());
+            param.insert("in".into(), "body".into());
+            if let Some(d) = body.get("description") {
+                param.insert("description".into(), d.clone());
+            }
+
+            if required {
+                param.insert("required".into(), true.into());
+            }
+
+            let schema_path = json_path(&json_path(&content_path, media), "schema");
+            let mut schema = match chosen.get("schema") {
+                Some(s) => self.schema(s, &schema_path),
+                None => json!({}),
+            };
+            if let (Some(example), Value::Object(s)) = (chosen.get("example"), &mut schema) {
+                if !s.contains_key("$ref") {
+                    s.entry("example").or_insert_with(|| example.clone());
+                }
+            }
+
+            param.insert("schema".into(), schema);
+            let consumes = other.iter().map(|(m, _)| (*m).clone()).collect();
+            return (vec![Value::Object(param)], consumes);
+        }
+
+        let (media, chosen) = match form.first() {
+            Some(&c) => c,
+            None => return (vec![], vec![]),
+        };
+
+        self.check_schemas(&form, media, &content_path);
+        let media_path = json_path(&content_path, media);
+        if chosen.get("encoding").is_some() {
+            self.report.drop(
+                &json_path(&media_path, "encoding"),
+                "encoding of form fields isn't supported in v2".into(),
+            );
+        }
+
+        let schema_path = json_path(&media_path, "schema");
+        let schema = self.resolve(&chosen["schema"]);
+        let required_fields: BTreeSet<_> = members(&schema["required"])
+            .filter_map(Value::as_str)
+            .collect();
+        let mut params = vec![];
+        for (name, prop) in entries(&schema["properties"]) {
+            let mut param = Map::new();
+            param.insert("name".into(), name.as_str().into());
+            param.insert("in".into(), "formData".into());
+            if let Some(d) = self.resolve(prop).get("description") {
+                param.insert("description".into(), d.clone());
+            }
+
+            if required_fields.contains(name.as_str()) {
+                param.insert("required".into(), true.into());
+            }
+
+            let prop_path = json_path(&json_path(&schema_path, "properties"), name);
+            self.primitive(prop, &prop_path, true, &mut param);
+            params.push(Value::Object(param));
+        }
+
+        if params.is_empty() {
+            self.report.drop(
+                &schema_path,
+                "form bodies without properties aren't supported in v2".into(),
+            );
+        }
+
+        let consumes = form.iter().map(|(m, _)| (*m).clone()).collect();
+        (params, consumes)
+    }
+
+    /// Converts the response and returns it along with the media types for `produces`.
+    fn response(&mut self, response: &'a Value, path: &str) -> (Value, Vec<String>) {
+        let media_types = |r: &Value| entries(&r["content"]).map(|(m, _)| m.clone()).collect();
+        if let Some(r) = response.get("$ref").and_then(Value::as_str) {
+            let produces = media_types(self.resolve(response));
+            return (json!({ "$ref": rewrite_ref(r) }), produces);
+        }
+
+        let mut out = Map::new();
+        out.insert(
+            "description".into(),
+            response
+                .get("description")
+                .cloned()
+                .unwrap_or_else(|| "".into()),
+        );
+        copy_extensions(response, &mut out);
+
+        let mut headers = Map::new();
+        for (name, header) in entries(&response["headers"]) {
+            let header_path = json_path(&json_path(path, "headers"), name);
+            let header = self.resolve(header);
+            let mut out = Map::new();
+            if let Some(d) = header.get("description") {
+                out.insert("description".into(), d.clone());
+            }
+
+            match header.get("schema") {
+                Some(s) => self.primitive(s, &json_path(&header_path, "schema"), false, &mut out),
+                None => {
+                    out.insert("type".into(), "string".into());
+                    self.report.approximate(
+                        &header_path,
+                        "header without a schema is represented as a string".into(),
+                    );
+                }
+            }
+
+            headers.insert(name.clone(), Value::Object(out));
+        }
+
+        if !headers.is_empty() {
+            out.insert("headers".into(), Value::Object(headers));
+        }
+
+        let content: Vec<_> = entries(&response["content"]).collect();
+        let content_path = json_path(path, "content");
+        if !content.is_empty() {
+            let (media, chosen) = preferred(&content);
+            self.check_schemas(&content, media, &content_path);
+            if let Some(schema) = chosen.get("schema") {
+                let schema_path = json_path(&json_path(&content_path, media), "schema");
+                out.insert("schema".into(), self.schema(schema, &schema_path));
+            }
+        }
+
+        for (media, value) in &content {
+            if value.get("example").is_some() || entries(&value["examples"]).next().is_some() {
+                self.report.drop(
+                    &json_path(&content_path, media),
+                    "response examples aren't supported".into(),
+                );
+            }
+        }
+
+        if entries(&response["links"]).next().is_some() {
+            self.report.drop(
+                &json_path(path, "links"),
+                "links aren't supported in v2".into(),
+            );
+        }
+
+        (Value::Object(out), media_types(response))
+    }
+
+    /// Reports the media types whose schemas differ from that of the chosen
+    /// one (v2 has a single schema for bodies and responses).
+    fn check_schemas(&mut self, content: &[(&String, &Value)], chosen: &str, path: &str) {
+        let schema = content
+            .iter()
+            .find(|(m, _)| *m == chosen)
+            .and_then(|(_, c)| c.get("schema"));
+        let differing: Vec<_> = content
+            .iter()
+            .filter(|(_, c)| c.get("schema") != schema)
+            .map(|(m, _)| m.as_str())
+            .collect();
+        if !differing.is_empty() {
+            self.report.approximate(
+                path,
+                format!(
+                    "only the schema for {} is kept ({} have different schemas)",
+                    chosen,
+                    differing.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Keeps the (valid) media ranges.
+    fn media_ranges(&mut self, media: Vec<String>, path: &str) -> Value {
+        let mut ranges = BTreeSet::new();
+        for m in media {
+            if m.parse::<mime::Mime>().is_ok() {
+                ranges.insert(m);
+            } else {
+                self.report
+                    .drop(path, format!("invalid media type {:?}", m));
+            }
+        }
+
+        ranges.into_iter().map(Value::String).collect()
+    }
+
+    fn schema(&mut self, schema: &'a Value, path: &str) -> Value {
+        let map = match schema.as_object() {
+            Some(m) => m,
+            None => return schema.clone(),
+        };
+
+        if let Some(r) = map.get("$ref").and_then(Value::as_str) {
+            return json!({ "$ref": rewrite_ref(r) });
+        }
+
+        let mut out = Map::new();
+        for (key, value) in map {
+            let child = json_path(path, key);
+            let value = match key.as_str() {
+                "properties" => Value::Object(
+                    entries(value)
+                        .map(|(k, v)| (k.clone(), self.schema(v, &json_path(&child, k))))
+                        .collect(),
+                ),
+                "items" | "additionalProperties" => self.schema(value, &child),
+                "allOf" => members(value)
+                    .enumerate()
+                    .map(|(i, v)| self.schema(v, &format!("{}[{}]", child, i)))
+                    .collect(),
+                "oneOf" | "anyOf" => {
+                    self.union(key, value, &child, &mut out);
+                    continue;
+                }
+                "nullable" => {
+                    if value == &Value::Bool(true) {
+                        out.insert("x-nullable".into(), true.into());
+                    }
+
+                    continue;
+                }
+                "discriminator" => {
+                    out.insert("discriminator".into(), value["propertyName"].clone());
+                    let mapping: Map<_, _> = entries(&value["mapping"])
+                        .map(|(k, v)| {
+                            let target = v.as_str().map(rewrite_ref).unwrap_or_default();
+                            (k.clone(), Value::String(target))
+                        })
+                        .collect();
+                    if !mapping.is_empty() {
+                        out.insert("x-discriminator-mapping".into(), Value::Object(mapping));
+                        self.report.approximate(
+                            &json_path(&child, "mapping"),
+                            "discriminator mapping is kept as `x-discriminator-mapping`".into(),
+                        );
+                    }
+
+                    continue;
+                }
+                k if V3_ONLY_KEYS.contains(&k) => {
+                    if value != &Value::Bool(false) {
+                        let value = if k == "not" {
+                            self.schema(value, &child)
+                        } else {
+                            value.clone()
+                        };
+
+                        out.insert(format!("x-{}", k), value);
+                        self.report
+                            .approximate(&child, format!("`{}` is kept as `x-{}`", k, k));
+                    }
+
+                    continue;
+                }
+                _ => value.clone(),
+            };
+
+            out.insert(key.clone(), value);
+        }
+
+        Value::Object(out)
+    }
+
+    /// v2 doesn't have `oneOf` and `anyOf`, so the (converted) members are kept
+    /// in `x-oneOf` (or `x-anyOf`) and the schema gets the type which is common
+    /// to all members (if any).
+    fn union(&mut self, key: &str, members_: &'a Value, path: &str, out: &mut Map<String, Value>) {
+        let originals: Vec<_> = members(members_).map(|m| self.resolve(m)).collect();
+        let types: BTreeSet<_> = originals.iter().map(|m| schema_type(m)).collect();
+        let common = match types.len() {
+            1 => types.into_iter().next().flatten(),
+            _ => None,
+        };
+
+        let message = match common {
+            Some(ty) => {
+                out.entry("type").or_insert_with(|| ty.into());
+                // Enums of primitive members can be merged.
+                let enums: Vec<_> = originals.iter().map(|m| m.get("enum")).collect();
+                if ty != "object" && ty != "array" && enums.iter().all(Option::is_some) {
+                    let mut variants = vec![];
+                    for v in enums.into_iter().flatten().flat_map(members) {
+                        if !variants.contains(v) {
+                            variants.push(v.clone());
+                        }
+                    }
+
+                    out.insert("enum".into(), Value::Array(variants));
+                }
+
+                format!(
+                    "`{}` is represented as a `{}` schema with `x-{}`",
+                    key, ty, key
+                )
+            }
+            None => format!(
+                "`{}` is represented as an untyped schema with `x-{}`",
+                key, key
+            ),
+        };
+
+        let converted = members(members_)
+            .enumerate()
+            .map(|(i, m)| self.schema(m, &format!("{}[{}]", path, i)))
+            .collect();
+        out.insert(format!("x-{}", key), converted);
+        self.report.approximate(path, message);
+    }
+
+    /// Copies the keywords of the given schema (following references) which
+    /// are allowed in v2 parameters, headers and items.
+    fn primitive(
+        &mut self,
+        schema: &'a Value,
+        path: &str,
+        form: bool,
+        out: &mut Map<String, Value>,
+    ) {
+        let schema = self.resolve(schema);
+        let converted = self.schema(schema, path);
+        let ty = converted.get("type").and_then(Value::as_str);
+        match ty {
+            Some("object") | None => {
+                out.insert("type".into(), "string".into());
+                self.report.approximate(
+                    path,
+                    "non-primitive schemas are represented as strings in v2 parameters and headers"
+                        .into(),
+                );
+                return;
+            }
+            Some("string") if form && converted.get("format") == Some(&json!("binary")) => {
+                out.insert("type".into(), "file".into());
+                return;
+            }
+            _ => (),
+        }
+
+        for key in PRIMITIVE_KEYS {
+            if let Some(value) = converted.get(*key) {
+                out.insert((*key).into(), value.clone());
+            }
+        }
+
+        if ty == Some("array") {
+            let mut items = Map::new();
+            self.primitive(
+                &schema["items"],
+                &json_path(path, "items"),
+                false,
+                &mut items,
+            );
+            out.insert("items".into(), Value::Object(items));
+        }
+    }
+
+    /// Follows local references in the document.
+    fn resolve(&self, mut value: &'a Value) -> &'a Value {
+        for _ in 0..MAX_REF_DEPTH {
+            let target = value
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(pointer::fragment)
+                .and_then(|pointer| self.doc.pointer(&pointer));
+            match target {
+                Some(t) => value = t,
+                None => break,
+            }
+        }
+
+        value
+    }
+}
+
+/// Returns the type of the given (resolved) schema.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type").and_then(Value::as_str) {
+        Some(ty) => Some(ty),
+        None if schema.get("properties").is_some() || schema.get("allOf").is_some() => {
+            Some("object")
+        }
+        None => None,
+    }
+}
+
+/// Picks the JSON media type (if any) or the first one.
+fn preferred<'c>(content: &[(&'c String, &'c Value)]) -> (&'c String, &'c Value) {
+    content
+        .iter()
+        .find(|(m, _)| m.starts_with("application/json"))
+        .copied()
+        .unwrap_or(content[0])
+}
+
+/// Splits a server URL into its scheme, host and base path.
+fn split_url(url: &str) -> (Option<String>, Option<String>, Option<String>) {
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_lowercase()), Some(rest)),
+        None => (None, url.strip_prefix("//")),
+    };
+
+    let (host, path) = match rest {
+        Some(rest) => match rest.find('/') {
+            Some(i) => (Some(rest[..i].to_owned()), &rest[i..]),
+            None => (Some(rest.to_owned()), ""),
+        },
+        None => (None, url),
+    };
+
+    let path = path.trim_matches('/');
+    let base = Some(path)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("/{}", p));
+    (scheme, host.filter(|h| !h.is_empty()), base)
+}
+
+fn rewrite_ref(reference: &str) -> String {
+    REF_PREFIXES
+        .iter()
+        .find_map(|(v3, v2)| {
+            reference
+                .strip_prefix(v3)
+                .map(|name| format!("{}{}", v2, name))
+        })
+        .unwrap_or_else(|| reference.into())
+}
+
+fn copy_extensions(from: &Value, to: &mut Map<String, Value>) {
+    for (key, value) in entries(from) {
+        if key.starts_with("x-") {
+            to.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+fn entries(value: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    value.as_object().into_iter().flatten()
+}
+
+fn members(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
@@ -1,11 +1,121 @@
 #![cfg(feature = "v3")]
-//! Conversion traits and helps functions that help converting openapi v2 types to openapi v3.
-//! For the OpenAPI v3 types the crate `openapiv3` is used.
+//! Conversion traits and helps functions that help converting openapi v2 types to openapi v3
+//! (and back). For the OpenAPI v3 types the crate `openapiv3` is used.
 
 use super::v2::models as v2;
+#[cfg(feature = "actix-base")]
+pub mod actix;
+mod downgrade;
 mod models;
+mod report;
+pub mod schema;
+pub mod v31;
+
+pub use self::report::{ConversionIssue, ConversionLoss, ConversionReport};
+pub use openapiv3;
 
 /// Convert this crates openapi v2 (`DefaultApiRaw`) to `openapiv3::OpenAPI`
 pub fn openapiv2_to_v3(v2: v2::DefaultApiRaw) -> openapiv3::OpenAPI {
     openapiv3::OpenAPI::from(v2)
 }
+
+/// Convert this crates openapi v2 (`DefaultApiRaw`) to `openapiv3::OpenAPI`,
+/// along with a report of the constructs which couldn't be carried across
+/// exactly.
+pub fn openapiv2_to_v3_with_report(
+    v2: v2::DefaultApiRaw,
+) -> (openapiv3::OpenAPI, ConversionReport) {
+    let mut report = ConversionReport::default();
+    let spec = models::api_to_v3(v2, &mut report);
+    (spec, report)
+}
+
+/// Convert `openapiv3::OpenAPI` to this crates openapi v2 (`DefaultApiRaw`).
+///
+/// Constructs which don't exist in v2 are either approximated (usually with
+/// vendor extensions) or dropped, and the returned report lists all of them.
+pub fn openapiv3_to_v2(
+    v3: &openapiv3::OpenAPI,
+) -> Result<(v2::DefaultApiRaw, ConversionReport), serde_json::Error> {
+    downgrade::downgrade(v3)
+}
+
+/// Upgrade `openapiv3::OpenAPI` (v3.0) to an OpenAPI v3.1 document.
+pub fn openapiv3_to_v31(v3: &openapiv3::OpenAPI) -> Result<v31::OpenApi, serde_json::Error> {
+    v31::convert::upgrade(v3)
+}
+
+/// Convert an OpenAPI v3.1 document to `openapiv3::OpenAPI` (v3.0).
+///
+/// Schemas are rewritten for v3.0 (for example, `type: [string, "null"]` becomes
+/// a nullable string), while constructs which don't exist in v3.0 (like webhooks
+/// and `$defs`) are dropped. The returned report lists all of them.
+pub fn openapiv31_to_v3(
+    v31: &v31::OpenApi,
+) -> Result<(openapiv3::OpenAPI, ConversionReport), serde_json::Error> {
+    v31::convert::downgrade(v31)
+}
+
+/// Deserializes an OpenAPI v3.0 or v3.1 document (based on its `openapi` version),
+/// downgrading v3.1 documents to v3.0. The report is empty for v3.0 documents.
+pub fn openapiv3_from_value(
+    doc: serde_json::Value,
+) -> Result<(openapiv3::OpenAPI, ConversionReport), serde_json::Error> {
+    let is_v31 = doc
+        .get("openapi")
+        .and_then(serde_json::Value::as_str)
+        .map(|v| v.starts_with("3.1"))
+        .unwrap_or(false);
+    if is_v31 {
+        openapiv31_to_v3(&serde_json::from_value(doc)?)
+    } else {
+        Ok((serde_json::from_value(doc)?, ConversionReport::default()))
+    }
+}
+
+/// Downgrades a single native v3 operation (along with its components), so that it can
+/// be added to a v2 spec. Returns the operation, its definitions and security definitions,
+/// along with the report of the constructs which couldn't be carried across. Paths in the
+/// report are relative to the operation (`$`), except for the ones in components.
+#[cfg(feature = "actix-base")]
+#[allow(clippy::type_complexity)]
+pub(crate) fn operation_to_v2(
+    op: openapiv3::Operation,
+    components: openapiv3::Components,
+) -> Result<
+    (
+        v2::DefaultOperationRaw,
+        std::collections::BTreeMap<String, v2::DefaultSchemaRaw>,
+        std::collections::BTreeMap<String, v2::SecurityScheme>,
+        ConversionReport,
+    ),
+    serde_json::Error,
+> {
+    const OPERATION_PATH: &str = "$.paths['/'].get";
+    let mut spec = openapiv3::OpenAPI {
+        openapi: "3.0.0".into(),
+        components: Some(components),
+        ..Default::default()
+    };
+    spec.paths.paths.insert(
+        "/".into(),
+        openapiv3::ReferenceOr::Item(openapiv3::PathItem {
+            get: Some(op),
+            ..Default::default()
+        }),
+    );
+
+    let (mut api, mut report) = downgrade::downgrade(&spec)?;
+    for issue in &mut report.issues {
+        if let Some(rest) = issue.path.strip_prefix(OPERATION_PATH) {
+            issue.path = format!("${}", rest);
+        }
+    }
+
+    let op = api
+        .paths
+        .remove("/")
+        .and_then(|mut item| item.methods.remove(&v2::HttpMethod::Get))
+        .unwrap_or_default();
+    Ok((op, api.definitions, api.security_definitions, report))
+}
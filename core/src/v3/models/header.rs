@@ -1,16 +1,74 @@
-use super::v2;
+use super::{items_to_v3, v2, ConversionReport};
+use crate::v2::source_map::json_path;
 
 impl From<v2::Header> for openapiv3::Header {
     fn from(v2: v2::Header) -> Self {
-        openapiv3::Header {
-            description: v2.description,
-            style: Default::default(),
-            required: false,
-            deprecated: None,
-            format: openapiv3::ParameterSchemaOrContent::Content(Default::default()),
-            example: None,
-            extensions: Default::default(),
-            examples: Default::default(),
+        header_to_v3(v2, "$", &mut ConversionReport::default())
+    }
+}
+
+/// Converts the (response) header at the given path, adding the constructs
+/// which can't be carried across to the report.
+pub(super) fn header_to_v3(
+    v2: v2::Header,
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::Header {
+    if v2.collection_format.unwrap_or_default() != v2::CollectionFormat::Csv {
+        report.approximate(
+            &json_path(path, "collectionFormat"),
+            "headers only have the `simple` (csv) style in v3".into(),
+        );
+    }
+
+    let format = match v2.data_type {
+        Some(data_type) => {
+            // Headers have the same keywords as items (along with a default value).
+            let items = v2::Items {
+                data_type: Some(data_type),
+                format: v2.format,
+                items: v2.items.map(Box::new),
+                // (which is the header's own format, and not of nested items)
+                collection_format: None,
+                enum_: v2.enum_,
+                maximum: v2.maximum,
+                exclusive_maximum: v2.exclusive_maximum,
+                minimum: v2.minimum,
+                exclusive_minimum: v2.exclusive_minimum,
+                max_length: v2.max_length,
+                min_length: v2.min_length,
+                pattern: v2.pattern,
+                max_items: v2.max_items,
+                min_items: v2.min_items,
+                unique_items: v2.unique_items,
+                multiple_of: v2.multiple_of,
+            };
+
+            let schema = items_to_v3(items, path, report);
+            openapiv3::ParameterSchemaOrContent::Schema(match schema {
+                openapiv3::ReferenceOr::Reference { reference } => {
+                    openapiv3::ReferenceOr::Reference { reference }
+                }
+                openapiv3::ReferenceOr::Item(mut schema) => {
+                    schema.schema_data.default = v2.default;
+                    openapiv3::ReferenceOr::Item(*schema)
+                }
+            })
         }
+        None => {
+            report.drop(path, "header without a type isn't supported".into());
+            openapiv3::ParameterSchemaOrContent::Content(Default::default())
+        }
+    };
+
+    openapiv3::Header {
+        description: v2.description,
+        style: Default::default(),
+        required: false,
+        deprecated: None,
+        format,
+        example: None,
+        extensions: Default::default(),
+        examples: Default::default(),
     }
 }
@@ -15,7 +15,15 @@ mod security_scheme;
 mod tag;
 
 pub use super::super::v2::{models as v2, models::Either};
+use super::report::ConversionReport;
 
+use header::header_to_v3;
+pub(crate) use openapi::api_to_v3;
+use operation::operation_to_v3;
 use parameter::non_body_parameter_to_v3_parameter;
-use reference::invalid_referenceor;
-use response::OperationEitherResponse;
+use paths::path_item_to_v3;
+use reference::{invalid_referenceor, v3_reference};
+use request_body::parameter_to_v3;
+use response::response_to_v3;
+use schema::{items_to_v3, schema_to_v3, take_flag};
+use security_scheme::security_scheme_to_v3;
@@ -1,59 +1,111 @@
-use super::v2;
+use super::{
+    parameter_to_v3, path_item_to_v3, response_to_v3, schema_to_v3, security_scheme_to_v3, v2,
+    ConversionReport, Either,
+};
+use crate::v2::source_map::json_path;
 
 impl From<v2::DefaultApiRaw> for openapiv3::OpenAPI {
     fn from(v2: v2::DefaultApiRaw) -> Self {
-        let mut spec = openapiv3::OpenAPI {
-            openapi: "3.0.0".into(),
-            tags: v2.tags.iter().cloned().map(From::from).collect(),
-            info: v2.info.clone().into(),
-            servers: openapi3_servers(v2.schemes, v2.host, v2.base_path),
-            external_docs: v2.external_docs.map(From::from),
-            ..Default::default()
-        };
+        api_to_v3(v2, &mut ConversionReport::default())
+    }
+}
+
+/// Converts the spec, adding the constructs which can't be carried across to
+/// the report.
+pub(crate) fn api_to_v3(
+    mut v2: v2::DefaultApiRaw,
+    report: &mut ConversionReport,
+) -> openapiv3::OpenAPI {
+    let mut spec = openapiv3::OpenAPI {
+        openapi: "3.0.0".into(),
+        tags: v2.tags.iter().cloned().map(From::from).collect(),
+        info: v2.info.clone().into(),
+        servers: openapi3_servers(
+            v2.schemes.clone(),
+            v2.host.clone(),
+            v2.base_path.clone(),
+            report,
+        ),
+        external_docs: v2.external_docs.clone().map(From::from),
+        ..Default::default()
+    };
+
+    let mut components = openapiv3::Components::default();
+    for (name, schema) in &v2.definitions {
+        let schema = schema_to_v3(schema.clone(), &json_path("$.definitions", name), report);
+        components.schemas.insert(name.clone(), schema);
+    }
 
-        let mut components = openapiv3::Components::default();
-        for (name, scheme) in v2.security_definitions {
+    for (name, param) in &v2.parameters {
+        let either = parameter_to_v3(param.clone(), &json_path("$.parameters", name), report);
+        if let Either::Left(param) = either {
             components
-                .security_schemes
-                .insert(name, openapiv3::ReferenceOr::Item(scheme.into()));
+                .parameters
+                .insert(name.clone(), openapiv3::ReferenceOr::Item(param));
         }
-        components.responses = v2.responses.iter().fold(Default::default(), |mut i, b| {
-            i.insert(b.0.to_string(), b.1.clone().into());
-            i
-        });
-        spec.extensions = v2
-            .extensions
-            .into_iter()
-            .fold(Default::default(), |mut i, (k, v)| {
-                i.insert(k, v);
-                i
-            });
-        spec.paths = openapiv3::Paths {
-            paths: v2.paths.iter().fold(Default::default(), |mut i, b| {
-                i.insert(
-                    b.0.to_string(),
-                    openapiv3::ReferenceOr::Item(b.1.clone().into()),
-                );
-                i
-            }),
-            ..Default::default()
-        };
+    }
 
-        components.schemas = v2.definitions.iter().fold(Default::default(), |mut i, b| {
-            i.insert(b.0.to_string(), b.1.clone().into());
-            i
-        });
-        spec.components = Some(components);
+    // Responses for all operations go with the media types produced by default.
+    let fake_op = v2::DefaultOperationRaw {
+        produces: Some(v2.produces.clone()).filter(|p| !p.is_empty()),
+        ..Default::default()
+    };
+    for (name, response) in &v2.responses {
+        let response = Either::Right(response.clone());
+        let path = json_path("$.responses", name);
+        components.responses.insert(
+            name.clone(),
+            response_to_v3(&fake_op, &response, &path, report),
+        );
+    }
 
-        spec
+    for (name, scheme) in &v2.security_definitions {
+        let path = json_path("$.securityDefinitions", name);
+        let scheme = security_scheme_to_v3(scheme.clone(), &path, report);
+        components
+            .security_schemes
+            .insert(name.clone(), openapiv3::ReferenceOr::Item(scheme));
     }
+
+    if !v2.security.is_empty() {
+        spec.security = Some(
+            v2.security
+                .iter()
+                .map(|s| {
+                    s.iter()
+                        .map(|(k, v)| (k.to_string(), v.iter().cloned().collect()))
+                        .collect()
+                })
+                .collect(),
+        );
+    }
+
+    let paths = std::mem::take(&mut v2.paths);
+    for (template, item) in paths {
+        let item = path_item_to_v3(item, &v2, &json_path("$.paths", &template), report);
+        spec.paths
+            .paths
+            .insert(template, openapiv3::ReferenceOr::Item(item));
+    }
+
+    spec.extensions = v2.extensions.into_iter().collect();
+    spec.components = Some(components);
+    spec
 }
 
 fn openapi3_servers(
     schemes: std::collections::BTreeSet<v2::OperationProtocol>,
     host: Option<String>,
     base: Option<String>,
+    report: &mut ConversionReport,
 ) -> Vec<openapiv3::Server> {
+    if !schemes.is_empty() && host.is_none() {
+        report.approximate(
+            "$.schemes",
+            "schemes are left out of the server URL (there's no host)".into(),
+        );
+    }
+
     if schemes.is_empty() && host.is_none() && base.is_none() {
         vec![]
     } else if let Some(host) = host {
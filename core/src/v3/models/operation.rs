@@ -1,131 +1,193 @@
-use super::{v2, Either, OperationEitherResponse};
+use super::{parameter_to_v3, response_to_v3, v2, ConversionReport, Either};
+use crate::v2::source_map::json_path;
 
 impl From<v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>> for openapiv3::Operation {
     fn from(v2: v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>) -> Self {
-        let mut request_body: Option<openapiv3::RequestBody> = None;
-        let mut form_data: Option<openapiv3::AnySchema> = None;
-        let v2v = v2.clone();
+        operation_to_v3(v2, &[], "$", &mut ConversionReport::default())
+    }
+}
 
-        let parameters = v2
-            .parameters
-            .iter()
-            .filter_map(|p| match p {
-                Either::Left(reference) => Some(reference.into()),
-                Either::Right(parameter) => {
-                    let either: Either<
-                        openapiv3::Parameter,
-                        Either<openapiv3::RequestBody, Option<openapiv3::Schema>>,
-                    > = parameter.clone().into();
-                    match either {
-                        Either::Right(r) => match r {
-                            Either::Left(l) => {
-                                request_body = Some(l);
-                                None
-                            }
-                            Either::Right(Some(schema)) => {
-                                if let Some(any) = form_data.as_mut() {
-                                    any.properties.insert(
-                                        parameter.name.clone(),
-                                        openapiv3::ReferenceOr::Item(Box::new(schema)),
-                                    );
-                                } else {
-                                    let mut any = openapiv3::AnySchema::default();
-                                    if parameter.required {
-                                        any.required.push(parameter.name.clone());
-                                    }
-                                    any.properties.insert(
-                                        parameter.name.clone(),
-                                        openapiv3::ReferenceOr::Item(Box::new(schema)),
-                                    );
-                                    form_data = Some(any);
-                                }
-                                None
-                            }
-                            Either::Right(None) => None,
-                        },
-                        Either::Left(parameter) => Some(openapiv3::ReferenceOr::Item(parameter)),
-                    }
-                }
-            })
-            .collect();
+/// Converts the operation at the given path, adding the constructs which can't
+/// be carried across to the report.
+///
+/// Parameters are reported at the given paths (by their index), which are
+/// where they're defined (for the ones inherited from path items or globals).
+pub(super) fn operation_to_v3(
+    v2: v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>,
+    param_paths: &[Option<String>],
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::Operation {
+    if !v2.schemes.is_empty() {
+        report.drop(
+            &json_path(path, "schemes"),
+            "schemes for operations aren't supported in v3".into(),
+        );
+    }
+
+    let mut request_body: Option<openapiv3::RequestBody> = None;
+    let mut form_data: Option<openapiv3::AnySchema> = None;
+    let mut form_paths = vec![];
+    // (for the parameters which aren't at a known path)
+    let mut unlocated = ConversionReport::default();
+    let v2v = v2.clone();
 
-        let request_body = if let Some(request_body) = request_body {
-            Some(openapiv3::ReferenceOr::Item(request_body))
-        } else if let Some(form_data) = form_data {
-            let mut request_body = openapiv3::RequestBody::default();
-            match v2.consumes {
-                None => None,
-                Some(consumes) => {
-                    for media in consumes {
-                        request_body.content.insert(media.0.to_string(), {
-                            openapiv3::MediaType {
-                                schema: Some(openapiv3::ReferenceOr::Item(openapiv3::Schema {
-                                    schema_data: Default::default(),
-                                    schema_kind: openapiv3::SchemaKind::Any(form_data.clone()),
-                                })),
+    let parameters = v2
+        .parameters
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, p)| match p {
+            Either::Left(reference) => Some(reference.into()),
+            Either::Right(parameter) => {
+                let param_path = param_paths.get(idx).and_then(Option::as_deref);
+                let either = match param_path {
+                    Some(param_path) => parameter_to_v3(parameter.clone(), param_path, report),
+                    None => parameter_to_v3(parameter.clone(), "$", &mut unlocated),
+                };
+                match either {
+                    Either::Right(r) => match r {
+                        Either::Left(l) => {
+                            request_body = Some(l);
+                            None
+                        }
+                        Either::Right(schema) => {
+                            form_paths.extend(param_path.map(String::from));
+                            let schema = schema?;
+                            let any = form_data.get_or_insert_with(|| openapiv3::AnySchema {
+                                typ: Some("object".into()),
                                 ..Default::default()
+                            });
+                            if parameter.required {
+                                any.required.push(parameter.name.clone());
                             }
-                        });
-                    }
-
-                    Some(openapiv3::ReferenceOr::Item(request_body))
+                            any.properties.insert(
+                                parameter.name.clone(),
+                                openapiv3::ReferenceOr::Item(Box::new(schema)),
+                            );
+                            None
+                        }
+                    },
+                    Either::Left(parameter) => Some(openapiv3::ReferenceOr::Item(parameter)),
                 }
             }
+        })
+        .collect();
+
+    let mut extensions = v2.extensions;
+    let consumes: Vec<String> = v2
+        .consumes
+        .iter()
+        .flatten()
+        .map(|m| m.0.to_string())
+        .collect();
+    let (form_media, body_media): (Vec<_>, Vec<_>) =
+        consumes.into_iter().partition(|m| is_form_media_type(m));
+
+    let request_body = if let Some(mut request_body) = request_body {
+        for form_path in &form_paths {
+            report.drop(
+                form_path,
+                "form parameters can't be combined with a body in v3".into(),
+            );
+        }
+
+        // The body goes with all the (non-form) media types it can be consumed as.
+        if let Some(media) = request_body.content.values().next().cloned() {
+            if !body_media.is_empty() {
+                request_body.content = body_media.into_iter().map(|m| (m, media.clone())).collect();
+            }
+        }
+
+        let name = v2v.parameters.iter().find_map(|p| match p {
+            Either::Right(p) if p.in_ == v2::ParameterIn::Body => Some(&p.name),
+            _ => None,
+        });
+        if let Some(name) = name.filter(|n| *n != "body") {
+            extensions.insert("x-codegen-request-body-name".into(), name.as_str().into());
+        }
+
+        Some(openapiv3::ReferenceOr::Item(request_body))
+    } else if let Some(form_data) = form_data {
+        let mut request_body = openapiv3::RequestBody::default();
+        let media = if !form_media.is_empty() {
+            form_media
+        } else if has_file_parameter(&v2v) {
+            vec!["multipart/form-data".into()]
         } else {
-            None
+            vec!["application/x-www-form-urlencoded".into()]
         };
 
-        openapiv3::Operation {
-            tags: v2.tags,
-            summary: v2.summary,
-            description: v2.description,
-            external_docs: None,
-            operation_id: v2.operation_id,
-            parameters,
-            request_body,
-            responses: openapiv3::Responses {
-                default: None,
-                responses: v2.responses.iter().fold(
-                    openapiv3::Responses::default().responses,
-                    |mut i, (k, v)| {
-                        if let Ok(code) = k.parse::<u16>() {
-                            let code = openapiv3::StatusCode::Code(code);
-                            i.insert(
-                                code,
-                                OperationEitherResponse {
-                                    operation: &v2v,
-                                    response: v,
-                                }
-                                .into(),
-                            );
-                        }
-                        i
-                    },
-                ),
-                ..Default::default()
-            },
-            deprecated: v2.deprecated,
-            security: if v2.security.is_empty() {
-                None
-            } else {
-                Some(
-                    v2.security
-                        .iter()
-                        .map(|s| {
-                            s.iter().fold(
-                                openapiv3::SecurityRequirement::default(),
-                                |mut i, (k, v)| {
-                                    i.insert(k.to_string(), v.clone());
-                                    i
-                                },
-                            )
-                        })
-                        .collect(),
-                )
-            },
-            servers: vec![],
-            extensions: Default::default(),
-            callbacks: Default::default(),
+        for media in media {
+            request_body.content.insert(media, {
+                openapiv3::MediaType {
+                    schema: Some(openapiv3::ReferenceOr::Item(openapiv3::Schema {
+                        schema_data: Default::default(),
+                        schema_kind: openapiv3::SchemaKind::Any(form_data.clone()),
+                    })),
+                    ..Default::default()
+                }
+            });
+        }
+
+        Some(openapiv3::ReferenceOr::Item(request_body))
+    } else {
+        None
+    };
+
+    let responses_path = json_path(path, "responses");
+    let mut responses = openapiv3::Responses::default();
+    for (code, response) in &v2.responses {
+        let response_path = json_path(&responses_path, code);
+        if code == "default" {
+            responses.default = Some(response_to_v3(&v2v, response, &response_path, report));
+        } else if let Ok(status) = code.parse::<u16>() {
+            let response = response_to_v3(&v2v, response, &response_path, report);
+            responses
+                .responses
+                .insert(openapiv3::StatusCode::Code(status), response);
+        } else {
+            report.drop(&response_path, format!("invalid status code {:?}", code));
         }
     }
+
+    openapiv3::Operation {
+        tags: v2.tags,
+        summary: v2.summary,
+        description: v2.description,
+        external_docs: None,
+        operation_id: v2.operation_id,
+        parameters,
+        request_body,
+        responses,
+        deprecated: v2.deprecated,
+        security: v2.security.as_ref().map(|security| {
+            security
+                .iter()
+                .map(|s| {
+                    s.iter().fold(
+                        openapiv3::SecurityRequirement::default(),
+                        |mut i, (k, v)| {
+                            i.insert(k.to_string(), v.clone());
+                            i
+                        },
+                    )
+                })
+                .collect()
+        }),
+        servers: vec![],
+        extensions: extensions.into_iter().collect(),
+        callbacks: Default::default(),
+    }
+}
+
+pub(crate) fn is_form_media_type(media: &str) -> bool {
+    media.starts_with("application/x-www-form-urlencoded")
+        || media.starts_with("multipart/form-data")
+}
+
+fn has_file_parameter(op: &v2::DefaultOperationRaw) -> bool {
+    op.parameters.iter().any(|p| match p {
+        Either::Right(p) => p.data_type == Some(v2::DataType::File),
+        Either::Left(_) => false,
+    })
 }
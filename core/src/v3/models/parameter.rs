@@ -1,9 +1,15 @@
-use super::v2;
+use super::{
+    schema::{integer_limits, items_to_v3},
+    v2, ConversionReport,
+};
+use crate::v2::source_map::json_path;
 
 /// helper function to convert a default raw parameter when we already know it's not part of a body
 pub(crate) fn non_body_parameter_to_v3_parameter(
     form_data: bool,
     v2: &v2::DefaultParameterRaw,
+    path: &str,
+    report: &mut ConversionReport,
 ) -> Option<openapiv3::Schema> {
     match v2.data_type {
         Some(data_type) => {
@@ -23,23 +29,28 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                                         openapiv3::IntegerFormat::Int64,
                                     )
                                 }
+                                v2::DataTypeFormat::Other(format) => {
+                                    openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                                }
                                 other => {
                                     debug_assert!(false, "Invalid data type format: {:?}", other);
                                     openapiv3::VariantOrUnknownOrEmpty::Empty
                                 }
                             },
                         },
-                        multiple_of: v2.multiple_of.map(|v| v as i64),
-                        exclusive_minimum: v2.exclusive_minimum.unwrap_or_default(),
-                        exclusive_maximum: v2.exclusive_maximum.unwrap_or_default(),
-                        minimum: v2.minimum.map(|v| v as i64),
-                        maximum: v2.maximum.map(|v| v as i64),
                         enumeration: v2
                             .enum_
                             .iter()
                             .cloned()
                             .map(|v| serde_json::from_value(v).unwrap_or_default())
                             .collect(),
+                        ..integer_limits(
+                            v2.multiple_of,
+                            (v2.minimum, v2.exclusive_minimum),
+                            (v2.maximum, v2.exclusive_maximum),
+                            path,
+                            report,
+                        )
                     }))
                 }
                 v2::DataType::Number => {
@@ -57,6 +68,9 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                                         openapiv3::NumberFormat::Double {},
                                     )
                                 }
+                                v2::DataTypeFormat::Other(format) => {
+                                    openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                                }
                                 other => {
                                     debug_assert!(false, "Invalid data type format: {:?}", other);
                                     openapiv3::VariantOrUnknownOrEmpty::Empty
@@ -106,12 +120,6 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                                         openapiv3::StringFormat::Password,
                                     )
                                 }
-                                v2::DataTypeFormat::Other => {
-                                    debug_assert!(false, "Invalid data type format: other");
-                                    openapiv3::VariantOrUnknownOrEmpty::Unknown(
-                                        v2::DataTypeFormat::Other.to_string(),
-                                    )
-                                }
                                 others => {
                                     openapiv3::VariantOrUnknownOrEmpty::Unknown(others.to_string())
                                 }
@@ -133,7 +141,9 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                 }
                 v2::DataType::Array => {
                     openapiv3::SchemaKind::Type(openapiv3::Type::Array(openapiv3::ArrayType {
-                        items: v2.items.as_ref().map(|items| items.clone().into()),
+                        items: v2.items.as_ref().map(|items| {
+                            items_to_v3(items.clone(), &json_path(path, "items"), report)
+                        }),
                         min_items: v2.min_items.map(|v| v as usize),
                         max_items: v2.max_items.map(|v| v as usize),
                         unique_items: v2.unique_items,
@@ -141,11 +151,16 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                 }
                 v2::DataType::Object => {
                     // objects comes from the parameter schema which would not trigger this call
+                    report.drop(
+                        path,
+                        "object parameters are only supported in bodies".into(),
+                    );
                     return None;
                 }
                 v2::DataType::File => {
                     if !form_data {
                         // File only usable from formData
+                        report.drop(path, "file parameters are only supported in forms".into());
                         return None;
                     }
                     openapiv3::SchemaKind::Type(openapiv3::Type::String(openapiv3::StringType {
@@ -165,13 +180,20 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                 }
             } else {
                 // properties set on the parameter and not on the schema's properties
-                openapiv3::SchemaData::default()
+                // (except for the default value, which v3 only has in schemas)
+                openapiv3::SchemaData {
+                    default: v2.default.clone(),
+                    ..Default::default()
+                }
             };
             Some(openapiv3::Schema {
                 schema_data,
                 schema_kind,
             })
         }
-        None => None,
+        None => {
+            report.drop(path, "parameter without a type isn't supported".into());
+            None
+        }
     }
 }
@@ -1,34 +1,136 @@
-use super::v2;
+use super::{operation_to_v3, v2, ConversionReport, Either};
+use crate::v2::source_map::json_path;
 use std::collections::BTreeMap;
 
 impl From<v2::DefaultPathItemRaw> for openapiv3::PathItem {
     fn from(v2: v2::DefaultPathItemRaw) -> Self {
-        let methods = v2
-            .methods
-            .iter()
-            .map(|(k, v)| (*k, v.clone().into()))
-            .collect::<BTreeMap<v2::HttpMethod, openapiv3::Operation>>();
-
-        openapiv3::PathItem {
-            get: methods.get(&v2::HttpMethod::Get).cloned(),
-            put: methods.get(&v2::HttpMethod::Put).cloned(),
-            post: methods.get(&v2::HttpMethod::Post).cloned(),
-            delete: methods.get(&v2::HttpMethod::Delete).cloned(),
-            options: methods.get(&v2::HttpMethod::Options).cloned(),
-            head: methods.get(&v2::HttpMethod::Head).cloned(),
-            patch: methods.get(&v2::HttpMethod::Patch).cloned(),
-            trace: None,
-            servers: vec![],
-            parameters: {
-                openapiv3::Operation::from(v2::DefaultOperationRaw {
-                    parameters: v2.parameters,
-                    ..Default::default()
-                })
-                .parameters
-            },
-            extensions: Default::default(),
-            description: None,
-            summary: None,
+        path_item_to_v3(
+            v2,
+            &Default::default(),
+            "$",
+            &mut ConversionReport::default(),
+        )
+    }
+}
+
+/// Converts the path item at the given path, adding the constructs which can't
+/// be carried across to the report.
+///
+/// Operations inherit the global media types, and the body and form parameters
+/// (which v3 doesn't have as components or in path items) are moved into them.
+pub(super) fn path_item_to_v3(
+    v2: v2::DefaultPathItemRaw,
+    api: &v2::DefaultApiRaw,
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::PathItem {
+    let mut moved = vec![];
+    let (mut parameters, mut param_paths) = (vec![], vec![]);
+    let params = own_parameters(
+        v2.parameters,
+        &api.parameters,
+        &json_path(path, "parameters"),
+    );
+    for (param, param_path) in params {
+        match param {
+            Either::Right(param) if is_body_or_form(&param) => moved.push((param, param_path)),
+            param => {
+                parameters.push(param);
+                param_paths.push(param_path);
+            }
         }
     }
+
+    let methods = v2
+        .methods
+        .into_iter()
+        .map(|(method, mut op)| {
+            let op_path = json_path(path, &method.to_string().to_lowercase());
+            let params_path = json_path(&op_path, "parameters");
+            let (mut params, mut paths): (Vec<_>, Vec<_>) =
+                own_parameters(op.parameters, &api.parameters, &params_path)
+                    .into_iter()
+                    .unzip();
+            for (param, param_path) in &moved {
+                let overridden = params.iter().any(|p| match p {
+                    Either::Right(p) => p.name == param.name && p.in_ == param.in_,
+                    _ => false,
+                });
+                if !overridden {
+                    params.push(Either::Right(param.clone()));
+                    paths.push(param_path.clone());
+                }
+            }
+
+            op.parameters = params;
+            if op.consumes.is_none() && !api.consumes.is_empty() {
+                op.consumes = Some(api.consumes.clone());
+            }
+
+            if op.produces.is_none() && !api.produces.is_empty() {
+                op.produces = Some(api.produces.clone());
+            }
+
+            (method, operation_to_v3(op, &paths, &op_path, report))
+        })
+        .collect::<BTreeMap<v2::HttpMethod, openapiv3::Operation>>();
+
+    openapiv3::PathItem {
+        get: methods.get(&v2::HttpMethod::Get).cloned(),
+        put: methods.get(&v2::HttpMethod::Put).cloned(),
+        post: methods.get(&v2::HttpMethod::Post).cloned(),
+        delete: methods.get(&v2::HttpMethod::Delete).cloned(),
+        options: methods.get(&v2::HttpMethod::Options).cloned(),
+        head: methods.get(&v2::HttpMethod::Head).cloned(),
+        patch: methods.get(&v2::HttpMethod::Patch).cloned(),
+        trace: None,
+        servers: vec![],
+        parameters: {
+            let op = v2::DefaultOperationRaw {
+                parameters,
+                ..Default::default()
+            };
+            operation_to_v3(op, &param_paths, path, report).parameters
+        },
+        extensions: v2.extensions.into_iter().collect(),
+        description: None,
+        summary: None,
+    }
+}
+
+/// Replaces references to global body and form parameters with the parameters,
+/// and returns the parameters along with the paths they're defined at.
+fn own_parameters(
+    params: Vec<Either<v2::Reference, v2::DefaultParameterRaw>>,
+    globals: &BTreeMap<String, v2::DefaultParameterRaw>,
+    path: &str,
+) -> Vec<(
+    Either<v2::Reference, v2::DefaultParameterRaw>,
+    Option<String>,
+)> {
+    params
+        .into_iter()
+        .enumerate()
+        .map(|(i, param)| match param {
+            Either::Left(r) => {
+                let global = r
+                    .reference
+                    .strip_prefix("#/parameters/")
+                    .and_then(|name| Some(name).zip(globals.get(name)))
+                    .filter(|(_, p)| is_body_or_form(p));
+                match global {
+                    Some((name, global)) => (
+                        Either::Right(global.clone()),
+                        Some(json_path("$.parameters", name)),
+                    ),
+                    None => (Either::Left(r), None),
+                }
+            }
+            Either::Right(p) => (Either::Right(p), Some(format!("{}[{}]", path, i))),
+        })
+        .collect()
+}
+
+fn is_body_or_form(param: &v2::DefaultParameterRaw) -> bool {
+    param.in_ == v2::ParameterIn::Body || param.in_ == v2::ParameterIn::FormData
 }
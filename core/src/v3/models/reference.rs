@@ -13,7 +13,23 @@ impl<T> From<v2::Reference> for openapiv3::ReferenceOr<T> {
 }
 impl<T> From<&v2::Reference> for openapiv3::ReferenceOr<T> {
     fn from(v2: &v2::Reference) -> Self {
-        let reference = v2.reference.replace("definitions", "components/schemas");
-        openapiv3::ReferenceOr::ref_(&reference)
+        openapiv3::ReferenceOr::ref_(&v3_reference(&v2.reference))
     }
 }
+
+/// Maps a reference to a v2 definition, parameter or response to its v3 component.
+pub(crate) fn v3_reference(reference: &str) -> String {
+    let (document, fragment) = reference.split_at(reference.find('#').unwrap_or(0));
+    [
+        ("#/definitions/", "#/components/schemas/"),
+        ("#/parameters/", "#/components/parameters/"),
+        ("#/responses/", "#/components/responses/"),
+    ]
+    .iter()
+    .find_map(|(v2, v3)| {
+        fragment
+            .strip_prefix(v2)
+            .map(|name| format!("{}{}{}", document, v3, name))
+    })
+    .unwrap_or_else(|| reference.into())
+}
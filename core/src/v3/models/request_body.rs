@@ -1,61 +1,138 @@
-use super::{invalid_referenceor, non_body_parameter_to_v3_parameter, v2, Either};
+use super::{
+    invalid_referenceor, non_body_parameter_to_v3_parameter, schema_to_v3, take_flag, v2,
+    ConversionReport, Either,
+};
+use crate::v2::source_map::json_path;
 
-impl From<v2::DefaultParameterRaw>
-    for Either<openapiv3::Parameter, Either<openapiv3::RequestBody, Option<openapiv3::Schema>>>
-{
+/// A converted parameter: either a v3 parameter, or a request body (for body
+/// parameters) or the schema of a form field (for form parameters).
+pub(super) type ParameterOrBody =
+    Either<openapiv3::Parameter, Either<openapiv3::RequestBody, Option<openapiv3::Schema>>>;
+
+impl From<v2::DefaultParameterRaw> for ParameterOrBody {
     fn from(v2: v2::DefaultParameterRaw) -> Self {
-        let parameter_data = |schema: Option<openapiv3::Schema>| openapiv3::ParameterData {
-            name: v2.name.clone(),
-            description: v2.description.clone(),
-            required: v2.required,
-            deprecated: None,
-            format: match &schema {
-                Some(schema) => openapiv3::ParameterSchemaOrContent::Schema(
-                    openapiv3::ReferenceOr::Item(schema.clone()),
+        parameter_to_v3(v2, "$", &mut ConversionReport::default())
+    }
+}
+
+/// Converts the parameter at the given path, adding the constructs which
+/// can't be carried across to the report.
+pub(super) fn parameter_to_v3(
+    mut v2: v2::DefaultParameterRaw,
+    path: &str,
+    report: &mut ConversionReport,
+) -> ParameterOrBody {
+    let has_extensions = !v2.extensions.is_empty();
+    let mut extensions = std::mem::take(&mut v2.extensions);
+    let example = extensions.remove("x-example");
+    let deprecated = Some(take_flag(&mut extensions, "x-deprecated")).filter(|d| *d);
+    let is_array = v2.data_type == Some(v2::DataType::Array);
+    let parameter_data = |schema: Option<openapiv3::Schema>| openapiv3::ParameterData {
+        name: v2.name.clone(),
+        description: v2.description.clone(),
+        required: v2.required,
+        deprecated,
+        format: match &schema {
+            Some(schema) => openapiv3::ParameterSchemaOrContent::Schema(
+                openapiv3::ReferenceOr::Item(schema.clone()),
+            ),
+            None => openapiv3::ParameterSchemaOrContent::Schema(invalid_referenceor(format!(
+                "No Schema found: {:?}",
+                v2
+            ))),
+        },
+        example: example.clone(),
+        examples: Default::default(),
+        // `csv` (the default in v2) doesn't explode arrays in query.
+        explode: match v2.collection_format {
+            None | Some(v2::CollectionFormat::Csv) | Some(v2::CollectionFormat::Tsv)
+                if is_array && v2.in_ == v2::ParameterIn::Query =>
+            {
+                Some(false)
+            }
+            _ => None,
+        },
+        extensions: extensions.clone().into_iter().collect(),
+    };
+
+    if let Some(format) = v2.collection_format {
+        let supported = match v2.in_ {
+            v2::ParameterIn::Body => true,
+            v2::ParameterIn::Query => format != v2::CollectionFormat::Tsv,
+            // Form fields are exploded by default.
+            v2::ParameterIn::FormData => format == v2::CollectionFormat::Multi,
+            _ => format == v2::CollectionFormat::Csv,
+        };
+
+        if !supported {
+            report.approximate(
+                &json_path(path, "collectionFormat"),
+                format!(
+                    "collection format `{}` is represented by the default style in v3",
+                    format!("{:?}", format).to_lowercase()
                 ),
-                None => openapiv3::ParameterSchemaOrContent::Schema(invalid_referenceor(format!(
-                    "No Schema found: {:?}",
-                    v2
-                ))),
+            );
+        }
+    }
+
+    match v2.in_ {
+        v2::ParameterIn::Query => Either::Left(openapiv3::Parameter::Query {
+            parameter_data: parameter_data(non_body_parameter_to_v3_parameter(
+                false, &v2, path, report,
+            )),
+            allow_reserved: false,
+            style: match v2.collection_format {
+                Some(v2::CollectionFormat::Ssv) => openapiv3::QueryStyle::SpaceDelimited,
+                Some(v2::CollectionFormat::Pipes) => openapiv3::QueryStyle::PipeDelimited,
+                _ => openapiv3::QueryStyle::Form,
             },
-            example: None,
-            examples: Default::default(),
-            explode: None,
-            extensions: Default::default(),
-        };
+            allow_empty_value: Some(v2.allow_empty_value).filter(|a| *a),
+        }),
+        v2::ParameterIn::Header => Either::Left(openapiv3::Parameter::Header {
+            parameter_data: parameter_data(non_body_parameter_to_v3_parameter(
+                false, &v2, path, report,
+            )),
+            style: Default::default(),
+        }),
+        v2::ParameterIn::Path => Either::Left(openapiv3::Parameter::Path {
+            parameter_data: parameter_data(non_body_parameter_to_v3_parameter(
+                false, &v2, path, report,
+            )),
+            style: Default::default(),
+        }),
+        v2::ParameterIn::FormData => {
+            if has_extensions {
+                report.drop(
+                    path,
+                    "extensions of form parameters aren't supported in v3".into(),
+                );
+            }
 
-        match v2.in_ {
-            v2::ParameterIn::Query => Either::Left(openapiv3::Parameter::Query {
-                parameter_data: parameter_data(non_body_parameter_to_v3_parameter(false, &v2)),
-                allow_reserved: false,
-                style: Default::default(),
-                allow_empty_value: None,
-            }),
-            v2::ParameterIn::Header => Either::Left(openapiv3::Parameter::Header {
-                parameter_data: parameter_data(non_body_parameter_to_v3_parameter(false, &v2)),
-                style: Default::default(),
-            }),
-            v2::ParameterIn::Path => Either::Left(openapiv3::Parameter::Path {
-                parameter_data: parameter_data(non_body_parameter_to_v3_parameter(false, &v2)),
-                style: Default::default(),
-            }),
-            v2::ParameterIn::FormData => {
-                Either::Right(Either::Right(non_body_parameter_to_v3_parameter(true, &v2)))
+            if v2.allow_empty_value {
+                report.drop(
+                    &json_path(path, "allowEmptyValue"),
+                    "empty values of form parameters aren't supported in v3".into(),
+                );
             }
-            v2::ParameterIn::Body => Either::Right(Either::Left(openapiv3::RequestBody {
-                description: v2.description,
-                content: {
-                    let media = openapiv3::MediaType {
-                        schema: v2.schema.map(|s| s.into()),
-                        ..Default::default()
-                    };
-                    let mut map = openapiv3::RequestBody::default().content;
-                    map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
-                    map
-                },
-                required: v2.required,
-                extensions: Default::default(),
-            })),
+
+            let schema = non_body_parameter_to_v3_parameter(true, &v2, path, report);
+            Either::Right(Either::Right(schema))
         }
+        v2::ParameterIn::Body => Either::Right(Either::Left(openapiv3::RequestBody {
+            description: v2.description,
+            content: {
+                let media = openapiv3::MediaType {
+                    schema: v2
+                        .schema
+                        .map(|s| schema_to_v3(s, &json_path(path, "schema"), report)),
+                    ..Default::default()
+                };
+                let mut map = openapiv3::RequestBody::default().content;
+                map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
+                map
+            },
+            required: v2.required,
+            extensions: extensions.into_iter().collect(),
+        })),
     }
 }
@@ -1,109 +1,105 @@
-use super::{v2, Either};
+use super::{header_to_v3, schema_to_v3, v2, ConversionReport, Either};
+use crate::v2::source_map::json_path;
 
-struct OperationResponse<'a> {
-    operation: &'a v2::DefaultOperationRaw,
-    response: &'a v2::DefaultResponseRaw,
-}
-
-impl From<OperationResponse<'_>> for openapiv3::Response {
-    fn from(v2: OperationResponse<'_>) -> Self {
-        openapiv3::Response {
-            description: v2.response.description.clone().unwrap_or_default(),
-            headers: v2
-                .response
+/// Converts the response (of the given operation) at the given path, adding
+/// the constructs which can't be carried across to the report.
+fn operation_response(
+    operation: &v2::DefaultOperationRaw,
+    response: &v2::DefaultResponseRaw,
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::Response {
+    openapiv3::Response {
+        description: response.description.clone().unwrap_or_default(),
+        headers: {
+            let path = json_path(path, "headers");
+            response
                 .headers
                 .iter()
                 .fold(Default::default(), |mut i, b| {
-                    i.insert(
-                        b.0.to_string(),
-                        openapiv3::ReferenceOr::Item(b.1.clone().into()),
-                    );
+                    let header = header_to_v3(b.1.clone(), &json_path(&path, b.0), report);
+                    i.insert(b.0.to_string(), openapiv3::ReferenceOr::Item(header));
                     i
-                }),
-            content: {
-                match v2.response.schema.clone() {
-                    Some(response) => {
-                        let is_file = v2
-                            .response
-                            .schema
-                            .as_ref()
-                            .and_then(|s| s.data_type.map(|d| d == v2::DataType::File))
-                            .unwrap_or_default();
-                        let media = openapiv3::MediaType {
-                            schema: Some(response.into()),
-                            ..Default::default()
-                        };
+                })
+        },
+        content: {
+            match response.schema.clone() {
+                Some(schema) => {
+                    let is_file = schema.data_type == Some(v2::DataType::File);
+                    let media = openapiv3::MediaType {
+                        schema: Some(schema_to_v3(schema, &json_path(path, "schema"), report)),
+                        ..Default::default()
+                    };
 
-                        let mut map = openapiv3::Response::default().content;
-                        match v2.operation.produces.as_ref() {
-                            Some(range) => {
-                                for mime in range {
-                                    map.insert(mime.0.to_string(), media.clone());
-                                }
+                    let mut map = openapiv3::Response::default().content;
+                    match operation.produces.as_ref() {
+                        Some(range) => {
+                            for mime in range {
+                                map.insert(mime.0.to_string(), media.clone());
                             }
-                            None => {
-                                if is_file {
-                                    // perhaps we should be conservative and use "*/*" instead?
-                                    map.insert("multipart/form-data".to_string(), media);
-                                } else {
-                                    map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
-                                }
+                        }
+                        None => {
+                            if is_file {
+                                // perhaps we should be conservative and use "*/*" instead?
+                                map.insert("multipart/form-data".to_string(), media);
+                            } else {
+                                map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
                             }
                         }
-                        map
                     }
-                    None => Default::default(),
+                    map
                 }
-            },
-            extensions: Default::default(),
-            links: Default::default(),
-        }
+                None => Default::default(),
+            }
+        },
+        extensions: response.extensions.clone().into_iter().collect(),
+        links: Default::default(),
     }
 }
 
-pub(crate) struct OperationEitherResponse<'a> {
-    pub(crate) operation: &'a v2::DefaultOperationRaw,
-    pub(crate) response: &'a Either<v2::Reference, v2::DefaultResponseRaw>,
-}
-
-impl From<OperationEitherResponse<'_>> for openapiv3::ReferenceOr<openapiv3::Response> {
-    fn from(v2: OperationEitherResponse<'_>) -> Self {
-        match v2.response {
-            Either::Left(reference) => {
-                let response = openapiv3::Response {
-                    description: "".to_string(),
-                    headers: Default::default(),
-                    content: {
-                        let media = openapiv3::MediaType {
-                            schema: Some(reference.into()),
-                            ..Default::default()
-                        };
-                        let mut map = openapiv3::Response::default().content;
-                        match v2.operation.produces.as_ref() {
-                            Some(range) => {
-                                for mime in range {
-                                    map.insert(mime.0.to_string(), media.clone());
-                                }
-                            }
-                            None => {
-                                // perhaps we should be conservative and use "*/*" instead?
-                                map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
+/// Converts the response (or the reference to one) of the given operation at
+/// the given path, adding the constructs which can't be carried across to the
+/// report.
+pub(super) fn response_to_v3(
+    operation: &v2::DefaultOperationRaw,
+    response: &Either<v2::Reference, v2::DefaultResponseRaw>,
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::ReferenceOr<openapiv3::Response> {
+    match response {
+        Either::Left(reference) if reference.reference.starts_with("#/responses/") => {
+            reference.into()
+        }
+        Either::Left(reference) => {
+            let response = openapiv3::Response {
+                description: "".to_string(),
+                headers: Default::default(),
+                content: {
+                    let media = openapiv3::MediaType {
+                        schema: Some(reference.into()),
+                        ..Default::default()
+                    };
+                    let mut map = openapiv3::Response::default().content;
+                    match operation.produces.as_ref() {
+                        Some(range) => {
+                            for mime in range {
+                                map.insert(mime.0.to_string(), media.clone());
                             }
                         }
-                        map
-                    },
-                    links: Default::default(),
-                    extensions: Default::default(),
-                };
-                openapiv3::ReferenceOr::Item(response)
-            }
-            Either::Right(response) => {
-                let response = OperationResponse {
-                    operation: v2.operation,
-                    response,
-                };
-                openapiv3::ReferenceOr::Item(response.into())
-            }
+                        None => {
+                            // perhaps we should be conservative and use "*/*" instead?
+                            map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
+                        }
+                    }
+                    map
+                },
+                links: Default::default(),
+                extensions: Default::default(),
+            };
+            openapiv3::ReferenceOr::Item(response)
+        }
+        Either::Right(response) => {
+            openapiv3::ReferenceOr::Item(operation_response(operation, response, path, report))
         }
     }
 }
@@ -111,10 +107,7 @@ impl From<OperationEitherResponse<'_>> for openapiv3::ReferenceOr<openapiv3::Res
 impl From<v2::DefaultResponseRaw> for openapiv3::ReferenceOr<openapiv3::Response> {
     fn from(v2: v2::DefaultResponseRaw) -> Self {
         let fake_op = v2::DefaultOperationRaw::default();
-        let response = OperationResponse {
-            operation: &fake_op,
-            response: &v2,
-        };
-        openapiv3::ReferenceOr::Item(response.into())
+        let response = operation_response(&fake_op, &v2, "$", &mut ConversionReport::default());
+        openapiv3::ReferenceOr::Item(response)
     }
 }
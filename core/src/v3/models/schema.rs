@@ -1,74 +1,249 @@
-use crate::v2::models::Either;
+use crate::v2::{models::Either, source_map::json_path};
 
-use super::{invalid_referenceor, v2};
-use std::ops::Deref;
+use super::{invalid_referenceor, v2, v3_reference, ConversionReport};
+use std::{collections::BTreeMap, ops::Deref};
 
 impl From<v2::DefaultSchemaRaw> for openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
     fn from(v2: v2::DefaultSchemaRaw) -> Self {
-        let x: openapiv3::ReferenceOr<openapiv3::Schema> = v2.into();
-        match x {
-            openapiv3::ReferenceOr::Reference { reference } => {
-                openapiv3::ReferenceOr::Reference { reference }
-            }
-            openapiv3::ReferenceOr::Item(item) => openapiv3::ReferenceOr::Item(Box::new(item)),
-        }
+        boxed(schema_to_v3(v2, "$", &mut ConversionReport::default()))
     }
 }
 
 impl From<v2::DefaultSchemaRaw> for openapiv3::ReferenceOr<openapiv3::Schema> {
     fn from(v2: v2::DefaultSchemaRaw) -> Self {
-        match v2.reference.clone() {
-            Some(reference) => v2::Reference { reference }.into(),
-            None => {
-                let item = openapiv3::Schema {
-                    schema_data: openapiv3::SchemaData {
-                        nullable: false,
-                        read_only: false,
-                        write_only: false,
-                        deprecated: false,
-                        external_docs: None,
-                        example: v2.example,
-                        title: v2.title,
-                        description: v2.description,
-                        discriminator: None,
-                        default: None,
-                        extensions: Default::default(),
-                    },
-                    schema_kind: {
-                        if let Some(data_type) = v2.data_type {
-                            v2_data_type_to_v3(
-                                &data_type,
-                                &v2.format,
-                                &v2.enum_,
-                                &v2.items,
-                                &v2.properties,
-                                &v2.extra_props,
-                                &v2.required,
-                            )
-                        } else {
-                            openapiv3::SchemaKind::Type(openapiv3::Type::Object(
-                                openapiv3::ObjectType::default(),
-                            ))
-                        }
-                    },
-                };
-                openapiv3::ReferenceOr::Item(item)
-            }
+        schema_to_v3(v2, "$", &mut ConversionReport::default())
+    }
+}
+
+fn boxed(
+    schema: openapiv3::ReferenceOr<openapiv3::Schema>,
+) -> openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
+    match schema {
+        openapiv3::ReferenceOr::Reference { reference } => {
+            openapiv3::ReferenceOr::Reference { reference }
+        }
+        openapiv3::ReferenceOr::Item(item) => openapiv3::ReferenceOr::Item(Box::new(item)),
+    }
+}
+
+/// Converts the schema at the given path, adding the constructs which can't
+/// be carried across to the report.
+pub(super) fn schema_to_v3(
+    mut v2: v2::DefaultSchemaRaw,
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::ReferenceOr<openapiv3::Schema> {
+    if let Some(reference) = v2.reference.clone() {
+        return v2::Reference { reference }.into();
+    }
+
+    let mut extensions = std::mem::take(&mut v2.extensions);
+    let all_of = std::mem::take(&mut v2.all_of);
+    let mut schema_kind = match v2.data_type {
+        Some(data_type) => v2_data_type_to_v3(&data_type, &v2, path, report),
+        // Properties imply an object.
+        None if !v2.properties.is_empty()
+            || !v2.required.is_empty()
+            || v2.extra_props.is_some() =>
+        {
+            v2_data_type_to_v3(&v2::DataType::Object, &v2, path, report)
+        }
+        None => openapiv3::SchemaKind::Any(Default::default()),
+    };
+
+    if !all_of.is_empty() {
+        // Members go as-is and the schema's own keywords (if any)
+        // are added as an additional inline member.
+        let all_of_path = json_path(path, "allOf");
+        let mut members: Vec<openapiv3::ReferenceOr<openapiv3::Schema>> = all_of
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| schema_to_v3(*s, &format!("{}[{}]", all_of_path, i), report))
+            .collect();
+        if v2.data_type.is_some() || !v2.properties.is_empty() {
+            members.push(openapiv3::ReferenceOr::Item(openapiv3::Schema {
+                schema_data: Default::default(),
+                schema_kind,
+            }));
         }
+
+        schema_kind = openapiv3::SchemaKind::AllOf { all_of: members };
+    }
+
+    // Compositions which v2 doesn't have are kept in extensions
+    // (like the ones written when downgrading v3 specs).
+    if let Some(one_of) = take_schemas(&mut extensions, "x-oneOf", path, report) {
+        schema_kind = openapiv3::SchemaKind::OneOf { one_of };
+    } else if let Some(any_of) = take_schemas(&mut extensions, "x-anyOf", path, report) {
+        schema_kind = openapiv3::SchemaKind::AnyOf { any_of };
+    } else if let Some(not) = take_schema(&mut extensions, "x-not", path, report) {
+        schema_kind = openapiv3::SchemaKind::Not { not: Box::new(not) };
+    }
+
+    let mapping = match extensions.remove("x-discriminator-mapping") {
+        Some(serde_json::Value::Object(mapping)) => mapping
+            .into_iter()
+            .filter_map(|(k, v)| v.as_str().map(|v| (k, v3_reference(v))))
+            .collect(),
+        _ => Default::default(),
+    };
+
+    let mut item = openapiv3::Schema {
+        schema_data: openapiv3::SchemaData {
+            nullable: take_flag(&mut extensions, "x-nullable"),
+            read_only: v2.read_only.unwrap_or_default(),
+            write_only: take_flag(&mut extensions, "x-writeOnly"),
+            deprecated: take_flag(&mut extensions, "x-deprecated"),
+            external_docs: v2.external_docs.map(From::from),
+            example: v2.example,
+            title: v2.title,
+            description: v2.description,
+            discriminator: v2
+                .discriminator
+                .map(|property_name| openapiv3::Discriminator {
+                    property_name,
+                    mapping,
+                    ..Default::default()
+                }),
+            default: v2.default,
+            extensions: extensions.into_iter().collect(),
+        },
+        schema_kind,
+    };
+
+    // `openapiv3` doesn't model the XML object, so it goes along
+    // with the (flattened) extensions.
+    if let Some(xml) = v2.xml {
+        item.schema_data.extensions.insert(
+            "xml".into(),
+            serde_json::to_value(xml).expect("serializing XML object"),
+        );
+    }
+
+    openapiv3::ReferenceOr::Item(item)
+}
+
+/// Removes the given boolean extension and returns whether it was set.
+pub(crate) fn take_flag(extensions: &mut BTreeMap<String, serde_json::Value>, key: &str) -> bool {
+    extensions.remove(key) == Some(serde_json::Value::Bool(true))
+}
+
+/// Returns the limits of a v3 integer schema, which can only have integer limits.
+/// Fractional bounds are rounded inwards (which keeps the same integers in range)
+/// and a fractional `multipleOf` is replaced by its smallest integral multiple.
+pub(super) fn integer_limits(
+    multiple_of: Option<f32>,
+    (minimum, exclusive_minimum): (Option<f32>, Option<bool>),
+    (maximum, exclusive_maximum): (Option<f32>, Option<bool>),
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::IntegerType {
+    let (minimum, exclusive_minimum) = integer_bound(minimum, exclusive_minimum, f32::ceil);
+    let (maximum, exclusive_maximum) = integer_bound(maximum, exclusive_maximum, f32::floor);
+    openapiv3::IntegerType {
+        multiple_of: multiple_of.and_then(|m| integral_multiple(m, path, report)),
+        exclusive_minimum,
+        exclusive_maximum,
+        minimum,
+        maximum,
+        ..Default::default()
+    }
+}
+
+fn integer_bound(
+    bound: Option<f32>,
+    exclusive: Option<bool>,
+    round: fn(f32) -> f32,
+) -> (Option<i64>, bool) {
+    match bound {
+        // Integers beyond a fractional bound don't depend on whether it's exclusive.
+        Some(b) if b.fract() != 0.0 => (Some(round(b) as i64), false),
+        b => (b.map(|b| b as i64), exclusive.unwrap_or_default()),
     }
 }
 
+/// Returns the smallest integral multiple of the given `multipleOf` (if it's
+/// within a thousand multiples), since integers which are multiples of both
+/// are the same. Fractional ones are reported (as approximated or dropped).
+fn integral_multiple(multiple_of: f32, path: &str, report: &mut ConversionReport) -> Option<i64> {
+    let multiple = (1..=1000)
+        .map(|k| multiple_of * k as f32)
+        .find(|v| v.fract() == 0.0)
+        .map(|v| v as i64);
+    if multiple_of.fract() == 0.0 {
+        return multiple;
+    }
+
+    let path = json_path(path, "multipleOf");
+    match multiple {
+        Some(v) => report.approximate(
+            &path,
+            format!(
+                "fractional `multipleOf` is replaced by its smallest integral multiple ({}) for integers",
+                v
+            ),
+        ),
+        None => report.drop(
+            &path,
+            "fractional `multipleOf` isn't supported for integers".into(),
+        ),
+    }
+
+    multiple
+}
+
+/// Removes the given extension if it's an array of (v2) schemas, and returns them.
+fn take_schemas(
+    extensions: &mut BTreeMap<String, serde_json::Value>,
+    key: &str,
+    path: &str,
+    report: &mut ConversionReport,
+) -> Option<Vec<openapiv3::ReferenceOr<openapiv3::Schema>>> {
+    let value = extensions.get(key)?.clone();
+    let members: Vec<v2::DefaultSchemaRaw> = serde_json::from_value(value).ok()?;
+    if members.is_empty() {
+        return None;
+    }
+
+    extensions.remove(key);
+    let path = json_path(path, key);
+    Some(
+        members
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| schema_to_v3(s, &format!("{}[{}]", path, i), report))
+            .collect(),
+    )
+}
+
+/// Removes the given extension if it's a (v2) schema, and returns it.
+fn take_schema(
+    extensions: &mut BTreeMap<String, serde_json::Value>,
+    key: &str,
+    path: &str,
+    report: &mut ConversionReport,
+) -> Option<openapiv3::ReferenceOr<openapiv3::Schema>> {
+    let value = extensions.get(key)?.clone();
+    let schema: v2::DefaultSchemaRaw = serde_json::from_value(value).ok()?;
+    extensions.remove(key);
+    Some(schema_to_v3(schema, &json_path(path, key), report))
+}
+
 // helper function to convert a v2 DataType to v3, with explicit types making it more
 // rust-analyzer friendly as the DefaultSchemaRaw is autogenerated by a macro
 fn v2_data_type_to_v3(
     data_type: &v2::DataType,
-    format: &Option<v2::DataTypeFormat>,
-    enum_: &[serde_json::Value],
-    items: &Option<Box<v2::DefaultSchemaRaw>>,
-    properties: &std::collections::BTreeMap<String, Box<v2::DefaultSchemaRaw>>,
-    extra_properties: &Option<Either<bool, Box<v2::DefaultSchemaRaw>>>,
-    required: &std::collections::BTreeSet<String>,
+    v2: &v2::DefaultSchemaRaw,
+    path: &str,
+    report: &mut ConversionReport,
 ) -> openapiv3::SchemaKind {
+    let format: &Option<v2::DataTypeFormat> = &v2.format;
+    let enum_: &[serde_json::Value] = &v2.enum_;
+    let items: &Option<Box<v2::DefaultSchemaRaw>> = &v2.items;
+    let properties: &std::collections::BTreeMap<String, Box<v2::DefaultSchemaRaw>> = &v2.properties;
+    let extra_properties: &Option<Either<bool, Box<v2::DefaultSchemaRaw>>> = &v2.extra_props;
+    let required: &std::collections::BTreeSet<String> = &v2.required;
+
     match data_type {
         v2::DataType::Integer => {
             openapiv3::SchemaKind::Type(openapiv3::Type::Integer(openapiv3::IntegerType {
@@ -81,22 +256,27 @@ fn v2_data_type_to_v3(
                         v2::DataTypeFormat::Int64 => openapiv3::VariantOrUnknownOrEmpty::Item(
                             openapiv3::IntegerFormat::Int64,
                         ),
+                        v2::DataTypeFormat::Other(format) => {
+                            openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                        }
                         other => {
                             debug_assert!(false, "Invalid data type format: {:?}", other);
                             openapiv3::VariantOrUnknownOrEmpty::Empty
                         }
                     },
                 },
-                multiple_of: None,
-                exclusive_minimum: false,
-                exclusive_maximum: false,
-                minimum: None,
-                maximum: None,
                 enumeration: enum_
                     .iter()
                     .cloned()
                     .map(|v| serde_json::from_value(v).unwrap_or_default())
                     .collect(),
+                ..integer_limits(
+                    v2.multiple_of,
+                    (v2.minimum, v2.exclusive_minimum),
+                    (v2.maximum, v2.exclusive_maximum),
+                    path,
+                    report,
+                )
             }))
         }
         v2::DataType::Number => {
@@ -110,17 +290,20 @@ fn v2_data_type_to_v3(
                         v2::DataTypeFormat::Double => openapiv3::VariantOrUnknownOrEmpty::Item(
                             openapiv3::NumberFormat::Double {},
                         ),
+                        v2::DataTypeFormat::Other(format) => {
+                            openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                        }
                         other => {
                             debug_assert!(false, "Invalid data type format: {:?}", other);
                             openapiv3::VariantOrUnknownOrEmpty::Empty
                         }
                     },
                 },
-                multiple_of: None,
-                exclusive_minimum: false,
-                exclusive_maximum: false,
-                minimum: None,
-                maximum: None,
+                multiple_of: v2.multiple_of.map(From::from),
+                exclusive_minimum: v2.exclusive_minimum.unwrap_or_default(),
+                exclusive_maximum: v2.exclusive_maximum.unwrap_or_default(),
+                minimum: v2.minimum.map(From::from),
+                maximum: v2.maximum.map(From::from),
                 enumeration: enum_
                     .iter()
                     .cloned()
@@ -148,23 +331,17 @@ fn v2_data_type_to_v3(
                         v2::DataTypeFormat::Password => openapiv3::VariantOrUnknownOrEmpty::Item(
                             openapiv3::StringFormat::Password,
                         ),
-                        v2::DataTypeFormat::Other => {
-                            debug_assert!(false, "Invalid data type format: other");
-                            openapiv3::VariantOrUnknownOrEmpty::Unknown(
-                                v2::DataTypeFormat::Other.to_string(),
-                            )
-                        }
                         others => openapiv3::VariantOrUnknownOrEmpty::Unknown(others.to_string()),
                     },
                 },
-                pattern: None,
+                pattern: v2.pattern.clone(),
                 enumeration: enum_
                     .iter()
                     .cloned()
                     .map(|v| serde_json::from_value(v).unwrap_or_default())
                     .collect(),
-                min_length: None,
-                max_length: None,
+                min_length: v2.min_length.map(|v| v as usize),
+                max_length: v2.max_length.map(|v| v as usize),
             }))
         }
         v2::DataType::Boolean => {
@@ -172,29 +349,42 @@ fn v2_data_type_to_v3(
         }
         v2::DataType::Array => {
             openapiv3::SchemaKind::Type(openapiv3::Type::Array(openapiv3::ArrayType {
-                items: items.as_ref().map(|items| items.deref().clone().into()),
-                min_items: None,
-                max_items: None,
-                unique_items: false,
+                items: items.as_ref().map(|items| {
+                    boxed(schema_to_v3(
+                        items.deref().clone(),
+                        &json_path(path, "items"),
+                        report,
+                    ))
+                }),
+                min_items: v2.min_items.map(|v| v as usize),
+                max_items: v2.max_items.map(|v| v as usize),
+                unique_items: v2.unique_items.unwrap_or_default(),
             }))
         }
         v2::DataType::Object => {
             openapiv3::SchemaKind::Type(openapiv3::Type::Object(openapiv3::ObjectType {
                 properties: {
+                    let path = json_path(path, "properties");
                     properties.iter().fold(Default::default(), |mut i, b| {
-                        i.insert(b.0.to_string(), b.1.deref().clone().into());
+                        let schema =
+                            schema_to_v3(b.1.deref().clone(), &json_path(&path, b.0), report);
+                        i.insert(b.0.to_string(), boxed(schema));
                         i
                     })
                 },
                 required: required.iter().cloned().collect::<Vec<_>>(),
                 additional_properties: extra_properties.as_ref().map(|e| match e {
-                    Either::Right(box_schema) => openapiv3::AdditionalProperties::Schema(Box::new(
-                        box_schema.deref().clone().into(),
-                    )),
+                    Either::Right(box_schema) => {
+                        openapiv3::AdditionalProperties::Schema(Box::new(schema_to_v3(
+                            box_schema.deref().clone(),
+                            &json_path(path, "additionalProperties"),
+                            report,
+                        )))
+                    }
                     Either::Left(v) => openapiv3::AdditionalProperties::Any(*v),
                 }),
-                min_properties: None,
-                max_properties: None,
+                min_properties: v2.min_properties.map(|v| v as usize),
+                max_properties: v2.max_properties.map(|v| v as usize),
             }))
         }
         v2::DataType::File => {
@@ -208,152 +398,168 @@ fn v2_data_type_to_v3(
 
 impl From<v2::Items> for openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
     fn from(v2: v2::Items) -> Self {
-        let kind = match v2.data_type {
-            None => {
-                return invalid_referenceor("Invalid Item, should have a data type".into());
-            }
-            Some(data_type) => match data_type {
-                v2::DataType::Integer => {
-                    openapiv3::SchemaKind::Type(openapiv3::Type::Integer(openapiv3::IntegerType {
-                        format: match &v2.format {
-                            None => openapiv3::VariantOrUnknownOrEmpty::Empty,
-                            Some(format) => match format {
-                                v2::DataTypeFormat::Int32 => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::IntegerFormat::Int32,
-                                    )
-                                }
-                                v2::DataTypeFormat::Int64 => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::IntegerFormat::Int64,
-                                    )
-                                }
-                                other => {
-                                    return invalid_referenceor(format!(
-                                        "Invalid data type format: {:?}",
-                                        other
-                                    ));
-                                }
-                            },
+        items_to_v3(v2, "$", &mut ConversionReport::default())
+    }
+}
+
+/// Converts the items (of a parameter or a header) at the given path, adding
+/// the constructs which can't be carried across to the report.
+pub(super) fn items_to_v3(
+    v2: v2::Items,
+    path: &str,
+    report: &mut ConversionReport,
+) -> openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
+    if v2.collection_format.is_some() {
+        report.drop(
+            &json_path(path, "collectionFormat"),
+            "collection format of nested items isn't supported in v3".into(),
+        );
+    }
+
+    let kind = match v2.data_type {
+        None => {
+            return invalid_referenceor("Invalid Item, should have a data type".into());
+        }
+        Some(data_type) => match data_type {
+            v2::DataType::Integer => {
+                openapiv3::SchemaKind::Type(openapiv3::Type::Integer(openapiv3::IntegerType {
+                    format: match &v2.format {
+                        None => openapiv3::VariantOrUnknownOrEmpty::Empty,
+                        Some(format) => match format {
+                            v2::DataTypeFormat::Int32 => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::IntegerFormat::Int32,
+                            ),
+                            v2::DataTypeFormat::Int64 => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::IntegerFormat::Int64,
+                            ),
+                            v2::DataTypeFormat::Other(format) => {
+                                openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                            }
+                            other => {
+                                return invalid_referenceor(format!(
+                                    "Invalid data type format: {:?}",
+                                    other
+                                ));
+                            }
                         },
-                        multiple_of: v2.multiple_of.map(|v| v as i64),
-                        exclusive_minimum: v2.exclusive_minimum.unwrap_or_default(),
-                        exclusive_maximum: v2.exclusive_maximum.unwrap_or_default(),
-                        minimum: v2.minimum.map(|v| v as i64),
-                        maximum: v2.maximum.map(|v| v as i64),
-                        enumeration: v2
-                            .enum_
-                            .iter()
-                            .cloned()
-                            .map(|v| serde_json::from_value(v).unwrap_or_default())
-                            .collect(),
-                    }))
-                }
-                v2::DataType::Number => {
-                    openapiv3::SchemaKind::Type(openapiv3::Type::Number(openapiv3::NumberType {
-                        format: match &v2.format {
-                            None => openapiv3::VariantOrUnknownOrEmpty::Empty,
-                            Some(format) => match format {
-                                v2::DataTypeFormat::Float => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::NumberFormat::Float {},
-                                    )
-                                }
-                                v2::DataTypeFormat::Double => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::NumberFormat::Double {},
-                                    )
-                                }
-                                other => {
-                                    return invalid_referenceor(format!(
-                                        "Invalid data type format: {:?}",
-                                        other
-                                    ));
-                                }
-                            },
+                    },
+                    enumeration: v2
+                        .enum_
+                        .iter()
+                        .cloned()
+                        .map(|v| serde_json::from_value(v).unwrap_or_default())
+                        .collect(),
+                    ..integer_limits(
+                        v2.multiple_of,
+                        (v2.minimum, v2.exclusive_minimum),
+                        (v2.maximum, v2.exclusive_maximum),
+                        path,
+                        report,
+                    )
+                }))
+            }
+            v2::DataType::Number => {
+                openapiv3::SchemaKind::Type(openapiv3::Type::Number(openapiv3::NumberType {
+                    format: match &v2.format {
+                        None => openapiv3::VariantOrUnknownOrEmpty::Empty,
+                        Some(format) => match format {
+                            v2::DataTypeFormat::Float => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::NumberFormat::Float {},
+                            ),
+                            v2::DataTypeFormat::Double => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::NumberFormat::Double {},
+                            ),
+                            v2::DataTypeFormat::Other(format) => {
+                                openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                            }
+                            other => {
+                                return invalid_referenceor(format!(
+                                    "Invalid data type format: {:?}",
+                                    other
+                                ));
+                            }
                         },
-                        multiple_of: v2.multiple_of.map(From::from),
-                        exclusive_minimum: v2.exclusive_minimum.unwrap_or_default(),
-                        exclusive_maximum: v2.exclusive_maximum.unwrap_or_default(),
-                        minimum: v2.minimum.map(From::from),
-                        maximum: v2.maximum.map(From::from),
-                        enumeration: v2
-                            .enum_
-                            .iter()
-                            .cloned()
-                            .map(|v| serde_json::from_value(v).unwrap_or_default())
-                            .collect(),
-                    }))
-                }
-                v2::DataType::String => {
-                    openapiv3::SchemaKind::Type(openapiv3::Type::String(openapiv3::StringType {
-                        format: match &v2.format {
-                            None => openapiv3::VariantOrUnknownOrEmpty::Empty,
-                            Some(format) => match format {
-                                v2::DataTypeFormat::Byte => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::StringFormat::Byte,
-                                    )
-                                }
-                                v2::DataTypeFormat::Binary => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::StringFormat::Binary,
-                                    )
-                                }
-                                v2::DataTypeFormat::Date => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::StringFormat::Date,
-                                    )
-                                }
-                                v2::DataTypeFormat::DateTime => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::StringFormat::DateTime,
-                                    )
-                                }
-                                v2::DataTypeFormat::Password => {
-                                    openapiv3::VariantOrUnknownOrEmpty::Item(
-                                        openapiv3::StringFormat::Password,
-                                    )
-                                }
-                                other => {
-                                    return invalid_referenceor(format!(
-                                        "Invalid data type format: {:?}",
-                                        other
-                                    ));
-                                }
-                            },
+                    },
+                    multiple_of: v2.multiple_of.map(From::from),
+                    exclusive_minimum: v2.exclusive_minimum.unwrap_or_default(),
+                    exclusive_maximum: v2.exclusive_maximum.unwrap_or_default(),
+                    minimum: v2.minimum.map(From::from),
+                    maximum: v2.maximum.map(From::from),
+                    enumeration: v2
+                        .enum_
+                        .iter()
+                        .cloned()
+                        .map(|v| serde_json::from_value(v).unwrap_or_default())
+                        .collect(),
+                }))
+            }
+            v2::DataType::String => {
+                openapiv3::SchemaKind::Type(openapiv3::Type::String(openapiv3::StringType {
+                    format: match &v2.format {
+                        None => openapiv3::VariantOrUnknownOrEmpty::Empty,
+                        Some(format) => match format {
+                            v2::DataTypeFormat::Byte => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::StringFormat::Byte,
+                            ),
+                            v2::DataTypeFormat::Binary => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::StringFormat::Binary,
+                            ),
+                            v2::DataTypeFormat::Date => openapiv3::VariantOrUnknownOrEmpty::Item(
+                                openapiv3::StringFormat::Date,
+                            ),
+                            v2::DataTypeFormat::DateTime => {
+                                openapiv3::VariantOrUnknownOrEmpty::Item(
+                                    openapiv3::StringFormat::DateTime,
+                                )
+                            }
+                            v2::DataTypeFormat::Password => {
+                                openapiv3::VariantOrUnknownOrEmpty::Item(
+                                    openapiv3::StringFormat::Password,
+                                )
+                            }
+                            v2::DataTypeFormat::Other(format) => {
+                                openapiv3::VariantOrUnknownOrEmpty::Unknown(format.clone())
+                            }
+                            other => {
+                                return invalid_referenceor(format!(
+                                    "Invalid data type format: {:?}",
+                                    other
+                                ));
+                            }
                         },
-                        pattern: v2.pattern.clone(),
-                        enumeration: v2
-                            .enum_
-                            .iter()
-                            .cloned()
-                            .map(|v| serde_json::from_value(v).unwrap_or_default())
-                            .collect(),
-                        min_length: v2.min_length.map(|v| v as usize),
-                        max_length: v2.max_length.map(|v| v as usize),
-                    }))
-                }
-                v2::DataType::Boolean => {
-                    openapiv3::SchemaKind::Type(openapiv3::Type::Boolean(Default::default()))
-                }
-                v2::DataType::Array => {
-                    openapiv3::SchemaKind::Type(openapiv3::Type::Array(openapiv3::ArrayType {
-                        items: v2.items.map(|items| items.deref().clone().into()),
-                        min_items: v2.min_items.map(|v| v as usize),
-                        max_items: v2.max_items.map(|v| v as usize),
-                        unique_items: v2.unique_items.unwrap_or_default(),
-                    }))
-                }
-                invalid => {
-                    return invalid_referenceor(format!("Invalid Item data_type: {:?}", invalid))
-                }
-            },
-        };
+                    },
+                    pattern: v2.pattern.clone(),
+                    enumeration: v2
+                        .enum_
+                        .iter()
+                        .cloned()
+                        .map(|v| serde_json::from_value(v).unwrap_or_default())
+                        .collect(),
+                    min_length: v2.min_length.map(|v| v as usize),
+                    max_length: v2.max_length.map(|v| v as usize),
+                }))
+            }
+            v2::DataType::Boolean => {
+                openapiv3::SchemaKind::Type(openapiv3::Type::Boolean(Default::default()))
+            }
+            v2::DataType::Array => {
+                openapiv3::SchemaKind::Type(openapiv3::Type::Array(openapiv3::ArrayType {
+                    items: v2
+                        .items
+                        .map(|items| items_to_v3(*items, &json_path(path, "items"), report)),
+                    min_items: v2.min_items.map(|v| v as usize),
+                    max_items: v2.max_items.map(|v| v as usize),
+                    unique_items: v2.unique_items.unwrap_or_default(),
+                }))
+            }
+            invalid => {
+                return invalid_referenceor(format!("Invalid Item data_type: {:?}", invalid))
+            }
+        },
+    };
 
-        openapiv3::ReferenceOr::Item(Box::new(openapiv3::Schema {
-            schema_data: Default::default(),
-            schema_kind: kind,
-        }))
-    }
+    openapiv3::ReferenceOr::Item(Box::new(openapiv3::Schema {
+        schema_data: Default::default(),
+        schema_kind: kind,
+    }))
 }
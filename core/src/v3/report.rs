@@ -0,0 +1,87 @@
+use serde::Serialize;
+
+use std::fmt;
+
+/// Constructs which couldn't be carried across (exactly) in a conversion
+/// between OpenAPI versions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ConversionReport {
+    pub issues: Vec<ConversionIssue>,
+}
+
+impl ConversionReport {
+    /// Whether all constructs were carried across as-is.
+    pub fn is_lossless(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues for constructs which were dropped.
+    pub fn dropped(&self) -> impl Iterator<Item = &ConversionIssue> + '_ {
+        self.issues
+            .iter()
+            .filter(|i| i.kind == ConversionLoss::Dropped)
+    }
+
+    /// Issues for constructs which were approximated.
+    pub fn approximated(&self) -> impl Iterator<Item = &ConversionIssue> + '_ {
+        self.issues
+            .iter()
+            .filter(|i| i.kind == ConversionLoss::Approximated)
+    }
+
+    pub(crate) fn approximate(&mut self, path: &str, message: String) {
+        self.push(ConversionLoss::Approximated, path, message);
+    }
+
+    pub(crate) fn drop(&mut self, path: &str, message: String) {
+        self.push(ConversionLoss::Dropped, path, message);
+    }
+
+    fn push(&mut self, kind: ConversionLoss, path: &str, message: String) {
+        let issue = ConversionIssue {
+            kind,
+            path: path.into(),
+            message,
+        };
+
+        // Constructs which are converted more than once (like the parameters
+        // inherited by operations) are reported once.
+        if !self.issues.contains(&issue) {
+            self.issues.push(issue);
+        }
+    }
+}
+
+/// A construct which couldn't be carried across (exactly).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ConversionIssue {
+    /// What happened to the construct.
+    pub kind: ConversionLoss,
+    /// JSON path of the construct in the source document (for example,
+    /// `$.paths['/pets'].get.requestBody`).
+    pub path: String,
+    /// Human readable description of this issue.
+    pub message: String,
+}
+
+impl fmt::Display for ConversionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ConversionLoss::Approximated => "approximated",
+            ConversionLoss::Dropped => "dropped",
+        };
+
+        write!(f, "{}: {} (at {})", kind, self.message, self.path)
+    }
+}
+
+/// What happened to a construct in a conversion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversionLoss {
+    /// The construct is represented by something close to it (usually
+    /// along with vendor extensions).
+    Approximated,
+    /// The construct was left out.
+    Dropped,
+}
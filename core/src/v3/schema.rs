@@ -0,0 +1,357 @@
+//! Traits used for generating native OpenAPI v3 specs.
+
+use crate::v2::schema::{Apiv2Schema, TypedData};
+use openapiv3::{
+    AdditionalProperties, AnySchema, ArrayType, Components, Discriminator, ObjectType, Operation,
+    ReferenceOr, Schema, SchemaData, SchemaKind, StringType, Type,
+};
+
+use std::collections::BTreeMap;
+
+/// Represents a OpenAPI v3 schema object convertible. This is auto-implemented by the
+/// [`Apiv3Schema`](https://paperclip-rs.github.io/paperclip/paperclip_actix/derive.Apiv3Schema.html)
+/// derive macro, and it's implemented for primitive types by default.
+///
+/// Unlike `Apiv2Schema`, named schemas are never inlined. Other schemas refer to
+/// them through `#/components/schemas/` and `update_schemas` collects them.
+pub trait Apiv3Schema {
+    /// Name of this schema. This is the name to which the schema is mapped in the components.
+    fn name() -> Option<String> {
+        None
+    }
+
+    /// Description of this schema. In case the trait is derived, uses the documentation on the type.
+    fn description() -> &'static str {
+        ""
+    }
+
+    /// Indicates the requirement of this schema.
+    fn required() -> bool {
+        true
+    }
+
+    /// Returns the raw schema for this object.
+    fn raw_schema() -> Schema {
+        any_schema()
+    }
+
+    /// Returns the reference to this schema if it's named, or the schema itself otherwise.
+    fn schema_with_ref() -> ReferenceOr<Schema> {
+        match Self::name() {
+            Some(name) => ReferenceOr::ref_(&schema_reference(&name)),
+            None => ReferenceOr::Item(Self::raw_schema()),
+        }
+    }
+
+    /// Adds this schema (if it's named) and the named schemas it refers to, to the given map.
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        if let Some(name) = Self::name() {
+            map.entry(name).or_insert_with(Self::raw_schema);
+        }
+    }
+}
+
+/// Represents a OpenAPI v3 operation convertible. This is auto-implemented by
+/// [`paperclip_actix::api_v3_operation`](https://paperclip-rs.github.io/paperclip/paperclip_actix/attr.api_v3_operation.html).
+pub trait Apiv3Operation {
+    /// Returns the definition for this operation.
+    fn operation() -> Operation;
+
+    /// Returns the schemas and security schemes used by this operation.
+    fn components() -> Components;
+
+    fn is_visible() -> bool {
+        true
+    }
+}
+
+/// Returns the reference to the component schema with the given name.
+pub fn schema_reference(name: &str) -> String {
+    String::from("#/components/schemas/") + &name.replace('<', "%3C").replace('>', "%3E")
+}
+
+/// Returns a schema which accepts anything.
+pub fn any_schema() -> Schema {
+    Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::Any(AnySchema::default()),
+    }
+}
+
+/// Returns a schema of the given kind.
+pub fn schema_of(kind: Type) -> Schema {
+    Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::Type(kind),
+    }
+}
+
+/// Marks the given schema as nullable. References can't have siblings in v3.0,
+/// so they're wrapped in an `allOf`.
+pub fn nullable(schema: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    match schema {
+        ReferenceOr::Item(mut schema) => {
+            schema.schema_data.nullable = true;
+            ReferenceOr::Item(schema)
+        }
+        reference => ReferenceOr::Item(Schema {
+            schema_data: SchemaData {
+                nullable: true,
+                ..Default::default()
+            },
+            schema_kind: SchemaKind::AllOf {
+                all_of: vec![reference],
+            },
+        }),
+    }
+}
+
+/// Boxes the given schema (for properties and items).
+pub fn boxed(schema: ReferenceOr<Schema>) -> ReferenceOr<Box<Schema>> {
+    match schema {
+        ReferenceOr::Item(schema) => ReferenceOr::Item(Box::new(schema)),
+        ReferenceOr::Reference { reference } => ReferenceOr::Reference { reference },
+    }
+}
+
+/// Returns an object schema without any properties.
+pub fn object_schema() -> Schema {
+    schema_of(Type::Object(ObjectType::default()))
+}
+
+/// Returns a string schema which only allows the given values.
+pub fn string_enum(values: &[&str]) -> Schema {
+    schema_of(Type::String(StringType {
+        enumeration: values.iter().map(|v| Some(v.to_string())).collect(),
+        ..Default::default()
+    }))
+}
+
+/// Returns a schema which matches exactly one of the given schemas.
+pub fn one_of(schemas: Vec<ReferenceOr<Schema>>) -> Schema {
+    Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::OneOf { one_of: schemas },
+    }
+}
+
+/// Returns a schema which matches all of the given schemas.
+pub fn all_of(schemas: Vec<ReferenceOr<Schema>>) -> Schema {
+    Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::AllOf { all_of: schemas },
+    }
+}
+
+/// Adds a property to the given object schema (ignored for other schemas).
+pub fn insert_property(
+    schema: &mut Schema,
+    name: &str,
+    property: ReferenceOr<Schema>,
+    required: bool,
+) {
+    if let SchemaKind::Type(Type::Object(object)) = &mut schema.schema_kind {
+        object.properties.insert(name.into(), boxed(property));
+        if required {
+            object.required.push(name.into());
+        }
+    }
+}
+
+/// Adds the properties of `other` to the given object schema (for flattened fields).
+pub fn extend_properties(schema: &mut Schema, other: Schema, required: bool) {
+    if let (SchemaKind::Type(Type::Object(object)), SchemaKind::Type(Type::Object(other))) =
+        (&mut schema.schema_kind, other.schema_kind)
+    {
+        object.properties.extend(other.properties);
+        if required {
+            object.required.extend(other.required);
+        }
+    }
+}
+
+/// Sets the description and example of the given schema. References can't
+/// have siblings in v3.0, so they're wrapped in an `allOf` when needed.
+pub fn describe(
+    schema: ReferenceOr<Schema>,
+    description: &str,
+    example: Option<serde_json::Value>,
+) -> ReferenceOr<Schema> {
+    if description.is_empty() && example.is_none() {
+        return schema;
+    }
+
+    let mut schema = match schema {
+        ReferenceOr::Item(schema) => schema,
+        reference => all_of(vec![reference]),
+    };
+    if !description.is_empty() {
+        schema.schema_data.description = Some(description.into());
+    }
+    if example.is_some() {
+        schema.schema_data.example = example;
+    }
+
+    ReferenceOr::Item(schema)
+}
+
+/// Sets the discriminator of the given schema.
+pub fn set_discriminator(schema: &mut Schema, property: &str) {
+    schema.schema_data.discriminator = Some(Discriminator {
+        property_name: property.into(),
+        ..Default::default()
+    });
+}
+
+impl Apiv3Schema for () {}
+impl Apiv3Schema for serde_json::Value {}
+impl Apiv3Schema for serde_yaml::Value {}
+
+/// Primitive types have the same schemas in both versions.
+impl<T: TypedData> Apiv3Schema for T {
+    fn raw_schema() -> Schema {
+        ReferenceOr::<Schema>::from(<T as Apiv2Schema>::raw_schema())
+            .into_item()
+            .unwrap_or_else(any_schema)
+    }
+}
+
+impl<T: Apiv3Schema> Apiv3Schema for Option<T> {
+    fn name() -> Option<String> {
+        T::name()
+    }
+
+    fn required() -> bool {
+        false
+    }
+
+    fn raw_schema() -> Schema {
+        T::raw_schema()
+    }
+
+    fn schema_with_ref() -> ReferenceOr<Schema> {
+        nullable(T::schema_with_ref())
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+}
+
+macro_rules! impl_schema_wrapper {
+    ($ty:ty) => {
+        impl<T: Apiv3Schema> Apiv3Schema for $ty {
+            fn name() -> Option<String> {
+                T::name()
+            }
+
+            fn description() -> &'static str {
+                T::description()
+            }
+
+            fn raw_schema() -> Schema {
+                T::raw_schema()
+            }
+
+            fn schema_with_ref() -> ReferenceOr<Schema> {
+                T::schema_with_ref()
+            }
+
+            fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+                T::update_schemas(map);
+            }
+        }
+    };
+}
+
+impl_schema_wrapper!(std::rc::Rc<T>);
+impl_schema_wrapper!(std::sync::Arc<T>);
+
+impl<T: Apiv3Schema + Clone> Apiv3Schema for std::borrow::Cow<'_, T> {
+    fn name() -> Option<String> {
+        T::name()
+    }
+
+    fn raw_schema() -> Schema {
+        T::raw_schema()
+    }
+
+    fn schema_with_ref() -> ReferenceOr<Schema> {
+        T::schema_with_ref()
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+}
+
+fn array_schema<T: Apiv3Schema>(unique_items: bool) -> Schema {
+    schema_of(Type::Array(ArrayType {
+        items: Some(boxed(T::schema_with_ref())),
+        min_items: None,
+        max_items: None,
+        unique_items,
+    }))
+}
+
+macro_rules! impl_schema_array {
+    ($ty:ty, $unique:expr) => {
+        impl<T: Apiv3Schema> Apiv3Schema for $ty {
+            fn raw_schema() -> Schema {
+                array_schema::<T>($unique)
+            }
+
+            fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+                T::update_schemas(map);
+            }
+        }
+    };
+}
+
+macro_rules! impl_schema_map {
+    ($ty:ty) => {
+        impl<K: ToString, V: Apiv3Schema> Apiv3Schema for $ty {
+            fn raw_schema() -> Schema {
+                schema_of(Type::Object(ObjectType {
+                    additional_properties: Some(AdditionalProperties::Schema(Box::new(
+                        V::schema_with_ref(),
+                    ))),
+                    ..Default::default()
+                }))
+            }
+
+            fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+                V::update_schemas(map);
+            }
+        }
+    };
+}
+
+use std::collections::*;
+
+impl_schema_array!(Vec<T>, false);
+impl_schema_array!(HashSet<T>, true);
+impl_schema_array!(LinkedList<T>, false);
+impl_schema_array!(VecDeque<T>, false);
+impl_schema_array!(BTreeSet<T>, true);
+impl_schema_array!(BinaryHeap<T>, false);
+impl_schema_array!(&[T], false);
+
+impl<T: Apiv3Schema, const N: usize> Apiv3Schema for [T; N] {
+    fn raw_schema() -> Schema {
+        let mut schema = array_schema::<T>(false);
+        if let SchemaKind::Type(Type::Array(array)) = &mut schema.schema_kind {
+            array.min_items = Some(N);
+            array.max_items = Some(N);
+        }
+
+        schema
+    }
+
+    fn update_schemas(map: &mut BTreeMap<String, Schema>) {
+        T::update_schemas(map);
+    }
+}
+
+impl_schema_map!(HashMap<K, V>);
+impl_schema_map!(BTreeMap<K, V>);
@@ -0,0 +1,578 @@
+//! Conversion between OpenAPI v3.0 and v3.1 documents.
+//!
+//! Both versions mostly differ in their schemas, so the conversion works on
+//! the serialized document and rewrites the schemas in place.
+
+use super::models::{OpenApi, VERSION};
+use crate::v2::{keywords, source_map::json_path};
+use crate::v3::report::ConversionReport;
+use serde_json::{json, Map, Value};
+
+use std::collections::BTreeSet;
+
+/// Version set by the downgrade from v3.1.
+const V30_VERSION: &str = "3.0.3";
+
+/// Keywords allowed in v3.0 schemas (other than vendor extensions).
+const V30_KEYWORDS: &[&str] = &[
+    "$ref",
+    "title",
+    "description",
+    "type",
+    "format",
+    "enum",
+    "default",
+    "example",
+    "nullable",
+    "readOnly",
+    "writeOnly",
+    "deprecated",
+    "multipleOf",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "items",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "properties",
+    "additionalProperties",
+    "required",
+    "minProperties",
+    "maxProperties",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+    "discriminator",
+    "xml",
+    "externalDocs",
+];
+
+/// Keywords (in both versions) whose values are maps of schemas.
+const SCHEMA_MAP_KEYWORDS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "$defs",
+    "dependentSchemas",
+];
+
+/// Keywords (in both versions) whose values are lists of schemas.
+const SCHEMA_LIST_KEYWORDS: &[&str] = &["allOf", "anyOf", "oneOf", "prefixItems"];
+
+/// Keywords (in both versions) whose values are schemas.
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "items",
+    "not",
+    "contains",
+    "propertyNames",
+    "if",
+    "then",
+    "else",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "contentSchema",
+];
+
+/// Keys which never have schemas (or path items) in them, outside of schemas.
+const OPAQUE_KEYS: &[&str] = &[
+    "example",
+    "examples",
+    "links",
+    "security",
+    "securitySchemes",
+];
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+pub(in crate::v3) fn upgrade(v3: &openapiv3::OpenAPI) -> Result<OpenApi, serde_json::Error> {
+    let mut doc = serde_json::to_value(v3)?;
+    doc["openapi"] = VERSION.into();
+    visit_schemas(&mut doc, &mut |schema, _| upgrade_schema(schema));
+    serde_json::from_value(doc)
+}
+
+pub(in crate::v3) fn downgrade(
+    v31: &OpenApi,
+) -> Result<(openapiv3::OpenAPI, ConversionReport), serde_json::Error> {
+    let mut doc = serde_json::to_value(v31)?;
+    let mut report = ConversionReport::default();
+    doc["openapi"] = V30_VERSION.into();
+
+    let root = doc.as_object_mut().expect("document is an object");
+    for key in &["webhooks", "jsonSchemaDialect"] {
+        if root.remove(*key).is_some() {
+            report.drop(
+                &json_path("$", key),
+                format!("`{}` isn't supported in v3.0", key),
+            );
+        }
+    }
+
+    root.entry("paths").or_insert_with(|| json!({}));
+    if let Some(info) = root.get_mut("info").and_then(Value::as_object_mut) {
+        if info.remove("summary").is_some() {
+            report.drop(
+                "$.info.summary",
+                "API summary isn't supported in v3.0".into(),
+            );
+        }
+
+        if let Some(license) = info.get_mut("license").and_then(Value::as_object_mut) {
+            if license.remove("identifier").is_some() {
+                report.drop(
+                    "$.info.license.identifier",
+                    "SPDX license identifiers aren't supported in v3.0".into(),
+                );
+            }
+        }
+    }
+
+    let mut dropped_schemes = BTreeSet::new();
+    if let Some(components) = root.get_mut("components").and_then(Value::as_object_mut) {
+        if components.remove("pathItems").is_some() {
+            report.drop(
+                "$.components.pathItems",
+                "reusable path items aren't supported in v3.0".into(),
+            );
+        }
+
+        if let Some(schemes) = components
+            .get_mut("securitySchemes")
+            .and_then(Value::as_object_mut)
+        {
+            schemes.retain(|name, scheme| {
+                if scheme["type"] != "mutualTLS" {
+                    return true;
+                }
+
+                report.drop(
+                    &json_path("$.components.securitySchemes", name),
+                    "mutual TLS security schemes aren't supported in v3.0".into(),
+                );
+                dropped_schemes.insert(name.clone());
+                false
+            });
+        }
+
+        if let Some(callbacks) = components
+            .get_mut("callbacks")
+            .and_then(Value::as_object_mut)
+        {
+            for (name, callback) in callbacks {
+                let path = json_path("$.components.callbacks", name);
+                for (expr, item) in object_entries(callback) {
+                    path_item(item, &json_path(&path, expr), &mut report);
+                }
+            }
+        }
+    }
+
+    if let Some(paths) = root.get_mut("paths").and_then(Value::as_object_mut) {
+        for (template, item) in paths {
+            path_item(item, &json_path("$.paths", template), &mut report);
+        }
+    }
+
+    if !dropped_schemes.is_empty() {
+        // Alternatives which need the dropped schemes are left out (instead of
+        // leaving out the schemes, which would make them less restrictive).
+        visit_security(&mut doc, &mut |requirements| {
+            requirements.retain(|requirement| {
+                requirement
+                    .as_object()
+                    .map(|r| !r.keys().any(|name| dropped_schemes.contains(name)))
+                    .unwrap_or(true)
+            });
+        });
+    }
+
+    visit_schemas(&mut doc, &mut |schema, path| {
+        downgrade_schema(schema, path, &mut report)
+    });
+    Ok((serde_json::from_value(doc)?, report))
+}
+
+/// Fills in the objects required in v3.0 (responses), and drops the callback
+/// references (which aren't allowed in operations).
+fn path_item(item: &mut Value, path: &str, report: &mut ConversionReport) {
+    for method in METHODS {
+        let op = match item.get_mut(*method).and_then(Value::as_object_mut) {
+            Some(op) => op,
+            None => continue,
+        };
+
+        let path = json_path(path, method);
+        op.entry("responses").or_insert_with(|| json!({}));
+        if let Some(callbacks) = op.get_mut("callbacks").and_then(Value::as_object_mut) {
+            callbacks.retain(|name, callback| {
+                if callback.get("$ref").is_none() {
+                    return true;
+                }
+
+                report.drop(
+                    &json_path(&json_path(&path, "callbacks"), name),
+                    "callback references in operations aren't supported in v3.0".into(),
+                );
+                false
+            });
+
+            for (name, callback) in callbacks {
+                let path = json_path(&json_path(&path, "callbacks"), name);
+                for (expr, item) in object_entries(callback) {
+                    path_item(item, &json_path(&path, expr), report);
+                }
+            }
+        }
+    }
+}
+
+/// Calls the given function with the global and operation security requirements.
+fn visit_security(doc: &mut Value, f: &mut dyn FnMut(&mut Vec<Value>)) {
+    if let Some(Value::Array(requirements)) = doc.get_mut("security") {
+        f(requirements);
+    }
+
+    for (_, item) in doc.get_mut("paths").into_iter().flat_map(object_entries) {
+        for method in METHODS {
+            if let Some(Value::Array(requirements)) =
+                item.get_mut(*method).and_then(|op| op.get_mut("security"))
+            {
+                f(requirements);
+            }
+        }
+    }
+}
+
+/// Calls the given function with every schema in the document (including the
+/// ones nested in other schemas), along with their JSON paths.
+fn visit_schemas(doc: &mut Value, f: &mut dyn FnMut(&mut Value, &str)) {
+    let schemas = doc.get_mut("components").and_then(|c| c.get_mut("schemas"));
+    for (name, schema) in schemas.into_iter().flat_map(object_entries) {
+        walk_schema(schema, &json_path("$.components.schemas", name), f);
+    }
+
+    walk_document(doc, "$", f);
+}
+
+fn walk_document(value: &mut Value, path: &str, f: &mut dyn FnMut(&mut Value, &str)) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if key.starts_with("x-")
+                    || OPAQUE_KEYS.contains(&key.as_str())
+                    || (path == "$.components" && key == "schemas")
+                {
+                    continue;
+                }
+
+                let path = json_path(path, key);
+                if key == "schema" {
+                    walk_schema(value, &path, f);
+                } else {
+                    walk_document(value, &path, f);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                walk_document(value, &format!("{}[{}]", path, i), f);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn walk_schema(schema: &mut Value, path: &str, f: &mut dyn FnMut(&mut Value, &str)) {
+    f(schema, path);
+    let schema = match schema.as_object_mut() {
+        Some(s) => s,
+        None => return,
+    };
+
+    for key in SCHEMA_MAP_KEYWORDS {
+        let path = json_path(path, key);
+        for (name, schema) in object_entries_in(schema, key) {
+            walk_schema(schema, &json_path(&path, name), f);
+        }
+    }
+
+    for key in SCHEMA_LIST_KEYWORDS {
+        if let Some(Value::Array(schemas)) = schema.get_mut(*key) {
+            for (i, schema) in schemas.iter_mut().enumerate() {
+                walk_schema(schema, &format!("{}[{}]", json_path(path, key), i), f);
+            }
+        }
+    }
+
+    for key in SCHEMA_KEYWORDS {
+        if let Some(child) = schema.get_mut(*key) {
+            walk_schema(child, &json_path(path, key), f);
+        }
+    }
+
+    // Boolean `additionalProperties` means the same in both versions.
+    if let Some(child @ Value::Object(_)) = schema.get_mut("additionalProperties") {
+        walk_schema(child, &json_path(path, "additionalProperties"), f);
+    }
+}
+
+fn upgrade_schema(schema: &mut Value) {
+    let schema = match schema.as_object_mut() {
+        Some(s) if !s.contains_key("$ref") => s,
+        // Siblings of references are ignored in v3.0.
+        _ => return,
+    };
+
+    keywords::upgrade(schema);
+
+    if schema.get("type").and_then(Value::as_str) == Some("string") {
+        match schema.get("format").and_then(Value::as_str) {
+            Some("binary") => {
+                schema.remove("format");
+                schema.insert("contentMediaType".into(), "application/octet-stream".into());
+            }
+            Some("byte") => {
+                schema.remove("format");
+                schema.insert("contentEncoding".into(), "base64".into());
+            }
+            _ => (),
+        }
+    }
+
+    let nullable = schema.remove("nullable") == Some(Value::Bool(true));
+    let single_ref = match schema.get("allOf") {
+        Some(Value::Array(members)) => {
+            members.len() == 1
+                && members[0].get("$ref").is_some()
+                && !schema.contains_key("oneOf")
+                && !schema.contains_key("anyOf")
+                && !schema.contains_key("type")
+        }
+        _ => false,
+    };
+
+    if !nullable {
+        // v3.1 allows siblings next to references, so the `allOf` wrapper isn't needed.
+        if single_ref {
+            if let Some(Value::Array(mut members)) = schema.remove("allOf") {
+                if let Some(Value::Object(member)) = members.pop() {
+                    schema.extend(member);
+                }
+            }
+        }
+
+        return;
+    }
+
+    let null_schema = json!({ "type": "null" });
+    if let Some(ty) = schema.get_mut("type") {
+        *ty = json!([ty.take(), "null"]);
+        if let Some(Value::Array(values)) = schema.get_mut("enum") {
+            if !values.contains(&Value::Null) {
+                values.push(Value::Null);
+            }
+        }
+    } else if let Some(Value::Array(members)) = schema.get_mut("oneOf") {
+        members.push(null_schema);
+    } else if let Some(Value::Array(members)) = schema.get_mut("anyOf") {
+        members.push(null_schema);
+    } else if single_ref {
+        if let Some(members) = schema.remove("allOf") {
+            schema.insert("anyOf".into(), json!([members[0], null_schema]));
+        }
+    } else if let Some(members) = schema.remove("allOf") {
+        schema.insert("anyOf".into(), json!([{ "allOf": members }, null_schema]));
+    } else if let Some(not) = schema.remove("not") {
+        schema.insert("anyOf".into(), json!([{ "not": not }, null_schema]));
+    }
+}
+
+fn downgrade_schema(schema: &mut Value, path: &str, report: &mut ConversionReport) {
+    match schema {
+        Value::Bool(true) => *schema = json!({}),
+        Value::Bool(false) => *schema = json!({ "not": {} }),
+        _ => (),
+    }
+
+    let schema = match schema.as_object_mut() {
+        Some(s) => s,
+        None => return,
+    };
+
+    // v3.0 ignores the siblings of references, so they're moved next to an `allOf` wrapper.
+    if schema.len() > 1 {
+        if let Some(reference) = schema.remove("$ref") {
+            let member = json!({ "$ref": reference });
+            match schema.get_mut("allOf") {
+                Some(Value::Array(members)) => members.insert(0, member),
+                _ => {
+                    schema.insert("allOf".into(), json!([member]));
+                }
+            }
+        }
+    }
+
+    if let Some(value) = schema.remove("const") {
+        schema.entry("enum").or_insert_with(|| json!([value]));
+    }
+
+    if let Some(Value::Array(mut examples)) = schema.remove("examples") {
+        if examples.len() > 1 {
+            report.approximate(
+                &json_path(path, "examples"),
+                format!("only the first of {} examples is kept", examples.len()),
+            );
+        }
+
+        if !examples.is_empty() {
+            schema.insert("example".into(), examples.swap_remove(0));
+        }
+    }
+
+    for (flag, bound) in &[
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        let value = match schema.remove(*flag) {
+            Some(Value::Number(n)) => n,
+            _ => continue,
+        };
+
+        // Both bounds could be set, in which case the stricter one is kept.
+        let exclusive = value.as_f64().unwrap_or_default();
+        let inclusive_is_stricter = match schema.get(*bound).and_then(Value::as_f64) {
+            Some(b) if *bound == "minimum" => b > exclusive,
+            Some(b) => b < exclusive,
+            None => false,
+        };
+
+        if !inclusive_is_stricter {
+            schema.insert((*bound).into(), Value::Number(value));
+            schema.insert((*flag).into(), true.into());
+        }
+    }
+
+    if !schema.contains_key("format") {
+        if schema
+            .remove("contentEncoding")
+            .as_ref()
+            .and_then(Value::as_str)
+            == Some("base64")
+        {
+            schema.insert("format".into(), "byte".into());
+        } else if schema.remove("contentMediaType").is_some() {
+            schema.insert("format".into(), "binary".into());
+        }
+    }
+
+    let mut nullable = false;
+    match schema.remove("type") {
+        Some(Value::Array(types)) => {
+            let len = types.len();
+            let mut types: Vec<_> = types.into_iter().filter(|t| t != "null").collect();
+            nullable = types.len() < len;
+            match types.len() {
+                0 => {
+                    report.approximate(
+                        &json_path(path, "type"),
+                        "`null` type is approximated with a nullable schema".into(),
+                    );
+                }
+                1 => {
+                    schema.insert("type".into(), types.remove(0));
+                }
+                _ if schema.contains_key("oneOf") => {
+                    report.drop(
+                        &json_path(path, "type"),
+                        "multiple types can't be combined with `oneOf` in v3.0".into(),
+                    );
+                }
+                _ => {
+                    report.approximate(
+                        &json_path(path, "type"),
+                        "multiple types are approximated with `oneOf`".into(),
+                    );
+                    let members = types.into_iter().map(|t| json!({ "type": t })).collect();
+                    schema.insert("oneOf".into(), Value::Array(members));
+                }
+            }
+        }
+        Some(Value::String(t)) if t == "null" => {
+            nullable = true;
+            report.approximate(
+                &json_path(path, "type"),
+                "`null` type is approximated with a nullable schema".into(),
+            );
+        }
+        Some(t) => {
+            schema.insert("type".into(), t);
+        }
+        None => (),
+    }
+
+    // `null` members of unions, like `anyOf: [{$ref: ...}, {type: "null"}]`.
+    for key in &["anyOf", "oneOf"] {
+        if let Some(Value::Array(members)) = schema.get_mut(*key) {
+            let len = members.len();
+            members.retain(|m| m.as_object().map(is_null_schema) != Some(true));
+            if members.len() == len {
+                continue;
+            }
+
+            nullable = true;
+            if members.len() == 1 && !schema.contains_key("allOf") {
+                let members = schema.remove(*key).expect("union members");
+                schema.insert("allOf".into(), members);
+            }
+        }
+    }
+
+    // Nullable enums list `null` in v3.1, but not in v3.0.
+    if let Some(Value::Array(values)) = schema.get_mut("enum") {
+        let len = values.len();
+        values.retain(|v| !v.is_null());
+        nullable |= values.len() < len;
+    }
+
+    if nullable {
+        schema.insert("nullable".into(), true.into());
+    }
+
+    let unsupported = schema
+        .keys()
+        .filter(|k| !k.starts_with("x-") && !V30_KEYWORDS.contains(&k.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    for key in unsupported {
+        schema.remove(&key);
+        report.drop(
+            &json_path(path, &key),
+            format!("`{}` isn't supported in v3.0 schemas", key),
+        );
+    }
+}
+
+fn is_null_schema(schema: &Map<String, Value>) -> bool {
+    schema.len() == 1 && schema.get("type").and_then(Value::as_str) == Some("null")
+}
+
+fn object_entries(value: &mut Value) -> impl Iterator<Item = (&String, &mut Value)> {
+    value.as_object_mut().into_iter().flat_map(|m| m.iter_mut())
+}
+
+fn object_entries_in<'a>(
+    map: &'a mut Map<String, Value>,
+    key: &str,
+) -> impl Iterator<Item = (&'a String, &'a mut Value)> {
+    map.get_mut(key)
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flat_map(|m| m.iter_mut())
+}
@@ -0,0 +1,11 @@
+//! OpenAPI 3.1 documents, along with their conversion from (and to) v3.0.
+//!
+//! v3.1 schemas are JSON Schema 2020-12 schemas: nullable types are listed in
+//! `type` (like `["string", "null"]`), examples are `examples` arrays, exclusive
+//! bounds are numbers and references can have sibling keywords. Documents can
+//! also have `webhooks`, `$defs` in schemas and reusable path items.
+
+pub(super) mod convert;
+mod models;
+
+pub use self::models::*;
@@ -19,6 +19,9 @@ use syn::{
 use proc_macro2::TokenStream as TokenStream2;
 use std::collections::HashMap;
 
+#[cfg(feature = "v3")]
+pub mod v3;
+
 const SCHEMA_MACRO_ATTR: &str = "openapi";
 
 lazy_static! {
@@ -30,7 +33,6 @@ lazy_static! {
 
 /// Actual parser and emitter for `api_v2_operation` macro.
 pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream {
-    let default_span = proc_macro2::Span::call_site();
     let mut item_ast: ItemFn = match syn::parse(input) {
         Ok(s) => s,
         Err(e) => {
@@ -39,98 +41,14 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
         }
     };
 
-    // Unit struct
-    let s_name = format!("paperclip_{}", item_ast.sig.ident);
-    let unit_struct = Ident::new(&s_name, default_span);
-    let generics = &item_ast.sig.generics;
-    let mut generics_call = quote!();
-    let mut struct_definition = quote!(
-        #[allow(non_camel_case_types, missing_docs)]
-        struct #unit_struct;
-    );
+    let HandlerWrapper {
+        unit_struct,
+        struct_definition,
+        wrapper,
+        is_responder,
+    } = wrap_handler(&mut item_ast, quote!(paperclip::actix::ResponseWrapper));
+    let generics = item_ast.sig.generics.clone();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    if !generics.params.is_empty() {
-        let turbofish = ty_generics.as_turbofish();
-        let generics_params = extract_generics_params(&item_ast);
-        generics_call = quote!(#turbofish { p: std::marker::PhantomData });
-        struct_definition = quote!(struct #unit_struct #ty_generics { p: std::marker::PhantomData<(#generics_params)> } )
-    }
-
-    // Get rid of async prefix. In the end, we'll have them all as `impl Future` thingies.
-    if item_ast.sig.asyncness.is_some() {
-        item_ast.sig.asyncness = None;
-    }
-
-    let mut wrapper = quote!(paperclip::actix::ResponseWrapper<actix_web::HttpResponse, #unit_struct #ty_generics>);
-    let mut is_impl_trait = false;
-    let mut is_responder = false;
-    match &mut item_ast.sig.output {
-        rt @ ReturnType::Default => {
-            // Not particularly useful, but let's deal with it anyway
-            *rt = ReturnType::Type(
-                Token![->](default_span),
-                Box::new(syn::parse2(wrapper.clone()).expect("parsing empty type")),
-            );
-        }
-        ReturnType::Type(_, ty) => {
-            let t = quote!(#ty).to_string();
-            if let Type::ImplTrait(_) = &**ty {
-                is_impl_trait = true;
-            }
-
-            if t == "impl Responder" {
-                // `impl Responder` is a special case because we have to add another wrapper.
-                // FIXME: Better way to deal with this?
-                is_responder = true;
-                *ty = syn::parse2(quote!(
-                    impl std::future::Future<Output=paperclip::actix::ResponderWrapper<#ty>>
-                ))
-                .expect("parsing impl trait");
-            } else if !is_impl_trait {
-                // Any handler that's not returning an impl trait should return an `impl Future`
-                *ty = syn::parse2(quote!(impl std::future::Future<Output=#ty>))
-                    .expect("parsing impl trait");
-            }
-
-            if let Type::ImplTrait(imp) = &**ty {
-                let obj = TypeTraitObject {
-                    dyn_token: Some(Token![dyn](default_span)),
-                    bounds: imp.bounds.clone(),
-                };
-                *ty = syn::parse2(quote!(#ty + paperclip::v2::schema::Apiv2Operation))
-                    .expect("parsing impl trait");
-
-                if !is_responder {
-                    // NOTE: We're only using the box "type" to generate the operation data, we're not boxing
-                    // the handlers at runtime.
-                    wrapper = quote!(paperclip::actix::ResponseWrapper<Box<#obj + std::marker::Unpin>, #unit_struct #ty_generics>);
-                }
-            }
-        }
-    }
-
-    let block = item_ast.block;
-    // We need a function because devs should be able to use "return" keyword along the way.
-    let wrapped_fn_call = if is_responder {
-        quote!(paperclip::util::ready(paperclip::actix::ResponderWrapper((move || #block)())))
-    } else if is_impl_trait {
-        quote!((move || #block)())
-    } else {
-        quote!((move || async move #block)())
-    };
-
-    item_ast.block = Box::new(
-        syn::parse2(quote!(
-            {
-                let f = #wrapped_fn_call;
-                paperclip::actix::ResponseWrapper {
-                    responder: f,
-                    operations: #unit_struct #generics_call,
-                }
-            }
-        ))
-        .expect("parsing wrapped block"),
-    );
 
     // Initialize operation parameters from macro attributes
     let (mut op_params, mut op_values) = parse_operation_attrs(attrs);
@@ -235,6 +153,117 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
         .into()
 }
 
+/// The unit struct (used for generating the operation) and the response
+/// wrapper of a handler.
+struct HandlerWrapper {
+    unit_struct: Ident,
+    struct_definition: TokenStream2,
+    wrapper: TokenStream2,
+    is_responder: bool,
+}
+
+/// Rewrites the handler so that it returns the given response wrapper, which holds
+/// the actual responder and the (autogenerated) unit struct for this operation.
+fn wrap_handler(item_ast: &mut ItemFn, wrapper_path: TokenStream2) -> HandlerWrapper {
+    let default_span = proc_macro2::Span::call_site();
+    let s_name = format!("paperclip_{}", item_ast.sig.ident);
+    let unit_struct = Ident::new(&s_name, default_span);
+    let generics = &item_ast.sig.generics;
+    let mut generics_call = quote!();
+    let mut struct_definition = quote!(
+        #[allow(non_camel_case_types, missing_docs)]
+        struct #unit_struct;
+    );
+    let ty_generics = generics.split_for_impl().1;
+    if !generics.params.is_empty() {
+        let turbofish = ty_generics.as_turbofish();
+        let generics_params = extract_generics_params(item_ast);
+        generics_call = quote!(#turbofish { p: std::marker::PhantomData });
+        struct_definition = quote!(struct #unit_struct #ty_generics { p: std::marker::PhantomData<(#generics_params)> } )
+    }
+
+    // Get rid of async prefix. In the end, we'll have them all as `impl Future` thingies.
+    if item_ast.sig.asyncness.is_some() {
+        item_ast.sig.asyncness = None;
+    }
+
+    let mut wrapper = quote!(#wrapper_path<actix_web::HttpResponse, #unit_struct #ty_generics>);
+    let mut is_impl_trait = false;
+    let mut is_responder = false;
+    match &mut item_ast.sig.output {
+        rt @ ReturnType::Default => {
+            // Not particularly useful, but let's deal with it anyway
+            *rt = ReturnType::Type(
+                Token![->](default_span),
+                Box::new(syn::parse2(wrapper.clone()).expect("parsing empty type")),
+            );
+        }
+        ReturnType::Type(_, ty) => {
+            let t = quote!(#ty).to_string();
+            if let Type::ImplTrait(_) = &**ty {
+                is_impl_trait = true;
+            }
+
+            if t == "impl Responder" {
+                // `impl Responder` is a special case because we have to add another wrapper.
+                // FIXME: Better way to deal with this?
+                is_responder = true;
+                *ty = syn::parse2(quote!(
+                    impl std::future::Future<Output=paperclip::actix::ResponderWrapper<#ty>>
+                ))
+                .expect("parsing impl trait");
+            } else if !is_impl_trait {
+                // Any handler that's not returning an impl trait should return an `impl Future`
+                *ty = syn::parse2(quote!(impl std::future::Future<Output=#ty>))
+                    .expect("parsing impl trait");
+            }
+
+            if let Type::ImplTrait(imp) = &**ty {
+                let obj = TypeTraitObject {
+                    dyn_token: Some(Token![dyn](default_span)),
+                    bounds: imp.bounds.clone(),
+                };
+                *ty = syn::parse2(quote!(#ty + paperclip::v2::schema::Apiv2Operation))
+                    .expect("parsing impl trait");
+
+                if !is_responder {
+                    // NOTE: We're only using the box "type" to generate the operation data, we're not boxing
+                    // the handlers at runtime.
+                    wrapper = quote!(#wrapper_path<Box<#obj + std::marker::Unpin>, #unit_struct #ty_generics>);
+                }
+            }
+        }
+    }
+
+    let block = &item_ast.block;
+    // We need a function because devs should be able to use "return" keyword along the way.
+    let wrapped_fn_call = if is_responder {
+        quote!(paperclip::util::ready(paperclip::actix::ResponderWrapper((move || #block)())))
+    } else if is_impl_trait {
+        quote!((move || #block)())
+    } else {
+        quote!((move || async move #block)())
+    };
+
+    *item_ast.block = syn::parse2(quote!(
+        {
+            let f = #wrapped_fn_call;
+            #wrapper_path {
+                responder: f,
+                operations: #unit_struct #generics_call,
+            }
+        }
+    ))
+    .expect("parsing wrapped block");
+
+    HandlerWrapper {
+        unit_struct,
+        struct_definition,
+        wrapper,
+        is_responder,
+    }
+}
+
 /// Extract punctuated generic parameters from fn definition
 fn extract_generics_params(item_ast: &ItemFn) -> Punctuated<Ident, syn::token::Comma> {
     item_ast
@@ -837,6 +866,8 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
 
     // FIXME: Use attr path segments to find flattening, skipping, etc.
     let mut props_gen = quote! {};
+    // Definitions of the variants of internally tagged enums.
+    let mut subtypes_gen = quote! {};
 
     match &item_ast.data {
         Data::Struct(ref s) => {
@@ -859,7 +890,7 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
                 }
             }
         }
-        Data::Enum(ref e) => handle_enum(e, &props, &mut props_gen),
+        Data::Enum(ref e) => handle_enum(e, &props, &mut props_gen, &mut subtypes_gen),
         Data::Union(ref u) => emit_error!(
             u.union_token.span().unwrap(),
             "unions are unsupported for deriving schema"
@@ -881,6 +912,24 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
         quote! { format!("{}<{}>", #base_name, #type_names) }
     };
     let props_gen_empty = props_gen.is_empty();
+    let subtypes_def = if subtypes_gen.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn subtype_definitions() -> std::collections::BTreeMap<String, paperclip::v2::models::DefaultSchemaRaw> {
+                use paperclip::v2::models::{DataType, DataTypeFormat, DefaultSchemaRaw};
+                use paperclip::v2::schema::TypedData;
+
+                let base = DefaultSchemaRaw {
+                    reference: Self::schema_with_ref().reference,
+                    ..Default::default()
+                };
+                let mut defs = std::collections::BTreeMap::new();
+                #subtypes_gen
+                defs
+            }
+        }
+    };
 
     #[cfg(not(feature = "path-in-definition"))]
     let default_schema_raw_def = quote! {
@@ -965,6 +1014,8 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
                 }
                 schema
             }
+
+            #subtypes_def
         }
 
         #opt_impl
@@ -994,6 +1045,10 @@ pub fn emit_v2_security(input: TokenStream) -> TokenStream {
     });
 
     let opt_impl = add_optional_impl(name, &generics);
+    #[cfg(feature = "v3")]
+    let v3_impl = v3::security_modifier(name, &generics);
+    #[cfg(not(feature = "v3"))]
+    let v3_impl = quote!();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let mut security_attrs = HashMap::new();
@@ -1173,6 +1228,8 @@ pub fn emit_v2_security(input: TokenStream) -> TokenStream {
             }
 
             #opt_impl
+
+            #v3_impl
         }
     } else {
         quote! {}
@@ -1202,6 +1259,10 @@ pub fn emit_v2_header(input: TokenStream) -> TokenStream {
     });
 
     let opt_impl = add_optional_impl(name, &generics);
+    #[cfg(feature = "v3")]
+    let v3_impl = v3::header_modifier(name, &generics);
+    #[cfg(not(feature = "v3"))]
+    let v3_impl = quote!();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let mut header_definitions = vec![];
@@ -1212,7 +1273,7 @@ pub fn emit_v2_header(input: TokenStream) -> TokenStream {
         valid_attrs
     );
 
-    fn quote_format(format: &str) -> proc_macro2::TokenStream {
+    fn quote_format(format: &str, span: proc_macro::Span) -> proc_macro2::TokenStream {
         match format {
             "int32" => quote! { Some(paperclip::v2::models::DataTypeFormat::Int32) },
             "int64" => quote! { Some(paperclip::v2::models::DataTypeFormat::Int64) },
@@ -1230,13 +1291,13 @@ pub fn emit_v2_header(input: TokenStream) -> TokenStream {
             "ip" => quote! { Some(paperclip::v2::models::DataTypeFormat::Ip) },
             "ipv4" => quote! { Some(paperclip::v2::models::DataTypeFormat::IpV4) },
             "ipv6" => quote! { Some(paperclip::v2::models::DataTypeFormat::IpV6) },
-            "other" => quote! { Some(paperclip::v2::models::DataTypeFormat::Other) },
             v => {
-                emit_error!(
-                    format.span().unwrap(),
-                    format!("Invalid format attribute value. Got {}", v)
+                emit_warning!(
+                    span,
+                    "Unknown format attribute value {}. It's added to the schema as is.",
+                    v
                 );
-                quote! { None }
+                quote! { Some(paperclip::v2::models::DataTypeFormat::Other(#v.into())) }
             }
         }
     }
@@ -1358,7 +1419,7 @@ pub fn emit_v2_header(input: TokenStream) -> TokenStream {
         };
 
         let (quoted_type, quoted_format) = if let Some(format) = parameter_attrs.get("format") {
-            let quoted_format = quote_format(format);
+            let quoted_format = quote_format(format, field.span().unwrap());
             let quoted_type = quote! { #quoted_format.map(|format| format.into()) };
             (quoted_type, quoted_format)
         } else {
@@ -1395,6 +1456,8 @@ pub fn emit_v2_header(input: TokenStream) -> TokenStream {
         }
 
         #opt_impl
+
+        #v3_impl
     };
 
     gen.into()
@@ -1689,7 +1752,23 @@ fn handle_field_struct(
 }
 
 /// Generates code for an enum (if supported).
-fn handle_enum(e: &DataEnum, serde: &SerdeProps, props_gen: &mut proc_macro2::TokenStream) {
+fn handle_enum(
+    e: &DataEnum,
+    serde: &SerdeProps,
+    props_gen: &mut proc_macro2::TokenStream,
+    subtypes_gen: &mut proc_macro2::TokenStream,
+) {
+    if serde.content.is_some() {
+        return emit_error!(
+            e.enum_token.span().unwrap(),
+            "adjacently tagged enums are unsupported for deriving schema"
+        );
+    }
+
+    if let Some(tag) = serde.tag.as_ref() {
+        return handle_tagged_enum(e, tag, serde, props_gen, subtypes_gen);
+    }
+
     props_gen.extend(quote!(
         schema.data_type = Some(DataType::String);
     ));
@@ -1727,6 +1806,93 @@ fn handle_enum(e: &DataEnum, serde: &SerdeProps, props_gen: &mut proc_macro2::To
     }
 }
 
+/// Generates code for an internally tagged enum (i.e., `#[serde(tag = "...")]`).
+/// The tag is added as the discriminator, along with a property listing the
+/// names of the variants. Each variant gets a definition (named after its tag
+/// value, as the discriminator requires) composed of the enum and the fields
+/// of the variant. Adjacently tagged enums (which also have
+/// `#[serde(content = "...")]`) are rejected by the caller, as the content
+/// can't be described by this property alone.
+fn handle_tagged_enum(
+    e: &DataEnum,
+    tag: &str,
+    serde: &SerdeProps,
+    props_gen: &mut proc_macro2::TokenStream,
+    subtypes_gen: &mut proc_macro2::TokenStream,
+) {
+    props_gen.extend(quote!(
+        schema.data_type = Some(DataType::Object);
+        schema.discriminator = Some(#tag.into());
+        schema.required.insert(#tag.into());
+        let mut tag_schema = DefaultSchemaRaw {
+            data_type: Some(DataType::String),
+            ..Default::default()
+        };
+    ));
+
+    for var in &e.variants {
+        if SerdeSkip::exists(&var.attrs) {
+            continue;
+        }
+
+        let mut name = var.ident.to_string();
+        if let Some(renamed) = SerdeRename::from_field_attrs(&var.attrs) {
+            name = renamed;
+        } else if let Some(prop) = serde.rename {
+            name = prop.rename(&name);
+        }
+
+        props_gen.extend(quote!(
+            tag_schema.enum_.push(paperclip::v2::serde_json::json!(#name));
+        ));
+
+        let mut fields_gen = quote!();
+        match &var.fields {
+            Fields::Unit => (),
+            Fields::Named(ref f) => {
+                let var_serde = SerdeProps::from_item_attrs(&var.attrs);
+                handle_field_struct(f, &var.attrs, &var_serde, &mut fields_gen);
+            }
+            // Newtype variants have the fields of their (struct) type.
+            Fields::Unnamed(ref f) if f.unnamed.len() == 1 => {
+                if let Some(ty_ref) = get_field_type(&f.unnamed[0]) {
+                    fields_gen.extend(quote!({
+                        let s = #ty_ref::raw_schema();
+                        schema.properties.extend(s.properties);
+                        schema.required.extend(s.required);
+                    }));
+                }
+            }
+            Fields::Unnamed(ref f) => {
+                emit_warning!(f.span().unwrap(), "skipping tuple enum variant in schema.");
+                continue;
+            }
+        }
+
+        subtypes_gen.extend(quote!({
+            let mut schema = DefaultSchemaRaw {
+                data_type: Some(DataType::Object),
+                ..Default::default()
+            };
+            #fields_gen
+            let mut def = DefaultSchemaRaw {
+                description: schema.description.take(),
+                ..Default::default()
+            };
+            def.all_of.push(base.clone().into());
+            if !schema.properties.is_empty() {
+                def.all_of.push(schema.into());
+            }
+
+            defs.insert(#name.to_string(), def);
+        }));
+    }
+
+    props_gen.extend(quote!(
+        schema.properties.insert(#tag.into(), tag_schema.into());
+    ));
+}
+
 /// An associated function of a generic type, say, a vector cannot be called
 /// like `Vec::foo` as it doesn't have a default type. We should instead call
 /// `Vec::<T>::foo`. Something similar applies to `str`. This function takes
@@ -1871,6 +2037,8 @@ impl SerdeSkip {
 #[derive(Clone, Debug, Default)]
 struct SerdeProps {
     rename: Option<SerdeRename>,
+    tag: Option<String>,
+    content: Option<String>,
 }
 
 impl SerdeProps {
@@ -1893,21 +2061,22 @@ impl SerdeProps {
             };
 
             for meta in inner_meta {
-                let global_rename = match meta {
-                    NestedMeta::Meta(Meta::NameValue(ref v))
-                        if v.path
-                            .segments
-                            .last()
-                            .map(|p| p.ident == "rename_all")
-                            .unwrap_or(false) =>
-                    {
-                        &v.lit
-                    }
+                let (ident, value) = match meta {
+                    NestedMeta::Meta(Meta::NameValue(ref v)) => match v.path.segments.last() {
+                        Some(p) => (&p.ident, &v.lit),
+                        None => continue,
+                    },
                     _ => continue,
                 };
 
-                if let Lit::Str(ref s) = global_rename {
-                    props.rename = s.value().parse().ok();
+                if let Lit::Str(ref s) = value {
+                    if ident == "rename_all" {
+                        props.rename = s.value().parse().ok();
+                    } else if ident == "tag" {
+                        props.tag = Some(s.value());
+                    } else if ident == "content" {
+                        props.content = Some(s.value());
+                    }
                 }
             }
         }
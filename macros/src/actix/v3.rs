@@ -0,0 +1,893 @@
+//! Emitters for the native OpenAPI v3 macros (exposed by paperclip with `v3` feature).
+
+use super::{
+    extract_deprecated, extract_documentation, extract_example, extract_fn_arguments_types,
+    extract_fn_documentation, extract_openapi_attrs, extract_rename, wrap_handler, HandlerWrapper,
+    SerdeFlatten, SerdeProps, SerdeRename, SerdeSkip, EMPTY_SCHEMA_HELP,
+};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    spanned::Spanned, Attribute, Data, DataEnum, Fields, FieldsNamed, FieldsUnnamed, Generics,
+    Ident, ItemFn, Lit, Meta, MetaList, MetaNameValue, NestedMeta, Path, TraitBound, Type,
+};
+
+use proc_macro2::TokenStream as TokenStream2;
+
+/// Actual parser and emitter for `api_v3_operation` macro.
+pub fn emit_v3_operation(attrs: TokenStream, input: TokenStream) -> TokenStream {
+    let mut item_ast: ItemFn = match syn::parse(input) {
+        Ok(s) => s,
+        Err(e) => {
+            emit_error!(e.span().unwrap(), "operation must be a function.");
+            return quote!().into();
+        }
+    };
+
+    let HandlerWrapper {
+        unit_struct,
+        struct_definition,
+        wrapper,
+        is_responder,
+    } = wrap_handler(&mut item_ast, quote!(paperclip::v3::actix::ResponseWrapper));
+    let generics = item_ast.sig.generics.clone();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Initialize operation parameters from macro attributes
+    let OperationAttrs {
+        params: mut op_params,
+        values: mut op_values,
+        updates,
+    } = parse_operation_attrs(attrs);
+
+    if op_params.iter().any(|i| *i == "skip") {
+        return quote!(
+            #[allow(non_camel_case_types, missing_docs)]
+            #struct_definition
+
+            #item_ast
+
+            impl #impl_generics paperclip::v3::schema::Apiv3Operation for #unit_struct #ty_generics #where_clause {
+                fn operation() -> paperclip::v3::openapiv3::Operation {
+                    Default::default()
+                }
+
+                fn components() -> paperclip::v3::openapiv3::Components {
+                    Default::default()
+                }
+
+                fn is_visible() -> bool {
+                    false
+                }
+            }
+        )
+        .into();
+    }
+
+    // Optionally extract summary and description from doc comments
+    if !op_params.iter().any(|i| *i == "summary") {
+        let (summary, description) = extract_fn_documentation(&item_ast);
+        if let Some(summary) = summary {
+            op_params.push(Ident::new("summary", item_ast.span()));
+            op_values.push(summary)
+        }
+        if let Some(description) = description {
+            op_params.push(Ident::new("description", item_ast.span()));
+            op_values.push(description)
+        }
+    }
+
+    if op_params.iter().any(|i| *i == "deprecated") || extract_deprecated(&item_ast.attrs) {
+        op_params.retain(|i| *i != "deprecated");
+        op_params.push(Ident::new("deprecated", item_ast.span()));
+        op_values.push(quote!(true))
+    }
+
+    let modifiers = extract_fn_arguments_types(&item_ast);
+
+    let operation_modifier = if is_responder {
+        quote! { paperclip::actix::ResponderWrapper::<actix_web::HttpResponse> }
+    } else {
+        quote! { <<#wrapper as std::future::Future>::Output> }
+    };
+
+    quote!(
+        #struct_definition
+
+        #item_ast
+
+        impl #impl_generics paperclip::v3::schema::Apiv3Operation for #unit_struct #ty_generics #where_clause {
+            fn operation() -> paperclip::v3::openapiv3::Operation {
+                use paperclip::v3::actix::OperationModifier;
+                let mut op = paperclip::v3::openapiv3::Operation {
+                    #(
+                        #op_params: #op_values,
+                    )*
+                    .. Default::default()
+                };
+                #(
+                    <#modifiers>::update_parameter(&mut op);
+                    <#modifiers>::update_security(&mut op);
+                )*
+                #operation_modifier::update_response(&mut op);
+                #(
+                    #updates
+                )*
+                op
+            }
+
+            #[allow(unused_mut)]
+            fn components() -> paperclip::v3::openapiv3::Components {
+                use paperclip::v3::actix::OperationModifier;
+                let mut schemas = std::collections::BTreeMap::new();
+                let mut security_schemes = std::collections::BTreeMap::new();
+                #(
+                    <#modifiers>::update_schemas(&mut schemas);
+                    <#modifiers>::update_security_schemes(&mut security_schemes);
+                )*
+                #operation_modifier::update_schemas(&mut schemas);
+                paperclip::v3::actix::components(schemas, security_schemes)
+            }
+        }
+    )
+    .into()
+}
+
+/// Operation fields initialized from the macro attributes, along with the statements
+/// which update the operation once the extractors and responders are done with it.
+struct OperationAttrs {
+    params: Vec<Ident>,
+    values: Vec<TokenStream2>,
+    updates: Vec<TokenStream2>,
+}
+
+/// Parse macro attrs, matching to v3 Operation fields.
+fn parse_operation_attrs(attrs: TokenStream) -> OperationAttrs {
+    let attrs = crate::parse_input_attrs(attrs);
+    let mut parsed = OperationAttrs {
+        params: Vec::new(),
+        values: Vec::new(),
+        updates: Vec::new(),
+    };
+
+    for attr in attrs.0 {
+        match &attr {
+            NestedMeta::Meta(Meta::Path(attr_path)) => {
+                if let Some(attr_) = attr_path.get_ident() {
+                    if *attr_ == "skip" || *attr_ == "deprecated" {
+                        parsed.params.push(attr_.clone());
+                    } else {
+                        emit_error!(attr_.span(), "Not supported bare attribute {:?}", attr_)
+                    }
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => {
+                let ident = match path.get_ident() {
+                    Some(ident) => ident,
+                    None => {
+                        emit_error!(
+                            path.span(),
+                            "Expected single identifier, got path {:?}",
+                            path
+                        );
+                        continue;
+                    }
+                };
+
+                let val = match lit {
+                    Lit::Str(val) => val,
+                    _ => {
+                        emit_error!(lit.span(), "Expected string literal: {:?}", lit);
+                        continue;
+                    }
+                };
+
+                match ident.to_string().as_str() {
+                    "summary" | "description" | "operation_id" => {
+                        parsed.params.push(ident.clone());
+                        parsed.values.push(quote!(Some(#val.to_string())));
+                    }
+                    "request_body_description" => parsed.updates.push(quote!(
+                        paperclip::v3::actix::set_request_body_description(&mut op, #val);
+                    )),
+                    "consumes" | "produces" => {
+                        let mut mime_types = Vec::new();
+                        for mime in val.value().split(',') {
+                            let mime = mime.trim();
+                            if let Err(err) = mime.parse::<mime::Mime>() {
+                                emit_error!(
+                                    lit.span(),
+                                    "Value {} does not parse as mime type: {}",
+                                    mime,
+                                    err
+                                );
+                            } else {
+                                mime_types.push(mime.to_owned());
+                            }
+                        }
+
+                        if !mime_types.is_empty() {
+                            let update = if *ident == "consumes" {
+                                quote!(paperclip::v3::actix::set_request_content_types)
+                            } else {
+                                quote!(paperclip::v3::actix::set_response_content_types)
+                            };
+                            parsed
+                                .updates
+                                .push(quote!(#update(&mut op, &[#(#mime_types),*]);));
+                        }
+                    }
+                    x => emit_error!(ident.span(), "Unknown attribute {}", x),
+                }
+            }
+            NestedMeta::Meta(Meta::List(list)) => {
+                if let Some(ident) = list.path.get_ident() {
+                    match ident.to_string().as_str() {
+                        "tags" => {
+                            let tags = parse_string_list(list, "tags");
+                            if !tags.is_empty() {
+                                parsed.params.push(ident.clone());
+                                parsed.values.push(quote!(vec![ #( #tags.to_string() ),* ]));
+                            }
+                        }
+                        "servers" => {
+                            let servers = parse_string_list(list, "server URLs");
+                            if !servers.is_empty() {
+                                parsed.params.push(ident.clone());
+                                parsed.values.push(quote!(vec![
+                                    #(
+                                        paperclip::v3::openapiv3::Server {
+                                            url: #servers.to_string(),
+                                            ..Default::default()
+                                        }
+                                    ),*
+                                ]));
+                            }
+                        }
+                        "cookie" => parsed.updates.extend(parse_cookie(list)),
+                        "link" => parsed.updates.extend(parse_link(list)),
+                        x => emit_error!(ident.span(), "Unknown list ident {}", x),
+                    }
+                }
+            }
+            _ => {
+                emit_error!(attr.span(), "Not supported attribute type {:?}", attr)
+            }
+        }
+    }
+
+    parsed
+}
+
+/// Parses a list of idents or string literals (e.g., `tags(Pets, "Pet store")`).
+fn parse_string_list(list: &MetaList, what: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    for meta in &list.nested {
+        if let NestedMeta::Meta(Meta::Path(Path { segments, .. })) = meta {
+            values.push(segments[0].ident.to_string());
+        } else if let NestedMeta::Lit(Lit::Str(lit)) = meta {
+            values.push(lit.value());
+        } else {
+            emit_error!(
+                meta.span(),
+                "Expected comma separated list of {}: {:?}",
+                what,
+                meta
+            )
+        }
+    }
+
+    values
+}
+
+/// Parses the named string values and bare flags in the given list attribute.
+fn parse_named_values(list: &MetaList, valid: &[&str]) -> (Vec<(String, Lit)>, Vec<String>) {
+    let mut values = Vec::new();
+    let mut flags = Vec::new();
+    for meta in &list.nested {
+        match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) => match nv.path.get_ident() {
+                Some(ident) if valid.contains(&ident.to_string().as_str()) => {
+                    values.push((ident.to_string(), nv.lit.clone()))
+                }
+                _ => emit_error!(
+                    meta.span(),
+                    "Invalid attribute. Expected one of {:?}",
+                    valid
+                ),
+            },
+            NestedMeta::Meta(Meta::Path(path)) => match path.get_ident() {
+                Some(ident) if valid.contains(&ident.to_string().as_str()) => {
+                    flags.push(ident.to_string())
+                }
+                _ => emit_error!(
+                    meta.span(),
+                    "Invalid attribute. Expected one of {:?}",
+                    valid
+                ),
+            },
+            _ => emit_error!(meta.span(), "Not supported attribute type {:?}", meta),
+        }
+    }
+
+    (values, flags)
+}
+
+fn string_value(values: &[(String, Lit)], name: &str) -> Option<String> {
+    values.iter().find(|(n, _)| n == name).and_then(|(_, lit)| {
+        if let Lit::Str(s) = lit {
+            Some(s.value())
+        } else {
+            emit_error!(lit.span(), "`{}` expects a string argument", name);
+            None
+        }
+    })
+}
+
+/// Parses `cookie(name = "...", description = "...", required)`.
+fn parse_cookie(list: &MetaList) -> Option<TokenStream2> {
+    let (values, flags) = parse_named_values(list, &["name", "description", "required"]);
+    let name = match string_value(&values, "name") {
+        Some(name) => name,
+        None => {
+            emit_error!(list.span(), "Missing cookie name");
+            return None;
+        }
+    };
+
+    let description = match string_value(&values, "description") {
+        Some(d) => quote!(Some(#d)),
+        None => quote!(None),
+    };
+    let required = flags.iter().any(|f| f == "required");
+    Some(quote!(
+        paperclip::v3::actix::add_cookie_parameter(&mut op, #name, #description, #required);
+    ))
+}
+
+/// Parses `link(status = 201, name = "...", operation_id = "...", parameters = "k=v, ...")`.
+fn parse_link(list: &MetaList) -> Option<TokenStream2> {
+    let (values, _) = parse_named_values(list, &["status", "name", "operation_id", "parameters"]);
+    let status = match values.iter().find(|(n, _)| n == "status") {
+        Some((_, Lit::Int(i))) => match i.base10_parse::<u16>() {
+            Ok(s) => s,
+            Err(_) => {
+                emit_error!(i.span(), "Invalid u16 in status argument");
+                return None;
+            }
+        },
+        _ => {
+            emit_error!(list.span(), "Missing (integer) link status");
+            return None;
+        }
+    };
+
+    let operation_id = match string_value(&values, "operation_id") {
+        Some(id) => id,
+        None => {
+            emit_error!(list.span(), "Missing link operation_id");
+            return None;
+        }
+    };
+
+    let name = string_value(&values, "name").unwrap_or_else(|| operation_id.clone());
+    let mut keys = Vec::new();
+    let mut expressions = Vec::new();
+    for pair in string_value(&values, "parameters")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|p| !p.trim().is_empty())
+    {
+        match pair.split_once('=') {
+            Some((k, v)) => {
+                keys.push(k.trim().to_owned());
+                expressions.push(v.trim().to_owned());
+            }
+            None => emit_error!(
+                list.span(),
+                "Expected link parameters in the form \"name=expression, ...\""
+            ),
+        }
+    }
+
+    Some(quote!(
+        paperclip::v3::actix::add_response_link(
+            &mut op,
+            #status,
+            #name,
+            #operation_id,
+            &[#((#keys, #expressions)),*],
+        );
+    ))
+}
+
+/// Actual parser and emitter for `Apiv3Schema` derive macro.
+pub fn emit_v3_definition(input: TokenStream) -> TokenStream {
+    let item_ast = match crate::expect_struct_or_enum(input) {
+        Ok(i) => i,
+        Err(ts) => return ts,
+    };
+
+    let name = &item_ast.ident;
+
+    // Add `Apiv3Schema` bound for impl if the type is generic.
+    let mut generics = item_ast.generics.clone();
+    let bound = syn::parse2::<TraitBound>(quote!(paperclip::v3::schema::Apiv3Schema))
+        .expect("expected to parse trait bound");
+    generics.type_params_mut().for_each(|param| {
+        param.bounds.push(bound.clone().into());
+    });
+
+    let opt_impl = add_optional_impl(name, &generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let needs_empty_schema = extract_openapi_attrs(&item_ast.attrs).any(|nested| {
+        nested.len() == 1
+            && matches!(&nested[0], NestedMeta::Meta(Meta::Path(path)) if path.is_ident("empty"))
+    });
+    if needs_empty_schema {
+        return quote!(
+            impl #impl_generics paperclip::v3::schema::Apiv3Schema for #name #ty_generics #where_clause {}
+
+            #opt_impl
+        )
+        .into();
+    }
+
+    let docs = extract_documentation(&item_ast.attrs);
+    let docs = docs.trim();
+    let example = quote_example(&item_ast.attrs);
+    let props = SerdeProps::from_item_attrs(&item_ast.attrs);
+
+    let mut field_types = vec![];
+    let schema_gen = match &item_ast.data {
+        Data::Struct(ref s) => match &s.fields {
+            Fields::Named(ref f) => named_fields_schema(f, props.rename, &mut field_types),
+            Fields::Unnamed(ref f) => unnamed_fields_schema(f, &mut field_types),
+            Fields::Unit => {
+                emit_warning!(
+                    s.struct_token.span().unwrap(),
+                    "unit structs do not have any fields and hence will have empty schema.";
+                    help = "{}", &*EMPTY_SCHEMA_HELP;
+                );
+                quote!(paperclip::v3::schema::object_schema())
+            }
+        },
+        Data::Enum(ref e) => enum_schema(e, &item_ast.attrs, &props, &mut field_types),
+        Data::Union(ref u) => {
+            emit_error!(
+                u.union_token.span().unwrap(),
+                "unions are unsupported for deriving schema"
+            );
+            quote!(paperclip::v3::schema::any_schema())
+        }
+    };
+
+    let base_name = extract_rename(&item_ast.attrs).unwrap_or_else(|| name.to_string());
+    let type_params: Vec<&Ident> = generics.type_params().map(|p| &p.ident).collect();
+    let schema_name = if type_params.is_empty() {
+        quote! { #base_name }
+    } else {
+        let type_names = quote! {
+            [#(<#type_params as paperclip::v3::schema::Apiv3Schema>::name()),*]
+                .iter()
+                .filter_map(|n| n.to_owned())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        quote! { format!("{}<{}>", #base_name, #type_names) }
+    };
+
+    let gen = quote! {
+        impl #impl_generics paperclip::v3::schema::Apiv3Schema for #name #ty_generics #where_clause {
+            fn name() -> Option<String> {
+                Some(#schema_name.to_string())
+            }
+
+            fn description() -> &'static str {
+                #docs
+            }
+
+            fn raw_schema() -> paperclip::v3::openapiv3::Schema {
+                let mut schema: paperclip::v3::openapiv3::Schema = #schema_gen;
+                if !#docs.is_empty() {
+                    schema.schema_data.description = Some(#docs.to_string());
+                }
+                if let Some(example) = #example {
+                    schema.schema_data.example = Some(example);
+                }
+                schema
+            }
+
+            fn update_schemas(map: &mut std::collections::BTreeMap<String, paperclip::v3::openapiv3::Schema>) {
+                let name = #schema_name.to_string();
+                // Insert this schema before its dependencies, so that recursive types terminate.
+                if !map.contains_key(&name) {
+                    map.insert(name, <Self as paperclip::v3::schema::Apiv3Schema>::raw_schema());
+                    #(
+                        <#field_types as paperclip::v3::schema::Apiv3Schema>::update_schemas(map);
+                    )*
+                }
+            }
+        }
+
+        #opt_impl
+    };
+
+    gen.into()
+}
+
+/// Returns a token stream evaluating to the (optional) example in the given attributes.
+fn quote_example(attrs: &[Attribute]) -> TokenStream2 {
+    if let Some(example) = extract_example(attrs) {
+        // allow to parse escaped json string or single str value
+        quote!(
+            paperclip::v2::serde_json::from_str::<paperclip::v2::serde_json::Value>(#example).ok().or_else(|| Some(#example.into()))
+        )
+    } else {
+        quote!(None::<paperclip::v2::serde_json::Value>)
+    }
+}
+
+/// Checks for `#[openapi(nullable)]` in the given field attributes.
+fn is_nullable(attrs: &[Attribute]) -> bool {
+    extract_openapi_attrs(attrs).any(|nested| {
+        nested.iter().any(
+            |meta| matches!(meta, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("nullable")),
+        )
+    })
+}
+
+/// Generates the property of a (non-flattened) field.
+fn field_property(
+    schema: &TokenStream2,
+    name: &str,
+    ty: &Type,
+    attrs: &[Attribute],
+) -> TokenStream2 {
+    let docs = extract_documentation(attrs);
+    let docs = docs.trim();
+    let example = quote_example(attrs);
+    let nullable = if is_nullable(attrs) {
+        quote!(let s = paperclip::v3::schema::nullable(s);)
+    } else {
+        quote!()
+    };
+
+    quote!({
+        let s = paperclip::v3::schema::describe(
+            <#ty as paperclip::v3::schema::Apiv3Schema>::schema_with_ref(),
+            #docs,
+            #example,
+        );
+        #nullable
+        paperclip::v3::schema::insert_property(
+            &mut #schema,
+            #name,
+            s,
+            <#ty as paperclip::v3::schema::Apiv3Schema>::required(),
+        );
+    })
+}
+
+/// Generates the properties of a flattened field.
+fn flattened_properties(schema: &TokenStream2, ty: &Type) -> TokenStream2 {
+    quote!({
+        paperclip::v3::schema::extend_properties(
+            &mut #schema,
+            <#ty as paperclip::v3::schema::Apiv3Schema>::raw_schema(),
+            <#ty as paperclip::v3::schema::Apiv3Schema>::required(),
+        );
+    })
+}
+
+/// Generates an object schema for named fields.
+fn named_fields_schema(
+    fields: &FieldsNamed,
+    rename: Option<SerdeRename>,
+    field_types: &mut Vec<Type>,
+) -> TokenStream2 {
+    let schema = quote!(schema);
+    let mut gen = quote!(let mut schema = paperclip::v3::schema::object_schema(););
+    for field in &fields.named {
+        let mut field_name = field
+            .ident
+            .as_ref()
+            .expect("missing field name?")
+            .to_string();
+
+        //Strip r# prefix if any
+        field_name = field_name
+            .strip_prefix("r#")
+            .map(|n| n.to_string())
+            .unwrap_or(field_name);
+
+        if SerdeSkip::exists(&field.attrs) {
+            continue;
+        }
+
+        if let Some(renamed) = SerdeRename::from_field_attrs(&field.attrs) {
+            field_name = renamed;
+        } else if let Some(prop) = rename {
+            field_name = prop.rename(&field_name);
+        }
+
+        field_types.push(field.ty.clone());
+        if SerdeFlatten::exists(&field.attrs) {
+            gen.extend(flattened_properties(&schema, &field.ty));
+        } else {
+            gen.extend(field_property(
+                &schema,
+                &field_name,
+                &field.ty,
+                &field.attrs,
+            ));
+        }
+    }
+
+    quote!({
+        #gen
+        schema
+    })
+}
+
+/// Generates the schema for a tuple struct. Newtypes take the schema of the
+/// inner type, while other tuple structs use the indices as property names.
+fn unnamed_fields_schema(fields: &FieldsUnnamed, field_types: &mut Vec<Type>) -> TokenStream2 {
+    if fields.unnamed.len() == 1 {
+        let field = fields.unnamed.iter().next().unwrap();
+        if SerdeSkip::exists(&field.attrs) {
+            return quote!(paperclip::v3::schema::any_schema());
+        }
+
+        let ty = &field.ty;
+        field_types.push(ty.clone());
+        return quote!(
+            match <#ty as paperclip::v3::schema::Apiv3Schema>::schema_with_ref() {
+                paperclip::v3::openapiv3::ReferenceOr::Item(s) => s,
+                reference => paperclip::v3::schema::all_of(vec![reference]),
+            }
+        );
+    }
+
+    let schema = quote!(schema);
+    let mut gen = quote!(let mut schema = paperclip::v3::schema::object_schema(););
+    for (inner_field_id, field) in fields.unnamed.iter().enumerate() {
+        if SerdeSkip::exists(&field.attrs) {
+            continue;
+        }
+
+        field_types.push(field.ty.clone());
+        if SerdeFlatten::exists(&field.attrs) {
+            gen.extend(flattened_properties(&schema, &field.ty));
+        } else {
+            gen.extend(field_property(
+                &schema,
+                &inner_field_id.to_string(),
+                &field.ty,
+                &field.attrs,
+            ));
+        }
+    }
+
+    quote!({
+        #gen
+        schema
+    })
+}
+
+/// Enum representations supported by serde (https://serde.rs/enum-representations.html).
+enum EnumRepr {
+    External,
+    Internal(String),
+    Adjacent(String, String),
+    Untagged,
+}
+
+impl EnumRepr {
+    /// Reads the representation from the serde attributes of the given enum.
+    fn from_item_attrs(attrs: &[Attribute], serde: &SerdeProps) -> Self {
+        let mut untagged = false;
+        let mut content = None;
+        for meta in attrs.iter().filter_map(|a| a.parse_meta().ok()) {
+            let inner_meta = match meta {
+                Meta::List(ref l) if l.path.is_ident("serde") => &l.nested,
+                _ => continue,
+            };
+
+            for meta in inner_meta {
+                match meta {
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("untagged") => untagged = true,
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("content") => {
+                        if let Lit::Str(s) = &nv.lit {
+                            content = Some(s.value());
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        match (&serde.tag, content) {
+            _ if untagged => EnumRepr::Untagged,
+            (Some(tag), Some(content)) => EnumRepr::Adjacent(tag.clone(), content),
+            (Some(tag), None) => EnumRepr::Internal(tag.clone()),
+            (None, _) => EnumRepr::External,
+        }
+    }
+}
+
+/// Generates the schema for an enum. Enums with only unit variants are string
+/// enums, while others are represented with `oneOf` (based on the serde representation).
+fn enum_schema(
+    e: &DataEnum,
+    attrs: &[Attribute],
+    serde: &SerdeProps,
+    field_types: &mut Vec<Type>,
+) -> TokenStream2 {
+    let repr = EnumRepr::from_item_attrs(attrs, serde);
+    let variants: Vec<_> = e
+        .variants
+        .iter()
+        .filter(|var| !SerdeSkip::exists(&var.attrs))
+        .map(|var| {
+            let mut name = var.ident.to_string();
+            if let Some(renamed) = SerdeRename::from_field_attrs(&var.attrs) {
+                name = renamed;
+            } else if let Some(prop) = serde.rename {
+                name = prop.rename(&name);
+            }
+
+            (name, var)
+        })
+        .collect();
+
+    let all_unit = variants
+        .iter()
+        .all(|(_, var)| matches!(var.fields, Fields::Unit));
+    if all_unit && matches!(repr, EnumRepr::External) {
+        let names = variants.iter().map(|(name, _)| name);
+        return quote!(paperclip::v3::schema::string_enum(&[#(#names),*]));
+    }
+
+    let mut gen = quote!(let mut variants = vec![];);
+    for (name, var) in &variants {
+        // Schema of the data in this variant (if any).
+        let content = match &var.fields {
+            Fields::Unit => None,
+            Fields::Named(f) => {
+                let object = named_fields_schema(f, None, field_types);
+                Some(quote!(paperclip::v3::openapiv3::ReferenceOr::Item(#object)))
+            }
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => {
+                let ty = &f.unnamed[0].ty;
+                field_types.push(ty.clone());
+                Some(quote!(<#ty as paperclip::v3::schema::Apiv3Schema>::schema_with_ref()))
+            }
+            Fields::Unnamed(f) => {
+                emit_warning!(f.span().unwrap(), "skipping tuple enum variant in schema.");
+                continue;
+            }
+        };
+
+        let tagged = |tag: &str| {
+            quote!(
+                let mut s = paperclip::v3::schema::object_schema();
+                paperclip::v3::schema::insert_property(
+                    &mut s,
+                    #tag,
+                    paperclip::v3::openapiv3::ReferenceOr::Item(paperclip::v3::schema::string_enum(&[#name])),
+                    true,
+                );
+            )
+        };
+
+        let variant = match (&repr, content) {
+            (EnumRepr::External, None) => {
+                quote!(paperclip::v3::openapiv3::ReferenceOr::Item(paperclip::v3::schema::string_enum(&[#name])))
+            }
+            (EnumRepr::External, Some(content)) => quote!({
+                let mut s = paperclip::v3::schema::object_schema();
+                paperclip::v3::schema::insert_property(&mut s, #name, #content, true);
+                paperclip::v3::openapiv3::ReferenceOr::Item(s)
+            }),
+            (EnumRepr::Untagged, None) => {
+                emit_warning!(
+                    var.span().unwrap(),
+                    "skipping unit variant of untagged enum in schema."
+                );
+                continue;
+            }
+            (EnumRepr::Untagged, Some(content)) => content,
+            (EnumRepr::Internal(tag), content) => {
+                let tagged = tagged(tag);
+                let with_content = match (&var.fields, content) {
+                    (_, None) => quote!(),
+                    (Fields::Named(_), Some(content)) => quote!(
+                        if let paperclip::v3::openapiv3::ReferenceOr::Item(content) = #content {
+                            paperclip::v3::schema::extend_properties(&mut s, content, true);
+                        }
+                    ),
+                    (_, Some(content)) => quote!(
+                        let s = paperclip::v3::schema::all_of(vec![
+                            #content,
+                            paperclip::v3::openapiv3::ReferenceOr::Item(s),
+                        ]);
+                    ),
+                };
+                quote!({
+                    #tagged
+                    #with_content
+                    paperclip::v3::openapiv3::ReferenceOr::Item(s)
+                })
+            }
+            (EnumRepr::Adjacent(tag, content_key), content) => {
+                let tagged = tagged(tag);
+                let with_content = content.map(|content| {
+                    quote!(paperclip::v3::schema::insert_property(&mut s, #content_key, #content, true);)
+                });
+                quote!({
+                    #tagged
+                    #with_content
+                    paperclip::v3::openapiv3::ReferenceOr::Item(s)
+                })
+            }
+        };
+
+        gen.extend(quote!(variants.push(#variant);));
+    }
+
+    let discriminator = match &repr {
+        EnumRepr::Internal(tag) | EnumRepr::Adjacent(tag, _) => {
+            quote!(paperclip::v3::schema::set_discriminator(&mut schema, #tag);)
+        }
+        _ => quote!(),
+    };
+
+    quote!({
+        #gen
+        let mut schema = paperclip::v3::schema::one_of(variants);
+        #discriminator
+        schema
+    })
+}
+
+/// Emits the v3 `OperationModifier` impl for a type deriving `Apiv3Schema`.
+fn add_optional_impl(name: &Ident, generics: &Generics) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics paperclip::v3::actix::OperationModifier for #name #ty_generics #where_clause {
+            fn update_schemas(map: &mut std::collections::BTreeMap<String, paperclip::v3::openapiv3::Schema>) {
+                <Self as paperclip::v3::schema::Apiv3Schema>::update_schemas(map);
+            }
+        }
+    }
+}
+
+/// Emits the v3 `OperationModifier` impl for a type deriving `Apiv2Security`.
+pub fn security_modifier(name: &Ident, generics: &Generics) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics paperclip::v3::actix::OperationModifier for #name #ty_generics #where_clause {
+            fn update_security(op: &mut paperclip::v3::openapiv3::Operation) {
+                paperclip::v3::actix::update_security::<Self>(op);
+            }
+
+            fn update_security_schemes(map: &mut std::collections::BTreeMap<String, paperclip::v2::models::SecurityScheme>) {
+                paperclip::v3::actix::update_security_schemes::<Self>(map);
+            }
+        }
+    }
+}
+
+/// Emits the v3 `OperationModifier` impl for a type deriving `Apiv2Header`.
+pub fn header_modifier(name: &Ident, generics: &Generics) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics paperclip::v3::actix::OperationModifier for #name #ty_generics #where_clause {
+            fn update_parameter(op: &mut paperclip::v3::openapiv3::Operation) {
+                paperclip::v3::actix::update_parameter::<Self>(op);
+            }
+        }
+    }
+}
@@ -62,6 +62,7 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
             pub fn remove_refs(&mut self) {
                 self.properties.values_mut().for_each(|s| s.remove_refs());
                 self.items.as_mut().map(|s| s.remove_refs());
+                self.all_of.iter_mut().for_each(|s| s.remove_refs());
                 self.extra_props.as_mut().and_then(|s| s.right_mut()).map(|s| s.remove_refs());
                 self.reference = None;
             }
@@ -76,6 +77,7 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
                 } else {
                     self.properties.values_mut().for_each(|s| s.retain_ref());
                     self.items.as_mut().map(|s| s.retain_ref());
+                    self.all_of.iter_mut().for_each(|s| s.retain_ref());
                     self.extra_props.as_mut().and_then(|s| s.right_mut()).map(|s| s.retain_ref());
                 }
             }
@@ -102,6 +104,16 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
                 self.cyclic
             }
 
+            #[inline]
+            fn add_subtype(&mut self, subtype: &std::sync::Arc<std::sync::RwLock<Self>>) {
+                self.subtypes.push(std::sync::Arc::downgrade(subtype));
+            }
+
+            #[inline]
+            fn subtypes(&self) -> Vec<std::sync::Arc<std::sync::RwLock<Self>>> {
+                self.subtypes.iter().filter_map(std::sync::Weak::upgrade).collect()
+            }
+
             #[inline]
             fn description(&self) -> Option<&str> {
                 self.description.as_ref().map(String::as_str)
@@ -174,6 +186,99 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
                 }
             }
 
+            #[inline]
+            fn all_of(&self) -> Option<&[paperclip::v2::models::Resolvable<Self>]> {
+                if self.all_of.is_empty() {
+                    None
+                } else {
+                    Some(&self.all_of)
+                }
+            }
+
+            #[inline]
+            fn all_of_mut(&mut self) -> Option<&mut [paperclip::v2::models::Resolvable<Self>]> {
+                if self.all_of.is_empty() {
+                    None
+                } else {
+                    Some(&mut self.all_of)
+                }
+            }
+
+            #[inline]
+            fn discriminator(&self) -> Option<&str> {
+                self.discriminator.as_ref().map(String::as_str)
+            }
+
+            #[inline]
+            fn xml(&self) -> Option<&paperclip::v2::models::Xml> {
+                self.xml.as_ref()
+            }
+
+            #[inline]
+            fn example(&self) -> Option<&paperclip::v2::serde_json::Value> {
+                self.example.as_ref()
+            }
+
+            #[inline]
+            fn default_value(&self) -> Option<&paperclip::v2::serde_json::Value> {
+                self.default.as_ref()
+            }
+
+            #[inline]
+            fn maximum(&self) -> Option<f32> {
+                self.maximum
+            }
+
+            #[inline]
+            fn minimum(&self) -> Option<f32> {
+                self.minimum
+            }
+
+            #[inline]
+            fn exclusive_maximum(&self) -> bool {
+                self.exclusive_maximum.unwrap_or(false)
+            }
+
+            #[inline]
+            fn exclusive_minimum(&self) -> bool {
+                self.exclusive_minimum.unwrap_or(false)
+            }
+
+            #[inline]
+            fn multiple_of(&self) -> Option<f32> {
+                self.multiple_of
+            }
+
+            #[inline]
+            fn max_length(&self) -> Option<u32> {
+                self.max_length
+            }
+
+            #[inline]
+            fn min_length(&self) -> Option<u32> {
+                self.min_length
+            }
+
+            #[inline]
+            fn pattern(&self) -> Option<&str> {
+                self.pattern.as_deref()
+            }
+
+            #[inline]
+            fn max_items(&self) -> Option<u32> {
+                self.max_items
+            }
+
+            #[inline]
+            fn min_items(&self) -> Option<u32> {
+                self.min_items
+            }
+
+            #[inline]
+            fn unique_items(&self) -> bool {
+                self.unique_items.unwrap_or(false)
+            }
+
             #[inline]
             fn enum_variants(&self) -> Option<&[paperclip::v2::serde_json::Value]> {
                 if self.enum_.is_empty() {
@@ -292,10 +397,70 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
         #[serde(skip_serializing_if = "Option::is_none")]
         pub minimum: Option<f32>,
     ));
+    gen.extend(quote!(
+        #[serde(rename = "exclusiveMaximum", skip_serializing_if = "Option::is_none")]
+        pub exclusive_maximum: Option<bool>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "exclusiveMinimum", skip_serializing_if = "Option::is_none")]
+        pub exclusive_minimum: Option<bool>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "multipleOf", skip_serializing_if = "Option::is_none")]
+        pub multiple_of: Option<f32>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+        pub max_length: Option<u32>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "minLength", skip_serializing_if = "Option::is_none")]
+        pub min_length: Option<u32>,
+    ));
+    gen.extend(quote!(
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub pattern: Option<String>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "maxItems", skip_serializing_if = "Option::is_none")]
+        pub max_items: Option<u32>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "minItems", skip_serializing_if = "Option::is_none")]
+        pub min_items: Option<u32>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "uniqueItems", skip_serializing_if = "Option::is_none")]
+        pub unique_items: Option<bool>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "maxProperties", skip_serializing_if = "Option::is_none")]
+        pub max_properties: Option<u32>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "minProperties", skip_serializing_if = "Option::is_none")]
+        pub min_properties: Option<u32>,
+    ));
+    gen.extend(quote!(
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub default: Option<paperclip::v2::serde_json::Value>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "readOnly", skip_serializing_if = "Option::is_none")]
+        pub read_only: Option<bool>,
+    ));
+    gen.extend(quote!(
+        #[serde(rename = "externalDocs", skip_serializing_if = "Option::is_none")]
+        pub external_docs: Option<paperclip::v2::models::ExternalDocs>,
+    ));
     gen.extend(quote!(
         #[serde(skip_serializing_if = "Option::is_none")]
         pub example: Option<paperclip::v2::serde_json::Value>,
     ));
+    gen.extend(quote!(
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub xml: Option<paperclip::v2::models::Xml>,
+    ));
 
     gen.extend(quote!(
         #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
@@ -311,6 +476,13 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
     add_self(&mut gen);
     gen.extend(quote!(>,));
 
+    gen.extend(quote!(
+        #[serde(default, rename = "allOf", skip_serializing_if = "Vec::is_empty")]
+        pub all_of: Vec<
+    ));
+    add_self(&mut gen);
+    gen.extend(quote!(>,));
+
     gen.extend(quote!(
         #[serde(default, rename = "enum", skip_serializing_if = "Vec::is_empty")]
         pub enum_: Vec<paperclip::v2::serde_json::Value>,
@@ -328,11 +500,31 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
         pub required: std::collections::BTreeSet<String>,
     ));
 
+    gen.extend(quote!(
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub discriminator: Option<String>,
+    ));
+
+    gen.extend(quote!(
+        #[serde(
+            flatten,
+            skip_serializing_if = "std::collections::BTreeMap::is_empty",
+            deserialize_with = "paperclip::v2::models::deserialize_extensions"
+        )]
+        pub extensions: std::collections::BTreeMap<String, paperclip::v2::serde_json::Value>,
+    ));
+
     if is_ref {
         gen.extend(quote!(
             #[serde(skip)]
             cyclic: bool,
         ));
+        // Subtypes refer to their base through `allOf`, so we hold weak
+        // references to avoid cycles.
+        gen.extend(quote!(
+            #[serde(skip)]
+            subtypes: Vec<std::sync::Weak<std::sync::RwLock<#name>>>,
+        ));
     }
 
     quote!({
@@ -43,6 +43,14 @@ pub fn api_v2_operation(attr: TokenStream, input: TokenStream) -> TokenStream {
     self::actix::emit_v2_operation(attr, input)
 }
 
+/// Marker attribute for indicating that a function is a native OpenAPI v3 operation.
+#[cfg(all(feature = "actix", feature = "v3"))]
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn api_v3_operation(attr: TokenStream, input: TokenStream) -> TokenStream {
+    self::actix::v3::emit_v3_operation(attr, input)
+}
+
 /// Derive attribute for indicating that a type is an OpenAPI v2 compatible definition.
 #[cfg(feature = "actix")]
 #[proc_macro_error]
@@ -51,6 +59,14 @@ pub fn api_v2_schema(input: TokenStream) -> TokenStream {
     self::actix::emit_v2_definition(input)
 }
 
+/// Derive attribute for indicating that a type is a native OpenAPI v3 schema.
+#[cfg(all(feature = "actix", feature = "v3"))]
+#[proc_macro_error]
+#[proc_macro_derive(Apiv3Schema, attributes(openapi))]
+pub fn api_v3_schema(input: TokenStream) -> TokenStream {
+    self::actix::v3::emit_v3_definition(input)
+}
+
 /// Marker attribute for indicating that an object forbids public access to operation (for example AccessToken).
 #[cfg(feature = "actix")]
 #[proc_macro_error]
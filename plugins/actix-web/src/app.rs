@@ -3,6 +3,8 @@
 extern crate actix_service2 as actix_service;
 extern crate actix_web4 as actix_web;
 
+#[cfg(feature = "v3")]
+use super::OperationsV3;
 #[cfg(feature = "rapidoc")]
 use super::RAPIDOC;
 #[cfg(feature = "swagger-ui")]
@@ -12,15 +14,19 @@ use super::{
     Mountable,
 };
 use actix_service::ServiceFactory;
-#[cfg(feature = "swagger-ui")]
-use actix_web::HttpRequest;
 use actix_web::{
     body::MessageBody,
     dev::{HttpServiceFactory, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpResponse,
+    http::header,
+    Error, HttpRequest, HttpResponse,
 };
 use futures::future::{ok as fut_ok, Ready};
-use paperclip_core::v2::models::{DefaultApiRaw, SecurityScheme};
+#[cfg(feature = "v3")]
+use paperclip_core::v2::models::HttpMethod;
+use paperclip_core::v2::models::{DefaultApiRaw, SecurityScheme, SpecFormat};
+#[cfg(feature = "v3")]
+use paperclip_core::v3::v31;
+use serde::Serialize;
 #[cfg(feature = "rapidoc")]
 use tinytemplate::TinyTemplate;
 
@@ -36,6 +42,10 @@ pub struct App<T> {
     spec: Arc<RwLock<DefaultApiRaw>>,
     #[cfg(feature = "v3")]
     spec_v3: Option<Arc<RwLock<openapiv3::OpenAPI>>>,
+    #[cfg(feature = "v3")]
+    spec_v31: Option<Arc<RwLock<v31::OpenApi>>>,
+    #[cfg(feature = "v3")]
+    paths_v3: Arc<RwLock<BTreeMap<String, OperationsV3>>>,
     #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
     spec_path: Option<String>,
     inner: Option<actix_web::App<T>>,
@@ -63,6 +73,10 @@ impl<T> OpenApiExt<T> for actix_web::App<T> {
             spec: Arc::new(RwLock::new(DefaultApiRaw::default())),
             #[cfg(feature = "v3")]
             spec_v3: None,
+            #[cfg(feature = "v3")]
+            spec_v31: None,
+            #[cfg(feature = "v3")]
+            paths_v3: Default::default(),
             #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
             spec_path: None,
             inner: Some(self),
@@ -74,6 +88,10 @@ impl<T> OpenApiExt<T> for actix_web::App<T> {
             spec: Arc::new(RwLock::new(spec)),
             #[cfg(feature = "v3")]
             spec_v3: None,
+            #[cfg(feature = "v3")]
+            spec_v31: None,
+            #[cfg(feature = "v3")]
+            paths_v3: Default::default(),
             #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
             spec_path: None,
             inner: Some(self),
@@ -200,6 +218,10 @@ where
             spec: self.spec,
             #[cfg(feature = "v3")]
             spec_v3: self.spec_v3,
+            #[cfg(feature = "v3")]
+            spec_v31: self.spec_v31,
+            #[cfg(feature = "v3")]
+            paths_v3: self.paths_v3,
             #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
             spec_path: None,
             inner: self.inner.take().map(|a| a.wrap(mw)),
@@ -230,6 +252,10 @@ where
             spec: self.spec,
             #[cfg(feature = "v3")]
             spec_v3: self.spec_v3,
+            #[cfg(feature = "v3")]
+            spec_v31: self.spec_v31,
+            #[cfg(feature = "v3")]
+            paths_v3: self.paths_v3,
             #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
             spec_path: None,
             inner: self.inner.take().map(|a| a.wrap_fn(mw)),
@@ -239,44 +265,88 @@ where
     /// Mounts the specification for all operations and definitions
     /// recorded by the wrapper and serves them in the given path
     /// as a JSON.
-    pub fn with_json_spec_at(mut self, path: &str) -> Self {
-        #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
-        {
-            self.spec_path = Some(path.to_owned());
-        }
+    pub fn with_json_spec_at(self, path: &str) -> Self {
+        self.mount_spec_v2(path, Some(SpecFormat::Json))
+    }
 
-        self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource(path)
-                    .route(actix_web::web::get().to(SpecHandler(self.spec.clone()))),
-            )
-        });
-        self
+    /// Same as `with_json_spec_at`, but serves the specification as a YAML.
+    pub fn with_yaml_spec_at(self, path: &str) -> Self {
+        self.mount_spec_v2(path, Some(SpecFormat::Yaml))
+    }
+
+    /// Same as `with_json_spec_at`, but serves the specification as a YAML
+    /// for requests which prefer it (with `Accept: application/yaml`) and as
+    /// a JSON otherwise.
+    pub fn with_spec_at(self, path: &str) -> Self {
+        self.mount_spec_v2(path, None)
     }
 
     #[cfg(feature = "v3")]
-    /// Converts the generated v2 specification to v3 and then
-    /// mounts the v3 specification for all operations and definitions
+    /// Mounts the v3 specification for all operations and definitions
     /// recorded by the wrapper and serves them in the given path
     /// as a JSON.
-    pub fn with_json_spec_v3_at(mut self, path: &str) -> Self {
+    ///
+    /// Operations recorded by `api_v3_operation` handlers are used as they are,
+    /// while the remaining (v2) operations are converted to v3.
+    pub fn with_json_spec_v3_at(self, path: &str) -> Self {
+        self.mount_spec_v3(path, Some(SpecFormat::Json))
+    }
+
+    #[cfg(feature = "v3")]
+    /// Same as `with_json_spec_v3_at`, but serves the v3 specification as a YAML.
+    pub fn with_yaml_spec_v3_at(self, path: &str) -> Self {
+        self.mount_spec_v3(path, Some(SpecFormat::Yaml))
+    }
+
+    #[cfg(feature = "v3")]
+    /// Same as `with_json_spec_v3_at`, but serves the v3 specification as a YAML
+    /// for requests which prefer it (with `Accept: application/yaml`) and as
+    /// a JSON otherwise.
+    pub fn with_spec_v3_at(self, path: &str) -> Self {
+        self.mount_spec_v3(path, None)
+    }
+
+    #[cfg(feature = "v3")]
+    /// Mounts the v3.1 specification for all operations and definitions
+    /// recorded by the wrapper and serves them in the given path
+    /// as a JSON.
+    ///
+    /// This is the same spec as the one mounted by `with_json_spec_v3_at`,
+    /// upgraded to OpenAPI 3.1 (JSON Schema 2020-12).
+    pub fn with_json_spec_v31_at(mut self, path: &str) -> Self {
+        let spec_v31 = self
+            .spec_v31
+            .get_or_insert_with(|| Arc::new(RwLock::new(v31::OpenApi::default())))
+            .clone();
+        self.mount_spec(path, SpecHandler(spec_v31, Some(SpecFormat::Json)))
+    }
+
+    fn mount_spec_v2(self, path: &str, format: Option<SpecFormat>) -> Self {
+        let spec = self.spec.clone();
+        self.mount_spec(path, SpecHandler(spec, format))
+    }
+
+    #[cfg(feature = "v3")]
+    fn mount_spec_v3(mut self, path: &str, format: Option<SpecFormat>) -> Self {
+        let spec_v3 = self
+            .spec_v3
+            .get_or_insert_with(|| Arc::new(RwLock::new(openapiv3::OpenAPI::default())))
+            .clone();
+        self.mount_spec(path, SpecHandler(spec_v3, format))
+    }
+
+    /// Serves the specification with the given handler in the given path.
+    fn mount_spec<S>(mut self, path: &str, handler: SpecHandler<S>) -> Self
+    where
+        S: Serialize + 'static,
+    {
         #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
         {
             self.spec_path = Some(path.to_owned());
         }
 
-        let spec_v3 = if let Some(spec_v3) = &self.spec_v3 {
-            spec_v3.clone()
-        } else {
-            let spec_v3 = Arc::new(RwLock::new(openapiv3::OpenAPI::default()));
-            self.spec_v3 = Some(spec_v3.clone());
-            spec_v3
-        };
         self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource(path)
-                    .route(actix_web::web::get().to(SpecHandlerV3(spec_v3.clone()))),
-            )
+            a.service(actix_web::web::resource(path).route(actix_web::web::get().to(handler)))
         });
         self
     }
@@ -306,8 +376,22 @@ where
     where
         F: FnMut(Self, serde_json::Value) -> Self,
     {
-        let v3 = paperclip_core::v3::openapiv2_to_v3(self.spec.read().unwrap().clone());
-        let spec = serde_json::to_value(v3).expect("generating json spec");
+        let spec = serde_json::to_value(self.build_spec_v3()).expect("generating json spec");
+        call(self, spec)
+    }
+
+    #[cfg(feature = "v3")]
+    /// Calls the given function with `App` and JSON `Value` representing your API
+    /// v2 specification **built until now** which is converted to v3.1.
+    ///
+    /// **NOTE:** Unlike `with_json_spec_at`, this only has the API spec built until
+    /// this function call. Any route handler added after this call won't affect the
+    /// spec. So, it's important to call this function after adding all route handlers.
+    pub fn with_raw_json_spec_v31<F>(self, mut call: F) -> Self
+    where
+        F: FnMut(Self, serde_json::Value) -> Self,
+    {
+        let spec = serde_json::to_value(self.build_spec_v31()).expect("generating json spec");
         call(self, spec)
     }
 
@@ -415,13 +499,69 @@ where
     /// Builds and returns the `actix_web::App`.
     pub fn build(self) -> actix_web::App<T> {
         #[cfg(feature = "v3")]
-        if let Some(v3) = self.spec_v3 {
-            let mut v3 = v3.write().unwrap();
-            *v3 = paperclip_core::v3::openapiv2_to_v3(self.spec.read().unwrap().clone());
+        if let Some(v3) = &self.spec_v3 {
+            let spec = self.build_spec_v3();
+            *v3.write().unwrap() = spec;
+        }
+        #[cfg(feature = "v3")]
+        if let Some(v31) = &self.spec_v31 {
+            let spec = self.build_spec_v31();
+            *v31.write().unwrap() = spec;
         }
         self.inner.expect("missing app?")
     }
 
+    /// Converts the v2 spec to v3 and replaces the converted operations
+    /// with the native v3 operations (if any).
+    #[cfg(feature = "v3")]
+    fn build_spec_v3(&self) -> openapiv3::OpenAPI {
+        let mut spec = paperclip_core::v3::openapiv2_to_v3(self.spec.read().unwrap().clone());
+        let components = spec.components.get_or_insert_with(Default::default);
+        for (path, ops) in &*self.paths_v3.read().unwrap() {
+            let item = spec
+                .paths
+                .paths
+                .entry(path.clone())
+                .or_insert_with(|| openapiv3::ReferenceOr::Item(Default::default()));
+            let item = match item {
+                openapiv3::ReferenceOr::Item(item) => item,
+                _ => continue,
+            };
+
+            for (method, (op, op_components)) in ops {
+                let slot = match method {
+                    HttpMethod::Get => &mut item.get,
+                    HttpMethod::Put => &mut item.put,
+                    HttpMethod::Post => &mut item.post,
+                    HttpMethod::Delete => &mut item.delete,
+                    HttpMethod::Options => &mut item.options,
+                    HttpMethod::Head => &mut item.head,
+                    HttpMethod::Patch => &mut item.patch,
+                };
+                *slot = Some(op.clone());
+
+                for (name, schema) in &op_components.schemas {
+                    components.schemas.insert(name.clone(), schema.clone());
+                }
+                for (name, scheme) in &op_components.security_schemes {
+                    if !components.security_schemes.contains_key(name) {
+                        components
+                            .security_schemes
+                            .insert(name.clone(), scheme.clone());
+                    }
+                }
+            }
+        }
+
+        spec
+    }
+
+    /// Upgrades the v3 spec to v3.1.
+    #[cfg(feature = "v3")]
+    fn build_spec_v31(&self) -> v31::OpenApi {
+        paperclip_core::v3::openapiv3_to_v31(&self.build_spec_v3()).expect("upgrading spec to v3.1")
+    }
+
     /// Trim's the Api base path from the start of all method paths.
     /// **NOTE:** much like `with_raw_json_spec` this only has the API spec built until
     /// this function call. Any route handler added after this call won't have the base path trimmed.
@@ -438,6 +578,17 @@ where
                 i
             });
         }
+
+        #[cfg(feature = "v3")]
+        {
+            let base_path = self.spec.read().unwrap().base_path.clone();
+            let base_path = base_path.unwrap_or_default();
+            let mut paths = self.paths_v3.write().unwrap();
+            *paths = std::mem::take(&mut *paths)
+                .into_iter()
+                .map(|(k, v)| (k.trim_start_matches(base_path.as_str()).to_string(), v))
+                .collect();
+        }
         self
     }
 
@@ -453,6 +604,8 @@ where
             &mut api.security_definitions,
         );
         factory.update_operations(&mut api.paths);
+        #[cfg(feature = "v3")]
+        factory.update_operations_v3(&mut self.paths_v3.write().unwrap());
         if cfg!(feature = "normalize") {
             for map in api.paths.values_mut() {
                 map.normalize();
@@ -461,28 +614,25 @@ where
     }
 }
 
-#[derive(Clone)]
-struct SpecHandler(Arc<RwLock<DefaultApiRaw>>);
-
-impl actix_web::dev::Handler<()> for SpecHandler {
-    type Output = Result<HttpResponse, Error>;
-    type Future = Ready<Self::Output>;
+/// Serves the spec in a fixed format, or in the one accepted by the client
+/// (if the format is `None`).
+struct SpecHandler<S>(Arc<RwLock<S>>, Option<SpecFormat>);
 
-    fn call(&self, _: ()) -> Self::Future {
-        fut_ok(HttpResponse::Ok().json(&*self.0.read().unwrap()))
+impl<S> Clone for SpecHandler<S> {
+    fn clone(&self) -> Self {
+        SpecHandler(self.0.clone(), self.1)
     }
 }
 
-#[cfg(feature = "v3")]
-#[derive(Clone)]
-struct SpecHandlerV3(Arc<RwLock<openapiv3::OpenAPI>>);
-
-#[cfg(feature = "v3")]
-impl actix_web::dev::Handler<()> for SpecHandlerV3 {
+impl<S: Serialize + 'static> actix_web::dev::Handler<(HttpRequest,)> for SpecHandler<S> {
     type Output = Result<HttpResponse, Error>;
     type Future = Ready<Self::Output>;
 
-    fn call(&self, _: ()) -> Self::Future {
-        fut_ok(HttpResponse::Ok().json(&*self.0.read().unwrap()))
+    fn call(&self, (req,): (HttpRequest,)) -> Self::Future {
+        fut_ok(super::spec_response(
+            &*self.0.read().unwrap(),
+            self.1,
+            req.headers().get(header::ACCEPT),
+        ))
     }
 }
@@ -16,15 +16,15 @@ use super::{
     Mountable,
 };
 use actix_service1::ServiceFactory;
-#[cfg(feature = "swagger-ui")]
-use actix_web::web::HttpRequest;
 use actix_web::{
     dev::{HttpServiceFactory, MessageBody, ServiceRequest, ServiceResponse, Transform},
-    web::HttpResponse,
+    http::header,
+    web::{HttpRequest, HttpResponse},
     Error,
 };
 use futures::future::{ok as fut_ok, Ready};
-use paperclip_core::v2::models::{DefaultApiRaw, SecurityScheme};
+use paperclip_core::v2::models::{DefaultApiRaw, SecurityScheme, SpecFormat};
+use serde::Serialize;
 #[cfg(feature = "rapidoc")]
 use tinytemplate::TinyTemplate;
 
@@ -260,17 +260,31 @@ where
     /// Mounts the specification for all operations and definitions
     /// recorded by the wrapper and serves them in the given path
     /// as a JSON.
-    pub fn with_json_spec_at(mut self, path: &str) -> Self {
+    pub fn with_json_spec_at(self, path: &str) -> Self {
+        self.mount_spec_v2(path, Some(SpecFormat::Json))
+    }
+
+    /// Same as `with_json_spec_at`, but serves the specification as a YAML.
+    pub fn with_yaml_spec_at(self, path: &str) -> Self {
+        self.mount_spec_v2(path, Some(SpecFormat::Yaml))
+    }
+
+    /// Same as `with_json_spec_at`, but serves the specification as a YAML
+    /// for requests which prefer it (with `Accept: application/yaml`) and as
+    /// a JSON otherwise.
+    pub fn with_spec_at(self, path: &str) -> Self {
+        self.mount_spec_v2(path, None)
+    }
+
+    fn mount_spec_v2(mut self, path: &str, format: Option<SpecFormat>) -> Self {
         #[cfg(any(feature = "swagger-ui", feature = "rapidoc"))]
         {
             self.spec_path = Some(path.to_owned());
         }
 
+        let handler = SpecHandler(self.spec.clone(), format);
         self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource(path)
-                    .route(actix_web::web::get().to(SpecHandler(self.spec.clone()))),
-            )
+            a.service(actix_web::web::resource(path).route(actix_web::web::get().to(handler)))
         });
         self
     }
@@ -280,19 +294,33 @@ where
     /// mounts the v3 specification for all operations and definitions
     /// recorded by the wrapper and serves them in the given path
     /// as a JSON.
-    pub fn with_json_spec_v3_at(mut self, path: &str) -> Self {
-        let spec_v3 = if let Some(spec_v3) = &self.spec_v3 {
-            spec_v3.clone()
-        } else {
-            let spec_v3 = Arc::new(RwLock::new(openapiv3::OpenAPI::default()));
-            self.spec_v3 = Some(spec_v3.clone());
-            spec_v3
-        };
+    pub fn with_json_spec_v3_at(self, path: &str) -> Self {
+        self.mount_spec_v3(path, Some(SpecFormat::Json))
+    }
+
+    #[cfg(feature = "v3")]
+    /// Same as `with_json_spec_v3_at`, but serves the v3 specification as a YAML.
+    pub fn with_yaml_spec_v3_at(self, path: &str) -> Self {
+        self.mount_spec_v3(path, Some(SpecFormat::Yaml))
+    }
+
+    #[cfg(feature = "v3")]
+    /// Same as `with_json_spec_v3_at`, but serves the v3 specification as a YAML
+    /// for requests which prefer it (with `Accept: application/yaml`) and as
+    /// a JSON otherwise.
+    pub fn with_spec_v3_at(self, path: &str) -> Self {
+        self.mount_spec_v3(path, None)
+    }
+
+    #[cfg(feature = "v3")]
+    fn mount_spec_v3(mut self, path: &str, format: Option<SpecFormat>) -> Self {
+        let spec_v3 = self
+            .spec_v3
+            .get_or_insert_with(|| Arc::new(RwLock::new(openapiv3::OpenAPI::default())))
+            .clone();
+        let handler = SpecHandler(spec_v3, format);
         self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource(path)
-                    .route(actix_web::web::get().to(SpecHandlerV3(spec_v3.clone()))),
-            )
+            a.service(actix_web::web::resource(path).route(actix_web::web::get().to(handler)))
         });
         self
     }
@@ -468,26 +496,28 @@ where
     }
 }
 
-#[derive(Clone)]
-struct SpecHandler(Arc<RwLock<DefaultApiRaw>>);
+/// Serves the spec in a fixed format, or in the one accepted by the client
+/// (if the format is `None`).
+struct SpecHandler<S>(Arc<RwLock<S>>, Option<SpecFormat>);
 
-impl actix_web::dev::Factory<(), Ready<Result<HttpResponse, Error>>, Result<HttpResponse, Error>>
-    for SpecHandler
-{
-    fn call(&self, _: ()) -> Ready<Result<HttpResponse, Error>> {
-        fut_ok(HttpResponse::Ok().json(&*self.0.read().unwrap()))
+impl<S> Clone for SpecHandler<S> {
+    fn clone(&self) -> Self {
+        SpecHandler(self.0.clone(), self.1)
     }
 }
 
-#[cfg(feature = "v3")]
-#[derive(Clone)]
-struct SpecHandlerV3(Arc<RwLock<openapiv3::OpenAPI>>);
-
-#[cfg(feature = "v3")]
-impl actix_web::dev::Factory<(), Ready<Result<HttpResponse, Error>>, Result<HttpResponse, Error>>
-    for SpecHandlerV3
+impl<S: Serialize + 'static>
+    actix_web::dev::Factory<
+        (HttpRequest,),
+        Ready<Result<HttpResponse, Error>>,
+        Result<HttpResponse, Error>,
+    > for SpecHandler<S>
 {
-    fn call(&self, _: ()) -> Ready<Result<HttpResponse, Error>> {
-        fut_ok(HttpResponse::Ok().json(&*self.0.read().unwrap()))
+    fn call(&self, (req,): (HttpRequest,)) -> Ready<Result<HttpResponse, Error>> {
+        fut_ok(super::spec_response(
+            &*self.0.read().unwrap(),
+            self.1,
+            req.headers().get(header::ACCEPT),
+        ))
     }
 }
@@ -35,18 +35,28 @@ pub use paperclip_macros::{
     api_v2_errors, api_v2_errors_overlay, api_v2_operation, delete, get, head, patch, post, put,
     Apiv2Header, Apiv2Schema, Apiv2Security,
 };
+#[cfg(feature = "v3")]
+pub use paperclip_macros::{api_v3_operation, Apiv3Schema};
 
+use actix_web::{http::header, HttpResponse};
 use paperclip_core::v2::models::{
     DefaultOperationRaw, DefaultPathItemRaw, DefaultSchemaRaw, HttpMethod, SecurityScheme,
+    SpecFormat,
 };
+use serde::Serialize;
 
-use std::collections::BTreeMap;
+use std::{cmp::Ordering, collections::BTreeMap};
 
 #[cfg(feature = "swagger-ui")]
 static SWAGGER_DIST: Dir = include_dir!("$CARGO_MANIFEST_DIR/swagger-ui/dist");
 #[cfg(feature = "rapidoc")]
 static RAPIDOC: Dir = include_dir!("$CARGO_MANIFEST_DIR/rapidoc");
 
+/// Map of HTTP methods and the associated native v3 operations (along with
+/// the components used by them).
+#[cfg(feature = "v3")]
+pub type OperationsV3 = BTreeMap<HttpMethod, (openapiv3::Operation, openapiv3::Components)>;
+
 /// Indicates that this thingmabob has a path and a bunch of definitions and operations.
 pub trait Mountable {
     /// Where this thing gets mounted.
@@ -72,4 +82,92 @@ pub trait Mountable {
             op_map.methods.extend(operations);
         }
     }
+
+    /// Map of HTTP methods and the associated native v3 operations (if any).
+    #[cfg(feature = "v3")]
+    fn operations_v3(&mut self) -> OperationsV3 {
+        BTreeMap::new()
+    }
+
+    /// Updates the given map with native v3 operations tracked by this object.
+    #[cfg(feature = "v3")]
+    fn update_operations_v3(&mut self, map: &mut BTreeMap<String, OperationsV3>) {
+        let operations = self.operations_v3();
+        if !operations.is_empty() {
+            map.entry(self.path().into())
+                .or_default()
+                .extend(operations);
+        }
+    }
+}
+
+/// Picks the format for serving a spec from the value of the `Accept` header
+/// of the request. YAML is served only if it's preferred over JSON.
+fn negotiate_spec_format(accept: Option<&str>) -> SpecFormat {
+    let mut ranges: Vec<_> = accept
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let format = match parts.next()?.to_ascii_lowercase().as_str() {
+                "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                    SpecFormat::Yaml
+                }
+                "application/json" | "application/*" | "*/*" => SpecFormat::Json,
+                _ => return None,
+            };
+            let quality = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((quality, format))
+        })
+        .filter(|(quality, _)| *quality > 0.0)
+        .collect();
+
+    // Stable sort, so that the first of the equally preferred ranges wins.
+    ranges.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    ranges
+        .first()
+        .map(|(_, format)| *format)
+        .unwrap_or(SpecFormat::Json)
+}
+
+/// Serves the spec in the given format (or the one negotiated with the client,
+/// if the format isn't fixed).
+fn spec_response<S: Serialize>(
+    spec: &S,
+    format: Option<SpecFormat>,
+    accept: Option<&header::HeaderValue>,
+) -> HttpResponse {
+    let negotiated = format.is_none();
+    let format =
+        format.unwrap_or_else(|| negotiate_spec_format(accept.and_then(|v| v.to_str().ok())));
+    let (body, content_type) = match format {
+        SpecFormat::Json => (
+            serde_json::to_vec(spec).map_err(|e| e.to_string()),
+            "application/json",
+        ),
+        SpecFormat::Yaml => (
+            serde_yaml::to_string(spec)
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string()),
+            "application/yaml",
+        ),
+    };
+
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+
+    let mut resp = HttpResponse::Ok();
+    if negotiated {
+        #[cfg(feature = "actix4")]
+        resp.insert_header((header::VARY, "Accept"));
+        #[cfg(not(feature = "actix4"))]
+        resp.header(header::VARY, "Accept");
+    }
+
+    resp.content_type(content_type).body(body)
 }
@@ -13,6 +13,8 @@ pub use actix_web::{
 };
 
 use crate::Mountable;
+#[cfg(feature = "v3")]
+use crate::OperationsV3;
 use actix_service::ServiceFactory;
 use actix_web::{
     body::MessageBody,
@@ -28,6 +30,9 @@ use paperclip_core::v2::{
     schema::Apiv2Operation,
 };
 
+#[cfg(feature = "v3")]
+use paperclip_core::v3::actix::set_parameter_names_from_path_template;
+
 use std::{collections::BTreeMap, fmt::Debug, future::Future, mem};
 
 const METHODS: &[Method] = &[
@@ -46,6 +51,8 @@ const METHODS: &[Method] = &[
 pub struct Resource<R = actix_web::Resource> {
     path: String,
     operations: BTreeMap<HttpMethod, DefaultOperationRaw>,
+    #[cfg(feature = "v3")]
+    operations_v3: OperationsV3,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
     inner: R,
@@ -57,6 +64,8 @@ impl Resource {
         Resource {
             path: path.into(),
             operations: BTreeMap::new(),
+            #[cfg(feature = "v3")]
+            operations_v3: BTreeMap::new(),
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
             inner: actix_web::Resource::new(path),
@@ -89,6 +98,11 @@ impl<T> Mountable for Resource<T> {
         mem::take(&mut self.operations)
     }
 
+    #[cfg(feature = "v3")]
+    fn operations_v3(&mut self) -> OperationsV3 {
+        mem::take(&mut self.operations_v3)
+    }
+
     fn definitions(&mut self) -> BTreeMap<String, DefaultSchemaRaw> {
         mem::take(&mut self.definitions)
     }
@@ -122,6 +136,8 @@ where
     pub fn route(mut self, route: Route) -> Self {
         let w = RouteWrapper::from(&self.path, route);
         self.operations.extend(w.operations);
+        #[cfg(feature = "v3")]
+        self.operations_v3.extend(w.operations_v3);
         self.definitions.extend(w.definitions);
         SecurityScheme::append_map(w.security, &mut self.security);
         self.inner = self.inner.route(w.inner);
@@ -180,6 +196,8 @@ where
         Resource {
             path: self.path,
             operations: self.operations,
+            #[cfg(feature = "v3")]
+            operations_v3: self.operations_v3,
             definitions: self.definitions,
             security: self.security,
             inner: self.inner.wrap(mw),
@@ -211,6 +229,8 @@ where
         Resource {
             path: self.path,
             operations: self.operations,
+            #[cfg(feature = "v3")]
+            operations_v3: self.operations_v3,
             definitions: self.definitions,
             security: self.security,
             inner: self.inner.wrap_fn(mw),
@@ -250,6 +270,15 @@ where
 
             self.definitions.extend(U::definitions());
             SecurityScheme::append_map(U::security_definitions(), &mut self.security);
+
+            #[cfg(feature = "v3")]
+            if let Some((mut op, components)) = U::v3_operation() {
+                set_parameter_names_from_path_template(&mut op, &self.path);
+                for method in METHODS {
+                    self.operations_v3
+                        .insert(method.into(), (op.clone(), components.clone()));
+                }
+            }
         }
     }
 }
@@ -265,6 +294,8 @@ pub fn resource(path: &str) -> Resource {
 pub struct Scope<S = actix_web::Scope> {
     path: String,
     path_map: BTreeMap<String, DefaultPathItemRaw>,
+    #[cfg(feature = "v3")]
+    path_map_v3: BTreeMap<String, OperationsV3>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
     inner: Option<S>,
@@ -276,6 +307,8 @@ impl Scope {
         Scope {
             path: path.into(),
             path_map: BTreeMap::new(),
+            #[cfg(feature = "v3")]
+            path_map_v3: BTreeMap::new(),
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
             inner: Some(actix_web::Scope::new(path)),
@@ -403,6 +436,8 @@ where
         Scope {
             path: self.path,
             path_map: self.path_map,
+            #[cfg(feature = "v3")]
+            path_map_v3: self.path_map_v3,
             definitions: self.definitions,
             security: self.security,
             inner: self.inner.take().map(|s| s.wrap(mw)),
@@ -433,6 +468,8 @@ where
         Scope {
             path: self.path,
             path_map: self.path_map,
+            #[cfg(feature = "v3")]
+            path_map_v3: self.path_map_v3,
             definitions: self.definitions,
             security: self.security,
             inner: self.inner.take().map(|s| s.wrap_fn(mw)),
@@ -448,11 +485,7 @@ where
         let mut path_map = BTreeMap::new();
         factory.update_operations(&mut path_map);
         for (path, mut map) in path_map {
-            let p = if !self.path.ends_with('/') && !path.starts_with('/') {
-                self.path.clone() + "/" + &path
-            } else {
-                self.path.clone() + &path
-            };
+            let p = self.prefixed(&path);
             for op in map.methods.values_mut() {
                 op.set_parameter_names_from_path_template(&p);
             }
@@ -465,8 +498,31 @@ where
             }
         }
 
+        #[cfg(feature = "v3")]
+        {
+            let mut path_map = BTreeMap::new();
+            factory.update_operations_v3(&mut path_map);
+            for (path, mut map) in path_map {
+                let p = self.prefixed(&path);
+                for (op, _) in map.values_mut() {
+                    set_parameter_names_from_path_template(op, &p);
+                }
+
+                self.path_map_v3.entry(p).or_default().append(&mut map);
+            }
+        }
+
         SecurityScheme::append_map(factory.security_definitions(), &mut self.security);
     }
+
+    /// Returns the given path prefixed with the path of this scope.
+    fn prefixed(&self, path: &str) -> String {
+        if !self.path.ends_with('/') && !path.starts_with('/') {
+            self.path.clone() + "/" + path
+        } else {
+            self.path.clone() + path
+        }
+    }
 }
 
 impl<T> Mountable for Scope<T> {
@@ -492,6 +548,13 @@ impl<T> Mountable for Scope<T> {
             op_map.methods.extend(item.methods);
         }
     }
+
+    #[cfg(feature = "v3")]
+    fn update_operations_v3(&mut self, map: &mut BTreeMap<String, OperationsV3>) {
+        for (path, ops) in mem::take(&mut self.path_map_v3) {
+            map.entry(path).or_default().extend(ops);
+        }
+    }
 }
 
 /// Wrapper for [`actix_web::web::scope`](https://docs.rs/actix-web/*/actix_web/web/fn.scope.html).
@@ -505,6 +568,8 @@ pub fn scope(path: &str) -> Scope {
 pub struct Route {
     method: Option<HttpMethod>,
     operation: Option<DefaultOperationRaw>,
+    #[cfg(feature = "v3")]
+    operation_v3: Option<(openapiv3::Operation, openapiv3::Components)>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
     inner: actix_web::Route,
@@ -534,6 +599,8 @@ impl Route {
         Route {
             method: None,
             operation: None,
+            #[cfg(feature = "v3")]
+            operation_v3: None,
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
             inner: actix_web::Route::new(),
@@ -567,6 +634,10 @@ impl Route {
             self.operation = Some(F::Future::operation());
             self.definitions = F::Future::definitions();
             self.security = F::Future::security_definitions();
+            #[cfg(feature = "v3")]
+            {
+                self.operation_v3 = F::Future::v3_operation();
+            }
         }
         self.inner = self.inner.to(handler);
         self
@@ -623,6 +694,8 @@ pub fn head() -> Route {
 pub(crate) struct RouteWrapper<S> {
     path: S,
     pub(crate) operations: BTreeMap<HttpMethod, DefaultOperationRaw>,
+    #[cfg(feature = "v3")]
+    pub(crate) operations_v3: OperationsV3,
     pub(crate) definitions: BTreeMap<String, DefaultSchemaRaw>,
     pub(crate) security: BTreeMap<String, SecurityScheme>,
     pub(crate) inner: actix_web::Route,
@@ -646,9 +719,26 @@ where
             }
         }
 
+        #[cfg(feature = "v3")]
+        let mut operations_v3 = BTreeMap::new();
+        #[cfg(feature = "v3")]
+        if let Some((mut op, components)) = route.operation_v3 {
+            set_parameter_names_from_path_template(&mut op, path.as_ref());
+
+            if let Some(meth) = route.method {
+                operations_v3.insert(meth, (op, components));
+            } else {
+                for method in METHODS {
+                    operations_v3.insert(method.into(), (op.clone(), components.clone()));
+                }
+            }
+        }
+
         RouteWrapper {
             path,
             operations,
+            #[cfg(feature = "v3")]
+            operations_v3,
             definitions: route.definitions,
             security: route.security,
             inner: route.inner,
@@ -668,6 +758,11 @@ where
         mem::take(&mut self.operations)
     }
 
+    #[cfg(feature = "v3")]
+    fn operations_v3(&mut self) -> OperationsV3 {
+        mem::take(&mut self.operations_v3)
+    }
+
     fn security_definitions(&mut self) -> BTreeMap<String, SecurityScheme> {
         mem::take(&mut self.security)
     }
@@ -682,6 +777,8 @@ where
 /// Wrapper for [`actix_web::web::ServiceConfig`](https://docs.rs/actix-web/*/actix_web/web/struct.ServiceConfig.html).
 pub struct ServiceConfig<'a> {
     path_map: BTreeMap<String, DefaultPathItemRaw>,
+    #[cfg(feature = "v3")]
+    path_map_v3: BTreeMap<String, OperationsV3>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
     inner: &'a mut actix_web::web::ServiceConfig,
@@ -691,6 +788,8 @@ impl<'a> From<&'a mut actix_web::web::ServiceConfig> for ServiceConfig<'a> {
     fn from(cfg: &'a mut actix_web::web::ServiceConfig) -> Self {
         ServiceConfig {
             path_map: BTreeMap::new(),
+            #[cfg(feature = "v3")]
+            path_map_v3: BTreeMap::new(),
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
             inner: cfg,
@@ -723,6 +822,13 @@ impl Mountable for ServiceConfig<'_> {
             op_map.methods.extend(item.methods);
         }
     }
+
+    #[cfg(feature = "v3")]
+    fn update_operations_v3(&mut self, map: &mut BTreeMap<String, OperationsV3>) {
+        for (path, ops) in mem::take(&mut self.path_map_v3) {
+            map.entry(path).or_default().extend(ops);
+        }
+    }
 }
 
 impl ServiceConfig<'_> {
@@ -731,6 +837,8 @@ impl ServiceConfig<'_> {
         let mut w = RouteWrapper::from(path, route);
         self.definitions.extend(w.definitions());
         w.update_operations(&mut self.path_map);
+        #[cfg(feature = "v3")]
+        w.update_operations_v3(&mut self.path_map_v3);
         SecurityScheme::append_map(w.security, &mut self.security);
         self.inner.route(path, w.inner);
         self
@@ -743,6 +851,8 @@ impl ServiceConfig<'_> {
     {
         self.definitions.extend(factory.definitions());
         factory.update_operations(&mut self.path_map);
+        #[cfg(feature = "v3")]
+        factory.update_operations_v3(&mut self.path_map_v3);
         SecurityScheme::append_map(factory.security_definitions(), &mut self.security);
         self.inner.service(factory);
         self
@@ -4,22 +4,38 @@ use paperclip::{
     v2::{
         self,
         codegen::{CrateMeta, DefaultEmitter, EmitMode, Emitter, EmitterState},
-        models::{DefaultSchema, ResolvableApi},
+        filter::{self, Filter},
+        json_schema::{self, Exporter},
+        merge::MergeInput,
+        models::{DefaultApiRaw, DefaultSchema, ResolvableApi},
+        overlay::Overlay,
     },
     PaperClipError,
 };
-use structopt::StructOpt;
-
-use std::{
-    fs::{self, File},
-    path::PathBuf,
+use structopt::{
+    clap::{Error as ClapError, ErrorKind},
+    StructOpt,
 };
 
+use std::{fs, path::PathBuf};
+
 #[cfg(feature = "cli-ng")]
 use heck::ToSnakeCase;
+#[cfg(feature = "mock")]
+use paperclip::v2::mock::MockServer;
+#[cfg(feature = "v3")]
+use paperclip::v2::models::SpecFormat;
 #[cfg(feature = "cli-ng")]
 use paperclip_ng::v3_03 as v3;
 
+/// Exit status of `diff` when there are breaking changes (other errors exit with 1).
+const BREAKING_CHANGES_STATUS: i32 = 2;
+
+/// Breaking changes were found by `diff`.
+#[derive(Debug, thiserror::Error)]
+#[error("Found {} breaking change(s).", _0)]
+struct BreakingChanges(usize);
+
 fn parse_version(s: &str) -> Result<OApiVersion, Error> {
     match s {
         "v2" => Ok(OApiVersion::V2),
@@ -28,14 +44,104 @@ fn parse_version(s: &str) -> Result<OApiVersion, Error> {
     }
 }
 
-fn parse_spec(s: &str) -> Result<ResolvableApi<DefaultSchema>, Error> {
-    let fd = File::open(s)?;
-    Ok(v2::from_reader(fd)?)
+/// Parses the spec at the given path after applying the given overlays (if any).
+fn parse_spec(s: &str, overlays: &[PathBuf]) -> Result<ResolvableApi<DefaultSchema>, Error> {
+    let overlays = overlays
+        .iter()
+        .map(Overlay::from_path)
+        .collect::<Result<Vec<_>, _>>()?;
+    #[cfg(feature = "v3")]
+    if let Some(api) = parse_downgraded_spec(s, &overlays)? {
+        return Ok(api);
+    }
+
+    let (api, unmatched) = v2::from_path_with_overlays(s, &overlays)?;
+    for target in &unmatched {
+        warn_unmatched(target);
+    }
+
+    Ok(api)
+}
+
+/// Warns about an overlay target which didn't match anything in the spec.
+fn warn_unmatched(target: &str) {
+    eprintln!("warning: overlay target {} didn't match anything", target);
+}
+
+/// Parses the spec at the given path if it's an OpenAPI v3 spec, and downgrades
+/// it to v2 (with a warning for every construct that couldn't be represented).
+#[cfg(feature = "v3")]
+fn parse_downgraded_spec(
+    s: &str,
+    overlays: &[Overlay],
+) -> Result<Option<ResolvableApi<DefaultSchema>>, Error> {
+    let text = match fs::read_to_string(s) {
+        Ok(t) => t,
+        Err(_) => return Ok(None),
+    };
+
+    let mut doc: serde_json::Value = match serde_yaml::from_str(&text) {
+        Ok(d) => d,
+        Err(_) => return Ok(None),
+    };
+
+    if doc.get("openapi").is_none() {
+        return Ok(None);
+    }
+
+    for overlay in overlays {
+        for target in overlay.apply(&mut doc)? {
+            warn_unmatched(target);
+        }
+    }
+
+    // v3.1 specs are downgraded to v3.0 first.
+    let (v3, mut report) = paperclip::v3::openapiv3_from_value(doc)?;
+    let (raw, v2_report) = paperclip::v3::openapiv3_to_v2(&v3)?;
+    report.issues.extend(v2_report.issues);
+    for issue in &report.issues {
+        eprintln!("warning: {}", issue);
+    }
+
+    let mut api: ResolvableApi<DefaultSchema> =
+        serde_json::from_value(serde_json::to_value(&raw)?)?;
+    api.spec_format = if text.trim_start().starts_with('{') {
+        SpecFormat::Json
+    } else {
+        SpecFormat::Yaml
+    };
+    Ok(Some(api))
+}
+
+fn parse_raw_spec(s: &str, overlays: &[PathBuf]) -> Result<DefaultApiRaw, Error> {
+    let api = parse_spec(s, overlays)?;
+    let mut raw: DefaultApiRaw = serde_json::from_value(serde_json::to_value(&api)?)?;
+    raw.spec_format = api.spec_format;
+    Ok(raw)
+}
+
+/// Parses `path[=prefix]` arguments for merging.
+fn parse_merge_input(s: &str) -> Result<MergeInput, Error> {
+    let (path, prefix) = match s.split_once('=') {
+        Some((p, prefix)) => (p, Some(prefix)),
+        None => (s, None),
+    };
+
+    let mut input = MergeInput::new(parse_raw_spec(path, &[])?);
+    input.path_prefix = prefix.map(String::from);
+    Ok(input)
 }
+/// Parses the OpenAPI v3 spec at the given path. v3.1 specs are downgraded to
+/// v3.0 (with a warning for every construct that couldn't be carried across).
 #[cfg(feature = "cli-ng")]
 fn parse_spec_v3(s: &str) -> Result<openapiv3::OpenAPI, Error> {
-    let fd = File::open(s)?;
-    Ok(v2::from_reader_v3(fd)?)
+    let doc: serde_json::Value = serde_yaml::from_str(&fs::read_to_string(s)?)?;
+    let (api, report) = paperclip_core::v3::openapiv3_from_value(doc)?;
+    for issue in &report.issues {
+        eprintln!("warning: {}", issue);
+    }
+
+    Ok(api)
 }
 
 #[derive(Debug)]
@@ -46,15 +152,20 @@ enum OApiVersion {
 
 #[derive(Debug, StructOpt)]
 struct Opt {
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
     /// Path to OpenAPI spec in JSON/YAML format (also supports publicly accessible URLs).
     #[structopt(long)]
-    spec: std::path::PathBuf,
+    spec: Option<PathBuf>,
     /// OpenAPI version (e.g., v2).
     #[structopt(long = "api", parse(try_from_str = parse_version))]
-    api: OApiVersion,
+    api: Option<OApiVersion>,
     /// Output directory to write code (default: current working directory).
     #[structopt(short = "o", long = "out", parse(from_os_str))]
     output: Option<PathBuf>,
+    /// Apply this OpenAPI Overlay to the spec before generating code (can be repeated).
+    #[structopt(long = "overlay", number_of_values = 1, parse(from_os_str))]
+    overlays: Vec<PathBuf>,
     /// Emit CLI target instead.
     #[structopt(long = "cli")]
     cli: bool,
@@ -66,6 +177,24 @@ struct Opt {
     /// Don't Render operations.
     #[structopt(long)]
     no_ops: bool,
+    /// Only generate code for operations with this tag (can be repeated).
+    #[structopt(long = "include-tag", number_of_values = 1)]
+    include_tags: Vec<String>,
+    /// Skip operations with this tag (can be repeated).
+    #[structopt(long = "exclude-tag", number_of_values = 1)]
+    exclude_tags: Vec<String>,
+    /// Only generate code for operations whose path matches this glob (can be repeated).
+    #[structopt(long = "include-path", number_of_values = 1)]
+    include_paths: Vec<String>,
+    /// Skip operations whose path matches this glob (can be repeated).
+    #[structopt(long = "exclude-path", number_of_values = 1)]
+    exclude_paths: Vec<String>,
+    /// Only generate code for operations whose ID matches this glob (can be repeated).
+    #[structopt(long = "include-operation", number_of_values = 1)]
+    include_operations: Vec<String>,
+    /// Skip operations whose ID matches this glob (can be repeated).
+    #[structopt(long = "exclude-operation", number_of_values = 1)]
+    exclude_operations: Vec<String>,
     /// Do not make the crate a root crate.
     #[structopt(long = "no-root")]
     no_root: bool,
@@ -87,16 +216,252 @@ struct Opt {
     templates: Option<PathBuf>,
 }
 
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Compare two v2 specs and report the changes. This exits with
+    /// status 2 if there are breaking changes (and 1 for errors).
+    Diff {
+        /// Path to the old spec.
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        /// Path to the new spec.
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+        /// Print the report as JSON.
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Merge multiple v2 specs into one. Conflicts are reported to stderr and
+    /// this exits with a non-zero status if some operations (or hosts) had to
+    /// be dropped.
+    Merge {
+        /// Paths to the specs. Each path can be followed by a prefix for
+        /// all paths in that spec (like `store.yaml=/store`).
+        #[structopt(required = true, min_values = 2)]
+        specs: Vec<String>,
+        /// Write the merged spec to this file (default: stdout). The spec is
+        /// written in the format of the first spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Rewrite v2 specs in canonical form (stable key order, sorted
+    /// definitions and paths).
+    Fmt {
+        /// Paths to the specs.
+        #[structopt(required = true, parse(from_os_str))]
+        specs: Vec<PathBuf>,
+        /// Don't write the files. Instead, list the specs which are not in
+        /// canonical form and exit with a non-zero status if there are any.
+        #[structopt(long)]
+        check: bool,
+    },
+    /// Export the definitions in a v2 spec as JSON Schema (draft 2020-12)
+    /// documents, one file per definition.
+    JsonSchema {
+        /// Path to the spec.
+        #[structopt(long, parse(from_os_str))]
+        spec: PathBuf,
+        /// Output directory to write the documents (default: current working directory).
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Apply this OpenAPI Overlay to the spec (can be repeated).
+        #[structopt(long = "overlay", number_of_values = 1, parse(from_os_str))]
+        overlays: Vec<PathBuf>,
+        /// Base URI for the `$id` of the documents.
+        #[structopt(long)]
+        base_uri: Option<String>,
+        /// Keep vendor extensions (`x-*`) as annotations.
+        #[structopt(long)]
+        keep_extensions: bool,
+        /// Write a single `schema.json` document with all the definitions in `$defs` instead.
+        #[structopt(long)]
+        bundle: bool,
+    },
+    /// Serve a mock backend for a v2 spec. Requests are validated against
+    /// the spec and responses have bodies synthesized from their schemas.
+    #[cfg(feature = "mock")]
+    Mock {
+        /// Path to the spec.
+        #[structopt(long, parse(from_os_str))]
+        spec: PathBuf,
+        /// Apply this OpenAPI Overlay to the spec (can be repeated).
+        #[structopt(long = "overlay", number_of_values = 1, parse(from_os_str))]
+        overlays: Vec<PathBuf>,
+        /// Address to listen on.
+        #[structopt(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on.
+        #[structopt(long, default_value = "8080")]
+        port: u16,
+        /// Seed for synthesizing response bodies.
+        #[structopt(long, default_value = "0")]
+        seed: u64,
+    },
+}
+
+fn run_command(cmd: Command) -> Result<(), Error> {
+    match cmd {
+        Command::Diff { old, new, json } => {
+            let old = parse_spec(&old.to_string_lossy(), &[])?;
+            let new = parse_spec(&new.to_string_lossy(), &[])?;
+            let report = v2::diff::diff(&old, &new);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                for change in &report.changes {
+                    println!("{}", change);
+                }
+            }
+
+            let breaking = report.breaking_changes().count();
+            if breaking > 0 {
+                return Err(BreakingChanges(breaking).into());
+            }
+
+            Ok(())
+        }
+        Command::Merge { specs, output } => {
+            let inputs = specs
+                .iter()
+                .map(|s| parse_merge_input(s))
+                .collect::<Result<Vec<_>, _>>()?;
+            let report = v2::merge::merge(inputs)?;
+            for conflict in &report.conflicts {
+                eprintln!("{}", conflict);
+            }
+
+            match output {
+                Some(path) => v2::to_writer(&report.api, fs::File::create(path)?)?,
+                None => v2::to_writer(&report.api, std::io::stdout())?,
+            }
+
+            let dropped = report.unresolved_conflicts().count();
+            if dropped > 0 {
+                return Err(anyhow::anyhow!(
+                    "Dropped {} conflicting operation(s) or host(s).",
+                    dropped
+                ));
+            }
+
+            Ok(())
+        }
+        Command::Fmt { specs, check } => {
+            let mut unformatted = 0;
+            for path in &specs {
+                let contents = fs::read_to_string(path)?;
+                let api: ResolvableApi<DefaultSchema> = v2::from_reader(contents.as_bytes())?;
+                let formatted = v2::to_string(&api)?;
+                if formatted == contents {
+                    continue;
+                }
+
+                if check {
+                    println!("{}", path.display());
+                    unformatted += 1;
+                } else {
+                    fs::write(path, formatted)?;
+                }
+            }
+
+            if unformatted > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} spec(s) are not formatted.",
+                    unformatted
+                ));
+            }
+
+            Ok(())
+        }
+        Command::JsonSchema {
+            spec,
+            output,
+            overlays,
+            base_uri,
+            keep_extensions,
+            bundle,
+        } => {
+            let api = parse_raw_spec(&spec.to_string_lossy(), &overlays)?;
+            let mut exporter = Exporter::from_api(&api).keep_extensions(keep_extensions);
+            if let Some(uri) = base_uri {
+                exporter = exporter.base_uri(uri);
+            }
+
+            let dir = output.unwrap_or_default();
+            fs::create_dir_all(&dir)?;
+            let docs = if bundle {
+                vec![("schema.json".to_owned(), exporter.bundle()?)]
+            } else {
+                exporter
+                    .definitions()?
+                    .into_iter()
+                    .map(|(name, doc)| (json_schema::file_name(&name), doc))
+                    .collect()
+            };
+
+            for (file, doc) in docs {
+                let mut contents = serde_json::to_string_pretty(&doc)?;
+                contents.push('\n');
+                fs::write(dir.join(file), contents)?;
+            }
+
+            Ok(())
+        }
+        #[cfg(feature = "mock")]
+        Command::Mock {
+            spec,
+            overlays,
+            host,
+            port,
+            seed,
+        } => {
+            let api = parse_spec(&spec.to_string_lossy(), &overlays)?.resolve()?;
+            let mock = MockServer::new(&api)?.seed(seed);
+            println!("Serving mock API at http://{}:{}", host, port);
+            actix_web4::rt::System::new().block_on(async move {
+                actix_web4::HttpServer::new(move || {
+                    let mock = mock.clone();
+                    actix_web4::App::new().configure(move |c| mock.configure(c))
+                })
+                .bind((host.as_str(), port))?
+                .run()
+                .await
+            })?;
+
+            Ok(())
+        }
+    }
+}
+
 fn parse_args_and_run() -> Result<(), Error> {
     let opt: Opt = Opt::from_args();
+    if let Some(cmd) = opt.cmd {
+        return run_command(cmd);
+    }
+
+    let (spec_path, api) = match (opt.spec, opt.api) {
+        (Some(s), Some(a)) => (s, a),
+        _ => ClapError::with_description(
+            "--spec and --api are required for generating code",
+            ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
 
     if let Some(o) = &opt.output {
         fs::create_dir_all(o)?;
     }
 
     #[cfg(feature = "cli-ng")]
-    if let OApiVersion::V3 = opt.api {
-        let spec = parse_spec_v3(opt.spec.to_string_lossy().as_ref())?;
+    if let OApiVersion::V3 = api {
+        if !opt.overlays.is_empty() {
+            ClapError::with_description(
+                "--overlay is only supported for v2 specs",
+                ErrorKind::ArgumentConflict,
+            )
+            .exit()
+        }
+
+        let spec = parse_spec_v3(spec_path.to_string_lossy().as_ref())?;
         let name = opt.name.map(Ok::<String, Error>).unwrap_or_else(|| {
             Ok(fs::canonicalize(std::path::Path::new("."))?
                 .file_name()
@@ -116,12 +481,38 @@ fn parse_args_and_run() -> Result<(), Error> {
         return Ok(());
     }
 
+    // Without the new generator, v3 specs can only be downgraded to v2 (when parsing).
     #[cfg(not(feature = "cli-ng"))]
-    if let OApiVersion::V3 = opt.api {
+    if let (OApiVersion::V3, false) = (api, cfg!(feature = "v3")) {
         return Err(PaperClipError::UnsupportedOpenAPIVersion.into());
     }
 
-    let spec = parse_spec(&opt.spec.to_string_lossy())?.resolve()?;
+    let mut rules = Filter::default();
+    for t in &opt.include_tags {
+        rules.include_tag(t.as_str());
+    }
+    for t in &opt.exclude_tags {
+        rules.exclude_tag(t.as_str());
+    }
+    for p in &opt.include_paths {
+        rules.include_path(p.as_str());
+    }
+    for p in &opt.exclude_paths {
+        rules.exclude_path(p.as_str());
+    }
+    for o in &opt.include_operations {
+        rules.include_operation(o.as_str());
+    }
+    for o in &opt.exclude_operations {
+        rules.exclude_operation(o.as_str());
+    }
+
+    let mut spec = parse_spec(&spec_path.to_string_lossy(), &opt.overlays)?;
+    if !rules.is_empty() {
+        filter::filter(&mut spec, &rules);
+    }
+
+    let spec = spec.resolve()?;
     let mut state = EmitterState::default();
 
     if let Some(o) = opt.output {
@@ -152,6 +543,10 @@ fn main() {
     env_logger::init();
     if let Err(e) = parse_args_and_run() {
         eprintln!("{}", e);
+        if e.downcast_ref::<BreakingChanges>().is_some() {
+            std::process::exit(BREAKING_CHANGES_STATUS);
+        }
+
         std::process::exit(1);
     }
 }
@@ -1,3 +1,5 @@
+use paperclip_core::Span;
+
 use std::{collections::HashSet, path::PathBuf};
 
 macro_rules! impl_err_from {
@@ -19,6 +21,9 @@ pub enum PaperClipError {
     /// Error encountered during spec validation.
     #[error("{}", _0)]
     Validation(paperclip_core::ValidationError),
+    /// Some error along with its location in the spec.
+    #[error("{}\n{}", _0, _1.snippet())]
+    Located(Box<PaperClipError>, Span),
     /// The given directory cannot be used for generating code.
     #[error("Cannot generate code in the given directory")]
     InvalidCodegenDirectory,
@@ -50,6 +55,18 @@ pub enum PaperClipError {
     /// A valid path cannot be obtained for the given definition.
     #[error("Invalid path for definition: {:?}", _0)]
     InvalidDefinitionPath(PathBuf),
+    /// Different definitions map to the same document file name (and `$id`).
+    #[error("Definitions {:?} and {:?} have the same file name {:?}", _0, _1, _2)]
+    FileNameCollision(String, String, String),
+    /// The given JSONPath expression (in an overlay) is invalid.
+    #[error("Invalid JSONPath {:?}: {}", _0, _1)]
+    InvalidJsonPath(String, String),
+    /// The given pattern (in the spec) is not a valid regular expression.
+    #[error("Invalid pattern {:?}: {}", _0, _1)]
+    InvalidPattern(String, String),
+    /// Only version 1.x of the OpenAPI Overlay specification is supported.
+    #[error("Unsupported overlay version {:?}", _0)]
+    UnsupportedOverlayVersion(String),
     /// I/O errors.
     #[error("I/O error: {}", _0)]
     Io(std::io::Error),
@@ -65,6 +82,17 @@ pub enum PaperClipError {
     Templating(tinytemplate::error::Error),
 }
 
+impl PaperClipError {
+    /// Location of this error in the spec (if it's known).
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            PaperClipError::Located(_, span) => Some(span),
+            PaperClipError::Validation(e) => e.span(),
+            _ => None,
+        }
+    }
+}
+
 impl_err_from!(PaperClipError::std::io::Error > Io);
 impl_err_from!(PaperClipError::serde_json::Error > Json);
 impl_err_from!(PaperClipError::serde_yaml::Error > Yaml);
@@ -14,6 +14,8 @@ pub mod v2;
 
 pub use error::{PaperClipError, PaperClipResult};
 pub use paperclip_core::util;
+#[cfg(feature = "v3")]
+pub use paperclip_core::v3;
 #[cfg(feature = "v2")]
 pub use paperclip_macros::api_v2_schema_struct as api_v2_schema;
 
@@ -29,6 +31,8 @@ pub mod actix {
         AcceptedJson, CreatedJson, NoContent, OperationModifier, ResponderWrapper, ResponseWrapper,
     };
 
+    #[cfg(feature = "v3")]
+    pub use paperclip_actix::{api_v3_operation, Apiv3Schema};
     #[cfg(feature = "actix4")]
     pub use paperclip_core::v2::HttpResponseWrapper;
 }
@@ -0,0 +1,379 @@
+//! Bundling of OpenAPI v2 specs into a single self-contained document.
+//!
+//! The bundler gets rid of references which can't be followed by other tools
+//! (references to other files and "deep" JSON pointers like
+//! `#/definitions/Pod/properties/spec`) in one of two ways:
+//!
+//! - [`BundleMode::Hoist`](enum.BundleMode.html#variant.Hoist) moves the
+//!   referenced objects into `definitions` (or `parameters` and `responses`)
+//!   of the root document and rewrites the references to point at them.
+//! - [`BundleMode::Inline`](enum.BundleMode.html#variant.Inline) replaces the
+//!   references with the objects they point to. References to cyclic schemas
+//!   can't be inlined, so they're kept as they are.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{self, bundle::{self, BundleMode}};
+//!
+//! let api = bundle::bundle_path("my_spec.yaml", BundleMode::Hoist).unwrap();
+//! // The bundled spec is written in the same format (JSON or YAML) as the source.
+//! v2::to_writer(&api, std::io::stdout()).unwrap();
+//! ```
+
+use super::loader::{self, SECTIONS};
+use crate::error::PaperClipError;
+use paperclip_core::{
+    v2::{
+        models::{DefaultApiRaw, ResolvableApi},
+        pointer::{self, MAX_REF_DEPTH},
+        schema::Schema,
+    },
+    ValidationError,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+/// How references should be handled by the bundler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundleMode {
+    /// Move every external or deep reference under `#/definitions` (or
+    /// `#/parameters` and `#/responses` for those objects) with collision-free
+    /// names and rewrite the references.
+    Hoist,
+    /// Replace every reference with the object it points to. References
+    /// involved in cycles are kept.
+    Inline,
+}
+
+/// Bundle the given (unresolved) spec. The spec must not have references
+/// to other files. Use [`bundle_path`](fn.bundle_path.html) for those.
+pub fn bundle<S>(api: &ResolvableApi<S>, mode: BundleMode) -> Result<DefaultApiRaw, PaperClipError>
+where
+    S: Schema + Serialize,
+{
+    let mut bundled = bundle_value(serde_json::to_value(api)?, mode)?;
+    bundled.spec_format = api.spec_format;
+    Ok(bundled)
+}
+
+/// Bundle the given spec (like the ones generated by plugins).
+pub fn bundle_raw(api: &DefaultApiRaw, mode: BundleMode) -> Result<DefaultApiRaw, PaperClipError> {
+    let mut bundled = bundle_value(serde_json::to_value(api)?, mode)?;
+    bundled.spec_format = api.spec_format;
+    Ok(bundled)
+}
+
+/// Load the spec from the given path (following relative file references
+/// like [`from_path`](../fn.from_path.html)) and bundle it.
+pub fn bundle_path<P>(path: P, mode: BundleMode) -> Result<DefaultApiRaw, PaperClipError>
+where
+    P: AsRef<Path>,
+{
+    let (root, fmt) = loader::load(path.as_ref())?;
+    let mut bundled = bundle_value(root, mode)?;
+    bundled.spec_format = fmt;
+    Ok(bundled)
+}
+
+fn bundle_value(mut root: Value, mode: BundleMode) -> Result<DefaultApiRaw, PaperClipError> {
+    match mode {
+        BundleMode::Hoist => hoist_refs(&mut root)?,
+        BundleMode::Inline => root = inline_refs(&root)?,
+    }
+
+    Ok(serde_json::from_value(root)?)
+}
+
+/* Hoisting */
+
+/// Moves the targets of deep references into the root document's sections
+/// and rewrites all references to point to those sections.
+fn hoist_refs(root: &mut Value) -> Result<(), PaperClipError> {
+    let mut uris = BTreeSet::new();
+    collect_refs(root, &mut uris);
+
+    let mut taken: HashMap<&'static str, HashSet<String>> = SECTIONS
+        .iter()
+        .map(|&s| {
+            let names = root
+                .get(s)
+                .and_then(Value::as_object)
+                .map(|m| m.keys().cloned().collect())
+                .unwrap_or_default();
+            (s, names)
+        })
+        .collect();
+
+    // Shorter pointers first, so that the names of nested objects are
+    // based on the names of their (already hoisted) parents.
+    let mut uris = uris.into_iter().collect::<Vec<_>>();
+    uris.sort_by_key(|u| u.len());
+
+    for uri in &uris {
+        let segments = locate_ref(root, uri)?;
+        if !is_deep(&segments) {
+            continue;
+        }
+
+        let section = section_for(&segments);
+        let name = unique_name(taken.entry(section).or_default(), hoisted_name(&segments));
+        let new_ref = Value::String(local_ref(&[section.into(), name.clone()]));
+        let target = root
+            .pointer_mut(&json_pointer(&segments))
+            .ok_or_else(|| ValidationError::MissingReference(uri.clone()))?;
+
+        let mut placeholder = Map::new();
+        placeholder.insert("$ref".into(), new_ref);
+        let value = std::mem::replace(target, Value::Object(placeholder));
+
+        if let Value::Object(map) = root {
+            let entry = map
+                .entry(section)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(objects) = entry {
+                objects.insert(name, value);
+            }
+        }
+    }
+
+    // Now that every target lives in a section, rewrite references to their final locations.
+    let mut rewrites = HashMap::new();
+    for uri in uris {
+        let segments = locate_ref(root, &uri)?;
+        rewrites.insert(uri, local_ref(&segments));
+    }
+
+    rewrite_refs(root, &rewrites);
+    Ok(())
+}
+
+/// Collects all `$ref` URIs in the given value.
+fn collect_refs(value: &Value, uris: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                match v {
+                    Value::String(uri) if k == "$ref" => {
+                        uris.insert(uri.clone());
+                    }
+                    _ => collect_refs(v, uris),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|v| collect_refs(v, uris)),
+        _ => (),
+    }
+}
+
+/// Replaces `$ref` URIs in the given value based on the given map.
+fn rewrite_refs(value: &mut Value, rewrites: &HashMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                match v {
+                    Value::String(uri) if k == "$ref" => {
+                        if let Some(new_uri) = rewrites.get(uri.as_str()) {
+                            *uri = new_uri.clone();
+                        }
+                    }
+                    _ => rewrite_refs(v, rewrites),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| rewrite_refs(v, rewrites)),
+        _ => (),
+    }
+}
+
+/// Checks whether the object at the given location is not an immediate
+/// child of a section (i.e., it needs to be hoisted).
+fn is_deep(segments: &[String]) -> bool {
+    !(segments.len() == 2 && SECTIONS.contains(&segments[0].as_str()))
+}
+
+/// Returns the section to which the object at the given location should be hoisted.
+fn section_for(segments: &[String]) -> &'static str {
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["paths", _, "parameters", _] | ["paths", _, _, "parameters", _] => "parameters",
+        ["paths", _, _, "responses", _] => "responses",
+        _ => "definitions",
+    }
+}
+
+/// Name for a hoisted object (`#/definitions/Pod/properties/spec` becomes `PodSpec`).
+fn hoisted_name(segments: &[String]) -> String {
+    let skip = match segments.first().map(String::as_str) {
+        Some(s) if SECTIONS.contains(&s) || s == "paths" => 1,
+        _ => 0,
+    };
+
+    let name = segments
+        .iter()
+        .skip(skip)
+        .filter(|s| *s != "properties")
+        .map(|s| loader::pascal_case(s))
+        .collect::<String>();
+    if name.is_empty() {
+        String::from("Object")
+    } else {
+        name
+    }
+}
+
+/// Returns a name (based on the given one) which hasn't been taken yet and marks it as taken.
+fn unique_name(taken: &mut HashSet<String>, base: String) -> String {
+    let mut name = base.clone();
+    let mut idx = 1;
+    while taken.contains(&name) {
+        idx += 1;
+        name = format!("{}{}", base, idx);
+    }
+
+    taken.insert(name.clone());
+    name
+}
+
+/* Inlining */
+
+/// Returns a copy of the given document where references have been replaced
+/// with their targets (unless they're cyclic).
+fn inline_refs(root: &Value) -> Result<Value, PaperClipError> {
+    let map = match root {
+        Value::Object(m) => m,
+        _ => return Ok(root.clone()),
+    };
+
+    let mut inlined = Map::new();
+    for (key, value) in map {
+        let value = match value {
+            // Objects in sections are the starting points of cycles.
+            Value::Object(objects) if SECTIONS.contains(&key.as_str()) => Value::Object(
+                objects
+                    .iter()
+                    .map(|(name, v)| {
+                        let mut stack = vec![vec![key.clone(), name.clone()]];
+                        inline_value(root, v, &mut stack).map(|v| (name.clone(), v))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => inline_value(root, value, &mut vec![])?,
+        };
+
+        inlined.insert(key.clone(), value);
+    }
+
+    Ok(Value::Object(inlined))
+}
+
+/// Inlines references in the given value. The stack holds the locations
+/// of the objects we're currently inlining.
+fn inline_value(
+    root: &Value,
+    value: &Value,
+    stack: &mut Vec<Vec<String>>,
+) -> Result<Value, PaperClipError> {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(uri)) = map.get("$ref") {
+                let segments = locate_ref(root, uri)?;
+                if stack.contains(&segments) {
+                    let mut reference = map.clone();
+                    reference.insert("$ref".into(), Value::String(local_ref(&segments)));
+                    return Ok(Value::Object(reference));
+                }
+
+                let target = root
+                    .pointer(&json_pointer(&segments))
+                    .ok_or_else(|| ValidationError::MissingReference(uri.clone()))?;
+                stack.push(segments);
+                let mut inlined = inline_value(root, target, stack)?;
+                stack.pop();
+
+                // Siblings of `$ref` (like descriptions) override the target's fields.
+                if let Value::Object(inlined) = &mut inlined {
+                    for (k, v) in map.iter().filter(|(k, _)| *k != "$ref") {
+                        inlined.insert(k.clone(), inline_value(root, v, stack)?);
+                    }
+                }
+
+                return Ok(inlined);
+            }
+
+            map.iter()
+                .map(|(k, v)| inline_value(root, v, stack).map(|v| (k.clone(), v)))
+                .collect::<Result<_, _>>()
+                .map(Value::Object)
+        }
+        Value::Array(values) => values
+            .iter()
+            .map(|v| inline_value(root, v, stack))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        _ => Ok(value.clone()),
+    }
+}
+
+/* JSON pointers */
+
+/// Returns the actual location (following references along the way)
+/// of the object referenced by the given URI.
+fn locate_ref(root: &Value, uri: &str) -> Result<Vec<String>, PaperClipError> {
+    if !uri.starts_with('#') {
+        return Err(ValidationError::InvalidRefUri(uri.into()).into());
+    }
+
+    let segments =
+        pointer::segments(uri).ok_or_else(|| ValidationError::InvalidRefUri(uri.into()))?;
+    locate(root, &segments, 0).ok_or_else(|| ValidationError::MissingReference(uri.into()).into())
+}
+
+fn locate(root: &Value, segments: &[String], depth: usize) -> Option<Vec<String>> {
+    if depth > MAX_REF_DEPTH {
+        return None;
+    }
+
+    let mut path = vec![];
+    let mut node = root;
+    for segment in segments {
+        match child(node, segment) {
+            Some(n) => node = n,
+            None => {
+                // Pointers can go through references.
+                let uri = node.get("$ref")?.as_str()?;
+                path = locate(root, &pointer::segments(uri)?, depth + 1)?;
+                node = child(root.pointer(&json_pointer(&path))?, segment)?;
+            }
+        }
+
+        path.push(segment.clone());
+    }
+
+    match node.get("$ref").and_then(Value::as_str) {
+        Some(uri) => locate(root, &pointer::segments(uri)?, depth + 1),
+        None => Some(path),
+    }
+}
+
+fn child<'a>(node: &'a Value, segment: &str) -> Option<&'a Value> {
+    match node {
+        Value::Object(map) => map.get(segment),
+        Value::Array(values) => values.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+fn json_pointer(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|s| String::from("/") + &pointer::escape(s))
+        .collect()
+}
+
+fn local_ref(segments: &[String]) -> String {
+    String::from("#") + &json_pointer(segments)
+}
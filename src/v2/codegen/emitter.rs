@@ -1,6 +1,7 @@
 use super::{
     object::{
         ApiObject, ObjectContainer, ObjectField, ObjectVariant, OpRequirement, Parameter, Response,
+        TaggedVariant,
     },
     state::{ChildModule, EmitterState},
     CrateMeta,
@@ -8,11 +9,12 @@ use super::{
 use crate::{
     error::PaperClipError,
     v2::{
+        graph::SchemaGraph,
         models::{
             Coder, CollectionFormat, DataType, DataTypeFormat, Either, HttpMethod, Items,
-            MediaRange, ParameterIn, Reference, ResolvableApi, ResolvableOperation,
-            ResolvableParameter, ResolvablePathItem, ResolvableResponse, JSON_CODER, JSON_MIME,
-            YAML_CODER, YAML_MIME,
+            MediaRange, ParameterIn, Reference, Resolvable, ResolvableApi, ResolvableOperation,
+            ResolvableParameter, ResolvablePathItem, ResolvableResponse, Xml, JSON_CODER,
+            JSON_MIME, XML_CODER, XML_MIME, YAML_CODER, YAML_MIME,
         },
         Schema,
     },
@@ -21,6 +23,7 @@ use anyhow::Error;
 use heck::{ToPascalCase, ToSnakeCase};
 use http::{header::HeaderName, HeaderMap};
 use itertools::Itertools;
+use paperclip_core::ValidationError;
 use std::{
     collections::{BTreeMap, BTreeSet, HashSet},
     fmt::Debug,
@@ -268,7 +271,25 @@ pub trait Emitter: Sized {
             coders.insert(YAML_MIME.clone(), YAML_CODER.clone());
         }
 
+        // XML coder is added only for specs which make use of XML.
+        let (has_xml, only_xml) = xml_usage(api);
+        if has_xml && !coders.contains_key(&XML_MIME) {
+            coders.insert(XML_MIME.clone(), XML_CODER.clone());
+        }
+
+        *state.xml_names.borrow_mut() = only_xml;
+
         state.set_media_info(api.spec_format, &coders);
+        // The schemas are borrowed from the spec for as long as we're
+        // emitting it, so their addresses don't change in the meantime.
+        let graph = SchemaGraph::new(api);
+        *state.schemas_any.borrow_mut() = graph
+            .nodes()
+            .map(|(id, _)| {
+                let schema = graph.schema(id).read().unwrap();
+                (&*schema as *const _ as usize, graph.contains_any(id))
+            })
+            .collect();
 
         // Set host and base path.
         if let Some(h) = api.host.as_ref() {
@@ -307,7 +328,14 @@ pub trait Emitter: Sized {
                 map,
                 template_params: HashSet::new(),
             }
-            .collect()?;
+            .collect()
+            .map_err(
+                |e| match (e.downcast::<ValidationError>(), &api.source_map) {
+                    (Ok(e), Some(map)) => map.annotate(e).into(),
+                    (Ok(e), None) => e.into(),
+                    (Err(e), _) => e,
+                },
+            )?;
         }
 
         state.declare_modules()?;
@@ -332,13 +360,13 @@ pub trait Emitter: Sized {
             return Ok(u);
         }
 
-        if let Some(ty) = matching_unit_type(def.format(), def.data_type()) {
+        if let Some(ty) = matching_unit_type(self.state(), def.format(), def.data_type()) {
             trace!("Matches unit type: {}", ty);
             if ctx.define {
                 return Ok(EmittedUnit::None);
             }
 
-            return Ok(EmittedUnit::Known(ty.to_owned()));
+            return Ok(EmittedUnit::Known(ty));
         }
 
         match def.data_type() {
@@ -346,6 +374,8 @@ pub trait Emitter: Sized {
             Some(DataType::Object) => CodegenEmitter(self).emit_object(def, ctx),
             Some(DataType::File) => Ok(EmittedUnit::Known(FILE_MARKER.into())),
             Some(_) => unreachable!("bleh?"), // we've already handled everything else
+            // Composed schemas are objects even if they don't specify a type.
+            None if def.all_of().is_some() => CodegenEmitter(self).emit_object(def, ctx),
             None => {
                 if ctx.define {
                     Ok(EmittedUnit::None)
@@ -489,7 +519,8 @@ where
         ctx: DefinitionContext<'_>,
     ) -> Result<Option<EmittedUnit>, Error> {
         // FIXME: Research on how we can support complex enums.
-        if def.data_type().is_some() && matching_unit_type(def.format(), def.data_type()).is_none()
+        if def.data_type().is_some()
+            && matching_unit_type(self.state(), def.format(), def.data_type()).is_none()
         {
             return Ok(None);
         }
@@ -627,6 +658,14 @@ where
         })?;
         let mut obj = ApiObject::with_name(&name);
         obj.description = def.description().map(String::from);
+        let xml_names = *self.state().xml_names.borrow();
+        if xml_names {
+            obj.xml_name = def
+                .xml()
+                .and_then(Xml::qualified_name)
+                .or_else(|| def.name().map(String::from))
+                .filter(|n| *n != name);
+        }
 
         // If we don't have any parents and there's a name for this object,
         // then it's the root object - add the name to parents before checking
@@ -639,7 +678,8 @@ where
         // Anonymous objects that we've collected along the way.
         let mut objects = vec![];
 
-        if let Some(props) = def.properties() {
+        let (props, required) = merged_properties(def);
+        if !props.is_empty() {
             props
                 .iter()
                 .try_for_each(|(name, prop)| -> Result<(), Error> {
@@ -647,18 +687,22 @@ where
                     let ctx = ctx.clone().define(false).add_parent(name);
                     let ty = self.build_def(&schema, ctx)?;
                     let ty_path = ty.known_type();
+                    let (xml_name, xml_item_name) = if xml_names && !schema.is_cyclic() {
+                        xml_field_names(name, prop, &schema)
+                    } else {
+                        (None, None)
+                    };
 
                     obj.fields_mut().push(ObjectField {
                         name: name.clone(),
                         description: prop.get_description(),
                         ty_path,
-                        is_required: def
-                            .required_properties()
-                            .map(|s| s.contains(name))
-                            .unwrap_or(false),
-                        needs_any: schema.contains_any(),
+                        is_required: required.contains(name),
+                        needs_any: contains_any(self.state(), &*schema),
                         boxed: schema.is_cyclic(),
                         child_req_fields: self.children_requirements(&schema),
+                        xml_name,
+                        xml_item_name,
                     });
 
                     if let EmittedUnit::KnownButAnonymous(_, mut o) = ty {
@@ -679,10 +723,50 @@ where
                     needs_any: true,
                     boxed: false,
                     child_req_fields: vec![],
+                    xml_name: None,
+                    xml_item_name: None,
                 });
             }
         }
 
+        if let Some(tag) = def.discriminator().filter(|_| is_tagged_base(def)) {
+            // The properties of the base go into a struct of its own, which
+            // becomes the enum's variant for the base's discriminator value.
+            obj.name.push_str("Base");
+            let mut variants = vec![TaggedVariant {
+                name: name.clone(),
+                value: def.name().unwrap_or_default().into(),
+                ty_path: format!(
+                    "{}Base",
+                    self.emit_known_object_path(def, DefinitionContext::default())?
+                        .known_type()
+                ),
+                needs_any: obj.needs_any(),
+            }];
+
+            for s in def.subtypes() {
+                let schema = s.read().unwrap();
+                let ty = self.build_def(&schema, DefinitionContext::default())?;
+                variants.push(TaggedVariant {
+                    name: self.def_name(&schema)?,
+                    value: schema.name().unwrap_or_default().into(),
+                    ty_path: ty.known_type(),
+                    needs_any: contains_any(self.state(), &*schema),
+                });
+            }
+
+            let mut tagged = ApiObject::with_name(&name);
+            tagged.description = obj.description.take();
+            tagged.inner = ObjectContainer::TaggedEnum {
+                tag: tag.into(),
+                variants,
+            };
+
+            objects.insert(0, obj);
+            objects.insert(0, tagged);
+            return Ok(EmittedUnit::Objects(objects));
+        }
+
         objects.insert(0, obj);
         Ok(EmittedUnit::Objects(objects))
     }
@@ -692,13 +776,20 @@ where
     /// See `ObjectField.children_req` field for what it means.
     #[allow(clippy::only_used_in_recursion)]
     fn children_requirements(&self, schema: &E::Definition) -> Vec<String> {
+        if is_tagged_base(schema) {
+            return vec![];
+        }
+
         match schema.data_type() {
             Some(DataType::Object) => {
                 if let Some(Either::Right(s)) = schema.additional_properties() {
                     return self.children_requirements(&s.read().unwrap());
-                } else if let Some(s) = schema.required_properties() {
-                    return s.iter().cloned().collect();
                 }
+
+                return merged_properties(schema).1.into_iter().collect();
+            }
+            None if schema.all_of().is_some() => {
+                return merged_properties(schema).1.into_iter().collect();
             }
             Some(DataType::Array) => {
                 if let Some(s) = schema.items() {
@@ -847,6 +938,7 @@ where
 
                 // Enforce that the parameter is an allowed type and collect it.
                 let (ty, mut it_fmts) = match resolve_parameter_type(
+                    self.emitter.state(),
                     info.data_type,
                     info.format.as_ref(),
                     info.items.as_ref(),
@@ -918,17 +1010,21 @@ where
             }
 
             // Enforce that the parameter is an allowed type and collect it.
-            let (ty, mut it_fmts) =
-                match resolve_parameter_type(p.data_type, p.format.as_ref(), p.items.as_ref()) {
-                    Some(t) => t,
-                    None => {
-                        warn!(
-                            "Skipping parameter {:?} with unknown type {:?} in path {:?}",
-                            p.name, p.data_type, self.path
-                        );
-                        continue;
-                    }
-                };
+            let (ty, mut it_fmts) = match resolve_parameter_type(
+                self.emitter.state(),
+                p.data_type,
+                p.format.as_ref(),
+                p.items.as_ref(),
+            ) {
+                Some(t) => t,
+                None => {
+                    warn!(
+                        "Skipping parameter {:?} with unknown type {:?} in path {:?}",
+                        p.name, p.data_type, self.path
+                    );
+                    continue;
+                }
+            };
 
             validate_collection_format(
                 &p.name,
@@ -978,7 +1074,7 @@ where
         let mut response_contains_any = false;
         let response_ty_path = if let Some(s) = Self::get_2xx_response_schema(op) {
             let schema = &*s.read().unwrap();
-            response_contains_any = schema.contains_any();
+            response_contains_any = contains_any(self.emitter.state(), schema);
             Some(
                 self.emitter
                     .build_def(schema, DefinitionContext::default())?
@@ -1039,6 +1135,7 @@ where
         let s = match schema.data_type() {
             // We can deal with object responses.
             Some(DataType::Object) => s.clone(),
+            None if schema.all_of().is_some() => s.clone(),
             // We can also deal with array of objects by mapping
             // the operation to that object.
             _ if listable => Clone::clone(&**schema.items().unwrap()),
@@ -1106,7 +1203,7 @@ where
                 listable,
                 response: Response {
                     ty_path: response_ty_path,
-                    contains_any: schema.contains_any(),
+                    contains_any: contains_any(self.emitter.state(), schema),
                     headers: self.collect_response_headers(&op.responses),
                 },
                 encoding: self.get_coder(op.consumes.as_ref(), &self.api.consumes),
@@ -1163,21 +1260,136 @@ where
     }
 }
 
+/// Returns the properties and the required properties of the given object,
+/// including those from the schemas it's composed of (`allOf`). Properties
+/// defined by the object itself override the ones from its members.
+fn merged_properties<S: Schema>(def: &S) -> (BTreeMap<String, Resolvable<S>>, BTreeSet<String>) {
+    let mut props = BTreeMap::new();
+    let mut required = BTreeSet::new();
+    collect_properties(def, &mut props, &mut required, &mut vec![]);
+    (props, required)
+}
+
+/// Returns whether the given schema "is" or "has" `Any` type.
+///
+/// This has been computed once (using `SchemaGraph`) for the schemas in the
+/// spec. Others (like the ones created during codegen) are walked through
+/// `Schema::contains_any`.
+fn contains_any<S: Schema>(state: &EmitterState, schema: &S) -> bool {
+    let schemas = state.schemas_any.borrow();
+    match schemas.get(&(schema as *const S as usize)) {
+        Some(any) => *any,
+        None => schema.contains_any(),
+    }
+}
+
+/// Returns the XML name of the given property (if it's different from the
+/// property name) and the name of its items (if it's an array whose items
+/// are wrapped in an element).
+///
+/// Referenced schemas describe the object and not the property, so their
+/// XML names are used only for the items of arrays.
+fn xml_field_names<S: Schema>(
+    name: &str,
+    prop: &Resolvable<S>,
+    schema: &S,
+) -> (Option<String>, Option<String>) {
+    let xml = match prop {
+        Resolvable::Raw(_) => schema.xml(),
+        Resolvable::Resolved { .. } => None,
+    };
+
+    let mut xml_name = xml
+        .and_then(Xml::qualified_name)
+        .unwrap_or_else(|| name.to_owned());
+    let mut item_name = None;
+    if schema.data_type() == Some(DataType::Array) {
+        let items = schema
+            .items()
+            .and_then(|s| s.read().unwrap().xml().and_then(Xml::qualified_name));
+        if matches!(xml, Some(x) if x.is_wrapped()) {
+            item_name = Some(items.unwrap_or_else(|| xml_name.clone()));
+        } else if let Some(items) = items {
+            // Items of unwrapped arrays are repeated elements (with the items' name).
+            xml_name = items;
+        }
+    } else if matches!(xml, Some(x) if x.is_attribute()) {
+        xml_name.insert(0, '@');
+    }
+
+    (Some(xml_name).filter(|n| n != name), item_name)
+}
+
+/// Returns whether the spec makes use of XML media types and whether
+/// those are the only media types used in the spec.
+fn xml_usage<S: Schema>(api: &ResolvableApi<S>) -> (bool, bool) {
+    let ops = api.paths.values().flat_map(|p| p.methods.values());
+    let ranges = api
+        .consumes
+        .iter()
+        .chain(&api.produces)
+        .chain(
+            ops.flat_map(|op| op.consumes.iter().chain(&op.produces))
+                .flatten(),
+        )
+        .collect::<Vec<_>>();
+    let has_xml = ranges.iter().any(|r| r.is_xml());
+    (has_xml, has_xml && ranges.iter().all(|r| r.is_xml()))
+}
+
+/// Returns whether the given schema is emitted as an internally tagged enum.
+fn is_tagged_base<S: Schema>(def: &S) -> bool {
+    def.discriminator().is_some() && !def.subtypes().is_empty()
+}
+
+fn collect_properties<S: Schema>(
+    def: &S,
+    props: &mut BTreeMap<String, Resolvable<S>>,
+    required: &mut BTreeSet<String>,
+    visited: &mut Vec<String>,
+) {
+    if let Some(name) = def.name() {
+        if visited.iter().any(|n| n == name) {
+            return; // cyclic composition
+        }
+
+        visited.push(name.into());
+    }
+
+    if let Some(members) = def.all_of() {
+        for member in members {
+            collect_properties(&*member.read().unwrap(), props, required, visited);
+        }
+    }
+
+    if let Some(p) = def.properties() {
+        props.extend(p.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    if let Some(r) = def.required_properties() {
+        required.extend(r.iter().cloned());
+    }
+}
+
 /// Ensures that a parameter type is either a simple type or an array
 /// and returns the resolved Rust type.
 fn resolve_parameter_type(
+    state: &EmitterState,
     dt: Option<DataType>,
     dt_fmt: Option<&DataTypeFormat>,
     items: Option<&Items>,
 ) -> Option<(String, Vec<CollectionFormat>)> {
-    match matching_unit_type(dt_fmt, dt) {
-        Some(t) => return Some((t.into(), vec![])),
+    match matching_unit_type(state, dt_fmt, dt) {
+        Some(t) => return Some((t, vec![])),
         None if dt == Some(DataType::File) => return Some((FILE_MARKER.into(), vec![])),
         None if dt == Some(DataType::Array) => {
             if let Some(i) = items {
-                if let Some((ty, mut fmts)) =
-                    resolve_parameter_type(i.data_type, i.format.as_ref(), i.items.as_deref())
-                {
+                if let Some((ty, mut fmts)) = resolve_parameter_type(
+                    state,
+                    i.data_type,
+                    i.format.as_ref(),
+                    i.items.as_deref(),
+                ) {
                     fmts.insert(0, i.collection_format.unwrap_or_default());
                     // We collect it as `Vec` for now - we'll replace it with our
                     // `Delimited` wrapper when we actually write the code.
@@ -1191,12 +1403,18 @@ fn resolve_parameter_type(
     None
 }
 
-/// Checks if the given type/format matches a known Rust type and returns it.
+/// Checks if the given type/format matches a known Rust type (or one
+/// configured for that format in the emitter state) and returns it.
 fn matching_unit_type(
+    state: &EmitterState,
     format: Option<&DataTypeFormat>,
     type_: Option<DataType>,
-) -> Option<&'static str> {
-    match format {
+) -> Option<String> {
+    if let Some(ty) = format.and_then(|f| state.format_types.get(f.as_str())) {
+        return Some(ty.clone());
+    }
+
+    let ty = match format {
         Some(DataTypeFormat::Int32) => Some("i32"),
         Some(DataTypeFormat::Int64) => Some("i64"),
         Some(DataTypeFormat::Float) => Some("f32"),
@@ -1208,7 +1426,9 @@ fn matching_unit_type(
             Some(DataType::String) => Some("String"),
             _ => None,
         },
-    }
+    };
+
+    ty.map(String::from)
 }
 
 /// If the parameter is an array, then validate the collection formats and
@@ -53,7 +53,7 @@ impl ApiObject {
             };
         }
 
-        let needs_any = self.fields().iter().any(|f| f.needs_any);
+        let needs_any = self.needs_any();
         // Always emit a builder for API objects (regardless of operations).
         let main_builder = ApiObjectBuilder {
             helper_module_prefix,
@@ -1152,7 +1152,7 @@ impl Display for ApiObjectImpl<'_> {
         }
 
         f.write_str("impl")?;
-        let needs_any = self.inner.fields().iter().any(|f| f.needs_any);
+        let needs_any = self.inner.needs_any();
         if needs_any {
             f.write_str("<")?;
             f.write_str(ANY_GENERIC_PARAMETER)?;
@@ -39,6 +39,9 @@ pub struct ApiObject {
     pub inner: ObjectContainer,
     /// Paths with operations which address this object.
     pub paths: BTreeMap<String, PathOps>,
+    /// Name of the (root) element for this object in XML (if it's different
+    /// from the name of the struct).
+    pub xml_name: Option<String>,
 }
 
 impl ApiObject {
@@ -52,14 +55,25 @@ impl ApiObject {
     }
 
     /// Get a reference to the struct fields. **Panics** if this
-    /// is not a struct.
+    /// is a simple enum. Tagged enums don't have any fields.
     pub fn fields(&self) -> &[ObjectField] {
         match &self.inner {
             ObjectContainer::Struct { fields } => fields,
+            ObjectContainer::TaggedEnum { .. } => &[],
             _ => panic!("cannot obtain fields for enum type"),
         }
     }
 
+    /// Returns whether this object is generic over `Any` type (i.e., some field
+    /// or subtype "is" or "has" `Any` type).
+    pub fn needs_any(&self) -> bool {
+        match &self.inner {
+            ObjectContainer::Struct { fields } => fields.iter().any(|f| f.needs_any),
+            ObjectContainer::TaggedEnum { variants, .. } => variants.iter().any(|v| v.needs_any),
+            ObjectContainer::Enum { .. } => false,
+        }
+    }
+
     /// Get a mutable reference to the enum variants. **Panics** if this
     /// is not an enum.
     pub fn variants_mut(&mut self) -> &mut Vec<ObjectVariant> {
@@ -96,6 +110,14 @@ pub enum ObjectContainer {
         /// Fields if it's a struct.
         fields: Vec<ObjectField>,
     },
+    /// Internally tagged enum over the subtypes of some schema
+    /// (based on its discriminator).
+    TaggedEnum {
+        /// Name of the discriminator property.
+        tag: String,
+        /// Variants (one for the base itself and one for each subtype).
+        variants: Vec<TaggedVariant>,
+    },
 }
 
 impl ObjectContainer {
@@ -204,6 +226,19 @@ pub struct ObjectVariant {
     pub value: serde_json::Value,
 }
 
+/// Represents a variant of an internally tagged enum.
+#[derive(Debug, Clone)]
+pub struct TaggedVariant {
+    /// Name of the variant (camel-cased).
+    pub name: String,
+    /// Value of the discriminator for this variant.
+    pub value: String,
+    /// Type of the subtype as a path.
+    pub ty_path: String,
+    /// Whether the subtype "is" or "has" an `Any` type.
+    pub needs_any: bool,
+}
+
 /// Represents a struct field.
 #[derive(Debug, Clone)]
 pub struct ObjectField {
@@ -230,6 +265,12 @@ pub struct ObjectField {
     ///
     /// Yours sincerely.
     pub child_req_fields: Vec<String>,
+    /// Name of the element (or the `@` prefixed attribute) for this field
+    /// in XML (if it's different from the name of the field).
+    pub xml_name: Option<String>,
+    /// Name of the items (if this field is an array whose items are wrapped
+    /// in an element in XML).
+    pub xml_item_name: Option<String>,
 }
 
 pub fn to_snake_case(name: &str) -> String {
@@ -900,9 +941,18 @@ impl Display for ApiObject {
             return self.write_enum(f);
         }
 
-        f.write_str("#[derive(Debug, Default, Clone, Serialize, Deserialize)]\npub struct ")?;
+        if let ObjectContainer::TaggedEnum { tag, variants } = &self.inner {
+            return self.write_tagged_enum(tag, variants, f);
+        }
+
+        f.write_str("#[derive(Debug, Default, Clone, Serialize, Deserialize)]\n")?;
+        if let Some(name) = &self.xml_name {
+            writeln!(f, "#[serde(rename = {:?})]", name)?;
+        }
+
+        f.write_str("pub struct ")?;
         f.write_str(&self.name)?;
-        if !self.inner.is_enum() && self.fields().iter().any(|f| f.needs_any) {
+        if self.needs_any() {
             ApiObject::write_any_generic(f)?;
         }
 
@@ -925,6 +975,19 @@ impl Display for ApiObject {
                 f.write_str("    ")?;
                 if field.name == EXTRA_PROPS_FIELD {
                     f.write_str("#[serde(flatten)]\n    ")?;
+                } else if field.xml_item_name.is_some() {
+                    write!(
+                        f,
+                        "#[serde(rename = {:?}, ",
+                        field.xml_name.as_ref().unwrap_or(&field.name)
+                    )?;
+                    if !field.is_required {
+                        f.write_str("default, ")?;
+                    }
+
+                    write!(f, "with = {:?})]\n    ", self.xml_module(&new_name))?;
+                } else if let Some(name) = &field.xml_name {
+                    write!(f, "#[serde(rename = {:?})]\n    ", name)?;
                 } else if new_name != field.name.as_str() {
                     f.write_str("#[serde(rename = \"")?;
                     f.write_str(&field.name)?;
@@ -964,11 +1027,88 @@ impl Display for ApiObject {
             f.write_str("\n")?;
         }
 
-        f.write_str("}\n")
+        f.write_str("}\n")?;
+        self.write_xml_modules(f)
     }
 }
 
 impl ApiObject {
+    /// Name of the module with the (de)serializer for the given field
+    /// (which is an array wrapped in an element in XML).
+    fn xml_module(&self, field_name: &str) -> String {
+        format!(
+            "{}_{}_xml",
+            to_snake_case(&self.name),
+            field_name.trim_end_matches('_')
+        )
+    }
+
+    /// Writes the modules for (de)serializing the fields which are arrays
+    /// wrapped in an element in XML (i.e., `<tags><tag>..</tag></tags>`).
+    fn write_xml_modules<F>(&self, f: &mut F) -> fmt::Result
+    where
+        F: Write,
+    {
+        for field in self.fields() {
+            let item_name = match &field.xml_item_name {
+                Some(n) => n,
+                None => continue,
+            };
+
+            let mut new_name = to_snake_case(&field.name);
+            if RUST_KEYWORDS.iter().any(|&k| k == new_name) {
+                new_name.push('_');
+            }
+
+            let (ty, wrap, unwrap) = if field.is_required {
+                ("Vec<T>", "items", "w.items")
+            } else {
+                (
+                    "Option<Vec<T>>",
+                    "items.as_deref().unwrap_or_default()",
+                    "Some(w.items)",
+                )
+            };
+
+            write!(
+                f,
+                "
+#[allow(clippy::ptr_arg)]
+mod {module} {{
+    use serde::{{Deserialize, Deserializer, Serialize, Serializer}};
+
+    #[derive(Serialize)]
+    struct Items<'a, T> {{
+        #[serde(rename = {item:?})]
+        items: &'a [T],
+    }}
+
+    #[derive(Deserialize)]
+    struct OwnedItems<T> {{
+        #[serde(rename = {item:?}, default)]
+        items: Vec<T>,
+    }}
+
+    pub fn serialize<S: Serializer, T: Serialize>(items: &{ty}, serializer: S) -> Result<S::Ok, S::Error> {{
+        Items {{ items: {wrap} }}.serialize(serializer)
+    }}
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<{ty}, D::Error> {{
+        OwnedItems::deserialize(deserializer).map(|w| {unwrap})
+    }}
+}}
+",
+                module = self.xml_module(&new_name),
+                item = item_name,
+                ty = ty,
+                wrap = wrap,
+                unwrap = unwrap,
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Writes an enum declaration along with serde impl if needed.
     fn write_enum<F>(&self, f: &mut F) -> fmt::Result
     where
@@ -1026,6 +1166,159 @@ impl ApiObject {
     }
 }
 
+impl ApiObject {
+    /// Writes an internally tagged enum declaration. Subtypes are boxed,
+    /// because they're allowed to refer to their base.
+    ///
+    /// The variants (de)serialize the discriminator property by themselves
+    /// (so that they can also be used on their own), which means that serde's
+    /// internal tagging can't be used. Instead, the value is buffered and
+    /// (de)serialized based on the discriminator.
+    fn write_tagged_enum<F>(&self, tag: &str, variants: &[TaggedVariant], f: &mut F) -> fmt::Result
+    where
+        F: fmt::Write,
+    {
+        let needs_any = self.needs_any();
+        f.write_str("#[derive(Debug, Clone)]\npub enum ")?;
+        f.write_str(&self.name)?;
+        if needs_any {
+            ApiObject::write_any_generic(f)?;
+        }
+
+        f.write_str(" {")?;
+        variants.iter().try_for_each(|var| {
+            f.write_str("\n    ")?;
+            f.write_str(&var.name)?;
+            f.write_str("(Box<")?;
+            if var.needs_any {
+                Self::write_field_with_any(&var.ty_path, f)?;
+            } else {
+                f.write_str(&var.ty_path)?;
+            }
+
+            f.write_str(">),")
+        })?;
+
+        f.write_str("\n}\n")?;
+
+        let any = if needs_any { "<Any>" } else { "" };
+        let (ser_generics, de_generics) = if needs_any {
+            (
+                "<Any: serde::Serialize>",
+                "<'de, Any: serde::de::DeserializeOwned>",
+            )
+        } else {
+            ("", "<'de>")
+        };
+
+        write!(
+            f,
+            "
+impl{generics} serde::Serialize for {name}{any} {{
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {{
+        use serde::ser::Error;
+        let (tag, value) = match self {{",
+            generics = ser_generics,
+            name = &self.name,
+            any = any
+        )?;
+
+        variants.iter().try_for_each(|var| {
+            write!(
+                f,
+                "
+            {}::{}(v) => ({:?}, serde_json::to_value(v)),",
+                self.name, var.name, var.value
+            )
+        })?;
+
+        write!(
+            f,
+            "
+        }};
+
+        let mut value = value.map_err(S::Error::custom)?;
+        if let Some(map) = value.as_object_mut() {{
+            map.insert({tag:?}.into(), tag.into());
+        }}
+
+        value.serialize(ser)
+    }}
+}}
+
+impl{generics} serde::Deserialize<'de> for {name}{any} {{
+    fn deserialize<D: serde::Deserializer<'de>>(deser: D) -> Result<Self, D::Error> {{
+        use serde::de::Error;
+        const VARIANTS: &[&str] = &[",
+            tag = tag,
+            generics = de_generics,
+            name = &self.name,
+            any = any
+        )?;
+
+        variants.iter().enumerate().try_for_each(|(i, var)| {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{:?}", var.value)
+        })?;
+
+        write!(
+            f,
+            "];
+        let value = serde_json::Value::deserialize(deser)?;
+        let tag = match value.get({tag:?}) {{
+            Some(serde_json::Value::String(s)) => s.clone(),
+            _ => return Err(D::Error::missing_field({tag:?})),
+        }};
+
+        match tag.as_str() {{",
+            tag = tag
+        )?;
+
+        variants.iter().try_for_each(|var| {
+            write!(
+                f,
+                "
+            {val:?} => serde_json::from_value(value).map(|v| {name}::{var}(Box::new(v))),",
+                val = var.value,
+                name = &self.name,
+                var = var.name
+            )
+        })?;
+
+        f.write_str(
+            "
+            _ => return Err(D::Error::unknown_variant(&tag, VARIANTS)),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+",
+        )?;
+
+        if let Some(var) = variants.first() {
+            let generics = if needs_any { "<Any: Default>" } else { "" };
+            writeln!(
+                f,
+                "
+impl{generics} Default for {name}{any} {{
+    fn default() -> Self {{
+        {name}::{first_var}(Default::default())
+    }}
+}}",
+                generics = generics,
+                name = &self.name,
+                any = any,
+                first_var = &var.name
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Abstraction for implementing Serialize/Deserialize mechanism
 /// for non-string enums.
 struct EnumSerdeImpl<'a> {
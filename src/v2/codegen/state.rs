@@ -37,6 +37,10 @@ pub struct EmitterState {
     pub ns_sep: &'static str,
     /// Module prefix for using in generated code.
     pub mod_prefix: &'static str,
+    /// Rust types to be used for data type formats (say, `decimal` to
+    /// `rust_decimal::Decimal`). These take precedence over the types
+    /// we'd otherwise pick for a format.
+    pub format_types: HashMap<String, String>,
 
     /* MARK: Private fields. */
     /// Base URL for the API.
@@ -63,6 +67,13 @@ pub struct EmitterState {
     cli_yaml: RefCell<String>,
     /// Generated match arms for clap subcommands and matches.
     cli_match_arms: RefCell<String>,
+    /// Whether the schemas in the spec that's being emitted (by their
+    /// addresses) "are" or "have" `Any` type.
+    pub(super) schemas_any: RefCell<HashMap<usize, bool>>,
+    /// Whether objects should be (de)serialized using their XML names
+    /// (attributes, element names, wrapped arrays). This is set only when
+    /// the spec uses XML media types exclusively.
+    pub(super) xml_names: RefCell<bool>,
 }
 
 /// Indicates a child module in codegen working directory.
@@ -102,6 +113,8 @@ impl EmitterState {
         *self.cli_yaml.borrow_mut() = Default::default();
         *self.cli_match_arms.borrow_mut() = Default::default();
         *self.media_coders.borrow_mut() = Default::default();
+        *self.schemas_any.borrow_mut() = Default::default();
+        *self.xml_names.borrow_mut() = false;
     }
 
     /// Sets the media type information for encoder/decoders.
@@ -449,6 +462,11 @@ impl EmitterState {
                     authors: &format!("{:?}", meta.authors.as_ref().unwrap()),
                     is_cli,
                     no_root,
+                    needs_xml: self
+                        .media_coders
+                        .borrow()
+                        .iter()
+                        .any(|c| c.decoder.starts_with("serde_xml_rs::")),
                 },
             )?;
 
@@ -548,6 +566,7 @@ impl Default for EmitterState {
             working_dir: PathBuf::from("."),
             mod_prefix: "crate::",
             ns_sep: ".",
+            format_types: HashMap::new(),
             #[cfg(feature = "cli")]
             crate_meta: Rc::new(RefCell::new(None)),
             base_url: RefCell::new("https://example.com".parse().expect("invalid URL?")),
@@ -557,6 +576,8 @@ impl Default for EmitterState {
             unit_types: RefCell::new(BTreeSet::new()),
             cli_yaml: RefCell::new(String::new()),
             cli_match_arms: RefCell::new(String::new()),
+            schemas_any: RefCell::new(HashMap::new()),
+            xml_names: RefCell::new(false),
             media_coders: RefCell::new(vec![]),
             default_encoding: RefCell::new(SpecFormat::Json),
         }
@@ -585,6 +606,7 @@ struct ManifestContext<'a> {
     authors: &'a str,
     is_cli: bool,
     no_root: bool,
+    needs_xml: bool,
 }
 
 #[derive(serde::Serialize)]
@@ -0,0 +1,709 @@
+//! Breaking change detection between two versions of an OpenAPI v2 spec.
+//!
+//! The diff engine walks the operations of both specs and classifies each
+//! change as breaking or non-breaking for existing clients. Requests and
+//! responses are treated differently. For example, a new enum variant is
+//! fine in a request body, but it breaks clients which read it from a response.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{self, diff, DefaultSchema, ResolvableApi};
+//!
+//! let old: ResolvableApi<DefaultSchema> = v2::from_path("old.yaml").unwrap();
+//! let new: ResolvableApi<DefaultSchema> = v2::from_path("new.yaml").unwrap();
+//! let report = diff::diff(&old, &new);
+//! if report.has_breaking_changes() {
+//!     println!("{}", serde_json::to_string_pretty(&report).unwrap());
+//! }
+//! ```
+
+use super::json_path;
+use crate::error::PaperClipError;
+use paperclip_core::v2::{
+    models::{
+        Api, DefaultApiRaw, DefaultSchema, Either, HttpMethod, Items, Parameter, ParameterIn,
+        Reference, Resolvable, ResolvableApi, ResolvableOperation, ResolvableParameter,
+        ResolvableResponse,
+    },
+    pointer::{self, DEF_REF_PREFIX, PARAM_REF_PREFIX, RESP_REF_PREFIX},
+    schema::Schema,
+};
+use serde::Serialize;
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+/// Kind of change between two specs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeKind {
+    OperationAdded,
+    OperationRemoved,
+    ParameterAdded,
+    ParameterRemoved,
+    /// An optional parameter is now required.
+    ParameterRequired,
+    /// A required parameter is now optional.
+    ParameterOptional,
+    /// Collection format of an array parameter has changed.
+    CollectionFormatChanged,
+    ResponseAdded,
+    ResponseRemoved,
+    /// Type or format of a parameter, property or response has changed.
+    TypeChanged,
+    EnumValuesAdded,
+    EnumValuesRemoved,
+    PropertyAdded,
+    PropertyRemoved,
+    /// An optional property is now required.
+    PropertyRequired,
+    /// A required property is now optional.
+    PropertyOptional,
+}
+
+/// A change between two specs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// Kind of this change.
+    pub kind: ChangeKind,
+    /// Whether this change breaks existing clients.
+    pub breaking: bool,
+    /// JSON path of the changed node (in the new spec, or the old spec
+    /// if it's been removed).
+    pub path: String,
+    /// Human readable description of this change.
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = if self.breaking {
+            "breaking"
+        } else {
+            "non-breaking"
+        };
+
+        write!(f, "{}: {} (at {})", label, self.message, self.path)
+    }
+}
+
+/// Report of all changes between two specs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DiffReport {
+    pub changes: Vec<Change>,
+}
+
+impl DiffReport {
+    /// Whether any of the changes would break existing clients.
+    pub fn has_breaking_changes(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+
+    /// Returns the changes which would break existing clients.
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|c| c.breaking)
+    }
+}
+
+/// Compares the old spec against the new spec and returns the changes.
+pub fn diff<S: Schema>(old: &ResolvableApi<S>, new: &ResolvableApi<S>) -> DiffReport {
+    let mut differ = Differ {
+        old,
+        new,
+        visited: HashSet::new(),
+        ancestors: HashSet::new(),
+        report: DiffReport::default(),
+    };
+
+    differ.compare_paths();
+    differ.report
+}
+
+/// Compares specs generated by plugins (like the actix plugin).
+pub fn diff_raw(old: &DefaultApiRaw, new: &DefaultApiRaw) -> Result<DiffReport, PaperClipError> {
+    let old: ResolvableApi<DefaultSchema> = serde_json::from_value(serde_json::to_value(old)?)?;
+    let new: ResolvableApi<DefaultSchema> = serde_json::from_value(serde_json::to_value(new)?)?;
+    Ok(diff(&old, &new))
+}
+
+/// Direction of data for the schema being compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Direction {
+    Request,
+    Response,
+}
+
+type SchemaPair<S> = (*const RwLock<S>, *const RwLock<S>, Direction);
+
+struct Differ<'a, S> {
+    old: &'a ResolvableApi<S>,
+    new: &'a ResolvableApi<S>,
+    /// Pairs of schemas which have already been compared at some path.
+    visited: HashSet<(SchemaPair<S>, String)>,
+    /// Pairs of schemas currently being compared (for cyclic schemas).
+    ancestors: HashSet<SchemaPair<S>>,
+    report: DiffReport,
+}
+
+impl<'a, S: Schema> Differ<'a, S> {
+    fn add(&mut self, kind: ChangeKind, breaking: bool, path: String, message: String) {
+        self.report.changes.push(Change {
+            kind,
+            breaking,
+            path,
+            message,
+        });
+    }
+
+    fn compare_paths(&mut self) {
+        let old_ops = operations(self.old);
+        let new_ops = operations(self.new);
+
+        for ((template, method), (path, op)) in &old_ops {
+            let op_path = json_path(&json_path("$.paths", path), &method_name(*method));
+            match new_ops.get(&(template.clone(), *method)) {
+                Some((new_path, new_op)) => {
+                    let new_op_path =
+                        json_path(&json_path("$.paths", new_path), &method_name(*method));
+                    self.compare_operation((path, op), (new_path, new_op), *method, new_op_path);
+                }
+                None => self.add(
+                    ChangeKind::OperationRemoved,
+                    true,
+                    op_path,
+                    format!("Operation {} {} was removed", method_label(*method), path),
+                ),
+            }
+        }
+
+        for ((template, method), (path, _)) in &new_ops {
+            if !old_ops.contains_key(&(template.clone(), *method)) {
+                self.add(
+                    ChangeKind::OperationAdded,
+                    false,
+                    json_path(&json_path("$.paths", path), &method_name(*method)),
+                    format!("Operation {} {} was added", method_label(*method), path),
+                );
+            }
+        }
+    }
+
+    fn compare_operation(
+        &mut self,
+        (old_path, old_op): (&str, &ResolvableOperation<S>),
+        (new_path, new_op): (&str, &ResolvableOperation<S>),
+        method: HttpMethod,
+        op_path: String,
+    ) {
+        let old_params = parameters(self.old, old_path, method, old_op);
+        let new_params = parameters(self.new, new_path, method, new_op);
+
+        for (key, (old_param_path, old_param)) in &old_params {
+            let old_param = old_param.read().unwrap();
+            match new_params.get(key) {
+                Some((path, new_param)) => {
+                    self.compare_parameter(&old_param, &new_param.read().unwrap(), path.clone());
+                }
+                None => self.add(
+                    ChangeKind::ParameterRemoved,
+                    false,
+                    old_param_path.clone(),
+                    format!(
+                        "Parameter {:?} ({}) was removed",
+                        old_param.name,
+                        param_location(old_param.in_)
+                    ),
+                ),
+            }
+        }
+
+        for (key, (path, new_param)) in &new_params {
+            if old_params.contains_key(key) {
+                continue;
+            }
+
+            let new_param = new_param.read().unwrap();
+            self.add(
+                ChangeKind::ParameterAdded,
+                new_param.required,
+                path.clone(),
+                format!(
+                    "{} parameter {:?} ({}) was added",
+                    if new_param.required {
+                        "Required"
+                    } else {
+                        "Optional"
+                    },
+                    new_param.name,
+                    param_location(new_param.in_)
+                ),
+            );
+        }
+
+        let responses_path = format!("{}.responses", op_path);
+        for (code, old_resp) in &old_op.responses {
+            let old_resp = match response(self.old, old_resp) {
+                Some(r) => r,
+                None => continue,
+            };
+
+            let path = json_path(&responses_path, code);
+            match new_op
+                .responses
+                .get(code)
+                .and_then(|r| response(self.new, r))
+            {
+                Some(new_resp) => {
+                    let old_schema = old_resp.read().unwrap().schema.clone();
+                    let new_schema = new_resp.read().unwrap().schema.clone();
+                    match (old_schema, new_schema) {
+                        (Some(o), Some(n)) => self.compare_schema(
+                            &o,
+                            &n,
+                            json_path(&path, "schema"),
+                            Direction::Response,
+                        ),
+                        (Some(_), None) => self.add(
+                            ChangeKind::TypeChanged,
+                            true,
+                            path,
+                            format!("Response {} no longer has a schema", code),
+                        ),
+                        (None, Some(_)) => self.add(
+                            ChangeKind::TypeChanged,
+                            false,
+                            path,
+                            format!("Response {} now has a schema", code),
+                        ),
+                        (None, None) => (),
+                    }
+                }
+                None => self.add(
+                    ChangeKind::ResponseRemoved,
+                    code.starts_with('2'),
+                    path,
+                    format!(
+                        "Response {} of {} {} was removed",
+                        code,
+                        method_label(method),
+                        old_path
+                    ),
+                ),
+            }
+        }
+
+        for code in new_op.responses.keys() {
+            if !old_op.responses.contains_key(code) {
+                self.add(
+                    ChangeKind::ResponseAdded,
+                    false,
+                    json_path(&responses_path, code),
+                    format!(
+                        "Response {} of {} {} was added",
+                        code,
+                        method_label(method),
+                        new_path
+                    ),
+                );
+            }
+        }
+    }
+
+    fn compare_parameter(
+        &mut self,
+        old: &Parameter<Resolvable<S>>,
+        new: &Parameter<Resolvable<S>>,
+        path: String,
+    ) {
+        match (old.required, new.required) {
+            (false, true) => self.add(
+                ChangeKind::ParameterRequired,
+                true,
+                path.clone(),
+                format!("Parameter {:?} is now required", new.name),
+            ),
+            (true, false) => self.add(
+                ChangeKind::ParameterOptional,
+                false,
+                path.clone(),
+                format!("Parameter {:?} is now optional", new.name),
+            ),
+            _ => (),
+        }
+
+        if let (Some(o), Some(n)) = (old.schema.as_ref(), new.schema.as_ref()) {
+            self.compare_schema(o, n, json_path(&path, "schema"), Direction::Request);
+            return;
+        }
+
+        if old.data_type != new.data_type || old.format != new.format {
+            self.add(
+                ChangeKind::TypeChanged,
+                true,
+                path,
+                format!("Type of parameter {:?} has changed", new.name),
+            );
+            return;
+        }
+
+        if old.collection_format.unwrap_or_default() != new.collection_format.unwrap_or_default() {
+            self.add(
+                ChangeKind::CollectionFormatChanged,
+                true,
+                path.clone(),
+                format!("Collection format of parameter {:?} has changed", new.name),
+            );
+        }
+
+        self.compare_enum(&old.enum_, &new.enum_, path.clone(), Direction::Request);
+        if let (Some(o), Some(n)) = (old.items.as_ref(), new.items.as_ref()) {
+            self.compare_items(o, n, json_path(&path, "items"));
+        }
+    }
+
+    fn compare_items(&mut self, old: &Items, new: &Items, path: String) {
+        if old.data_type != new.data_type || old.format != new.format {
+            self.add(
+                ChangeKind::TypeChanged,
+                true,
+                path,
+                "Type of array items has changed".into(),
+            );
+            return;
+        }
+
+        self.compare_enum(&old.enum_, &new.enum_, path.clone(), Direction::Request);
+        if let (Some(o), Some(n)) = (old.items.as_ref(), new.items.as_ref()) {
+            self.compare_items(o, n, json_path(&path, "items"));
+        }
+    }
+
+    fn compare_enum(
+        &mut self,
+        old: &[serde_json::Value],
+        new: &[serde_json::Value],
+        path: String,
+        direction: Direction,
+    ) {
+        // An empty enum allows everything.
+        let removed = if new.is_empty() {
+            vec![]
+        } else if old.is_empty() {
+            vec![serde_json::Value::String("<any>".into())]
+        } else {
+            old.iter().filter(|v| !new.contains(v)).cloned().collect()
+        };
+
+        let added = if old.is_empty() {
+            vec![]
+        } else if new.is_empty() {
+            vec![serde_json::Value::String("<any>".into())]
+        } else {
+            new.iter().filter(|v| !old.contains(v)).cloned().collect()
+        };
+
+        if !removed.is_empty() {
+            self.add(
+                ChangeKind::EnumValuesRemoved,
+                direction == Direction::Request,
+                path.clone(),
+                format!("Enum values {} were removed", join_values(&removed)),
+            );
+        }
+
+        if !added.is_empty() {
+            self.add(
+                ChangeKind::EnumValuesAdded,
+                direction == Direction::Response,
+                path,
+                format!("Enum values {} were added", join_values(&added)),
+            );
+        }
+    }
+
+    fn compare_schema(
+        &mut self,
+        old: &Resolvable<S>,
+        new: &Resolvable<S>,
+        path: String,
+        direction: Direction,
+    ) {
+        let (old, new) = match (schema_node(self.old, old), schema_node(self.new, new)) {
+            (Some(o), Some(n)) => (o, n),
+            _ => return,
+        };
+
+        // The same schemas can be reached through different paths (say, a
+        // definition used by multiple operations) and changes are reported
+        // for each of them, but a schema is never compared inside itself.
+        let pair = (&*old as *const _, &*new as *const _, direction);
+        if !self.visited.insert((pair, path.clone())) || !self.ancestors.insert(pair) {
+            return;
+        }
+
+        self.compare_schema_nodes(&old.read().unwrap(), &new.read().unwrap(), path, direction);
+        self.ancestors.remove(&pair);
+    }
+
+    fn compare_schema_nodes(&mut self, old: &S, new: &S, path: String, direction: Direction) {
+        if old.data_type() != new.data_type() || old.format() != new.format() {
+            self.add(
+                ChangeKind::TypeChanged,
+                true,
+                path,
+                format!(
+                    "Type has changed from {} to {}",
+                    type_label(old),
+                    type_label(new)
+                ),
+            );
+            return;
+        }
+
+        self.compare_enum(
+            old.enum_variants().unwrap_or_default(),
+            new.enum_variants().unwrap_or_default(),
+            path.clone(),
+            direction,
+        );
+
+        if let (Some(o), Some(n)) = (old.items(), new.items()) {
+            self.compare_schema(o, n, json_path(&path, "items"), direction);
+        }
+
+        if let (Some(o), Some(n)) = (old.all_of(), new.all_of()) {
+            let members_path = json_path(&path, "allOf");
+            for (i, (o, n)) in o.iter().zip(n).enumerate() {
+                self.compare_schema(o, n, format!("{}[{}]", members_path, i), direction);
+            }
+        }
+
+        if let (Some(Either::Right(o)), Some(Either::Right(n))) =
+            (old.additional_properties(), new.additional_properties())
+        {
+            self.compare_schema(o, n, json_path(&path, "additionalProperties"), direction);
+        }
+
+        let empty = BTreeMap::new();
+        let old_props = old.properties().unwrap_or(&empty);
+        let new_props = new.properties().unwrap_or(&empty);
+        let is_required = |s: &S, name: &str| {
+            s.required_properties()
+                .map(|r| r.contains(name))
+                .unwrap_or(false)
+        };
+
+        let props_path = json_path(&path, "properties");
+        for (name, old_prop) in old_props {
+            let prop_path = json_path(&props_path, name);
+            let new_prop = match new_props.get(name) {
+                Some(p) => p,
+                None => {
+                    self.add(
+                        ChangeKind::PropertyRemoved,
+                        direction == Direction::Response,
+                        prop_path,
+                        format!("Property {:?} was removed", name),
+                    );
+                    continue;
+                }
+            };
+
+            match (is_required(old, name), is_required(new, name)) {
+                (false, true) => self.add(
+                    ChangeKind::PropertyRequired,
+                    direction == Direction::Request,
+                    prop_path.clone(),
+                    format!("Property {:?} is now required", name),
+                ),
+                (true, false) => self.add(
+                    ChangeKind::PropertyOptional,
+                    direction == Direction::Response,
+                    prop_path.clone(),
+                    format!("Property {:?} is now optional", name),
+                ),
+                _ => (),
+            }
+
+            self.compare_schema(old_prop, new_prop, prop_path, direction);
+        }
+
+        for name in new_props.keys() {
+            if old_props.contains_key(name) {
+                continue;
+            }
+
+            let required = is_required(new, name);
+            self.add(
+                ChangeKind::PropertyAdded,
+                required && direction == Direction::Request,
+                json_path(&props_path, name),
+                format!(
+                    "{} property {:?} was added",
+                    if required { "Required" } else { "Optional" },
+                    name
+                ),
+            );
+        }
+    }
+}
+
+type OperationsMap<'a, S> = BTreeMap<(String, HttpMethod), (&'a str, &'a ResolvableOperation<S>)>;
+
+/// Returns the operations in the given spec, keyed by their path template
+/// (without parameter names) and method.
+fn operations<S>(api: &ResolvableApi<S>) -> OperationsMap<'_, S> {
+    let mut ops = BTreeMap::new();
+    for (path, map) in &api.paths {
+        let template = Api::<(), (), ()>::path_parameters_map(path, |_| "{}".into()).into_owned();
+        for (&method, op) in &map.methods {
+            ops.insert((template.clone(), method), (path.as_str(), op));
+        }
+    }
+
+    ops
+}
+
+type ParametersMap<'a, S> = BTreeMap<(String, String), (String, &'a ResolvableParameter<S>)>;
+
+/// Returns the parameters (including the ones shared across operations in a path)
+/// of the given operation, keyed by their location and name, along with their JSON paths.
+fn parameters<'a, S>(
+    api: &'a ResolvableApi<S>,
+    path: &str,
+    method: HttpMethod,
+    op: &'a ResolvableOperation<S>,
+) -> ParametersMap<'a, S> {
+    let path_item_path = json_path("$.paths", path);
+    let op_path = json_path(&path_item_path, &method_name(method));
+    let shared = api
+        .paths
+        .get(path)
+        .map(|m| &m.parameters[..])
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (format!("{}.parameters[{}]", path_item_path, i), p));
+    let own = op
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (format!("{}.parameters[{}]", op_path, i), p));
+
+    // Path parameters are identified by their position in the template,
+    // because renaming them doesn't affect clients.
+    let mut template_params = vec![];
+    Api::<(), (), ()>::path_parameters_map(path, |name| {
+        let name = name.split(':').next().unwrap_or(name);
+        template_params.push(name.to_owned());
+        "".into()
+    });
+
+    let mut params = BTreeMap::new();
+    // Operation parameters override the shared parameters.
+    for (param_path, p) in shared.chain(own) {
+        if let Some(p) = parameter(api, p) {
+            let key = {
+                let param = p.read().unwrap();
+                let name = match template_params.iter().position(|n| *n == param.name) {
+                    Some(idx) if param.in_ == ParameterIn::Path => format!("{{{}}}", idx),
+                    _ => param.name.clone(),
+                };
+
+                (param_location(param.in_).to_owned(), name)
+            };
+
+            params.insert(key, (param_path, p));
+        }
+    }
+
+    params
+}
+
+fn parameter<'a, S>(
+    api: &'a ResolvableApi<S>,
+    param: &'a Either<Reference, ResolvableParameter<S>>,
+) -> Option<&'a ResolvableParameter<S>> {
+    match param {
+        Either::Left(r) => {
+            pointer::name(&r.reference, PARAM_REF_PREFIX).and_then(|name| api.parameters.get(&name))
+        }
+        Either::Right(p) => Some(p),
+    }
+}
+
+fn response<'a, S>(
+    api: &'a ResolvableApi<S>,
+    resp: &'a Either<Reference, ResolvableResponse<S>>,
+) -> Option<&'a ResolvableResponse<S>> {
+    match resp {
+        Either::Left(r) => {
+            pointer::name(&r.reference, RESP_REF_PREFIX).and_then(|name| api.responses.get(&name))
+        }
+        Either::Right(r) => Some(r),
+    }
+}
+
+/// Returns the actual schema for the given schema, following references
+/// to global definitions (if the spec hasn't been resolved).
+fn schema_node<S: Schema>(
+    api: &ResolvableApi<S>,
+    schema: &Resolvable<S>,
+) -> Option<Arc<RwLock<S>>> {
+    let mut node = Arc::clone(schema);
+    let mut seen = HashSet::new();
+    loop {
+        let name = {
+            let s = node.read().unwrap();
+            match s.reference().and_then(|r| pointer::name(r, DEF_REF_PREFIX)) {
+                Some(name) => name,
+                None => return Some(node.clone()),
+            }
+        };
+
+        if !seen.insert(name.clone()) {
+            return None;
+        }
+
+        node = Arc::clone(api.definitions.get(&name)?);
+    }
+}
+
+fn type_label<S: Schema>(schema: &S) -> String {
+    let ty = schema
+        .data_type()
+        .map(|t| format!("{:?}", t).to_lowercase())
+        .unwrap_or_else(|| "any".into());
+    match schema.format() {
+        Some(f) => format!("{} ({})", ty, f.to_string()),
+        None => ty,
+    }
+}
+
+fn join_values(values: &[serde_json::Value]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn param_location(in_: ParameterIn) -> &'static str {
+    match in_ {
+        ParameterIn::Query => "query",
+        ParameterIn::Header => "header",
+        ParameterIn::Path => "path",
+        ParameterIn::FormData => "formData",
+        ParameterIn::Body => "body",
+    }
+}
+
+fn method_name(method: HttpMethod) -> String {
+    method.to_string().to_lowercase()
+}
+
+fn method_label(method: HttpMethod) -> String {
+    method.to_string().to_uppercase()
+}
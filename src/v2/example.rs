@@ -0,0 +1,480 @@
+//! Synthesizing example payloads from v2 schemas.
+//!
+//! [`Synthesizer`](struct.Synthesizer.html) walks a (resolved) schema and
+//! produces a `serde_json::Value` which conforms to it. It's useful for
+//! documentation, mock servers and for seeding tests. The output only
+//! depends on the schema and the seed, so the same seed always produces
+//! the same payload.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{self, example::Synthesizer, DefaultSchema, ResolvableApi};
+//!
+//! let api: ResolvableApi<DefaultSchema> = v2::from_path("pets.yaml").unwrap();
+//! let api = api.resolve().unwrap();
+//! let pet = &api.definitions["Pet"];
+//! let value = Synthesizer::new(42).generate(pet);
+//! println!("{}", serde_json::to_string_pretty(&value).unwrap());
+//! ```
+//!
+//! The values are picked in this order:
+//!
+//! - `example` of the schema (if any).
+//! - `default` of the schema (if any).
+//! - One of the `enum` variants (if any).
+//! - A value based on the type and format of the schema, within the bounds
+//!   (`minimum`, `maxLength`, `minItems`, etc.) specified by the schema.
+//!   Patterns are not taken into account.
+//!
+//! Required properties are always filled. Recursion stops at cyclic
+//! definitions (and beyond the maximum depth) - optional properties are
+//! then skipped, arrays are left empty and required properties get an
+//! empty value.
+
+use crate::error::PaperClipError;
+use paperclip_core::v2::{
+    models::{DataType, DataTypeFormat, DefaultSchema, DefaultSchemaRaw, Either, Resolvable},
+    schema::Schema,
+};
+use serde_json::{Map, Value};
+
+use std::sync::RwLock;
+
+const DEFAULT_MAX_DEPTH: usize = 8;
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet",
+    "kilo", "lima", "mike", "november", "oscar", "papa",
+];
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// 2020-01-01T00:00:00Z
+const BASE_TIMESTAMP: i64 = 1_577_836_800;
+const FIVE_YEARS: u64 = 5 * 365 * 24 * 60 * 60;
+
+/// Generates example values for schemas.
+#[derive(Clone, Debug)]
+pub struct Synthesizer {
+    rng: Rng,
+    max_depth: usize,
+    optional_properties: bool,
+}
+
+impl Synthesizer {
+    /// Creates a synthesizer with the given seed.
+    pub fn new(seed: u64) -> Self {
+        Synthesizer {
+            rng: Rng(seed),
+            max_depth: DEFAULT_MAX_DEPTH,
+            optional_properties: true,
+        }
+    }
+
+    /// Sets the maximum depth of nested schemas (defaults to 8).
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets whether optional properties should be filled (they are, by default).
+    pub fn optional_properties(mut self, include: bool) -> Self {
+        self.optional_properties = include;
+        self
+    }
+
+    /// Generates a value for the given schema.
+    ///
+    /// References are expected to be resolved. Unresolved references
+    /// are treated like cyclic definitions.
+    pub fn generate<S: Schema>(&mut self, schema: &Resolvable<S>) -> Value {
+        let mut stack = vec![];
+        self.value(schema, &mut stack)
+            .unwrap_or_else(|| empty_value(&*schema.read().unwrap()))
+    }
+
+    /// Generates a value for the given raw schema (like the ones generated
+    /// by the actix plugin). References in this schema cannot be followed.
+    ///
+    /// This fails if the raw schema cannot be converted into a `DefaultSchema`.
+    pub fn generate_raw(&mut self, schema: &DefaultSchemaRaw) -> Result<Value, PaperClipError> {
+        let schema: DefaultSchema = serde_json::from_value(serde_json::to_value(schema)?)?;
+        Ok(self.generate(&Resolvable::from(schema)))
+    }
+
+    /// Returns the value for a schema or `None` if it's not possible to
+    /// go deeper.
+    fn value<S: Schema>(
+        &mut self,
+        schema: &Resolvable<S>,
+        stack: &mut Vec<*const RwLock<S>>,
+    ) -> Option<Value> {
+        let ptr = &**schema as &RwLock<S> as *const _;
+        if stack.len() >= self.max_depth || stack.contains(&ptr) {
+            return None;
+        }
+
+        let s = schema.read().unwrap();
+        if let Resolvable::Raw(_) = schema {
+            if s.reference().is_some() {
+                return None;
+            }
+        }
+
+        if let Some(v) = s.example().or_else(|| s.default_value()) {
+            return Some(v.clone());
+        }
+
+        if let Some(variants) = s.enum_variants() {
+            let idx = self.rng.below(variants.len() as u64) as usize;
+            return Some(variants[idx].clone());
+        }
+
+        stack.push(ptr);
+        let value = match s.data_type() {
+            Some(DataType::Integer) => self.integer(&*s),
+            Some(DataType::Number) => self.number(&*s),
+            Some(DataType::String) => self.string(&*s),
+            Some(DataType::Boolean) => Value::Bool(self.rng.below(2) == 1),
+            Some(DataType::File) => Value::String(self.word()),
+            Some(DataType::Array) => self.array(&*s, stack),
+            Some(DataType::Object) => self.object(&*s, stack),
+            None if s.items().is_some() => self.array(&*s, stack),
+            None if s.properties().is_some() || s.all_of().is_some() => self.object(&*s, stack),
+            None => Value::Null,
+        };
+        stack.pop();
+
+        Some(value)
+    }
+
+    fn integer<S: Schema>(&mut self, schema: &S) -> Value {
+        let (min, max) = if schema.format() == Some(&DataTypeFormat::Int32) {
+            (i64::from(i32::MIN), i64::from(i32::MAX))
+        } else {
+            (i64::MIN, i64::MAX)
+        };
+
+        let mut lo = schema.minimum().map(|m| {
+            let m = f64::from(m).ceil() as i64;
+            if schema.exclusive_minimum() && m as f32 == schema.minimum().unwrap() {
+                m.saturating_add(1)
+            } else {
+                m
+            }
+        });
+        let mut hi = schema.maximum().map(|m| {
+            let m = f64::from(m).floor() as i64;
+            if schema.exclusive_maximum() && m as f32 == schema.maximum().unwrap() {
+                m.saturating_sub(1)
+            } else {
+                m
+            }
+        });
+
+        match (lo, hi) {
+            (None, None) => {
+                lo = Some(1);
+                hi = Some(100);
+            }
+            (Some(l), None) => hi = Some(l.saturating_add(100)),
+            (None, Some(h)) => lo = Some(h.saturating_sub(100)),
+            _ => (),
+        }
+
+        let lo = lo.unwrap().max(min);
+        let hi = hi.unwrap().min(max).max(lo);
+        if let Some(step) = schema.multiple_of().map(|m| f64::from(m) as i64) {
+            if step > 0 {
+                let first = div_ceil(lo, step);
+                let last = hi.div_euclid(step);
+                if first <= last {
+                    return Value::from(self.rng.between(first, last) * step);
+                }
+            }
+        }
+
+        Value::from(self.rng.between(lo, hi))
+    }
+
+    fn number<S: Schema>(&mut self, schema: &S) -> Value {
+        let mut lo = schema.minimum().map(f64::from);
+        let mut hi = schema.maximum().map(f64::from);
+        match (lo, hi) {
+            (None, None) => {
+                lo = Some(0.0);
+                hi = Some(100.0);
+            }
+            (Some(l), None) => hi = Some(l + 100.0),
+            (None, Some(h)) => lo = Some(h - 100.0),
+            _ => (),
+        }
+
+        let (lo, hi) = (lo.unwrap(), hi.unwrap().max(lo.unwrap()));
+        let within = |v: f64| {
+            (v > lo || (v == lo && !schema.exclusive_minimum()))
+                && (v < hi || (v == hi && !schema.exclusive_maximum()))
+        };
+
+        if let Some(step) = schema.multiple_of().map(f64::from).filter(|s| *s > 0.0) {
+            let first = (lo / step).ceil() as i64;
+            let last = (hi / step).floor() as i64;
+            let candidates: Vec<_> = (first..=last.min(first.saturating_add(100)))
+                .map(|k| k as f64 * step)
+                .filter(|v| within(*v))
+                .collect();
+            if !candidates.is_empty() {
+                let idx = self.rng.below(candidates.len() as u64) as usize;
+                return Value::from(candidates[idx]);
+            }
+        }
+
+        let value = lo + self.rng.unit() * (hi - lo);
+        let rounded = (value * 100.0).round() / 100.0;
+        if within(rounded) {
+            Value::from(rounded)
+        } else {
+            Value::from(lo + (hi - lo) / 2.0)
+        }
+    }
+
+    fn string<S: Schema>(&mut self, schema: &S) -> Value {
+        let value = match schema.format() {
+            Some(DataTypeFormat::Uuid) => self.uuid(),
+            Some(DataTypeFormat::DateTime) => {
+                let (date, secs) = self.timestamp();
+                format!(
+                    "{}T{:02}:{:02}:{:02}Z",
+                    date,
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60
+                )
+            }
+            Some(DataTypeFormat::Date) => self.timestamp().0,
+            Some(DataTypeFormat::Ip) | Some(DataTypeFormat::IpV4) => format!(
+                "{}.{}.{}.{}",
+                self.rng.between(1, 223),
+                self.rng.below(256),
+                self.rng.below(256),
+                self.rng.between(1, 254)
+            ),
+            Some(DataTypeFormat::IpV6) => {
+                let mut addr = String::from("2001:db8");
+                for _ in 0..6 {
+                    addr.push_str(&format!(":{:x}", self.rng.below(0x10000)));
+                }
+                addr
+            }
+            Some(DataTypeFormat::Url) => format!("https://example.com/{}", self.word()),
+            Some(DataTypeFormat::Byte) => (0..12)
+                .map(|_| BASE64_CHARS[self.rng.below(64) as usize] as char)
+                .collect(),
+            Some(DataTypeFormat::Other(f)) if f == "email" => {
+                format!("{}@example.com", self.word())
+            }
+            Some(DataTypeFormat::Other(f)) if f == "hostname" => {
+                format!("{}.example.com", self.word())
+            }
+            Some(DataTypeFormat::Other(f)) if f == "uri" => {
+                format!("https://example.com/{}", self.word())
+            }
+            _ => {
+                let mut value = self.word();
+                let min = schema.min_length().unwrap_or(0) as usize;
+                while value.len() < min {
+                    value.push((b'a' + self.rng.below(26) as u8) as char);
+                }
+
+                if let Some(max) = schema.max_length() {
+                    value.truncate(max as usize);
+                }
+
+                value
+            }
+        };
+
+        Value::String(value)
+    }
+
+    fn array<S: Schema>(&mut self, schema: &S, stack: &mut Vec<*const RwLock<S>>) -> Value {
+        let items = match schema.items() {
+            Some(i) => i,
+            None => return Value::Array(vec![]),
+        };
+
+        let min = schema.min_items().unwrap_or(1) as u64;
+        let max = schema
+            .max_items()
+            .map(u64::from)
+            .unwrap_or(min + 2)
+            .min(min + 2)
+            .max(min);
+        let len = self.rng.between(min as i64, max as i64) as usize;
+
+        let mut values = Vec::with_capacity(len);
+        'outer: while values.len() < len {
+            // Give up after a few attempts at unique values.
+            for _ in 0..8 {
+                let value = match self.value(items, stack) {
+                    Some(v) => v,
+                    None => break 'outer,
+                };
+
+                if !schema.unique_items() || !values.contains(&value) {
+                    values.push(value);
+                    continue 'outer;
+                }
+            }
+
+            break;
+        }
+
+        Value::Array(values)
+    }
+
+    fn object<S: Schema>(&mut self, schema: &S, stack: &mut Vec<*const RwLock<S>>) -> Value {
+        let mut map = Map::new();
+        for member in schema.all_of().into_iter().flatten() {
+            if let Some(Value::Object(m)) = self.value(member, stack) {
+                map.extend(m);
+            }
+        }
+
+        let required = schema.required_properties();
+        for (name, prop) in schema.properties().into_iter().flatten() {
+            let is_required = required.map(|r| r.contains(name)) == Some(true);
+            if !is_required && !self.optional_properties {
+                continue;
+            }
+
+            match self.value(prop, stack) {
+                Some(v) => {
+                    map.insert(name.clone(), v);
+                }
+                None if is_required => {
+                    map.insert(name.clone(), empty_value(&*prop.read().unwrap()));
+                }
+                None => (),
+            }
+        }
+
+        if map.is_empty() {
+            if let Some(Either::Right(extra)) = schema.additional_properties() {
+                if let Some(v) = self.value(extra, stack) {
+                    map.insert("additionalProp1".into(), v);
+                }
+            }
+        }
+
+        // Subtypes identify themselves through the discriminator of their base.
+        let discriminator = schema.discriminator().map(String::from).or_else(|| {
+            schema
+                .all_of()
+                .into_iter()
+                .flatten()
+                .find_map(|member| member.read().unwrap().discriminator().map(String::from))
+        });
+        if let (Some(field), Some(name)) = (discriminator, schema.name()) {
+            map.insert(field, Value::String(name.into()));
+        }
+
+        Value::Object(map)
+    }
+
+    fn word(&mut self) -> String {
+        WORDS[self.rng.below(WORDS.len() as u64) as usize].into()
+    }
+
+    fn uuid(&mut self) -> String {
+        let (hi, lo) = (self.rng.next(), self.rng.next());
+        // Version 4 (random) with RFC 4122 variant.
+        let hi = (hi & !0xf000) | 0x4000;
+        let lo = (lo & !(0b11 << 62)) | (0b10 << 62);
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            hi >> 32,
+            (hi >> 16) & 0xffff,
+            hi & 0xffff,
+            lo >> 48,
+            lo & 0xffff_ffff_ffff
+        )
+    }
+
+    /// Returns a date (in `YYYY-MM-DD` format) and the seconds in that day.
+    fn timestamp(&mut self) -> (String, i64) {
+        let ts = BASE_TIMESTAMP + self.rng.below(FIVE_YEARS) as i64;
+        let (days, secs) = (ts.div_euclid(86400), ts.rem_euclid(86400));
+        let (y, m, d) = civil_from_days(days);
+        (format!("{:04}-{:02}-{:02}", y, m, d), secs)
+    }
+}
+
+/// Value used when we can't go deeper into a schema.
+fn empty_value<S: Schema>(schema: &S) -> Value {
+    match schema.data_type() {
+        Some(DataType::Array) => Value::Array(vec![]),
+        Some(DataType::Object) => Value::Object(Map::new()),
+        None if schema.properties().is_some() => Value::Object(Map::new()),
+        _ => Value::Null,
+    }
+}
+
+fn div_ceil(a: i64, b: i64) -> i64 {
+    let q = a.div_euclid(b);
+    if a.rem_euclid(b) == 0 {
+        q
+    } else {
+        q + 1
+    }
+}
+
+/// Converts days since UNIX epoch to a (proleptic Gregorian) date.
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+/// SplitMix64 - small, fast and (most importantly) stable across platforms
+/// and releases, unlike the generators from external crates.
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, n)` (or 0 if `n` is 0).
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next() % n
+        }
+    }
+
+    /// Returns a number in `[lo, hi]`.
+    fn between(&mut self, lo: i64, hi: i64) -> i64 {
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        if span > u128::from(u64::MAX) {
+            return self.next() as i64;
+        }
+
+        (lo as i128 + self.below(span as u64) as i128) as i64
+    }
+
+    /// Returns a number in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,330 @@
+//! Filtering operations in v2 specs.
+//!
+//! Large specs (like the Kubernetes API spec) have thousands of operations,
+//! most of which are usually not needed. [`filter`](fn.filter.html) keeps the
+//! operations matching some include/exclude rules and prunes the definitions,
+//! parameters and responses which are no longer reachable from them, so that
+//! the code generated for the spec stays small.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{self, filter::{self, Filter}, DefaultSchema, ResolvableApi};
+//!
+//! let mut api: ResolvableApi<DefaultSchema> = v2::from_path("k8s.json").unwrap();
+//! let mut rules = Filter::default();
+//! rules
+//!     .include_tag("core_v1")
+//!     .include_path("/api/v1/namespaces/{namespace}/pods*")
+//!     .exclude_operation("*Collection*");
+//!
+//! filter::filter(&mut api, &rules);
+//! let api = api.resolve().unwrap();
+//! ```
+
+use super::visit_schema;
+use paperclip_core::v2::{
+    models::{
+        Either, Operation, Reference, ResolvableApi, ResolvableParameter, ResolvableResponse,
+    },
+    pointer::{self, DEF_REF_PREFIX, PARAM_REF_PREFIX, RESP_REF_PREFIX},
+    schema::Schema,
+};
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::RwLock,
+};
+
+/// Rules for choosing the operations to keep.
+///
+/// An operation is kept if it matches at least one of the include rules
+/// (or if there are no include rules) and none of the exclude rules. Paths
+/// and operation IDs are matched against glob patterns, where `?` matches
+/// a single character, `*` matches any characters other than `/` and `**`
+/// matches any characters.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    include_tags: Vec<String>,
+    exclude_tags: Vec<String>,
+    include_paths: Vec<String>,
+    exclude_paths: Vec<String>,
+    include_operations: Vec<String>,
+    exclude_operations: Vec<String>,
+}
+
+impl Filter {
+    /// Keeps operations with the given tag.
+    pub fn include_tag<T: Into<String>>(&mut self, tag: T) -> &mut Self {
+        self.include_tags.push(tag.into());
+        self
+    }
+
+    /// Drops operations with the given tag.
+    pub fn exclude_tag<T: Into<String>>(&mut self, tag: T) -> &mut Self {
+        self.exclude_tags.push(tag.into());
+        self
+    }
+
+    /// Keeps operations whose path matches the given glob.
+    pub fn include_path<T: Into<String>>(&mut self, glob: T) -> &mut Self {
+        self.include_paths.push(glob.into());
+        self
+    }
+
+    /// Drops operations whose path matches the given glob.
+    pub fn exclude_path<T: Into<String>>(&mut self, glob: T) -> &mut Self {
+        self.exclude_paths.push(glob.into());
+        self
+    }
+
+    /// Keeps operations whose ID matches the given glob.
+    pub fn include_operation<T: Into<String>>(&mut self, glob: T) -> &mut Self {
+        self.include_operations.push(glob.into());
+        self
+    }
+
+    /// Drops operations whose ID matches the given glob.
+    pub fn exclude_operation<T: Into<String>>(&mut self, glob: T) -> &mut Self {
+        self.exclude_operations.push(glob.into());
+        self
+    }
+
+    /// Returns whether this filter has no rules (i.e., it keeps everything).
+    pub fn is_empty(&self) -> bool {
+        self.include_tags.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.include_paths.is_empty()
+            && self.exclude_paths.is_empty()
+            && self.include_operations.is_empty()
+            && self.exclude_operations.is_empty()
+    }
+
+    /// Checks whether the operation at the given path should be kept.
+    pub fn matches<P, R>(&self, path: &str, op: &Operation<P, R>) -> bool {
+        let has_includes = !(self.include_tags.is_empty()
+            && self.include_paths.is_empty()
+            && self.include_operations.is_empty());
+        if has_includes
+            && !self.matches_any(
+                &self.include_tags,
+                &self.include_paths,
+                &self.include_operations,
+                path,
+                op,
+            )
+        {
+            return false;
+        }
+
+        !self.matches_any(
+            &self.exclude_tags,
+            &self.exclude_paths,
+            &self.exclude_operations,
+            path,
+            op,
+        )
+    }
+
+    fn matches_any<P, R>(
+        &self,
+        tags: &[String],
+        paths: &[String],
+        operations: &[String],
+        path: &str,
+        op: &Operation<P, R>,
+    ) -> bool {
+        tags.iter().any(|t| op.tags.contains(t))
+            || paths.iter().any(|g| glob_match(g, path))
+            || op
+                .operation_id
+                .as_deref()
+                .map(|id| operations.iter().any(|g| glob_match(g, id)))
+                .unwrap_or(false)
+    }
+}
+
+/// Removes the operations which don't match the given filter, along with
+/// the definitions, parameters and responses which are no longer reachable
+/// from the remaining operations. This should be called before resolving
+/// the spec.
+pub fn filter<S: Schema>(api: &mut ResolvableApi<S>, filter: &Filter) {
+    for (path, map) in &mut api.paths {
+        map.methods.retain(|_, op| filter.matches(path, op));
+    }
+
+    api.paths.retain(|_, map| !map.methods.is_empty());
+
+    let reachable = Reachable::collect(api);
+    api.definitions
+        .retain(|name, _| reachable.definitions.contains(name));
+    api.parameters
+        .retain(|name, _| reachable.parameters.contains(name));
+    api.responses
+        .retain(|name, _| reachable.responses.contains(name));
+}
+
+/// Names of global objects reachable from operations.
+#[derive(Default)]
+struct Reachable {
+    definitions: BTreeSet<String>,
+    parameters: BTreeSet<String>,
+    responses: BTreeSet<String>,
+}
+
+impl Reachable {
+    fn collect<S: Schema>(api: &ResolvableApi<S>) -> Self {
+        let mut reachable = Reachable::default();
+        let mut visited = HashSet::new();
+        let mut pending = vec![];
+
+        // Subtypes of a polymorphic base only refer to it (through `allOf`),
+        // so they must be kept explicitly whenever the base is kept.
+        let mut subtypes = BTreeMap::<_, Vec<_>>::new();
+        for (name, def) in &api.definitions {
+            for s in def.read().unwrap().all_of().into_iter().flatten() {
+                let base = s.read().unwrap().reference().map(String::from);
+                if let Some(base) = base.and_then(|r| pointer::root_name(&r, DEF_REF_PREFIX)) {
+                    subtypes.entry(base).or_default().push(name.as_str());
+                }
+            }
+        }
+
+        for map in api.paths.values() {
+            let params = map
+                .methods
+                .values()
+                .flat_map(|op| op.parameters.iter())
+                .chain(map.parameters.iter());
+            for p in params {
+                match p {
+                    Either::Left(Reference { reference }) => pending.push(reference.clone()),
+                    Either::Right(p) => visit_parameter(p, &mut visited, &mut pending),
+                }
+            }
+
+            let responses = map.methods.values().flat_map(|op| op.responses.values());
+            for r in responses {
+                match r {
+                    Either::Left(Reference { reference }) => pending.push(reference.clone()),
+                    Either::Right(r) => visit_response(r, &mut visited, &mut pending),
+                }
+            }
+        }
+
+        while let Some(uri) = pending.pop() {
+            if let Some(name) = pointer::root_name(&uri, DEF_REF_PREFIX) {
+                if let Some(def) = api.definitions.get(&name) {
+                    if reachable.definitions.insert(name.clone()) {
+                        visit_schema(def, &mut visited, &mut pending);
+                        if def.read().unwrap().discriminator().is_some() {
+                            let names = subtypes.get(&name).into_iter().flatten();
+                            pending.extend(
+                                names.map(|n| format!("{}{}", DEF_REF_PREFIX, pointer::escape(n))),
+                            );
+                        }
+                    }
+                }
+            } else if let Some(name) = pointer::root_name(&uri, PARAM_REF_PREFIX) {
+                if let Some(p) = api.parameters.get(&name) {
+                    if reachable.parameters.insert(name) {
+                        visit_parameter(p, &mut visited, &mut pending);
+                    }
+                }
+            } else if let Some(name) = pointer::root_name(&uri, RESP_REF_PREFIX) {
+                if let Some(r) = api.responses.get(&name) {
+                    if reachable.responses.insert(name) {
+                        visit_response(r, &mut visited, &mut pending);
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+}
+
+fn visit_parameter<S: Schema>(
+    param: &ResolvableParameter<S>,
+    visited: &mut HashSet<*const RwLock<S>>,
+    refs: &mut Vec<String>,
+) {
+    if let Some(s) = param.read().unwrap().schema.as_ref() {
+        visit_schema(s, visited, refs);
+    }
+}
+
+fn visit_response<S: Schema>(
+    resp: &ResolvableResponse<S>,
+    visited: &mut HashSet<*const RwLock<S>>,
+    refs: &mut Vec<String>,
+) {
+    if let Some(s) = resp.read().unwrap().schema.as_ref() {
+        visit_schema(s, visited, refs);
+    }
+}
+
+/// Matches the text against the glob pattern.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // Positions (in pattern and text) to resume from when we hit a mismatch
+    // after the last `*` and `**`, so that we never backtrack any further.
+    let mut star: Option<(usize, usize)> = None;
+    let mut globstar: Option<(usize, usize)> = None;
+
+    while p < pattern.len() || t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' if pattern.get(p + 1) == Some(&'*') => {
+                    globstar = Some((p + 2, t));
+                    star = None;
+                    p += 2;
+                    continue;
+                }
+                '*' => {
+                    star = Some((p + 1, t));
+                    p += 1;
+                    continue;
+                }
+                '?' => {
+                    if t < text.len() && text[t] != '/' {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if t < text.len() && text[t] == c {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        // `*` can consume one more character, as long as it's in the same segment.
+        if let Some((sp, st)) = star {
+            if st < text.len() && text[st] != '/' {
+                star = Some((sp, st + 1));
+                p = sp;
+                t = st + 1;
+                continue;
+            }
+        }
+
+        if let Some((gp, gt)) = globstar {
+            if gt < text.len() {
+                globstar = Some((gp, gt + 1));
+                star = None;
+                p = gp;
+                t = gt + 1;
+                continue;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
@@ -0,0 +1,351 @@
+//! Exporting v2 schemas as [JSON Schema (draft 2020-12)](https://json-schema.org/draft/2020-12/json-schema-core.html)
+//! documents.
+//!
+//! [`Exporter`](struct.Exporter.html) works on raw schemas (like the ones
+//! generated by `#[derive(Apiv2Schema)]`), so that the same types can be
+//! used for validating data outside the HTTP API.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{json_schema::Exporter, models::DefaultApiRaw};
+//!
+//! # let api = DefaultApiRaw::default();
+//! let exporter = Exporter::from_api(&api).base_uri("https://example.com/schemas/");
+//! for (name, doc) in exporter.definitions().unwrap() {
+//!     println!("{}: {}", name, doc);
+//! }
+//! ```
+//!
+//! References to `#/definitions/*` are rewritten to `#/$defs/*` and each
+//! document carries the definitions it (transitively) refers to. Swagger-only
+//! constructs are handled as follows:
+//!
+//! - `type: file` becomes a string with `application/octet-stream` media type.
+//! - `exclusiveMinimum` and `exclusiveMaximum` flags become numeric bounds.
+//! - `example` becomes `examples` and `x-nullable` adds `null` to the type.
+//! - `collectionFormat` (in parameters) only affects how arrays are encoded
+//!   in URLs and headers, so it's dropped.
+//! - `discriminator`, `xml` and `externalDocs` are dropped, along with vendor
+//!   extensions (`x-*`) unless they're explicitly kept.
+
+use crate::error::PaperClipError;
+use paperclip_core::{
+    v2::{
+        keywords,
+        models::{DefaultApiRaw, DefaultParameterRaw, DefaultSchemaRaw, ParameterIn},
+        pointer::{self, DEF_REF_PREFIX},
+    },
+    ValidationError,
+};
+use serde_json::{Map, Value};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// URI of the JSON Schema draft 2020-12 meta-schema.
+pub const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+const DEFS_REF_PREFIX: &str = "#/$defs/";
+
+/// Keys which are not JSON Schema keywords.
+const DROPPED_KEYS: &[&str] = &[
+    "discriminator",
+    "xml",
+    "externalDocs",
+    "collectionFormat",
+    "x-nullable",
+];
+
+/// Keys of non-body parameters which aren't schema keywords.
+const PARAMETER_KEYS: &[&str] = &["name", "in", "required", "allowEmptyValue"];
+
+/// Exports v2 schemas as JSON Schema documents.
+#[derive(Clone, Debug)]
+pub struct Exporter<'a> {
+    definitions: &'a BTreeMap<String, DefaultSchemaRaw>,
+    base_uri: Option<String>,
+    keep_extensions: bool,
+}
+
+impl<'a> Exporter<'a> {
+    /// Creates an exporter which resolves references using the given definitions.
+    pub fn new(definitions: &'a BTreeMap<String, DefaultSchemaRaw>) -> Self {
+        Exporter {
+            definitions,
+            base_uri: None,
+            keep_extensions: false,
+        }
+    }
+
+    /// Creates an exporter for the definitions in the given spec.
+    pub fn from_api(api: &'a DefaultApiRaw) -> Self {
+        Self::new(&api.definitions)
+    }
+
+    /// Sets the base URI for the `$id` of definition documents. The ID of a
+    /// definition is the base URI followed by its [file name](fn.file_name.html).
+    pub fn base_uri<T: Into<String>>(mut self, uri: T) -> Self {
+        self.base_uri = Some(uri.into());
+        self
+    }
+
+    /// Sets whether vendor extensions (`x-*`) should be kept (as annotations).
+    pub fn keep_extensions(mut self, keep: bool) -> Self {
+        self.keep_extensions = keep;
+        self
+    }
+
+    /// Returns a standalone document for the given schema.
+    pub fn schema(&self, schema: &DefaultSchemaRaw) -> Result<Value, PaperClipError> {
+        self.document(to_value(schema), None)
+    }
+
+    /// Returns a standalone document for the given non-body parameter. Body
+    /// parameters are exported as their schemas.
+    pub fn parameter(&self, param: &DefaultParameterRaw) -> Result<Value, PaperClipError> {
+        if param.in_ == ParameterIn::Body {
+            return self.schema(param.schema.as_ref().unwrap_or(&Default::default()));
+        }
+
+        let mut value = to_value(param);
+        if let Value::Object(map) = &mut value {
+            for key in PARAMETER_KEYS {
+                map.remove(*key);
+            }
+        }
+
+        self.document(value, None)
+    }
+
+    /// Returns a standalone document for the definition with the given name.
+    /// If a base URI has been set, this fails when some other definition has
+    /// the same [file name](fn.file_name.html) (and hence the same `$id`).
+    pub fn definition(&self, name: &str) -> Result<Value, PaperClipError> {
+        let schema = self
+            .definitions
+            .get(name)
+            .ok_or_else(|| ValidationError::MissingReference(name.into()))?;
+        if self.base_uri.is_some() {
+            let file = file_name(name);
+            if let Some(other) = self
+                .definitions
+                .keys()
+                .find(|n| *n != name && file_name(n) == file)
+            {
+                return Err(PaperClipError::FileNameCollision(
+                    name.into(),
+                    other.clone(),
+                    file,
+                ));
+            }
+        }
+
+        self.definition_document(name, schema)
+    }
+
+    /// Returns standalone documents for all the definitions. This fails if
+    /// the [file names](fn.file_name.html) of two definitions are the same.
+    pub fn definitions(&self) -> Result<BTreeMap<String, Value>, PaperClipError> {
+        let mut files = BTreeMap::new();
+        for name in self.definitions.keys() {
+            if let Some(other) = files.insert(file_name(name), name) {
+                return Err(PaperClipError::FileNameCollision(
+                    other.clone(),
+                    name.clone(),
+                    file_name(name),
+                ));
+            }
+        }
+
+        self.definitions
+            .iter()
+            .map(|(name, schema)| Ok((name.clone(), self.definition_document(name, schema)?)))
+            .collect()
+    }
+
+    /// Returns the document for the given definition, with its `$id` and `title`.
+    fn definition_document(
+        &self,
+        name: &str,
+        schema: &DefaultSchemaRaw,
+    ) -> Result<Value, PaperClipError> {
+        let mut doc = self.document(to_value(schema), Some(name))?;
+        if let Value::Object(map) = &mut doc {
+            if let Some(base) = &self.base_uri {
+                map.insert(
+                    "$id".into(),
+                    Value::String(format!("{}{}", base, file_name(name))),
+                );
+            }
+
+            if !map.contains_key("title") {
+                map.insert("title".into(), Value::String(name.into()));
+            }
+        }
+
+        Ok(doc)
+    }
+
+    /// Returns a single document with all the definitions in `$defs`.
+    pub fn bundle(&self) -> Result<Value, PaperClipError> {
+        let mut defs = Map::new();
+        let mut refs = BTreeSet::new();
+        for (name, schema) in self.definitions {
+            defs.insert(
+                name.clone(),
+                self.convert(to_value(schema), None, &mut refs)?,
+            );
+        }
+
+        if let Some(name) = refs.into_iter().find(|r| !defs.contains_key(r)) {
+            return Err(ValidationError::MissingReference(name).into());
+        }
+
+        let mut doc = Map::new();
+        doc.insert("$schema".into(), DRAFT_2020_12.into());
+        if let Some(base) = &self.base_uri {
+            doc.insert("$id".into(), Value::String(format!("{}schema.json", base)));
+        }
+
+        doc.insert("$defs".into(), Value::Object(defs));
+        Ok(Value::Object(doc))
+    }
+
+    /// Converts a schema into a document along with the definitions it
+    /// refers to. References to the root definition (if any) point to the
+    /// document itself.
+    fn document(&self, schema: Value, root: Option<&str>) -> Result<Value, PaperClipError> {
+        let mut refs = BTreeSet::new();
+        let schema = self.convert(schema, root, &mut refs)?;
+
+        let mut defs = Map::new();
+        let mut pending: Vec<_> = refs.iter().cloned().collect();
+        while let Some(name) = pending.pop() {
+            if defs.contains_key(&name) {
+                continue;
+            }
+
+            let def = self
+                .definitions
+                .get(&name)
+                .ok_or_else(|| ValidationError::MissingReference(name.clone()))?;
+            let mut new_refs = BTreeSet::new();
+            defs.insert(
+                name.clone(),
+                self.convert(to_value(def), root, &mut new_refs)?,
+            );
+            pending.extend(new_refs.into_iter().filter(|r| !defs.contains_key(r)));
+        }
+
+        let mut doc = Map::new();
+        doc.insert("$schema".into(), DRAFT_2020_12.into());
+        match schema {
+            Value::Object(map) => doc.extend(map),
+            // Boolean schemas can't have other keywords.
+            v => return Ok(v),
+        }
+
+        if !defs.is_empty() {
+            doc.insert("$defs".into(), Value::Object(defs));
+        }
+
+        Ok(Value::Object(doc))
+    }
+
+    /// Converts a (serialized) v2 schema, collecting the names of the
+    /// definitions it refers to.
+    fn convert(
+        &self,
+        value: Value,
+        root: Option<&str>,
+        refs: &mut BTreeSet<String>,
+    ) -> Result<Value, PaperClipError> {
+        let mut map = match value {
+            Value::Object(map) => map,
+            v => return Ok(v),
+        };
+
+        if let Some(Value::String(r)) = map.get_mut("$ref") {
+            if let Some(name) = pointer::name(r, DEF_REF_PREFIX) {
+                if root == Some(name.as_str()) {
+                    *r = "#".into();
+                } else {
+                    *r = r.replacen(DEF_REF_PREFIX, DEFS_REF_PREFIX, 1);
+                    refs.insert(name);
+                }
+            } else if r.starts_with('#') {
+                return Err(ValidationError::MissingReference(r.clone()).into());
+            }
+        }
+
+        if map.get("type").and_then(Value::as_str) == Some("file") {
+            map.insert("type".into(), "string".into());
+            map.insert("contentMediaType".into(), "application/octet-stream".into());
+        }
+
+        if map.get("format").and_then(Value::as_str) == Some("byte") {
+            map.insert("contentEncoding".into(), "base64".into());
+        }
+
+        keywords::upgrade(&mut map);
+
+        if map.get("x-nullable") == Some(&Value::Bool(true)) {
+            if let Some(ty) = map.remove("type") {
+                map.insert("type".into(), Value::Array(vec![ty, "null".into()]));
+            }
+
+            if let Some(Value::Array(variants)) = map.get_mut("enum") {
+                variants.push(Value::Null);
+            }
+        }
+
+        for key in DROPPED_KEYS {
+            map.remove(*key);
+        }
+
+        if !self.keep_extensions {
+            map.retain(|k, _| !k.starts_with("x-"));
+        }
+
+        let mut out = Map::new();
+        for (key, value) in map {
+            let value = match (key.as_str(), value) {
+                ("properties", Value::Object(props)) => Value::Object(
+                    props
+                        .into_iter()
+                        .map(|(k, v)| Ok((k, self.convert(v, root, refs)?)))
+                        .collect::<Result<_, PaperClipError>>()?,
+                ),
+                ("allOf", Value::Array(members)) => Value::Array(
+                    members
+                        .into_iter()
+                        .map(|v| self.convert(v, root, refs))
+                        .collect::<Result<_, _>>()?,
+                ),
+                ("items", v) | ("additionalProperties", v) => self.convert(v, root, refs)?,
+                (_, v) => v,
+            };
+
+            out.insert(key, value);
+        }
+
+        Ok(Value::Object(out))
+    }
+}
+
+/// Returns the file name for the document of some definition (`<name>.json`,
+/// with characters other than ASCII alphanumerics, `-`, `_` and `.` in the
+/// name replaced by `_`). Since this is lossy, different names may end up
+/// with the same file name.
+pub fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{}.json", name)
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("serializing schema")
+}
@@ -0,0 +1,557 @@
+//! Linting for OpenAPI v2 specs.
+//!
+//! Unlike resolution (which stops at the first `ValidationError`), the linter
+//! walks the entire spec and collects every issue it finds as a
+//! [`Diagnostic`](struct.Diagnostic.html), which has a severity, the ID of the
+//! rule which raised it and the JSON path of the offending node.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{
+//!     self,
+//!     lint::{self, LintConfig, Rule, RuleLevel, Severity},
+//!     DefaultSchema, ResolvableApi,
+//! };
+//!
+//! let api: ResolvableApi<DefaultSchema> = v2::from_path("my_spec.yaml").unwrap();
+//! let mut config = LintConfig::default();
+//! config.set_level(Rule::MissingDescription, RuleLevel::Off);
+//! config.set_level(Rule::UnusedDefinition, RuleLevel::Error);
+//!
+//! let diagnostics = lint::lint(&api, &config);
+//! for d in &diagnostics {
+//!     eprintln!("{}", d);
+//! }
+//!
+//! assert!(diagnostics.iter().all(|d| d.severity < Severity::Error));
+//! ```
+
+use super::{json_path, visit_schema};
+use paperclip_core::v2::{
+    models::{
+        Api, CollectionFormat, DataType, Either, Items, ParameterIn, Reference, ResolvableApi,
+        ResolvableParameter, ResolvableResponse,
+    },
+    pointer::{self, DEF_REF_PREFIX, PARAM_REF_PREFIX, RESP_REF_PREFIX},
+    schema::Schema,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+/// Severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Lint rules known to the linter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// Two or more operations share the same `operationId`.
+    DuplicateOperationId,
+    /// A parameter in the path template is not defined as a path parameter.
+    MissingPathParameter,
+    /// A path parameter is defined, but it's not in the path template.
+    UnknownPathParameter,
+    /// A body parameter in an operation whose method doesn't allow a body (like GET).
+    UnexpectedBody,
+    /// A definition which is not reachable from any operation.
+    UnusedDefinition,
+    /// An operation, parameter, response or definition doesn't have a description.
+    MissingDescription,
+    /// `collectionFormat` is specified for a non-array or `multi` is used
+    /// where it's not allowed.
+    InvalidCollectionFormat,
+}
+
+impl Rule {
+    /// All the rules known to the linter.
+    pub const ALL: &'static [Rule] = &[
+        Rule::DuplicateOperationId,
+        Rule::MissingPathParameter,
+        Rule::UnknownPathParameter,
+        Rule::UnexpectedBody,
+        Rule::UnusedDefinition,
+        Rule::MissingDescription,
+        Rule::InvalidCollectionFormat,
+    ];
+
+    /// ID of this rule (used in diagnostics and configuration).
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::DuplicateOperationId => "duplicate-operation-id",
+            Rule::MissingPathParameter => "missing-path-parameter",
+            Rule::UnknownPathParameter => "unknown-path-parameter",
+            Rule::UnexpectedBody => "unexpected-body",
+            Rule::UnusedDefinition => "unused-definition",
+            Rule::MissingDescription => "missing-description",
+            Rule::InvalidCollectionFormat => "invalid-collection-format",
+        }
+    }
+
+    /// Severity of this rule's diagnostics, unless it's configured otherwise.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Rule::DuplicateOperationId
+            | Rule::MissingPathParameter
+            | Rule::UnknownPathParameter
+            | Rule::InvalidCollectionFormat => Severity::Error,
+            Rule::UnexpectedBody | Rule::UnusedDefinition => Severity::Warning,
+            Rule::MissingDescription => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Configured level of a rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// The rule is disabled.
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+impl RuleLevel {
+    /// Severity of diagnostics for this level (if the rule is enabled).
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            RuleLevel::Off => None,
+            RuleLevel::Info => Some(Severity::Info),
+            RuleLevel::Warning => Some(Severity::Warning),
+            RuleLevel::Error => Some(Severity::Error),
+        }
+    }
+}
+
+/// Linter configuration. This overrides the levels of rules (rules which
+/// are not configured use their default severity).
+///
+/// This can be deserialized from a map of rule IDs to levels. For example:
+///
+/// ```yaml
+/// unused-definition: error
+/// missing-description: off
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LintConfig {
+    levels: BTreeMap<Rule, RuleLevel>,
+}
+
+impl LintConfig {
+    /// Sets the level for the given rule.
+    pub fn set_level(&mut self, rule: Rule, level: RuleLevel) -> &mut Self {
+        self.levels.insert(rule, level);
+        self
+    }
+
+    /// Returns the severity of diagnostics raised by the given rule
+    /// (or `None` if the rule is disabled).
+    pub fn severity(&self, rule: Rule) -> Option<Severity> {
+        match self.levels.get(&rule) {
+            Some(level) => level.severity(),
+            None => Some(rule.default_severity()),
+        }
+    }
+}
+
+/// An issue found by the linter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Severity of this issue.
+    pub severity: Severity,
+    /// Rule which raised this issue.
+    pub rule: Rule,
+    /// JSON path of the offending node (for example, `$.paths['/pets'].get.operationId`).
+    pub path: String,
+    /// Human readable description of this issue.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(
+            f,
+            "{}[{}]: {} (at {})",
+            severity, self.rule, self.message, self.path
+        )
+    }
+}
+
+/// Lints the given spec (raw or resolved) with the given configuration
+/// and returns all the issues found.
+pub fn lint<S: Schema>(api: &ResolvableApi<S>, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        api,
+        config,
+        diagnostics: vec![],
+    };
+
+    linter.check_operations();
+    linter.check_definitions();
+    linter.diagnostics
+}
+
+struct Linter<'a, S> {
+    api: &'a ResolvableApi<S>,
+    config: &'a LintConfig,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, S: Schema> Linter<'a, S> {
+    fn report(&mut self, rule: Rule, path: String, message: String) {
+        if let Some(severity) = self.config.severity(rule) {
+            self.diagnostics.push(Diagnostic {
+                severity,
+                rule,
+                path,
+                message,
+            });
+        }
+    }
+
+    /// Returns the parameter, looking it up in global parameters if it's a reference.
+    fn parameter(
+        &self,
+        param: &'a Either<Reference, ResolvableParameter<S>>,
+    ) -> Option<&'a ResolvableParameter<S>> {
+        match param {
+            Either::Left(r) => pointer::name(&r.reference, PARAM_REF_PREFIX)
+                .and_then(|name| self.api.parameters.get(&name)),
+            Either::Right(p) => Some(p),
+        }
+    }
+
+    /// Returns the response, looking it up in global responses if it's a reference.
+    fn response(
+        &self,
+        resp: &'a Either<Reference, ResolvableResponse<S>>,
+    ) -> Option<&'a ResolvableResponse<S>> {
+        match resp {
+            Either::Left(r) => pointer::name(&r.reference, RESP_REF_PREFIX)
+                .and_then(|name| self.api.responses.get(&name)),
+            Either::Right(r) => Some(r),
+        }
+    }
+
+    fn check_operations(&mut self) {
+        let mut operation_ids = HashMap::new();
+
+        for (path, map) in &self.api.paths {
+            let path_json = json_path("$.paths", path);
+            let mut template_params = BTreeSet::new();
+            Api::<(), (), ()>::path_parameters_map(path, |name| {
+                let name = name.split(':').next().unwrap_or(name);
+                template_params.insert(name.to_owned());
+                ":".into()
+            });
+
+            let mut path_item_params = BTreeSet::new();
+            for (i, p) in map.parameters.iter().enumerate() {
+                let p_path = format!("{}.parameters[{}]", path_json, i);
+                if let Some(name) = self.check_parameter(p, &p_path, &template_params) {
+                    path_item_params.insert(name);
+                }
+            }
+
+            for (&method, op) in &map.methods {
+                let method_name = method.to_string().to_lowercase();
+                let op_path = json_path(&path_json, &method_name);
+
+                if let Some(id) = op.operation_id.as_ref() {
+                    match operation_ids.get(id) {
+                        Some(first) => self.report(
+                            Rule::DuplicateOperationId,
+                            format!("{}.operationId", op_path),
+                            format!("Operation ID {:?} is already used by {}", id, first),
+                        ),
+                        None => {
+                            operation_ids.insert(id, op_path.clone());
+                        }
+                    }
+                }
+
+                if op.summary.is_none() && op.description.is_none() {
+                    self.report(
+                        Rule::MissingDescription,
+                        op_path.clone(),
+                        "Operation doesn't have a summary or description".into(),
+                    );
+                }
+
+                let mut op_params = path_item_params.clone();
+                for (i, p) in op.parameters.iter().enumerate() {
+                    let p_path = format!("{}.parameters[{}]", op_path, i);
+                    if let Some(name) = self.check_parameter(p, &p_path, &template_params) {
+                        op_params.insert(name);
+                    }
+
+                    let is_body = self
+                        .parameter(p)
+                        .map(|p| p.read().unwrap().in_ == ParameterIn::Body)
+                        .unwrap_or(false);
+                    if is_body && !method.allows_body() {
+                        self.report(
+                            Rule::UnexpectedBody,
+                            p_path,
+                            format!(
+                                "Body parameter in {} operation",
+                                method.to_string().to_uppercase()
+                            ),
+                        );
+                    }
+                }
+
+                for name in template_params.difference(&op_params) {
+                    self.report(
+                        Rule::MissingPathParameter,
+                        op_path.clone(),
+                        format!(
+                            "Parameter {:?} in path template isn't defined as a path parameter",
+                            name
+                        ),
+                    );
+                }
+
+                for (code, resp) in &op.responses {
+                    let resp_path = json_path(&format!("{}.responses", op_path), code);
+                    self.check_response(resp, resp_path);
+                }
+            }
+        }
+    }
+
+    /// Checks the given parameter and returns its name if it's a path parameter.
+    fn check_parameter(
+        &mut self,
+        param: &Either<Reference, ResolvableParameter<S>>,
+        path: &str,
+        template_params: &BTreeSet<String>,
+    ) -> Option<String> {
+        // Global parameters are checked separately.
+        if let Either::Right(p) = param {
+            if !self.api.parameters.values().any(|g| Arc::ptr_eq(g, p)) {
+                self.check_parameter_object(p, path);
+            }
+        }
+
+        let param = self.parameter(param)?.read().unwrap();
+        if param.in_ != ParameterIn::Path {
+            return None;
+        }
+
+        if !template_params.contains(&param.name) {
+            self.report(
+                Rule::UnknownPathParameter,
+                path.into(),
+                format!(
+                    "Path parameter {:?} is not in the path template",
+                    param.name
+                ),
+            );
+        }
+
+        Some(param.name.clone())
+    }
+
+    /// Checks the description and collection formats of the given parameter.
+    fn check_parameter_object(&mut self, param: &ResolvableParameter<S>, path: &str) {
+        let param = param.read().unwrap();
+        if param.description.is_none() {
+            self.report(
+                Rule::MissingDescription,
+                path.into(),
+                format!("Parameter {:?} doesn't have a description", param.name),
+            );
+        }
+
+        if param.collection_format.is_some() && param.data_type != Some(DataType::Array) {
+            self.report(
+                Rule::InvalidCollectionFormat,
+                format!("{}.collectionFormat", path),
+                format!(
+                    "Parameter {:?} has collectionFormat, but it's not an array",
+                    param.name
+                ),
+            );
+        } else if param.collection_format == Some(CollectionFormat::Multi)
+            && param.in_ != ParameterIn::Query
+            && param.in_ != ParameterIn::FormData
+        {
+            self.report(
+                Rule::InvalidCollectionFormat,
+                format!("{}.collectionFormat", path),
+                format!(
+                    "Parameter {:?} uses 'multi' collectionFormat, which is only valid for query and formData parameters",
+                    param.name
+                ),
+            );
+        }
+
+        if let Some(items) = param.items.as_ref() {
+            self.check_items(items, format!("{}.items", path));
+        }
+    }
+
+    /// Checks the `collectionFormat` of nested items.
+    fn check_items(&mut self, items: &Items, path: String) {
+        match items.collection_format {
+            Some(_) if items.data_type != Some(DataType::Array) => self.report(
+                Rule::InvalidCollectionFormat,
+                format!("{}.collectionFormat", path),
+                "Items have collectionFormat, but they're not arrays".into(),
+            ),
+            Some(CollectionFormat::Multi) => self.report(
+                Rule::InvalidCollectionFormat,
+                format!("{}.collectionFormat", path),
+                "'multi' collectionFormat is only valid for query and formData parameters".into(),
+            ),
+            _ => (),
+        }
+
+        if let Some(inner) = items.items.as_ref() {
+            self.check_items(inner, format!("{}.items", path));
+        }
+    }
+
+    /// Checks the given operation response.
+    fn check_response(&mut self, resp: &Either<Reference, ResolvableResponse<S>>, path: String) {
+        // Global responses are checked separately.
+        let resp = match resp {
+            Either::Right(r) if !self.api.responses.values().any(|g| Arc::ptr_eq(g, r)) => r,
+            _ => return,
+        };
+
+        self.check_response_object(resp, path)
+    }
+
+    /// Checks the description and headers of the given response.
+    fn check_response_object(&mut self, resp: &ResolvableResponse<S>, path: String) {
+        let resp = resp.read().unwrap();
+        if resp.description.is_none() {
+            self.report(
+                Rule::MissingDescription,
+                path.clone(),
+                "Response doesn't have a description".into(),
+            );
+        }
+
+        for (name, header) in &resp.headers {
+            let header_path = json_path(&format!("{}.headers", path), name);
+            match header.collection_format {
+                Some(_) if header.data_type != Some(DataType::Array) => self.report(
+                    Rule::InvalidCollectionFormat,
+                    format!("{}.collectionFormat", header_path),
+                    format!(
+                        "Header {:?} has collectionFormat, but it's not an array",
+                        name
+                    ),
+                ),
+                Some(CollectionFormat::Multi) => self.report(
+                    Rule::InvalidCollectionFormat,
+                    format!("{}.collectionFormat", header_path),
+                    format!(
+                        "Header {:?} uses 'multi' collectionFormat, which is only valid for query and formData parameters",
+                        name
+                    ),
+                ),
+                _ => (),
+            }
+
+            if let Some(items) = header.items.as_ref() {
+                self.check_items(items, format!("{}.items", header_path));
+            }
+        }
+    }
+
+    fn check_definitions(&mut self) {
+        for (name, param) in &self.api.parameters {
+            self.check_parameter_object(param, &json_path("$.parameters", name));
+        }
+
+        for (name, resp) in &self.api.responses {
+            self.check_response_object(resp, json_path("$.responses", name));
+        }
+
+        let used = self.used_definitions();
+        for (name, def) in &self.api.definitions {
+            let path = json_path("$.definitions", name);
+            if def.read().unwrap().description().is_none() {
+                self.report(
+                    Rule::MissingDescription,
+                    path.clone(),
+                    format!("Definition {:?} doesn't have a description", name),
+                );
+            }
+
+            if !used.contains(&(&***def as *const RwLock<S>)) {
+                self.report(
+                    Rule::UnusedDefinition,
+                    path,
+                    format!("Definition {:?} is not used by any operation", name),
+                );
+            }
+        }
+    }
+
+    /// Returns the schemas reachable from operations.
+    fn used_definitions(&self) -> HashSet<*const RwLock<S>> {
+        let mut visited = HashSet::new();
+        let mut pending = vec![];
+
+        for map in self.api.paths.values() {
+            let params = map
+                .methods
+                .values()
+                .flat_map(|op| op.parameters.iter())
+                .chain(map.parameters.iter());
+            for p in params.filter_map(|p| self.parameter(p)) {
+                if let Some(s) = p.read().unwrap().schema.as_ref() {
+                    visit_schema(s, &mut visited, &mut pending);
+                }
+            }
+
+            let responses = map.methods.values().flat_map(|op| op.responses.values());
+            for r in responses.filter_map(|r| self.response(r)) {
+                if let Some(s) = r.read().unwrap().schema.as_ref() {
+                    visit_schema(s, &mut visited, &mut pending);
+                }
+            }
+        }
+
+        while let Some(uri) = pending.pop() {
+            let name = match pointer::root_name(&uri, DEF_REF_PREFIX) {
+                Some(n) => n,
+                None => continue,
+            };
+
+            if let Some(def) = self.api.definitions.get(&name) {
+                visit_schema(def, &mut visited, &mut pending);
+            }
+        }
+
+        visited
+    }
+}
@@ -0,0 +1,326 @@
+//! Loader for specs split across multiple files.
+//!
+//! The resolver only understands local references (`#/definitions/...`,
+//! `#/parameters/...` and `#/responses/...`). This module follows relative
+//! file references (like `common.yaml#/definitions/Error` or `./models/pet.json`),
+//! loads (and caches) the referenced documents and hoists the referenced
+//! objects into the root document, so that the resolver sees a single
+//! namespace with local references.
+
+use crate::error::PaperClipError;
+use paperclip_core::{
+    v2::{
+        models::{ResolvableApi, SpecFormat},
+        pointer,
+        source_map::SourceMap,
+    },
+    ValidationError,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{overlay::Overlay, Schema};
+
+/// Sections in the root document which can hold hoisted objects.
+pub(super) const SECTIONS: &[&str] = &["definitions", "parameters", "responses"];
+
+/// Load the spec from the given path, following relative file references in
+/// `$ref` fields. Objects from other documents are added to the root document's
+/// definitions, parameters or responses (based on where they live in their own
+/// document) and all references are rewritten to local references.
+///
+/// Names of hoisted objects are stable: it's the last segment of the JSON pointer
+/// (or the pascal cased file stem if the whole document is referenced). If that name is already
+/// taken by some other object, then it's prefixed with the (pascal cased) file stem
+/// and if that's taken too, a numeric suffix is added.
+pub fn from_path<P, S>(path: P) -> Result<ResolvableApi<S>, PaperClipError>
+where
+    P: AsRef<Path>,
+    for<'de> S: Deserialize<'de> + Schema,
+{
+    from_path_with_overlays(path, &[]).map(|(api, _)| api)
+}
+
+/// Same as [`from_path`](fn.from_path.html), but the given
+/// [overlays](overlay/index.html) are applied (in order) to the document
+/// (after objects from other documents have been hoisted into it) before
+/// it's deserialized. This also returns the overlay targets which didn't
+/// match anything.
+///
+/// **NOTE:** Locations in the document change with overlays, so the spec
+/// doesn't have a [source map](../source_map/index.html) in that case.
+pub fn from_path_with_overlays<P, S>(
+    path: P,
+    overlays: &[Overlay],
+) -> Result<(ResolvableApi<S>, Vec<String>), PaperClipError>
+where
+    P: AsRef<Path>,
+    for<'de> S: Deserialize<'de> + Schema,
+{
+    let path = path.as_ref();
+    let (mut root, fmt, map) = load_root(path)?;
+    let unmatched = super::apply_overlays(&mut root, overlays)?;
+    let mut api: ResolvableApi<S> = match serde_json::from_value(root) {
+        Ok(api) => api,
+        Err(e) if !overlays.is_empty() => return Err(e.into()),
+        // Values don't carry their locations, but errors in the root document
+        // can be located by deserializing the document itself.
+        Err(e) => {
+            let text = fs::read_to_string(path)?;
+            return Err(
+                match super::deserialize::<ResolvableApi<S>>(&text, fmt, &map) {
+                    Err(err) if err.span().is_some() => err,
+                    _ => e.into(),
+                },
+            );
+        }
+    };
+
+    api.spec_format = fmt;
+    if overlays.is_empty() {
+        api.source_map = Some(Arc::new(map));
+    }
+
+    Ok((api, unmatched))
+}
+
+/// Load the document at the given path (with objects from other documents
+/// hoisted into it) along with the format of the document.
+pub(super) fn load(path: &Path) -> Result<(Value, SpecFormat), PaperClipError> {
+    load_root(path).map(|(root, fmt, _)| (root, fmt))
+}
+
+/// Same as [`load`](fn.load.html), but this also returns the source map
+/// of the root document.
+fn load_root(path: &Path) -> Result<(Value, SpecFormat, SourceMap), PaperClipError> {
+    let root_path = path.canonicalize()?;
+    let (mut root, fmt, source_map) = read_document(path)?;
+
+    let mut loader = Loader::new(root_path.clone(), &root);
+    loader
+        .rewrite_refs(&root_path, &mut root)
+        .map_err(|e| match e {
+            PaperClipError::Validation(e) => source_map.annotate(e).into(),
+            e => e,
+        })?;
+
+    if let Value::Object(map) = &mut root {
+        for (section, hoisted) in loader.hoisted {
+            let entry = map
+                .entry(section)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(objects) = entry {
+                objects.extend(hoisted);
+            }
+        }
+    }
+
+    Ok((root, fmt, source_map))
+}
+
+/// Read the JSON or YAML document at the given path along with its format
+/// and source map.
+fn read_document(path: &Path) -> Result<(Value, SpecFormat, SourceMap), PaperClipError> {
+    let text = fs::read_to_string(path)?;
+    let fmt = super::spec_format(&text);
+    let map = SourceMap::new(&text, fmt).with_file(path);
+    let value = super::deserialize(&text, fmt, &map)?;
+    Ok((value, fmt, map))
+}
+
+struct Loader {
+    /// Canonical path of the root document.
+    root_path: PathBuf,
+    /// Documents loaded so far (other than the root).
+    docs: HashMap<PathBuf, Value>,
+    /// Names assigned to (document, pointer) pairs we've already seen.
+    names: HashMap<(PathBuf, String), String>,
+    /// Names taken in each section of the root document.
+    taken: HashMap<&'static str, HashSet<String>>,
+    /// Objects hoisted into the root document (by section).
+    hoisted: BTreeMap<String, Map<String, Value>>,
+}
+
+impl Loader {
+    fn new(root_path: PathBuf, root: &Value) -> Self {
+        let taken = SECTIONS
+            .iter()
+            .map(|&s| {
+                let names = root
+                    .get(s)
+                    .and_then(Value::as_object)
+                    .map(|m| m.keys().cloned().collect())
+                    .unwrap_or_default();
+                (s, names)
+            })
+            .collect();
+
+        Loader {
+            root_path,
+            docs: HashMap::new(),
+            names: HashMap::new(),
+            taken,
+            hoisted: BTreeMap::new(),
+        }
+    }
+
+    /// Walk the given value (which lives in the document at `doc_path`) and
+    /// rewrite all references to local references in the root document.
+    fn rewrite_refs(&mut self, doc_path: &Path, value: &mut Value) -> Result<(), PaperClipError> {
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map.iter_mut() {
+                    match v {
+                        Value::String(uri) if k == "$ref" => {
+                            if let Some(new_uri) = self.rewrite_ref(doc_path, uri)? {
+                                *uri = new_uri;
+                            }
+                        }
+                        _ => self.rewrite_refs(doc_path, v)?,
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for v in values {
+                    self.rewrite_refs(doc_path, v)?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the local reference for the given URI (if it needs rewriting).
+    fn rewrite_ref(
+        &mut self,
+        doc_path: &Path,
+        uri: &str,
+    ) -> Result<Option<String>, PaperClipError> {
+        let (file, pointer) = match uri.find('#') {
+            Some(i) => (&uri[..i], &uri[i + 1..]),
+            None => (uri, ""),
+        };
+
+        if file.contains("://") {
+            return Err(ValidationError::InvalidRefUri(uri.into()).into());
+        }
+
+        let target_path = if file.is_empty() {
+            doc_path.to_path_buf()
+        } else {
+            let dir = doc_path.parent().unwrap_or_else(|| Path::new("."));
+            dir.join(file)
+                .canonicalize()
+                .map_err(|_| ValidationError::MissingReference(uri.into()))?
+        };
+
+        if target_path == self.root_path {
+            // Local reference in the root document.
+            return Ok(if file.is_empty() {
+                None
+            } else {
+                Some(format!("#{}", pointer))
+            });
+        }
+
+        let key = (target_path, pointer.to_owned());
+        if let Some(name) = self.names.get(&key) {
+            return Ok(Some(local_ref(pointer, name)));
+        }
+
+        if !self.docs.contains_key(&key.0) {
+            let (doc, _, _) = read_document(&key.0)?;
+            self.docs.insert(key.0.clone(), doc);
+        }
+
+        let mut target = self.docs[&key.0]
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| ValidationError::MissingReference(uri.into()))?;
+
+        // Register the name before walking the target, so that cyclic
+        // references resolve to the same name.
+        let section = section_for(pointer);
+        let name = self.assign_name(section, &key.0, pointer);
+        self.names.insert(key.clone(), name.clone());
+        self.rewrite_refs(&key.0, &mut target)?;
+        self.hoisted
+            .entry(section.into())
+            .or_default()
+            .insert(name.clone(), target);
+
+        Ok(Some(local_ref(pointer, &name)))
+    }
+
+    /// Assign a unique name in the given section for the object at the pointer.
+    fn assign_name(&mut self, section: &'static str, path: &Path, pointer: &str) -> String {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let base = pointer
+            .rsplit('/')
+            .next()
+            .filter(|s| !s.is_empty())
+            .map(pointer::unescape)
+            .unwrap_or_else(|| pascal_case(&stem));
+
+        let taken = self.taken.entry(section).or_default();
+        let mut name = base.clone();
+        if taken.contains(&name) {
+            name = format!("{}{}", pascal_case(&stem), base);
+        }
+
+        let prefixed = name.clone();
+        let mut idx = 1;
+        while taken.contains(&name) {
+            idx += 1;
+            name = format!("{}{}", prefixed, idx);
+        }
+
+        taken.insert(name.clone());
+        name
+    }
+}
+
+/// Returns the root document's section for the object at the given pointer.
+fn section_for(pointer: &str) -> &'static str {
+    SECTIONS
+        .iter()
+        .find(|&&s| {
+            pointer
+                .strip_prefix('/')
+                .and_then(|p| p.strip_prefix(s))
+                .map(|p| p.starts_with('/'))
+                .unwrap_or(false)
+        })
+        .copied()
+        .unwrap_or("definitions")
+}
+
+/// Returns the local reference for a hoisted object.
+fn local_ref(pointer: &str, name: &str) -> String {
+    format!("#/{}/{}", section_for(pointer), pointer::escape(name))
+}
+
+/// Converts file stems like `common-types` into `CommonTypes`.
+pub(super) fn pascal_case(s: &str) -> String {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let mut chars = p.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
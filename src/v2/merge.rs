@@ -0,0 +1,520 @@
+//! Merging multiple OpenAPI v2 specs into one.
+//!
+//! This is useful for building a single (gateway-level) spec out of specs
+//! exposed by several services. Paths of each spec can be prefixed, tags and
+//! security definitions are combined, and identical definitions are shared.
+//! The base path of the merged spec is the one shared by all specs (the rest
+//! of a spec's base path goes into its paths). Global media types, schemes
+//! and security requirements are taken from the first spec, and those of the
+//! later specs are moved to their operations (if they're different).
+//! Conflicts are reported instead of silently overwriting stuff:
+//!
+//! - Definitions, parameters and responses with the same name but different
+//!   shapes are renamed in the later spec (along with its references).
+//! - Operations with the same path and method are kept from the first spec
+//!   and dropped from the later ones.
+//! - Hosts different from that of the first spec are dropped.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{merge::{self, MergeInput}, models::DefaultApiRaw};
+//!
+//! # let (store, users) = (DefaultApiRaw::default(), DefaultApiRaw::default());
+//! let report = merge::merge(vec![
+//!     MergeInput::new(store).path_prefix("/store"),
+//!     MergeInput::new(users).path_prefix("/users"),
+//! ])
+//! .unwrap();
+//!
+//! for conflict in &report.conflicts {
+//!     eprintln!("{}", conflict);
+//! }
+//! ```
+
+use super::{json_path, loader};
+use crate::error::PaperClipError;
+use paperclip_core::v2::{
+    models::{
+        DefaultApiRaw, DefaultParameterRaw, DefaultPathItemRaw, Either, ParameterIn, Reference,
+        SecurityScheme,
+    },
+    pointer,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, mem,
+};
+
+/// Sections of the document whose objects can be referenced.
+const SECTIONS: &[(&str, ConflictKind)] = &[
+    ("definitions", ConflictKind::Definition),
+    ("parameters", ConflictKind::Parameter),
+    ("responses", ConflictKind::Response),
+];
+
+/// A spec to be merged.
+#[derive(Clone, Debug)]
+pub struct MergeInput {
+    pub api: DefaultApiRaw,
+    /// Prefix to be added to all paths in this spec (like `/store`).
+    pub path_prefix: Option<String>,
+}
+
+impl MergeInput {
+    /// Creates a new input for the given spec.
+    pub fn new(api: DefaultApiRaw) -> Self {
+        MergeInput {
+            api,
+            path_prefix: None,
+        }
+    }
+
+    /// Sets the prefix for all paths in this spec.
+    pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.path_prefix = Some(prefix.into());
+        self
+    }
+}
+
+impl From<DefaultApiRaw> for MergeInput {
+    fn from(api: DefaultApiRaw) -> Self {
+        MergeInput::new(api)
+    }
+}
+
+/// Kind of conflict between the merged specs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictKind {
+    /// Same path and method exists in more than one spec.
+    Operation,
+    /// Definitions with the same name have different schemas.
+    Definition,
+    /// Global parameters with the same name are different.
+    Parameter,
+    /// Global responses with the same name are different.
+    Response,
+    /// Specs are served from different hosts.
+    Host,
+}
+
+/// A conflict found while merging.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    /// Kind of this conflict.
+    pub kind: ConflictKind,
+    /// Index of the (later) input which conflicts with the previous ones.
+    pub input: usize,
+    /// JSON path of the conflicting node (in that input).
+    pub path: String,
+    /// New name of the object (if it was renamed). If this is `None`,
+    /// then the object has been dropped from the merged spec.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_to: Option<String>,
+    /// Human readable description of this conflict.
+    pub message: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "input {}: {} (at {})",
+            self.input, self.message, self.path
+        )
+    }
+}
+
+/// Result of merging specs.
+#[derive(Clone, Debug)]
+pub struct MergeReport {
+    /// The merged spec.
+    pub api: DefaultApiRaw,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeReport {
+    /// Returns the conflicts which resulted in objects being dropped.
+    pub fn unresolved_conflicts(&self) -> impl Iterator<Item = &Conflict> {
+        self.conflicts.iter().filter(|c| c.renamed_to.is_none())
+    }
+}
+
+/// Merge the given specs into one. Info and host of the merged spec are
+/// taken from the first spec.
+pub fn merge<I>(inputs: I) -> Result<MergeReport, PaperClipError>
+where
+    I: IntoIterator<Item = MergeInput>,
+{
+    let inputs = inputs.into_iter().collect::<Vec<_>>();
+    let mut merger = Merger::default();
+    if let Some(first) = inputs.first() {
+        merger.api = DefaultApiRaw {
+            swagger: first.api.swagger,
+            info: first.api.info.clone(),
+            host: first.api.host.clone(),
+            base_path: common_base_path(&inputs),
+            consumes: first.api.consumes.clone(),
+            produces: first.api.produces.clone(),
+            schemes: first.api.schemes.clone(),
+            security: first.api.security.clone(),
+            external_docs: first.api.external_docs.clone(),
+            spec_format: first.api.spec_format,
+            ..Default::default()
+        };
+    }
+
+    for (idx, input) in inputs.into_iter().enumerate() {
+        merger.add(idx, input)?;
+    }
+
+    Ok(MergeReport {
+        api: merger.api,
+        conflicts: merger.conflicts,
+    })
+}
+
+#[derive(Default)]
+struct Merger {
+    api: DefaultApiRaw,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger {
+    fn add(&mut self, idx: usize, input: MergeInput) -> Result<(), PaperClipError> {
+        let tag = input
+            .path_prefix
+            .as_deref()
+            .map(loader::pascal_case)
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| loader::pascal_case(&input.api.info.title));
+        // Whatever's left of the base path (after the common base path)
+        // goes before the prefix.
+        let base = path_segments(input.api.base_path.as_deref());
+        let common = path_segments(self.api.base_path.as_deref()).len();
+        let prefix = base[common..]
+            .iter()
+            .copied()
+            .chain(path_segments(input.path_prefix.as_deref()))
+            .map(|s| format!("/{}", s))
+            .collect::<String>();
+
+        if idx > 0 && input.api.host != self.api.host {
+            self.conflicts.push(Conflict {
+                kind: ConflictKind::Host,
+                input: idx,
+                path: "$.host".into(),
+                renamed_to: None,
+                message: format!(
+                    "Host {:?} is different from the host of the merged spec ({:?})",
+                    input.api.host.as_deref().unwrap_or_default(),
+                    self.api.host.as_deref().unwrap_or_default()
+                ),
+            });
+        }
+
+        let mut api = self.rename_conflicting(idx, input.api, &tag)?;
+        self.push_down_globals(&mut api);
+
+        for (name, def) in mem::take(&mut api.definitions) {
+            self.api.definitions.entry(name).or_insert(def);
+        }
+
+        for (name, param) in mem::take(&mut api.parameters) {
+            self.api.parameters.entry(name).or_insert(param);
+        }
+
+        for (name, resp) in mem::take(&mut api.responses) {
+            self.api.responses.entry(name).or_insert(resp);
+        }
+
+        for (path, mut item) in mem::take(&mut api.paths) {
+            let input_path = json_path("$.paths", &path);
+            let path = prefix.clone() + &path;
+            let existing = match self.api.paths.get_mut(&path) {
+                Some(i) => i,
+                None => {
+                    self.api.paths.insert(path, item);
+                    continue;
+                }
+            };
+
+            // Parameters shared by the operations in a path can't be shared anymore.
+            if serde_json::to_value(&existing.parameters)?
+                != serde_json::to_value(&item.parameters)?
+            {
+                push_down_parameters(existing, &self.api.parameters);
+                push_down_parameters(&mut item, &self.api.parameters);
+            }
+
+            for (method, op) in item.methods {
+                if existing.methods.contains_key(&method) {
+                    let method = method.to_string().to_lowercase();
+                    self.conflicts.push(Conflict {
+                        kind: ConflictKind::Operation,
+                        input: idx,
+                        path: json_path(&input_path, &method),
+                        renamed_to: None,
+                        message: format!(
+                            "Operation {} {:?} already exists",
+                            method.to_uppercase(),
+                            path
+                        ),
+                    });
+                    continue;
+                }
+
+                existing.methods.insert(method, op);
+            }
+        }
+
+        for tag in api.tags {
+            if !self.api.tags.iter().any(|t| t.name == tag.name) {
+                self.api.tags.push(tag);
+            }
+        }
+
+        SecurityScheme::append_map(api.security_definitions, &mut self.api.security_definitions);
+        for (range, coder) in api.coders.iter() {
+            self.api
+                .coders
+                .entry(range.clone())
+                .or_insert_with(|| coder.clone());
+        }
+
+        for (name, req) in api.support_crates {
+            self.api.support_crates.entry(name).or_insert(req);
+        }
+
+        for (key, value) in api.extensions {
+            self.api.extensions.entry(key).or_insert(value);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the global media types, schemes and security requirements of the
+    /// given spec to its operations (unless they've been overridden by the
+    /// operation), if they're different from those of the merged spec.
+    fn push_down_globals(&self, api: &mut DefaultApiRaw) {
+        let ops = api.paths.values_mut().flat_map(|p| p.methods.values_mut());
+        for op in ops {
+            if api.consumes != self.api.consumes && op.consumes.is_none() {
+                op.consumes = Some(api.consumes.clone());
+            }
+
+            if api.produces != self.api.produces && op.produces.is_none() {
+                op.produces = Some(api.produces.clone());
+            }
+
+            if api.schemes != self.api.schemes && op.schemes.is_empty() {
+                op.schemes = api.schemes.clone();
+            }
+
+            if api.security != self.api.security && op.security.is_none() {
+                op.security = Some(
+                    api.security
+                        .iter()
+                        .map(|req| {
+                            req.iter()
+                                .map(|(name, scopes)| {
+                                    (name.clone(), scopes.iter().cloned().collect())
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    /// Renames the objects in the given spec whose names have been taken by
+    /// different objects in the merged spec and rewrites the references to them.
+    fn rename_conflicting(
+        &mut self,
+        idx: usize,
+        api: DefaultApiRaw,
+        tag: &str,
+    ) -> Result<DefaultApiRaw, PaperClipError> {
+        let spec_format = api.spec_format;
+        let mut value = serde_json::to_value(&api)?;
+        let merged = serde_json::to_value(&self.api)?;
+        let mut renames = HashMap::new();
+        let mut taken = HashSet::new();
+
+        // Renaming an object changes the objects referencing it, so we
+        // keep going until nothing changes.
+        loop {
+            let mut renamed = value.clone();
+            rewrite_refs(&mut renamed, &renames);
+            let mut changed = false;
+
+            for &(section, kind) in SECTIONS {
+                let (objects, existing) = match (
+                    renamed.get(section).and_then(Value::as_object),
+                    merged.get(section).and_then(Value::as_object),
+                ) {
+                    (Some(o), Some(e)) => (o, e),
+                    _ => continue,
+                };
+
+                for (name, object) in objects {
+                    let key = (section, name.clone());
+                    if renames.contains_key(&key)
+                        || existing.get(name).map(|e| e == object).unwrap_or(true)
+                    {
+                        continue;
+                    }
+
+                    let mut new_name = format!("{}{}", tag, name);
+                    let mut i = 1;
+                    while existing.contains_key(&new_name)
+                        || objects.contains_key(&new_name)
+                        || taken.contains(&(section, new_name.clone()))
+                    {
+                        i += 1;
+                        new_name = format!("{}{}{}", tag, name, i);
+                    }
+
+                    taken.insert((section, new_name.clone()));
+                    self.conflicts.push(Conflict {
+                        kind,
+                        input: idx,
+                        path: json_path(&format!("$.{}", section), name),
+                        renamed_to: Some(new_name.clone()),
+                        message: format!(
+                            "{:?} is different from an existing object with the same name",
+                            name
+                        ),
+                    });
+                    renames.insert(key, new_name);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                value = renamed;
+                break;
+            }
+        }
+
+        if let Value::Object(map) = &mut value {
+            for ((section, old), new) in &renames {
+                if let Some(Value::Object(objects)) = map.get_mut(*section) {
+                    if let Some(object) = objects.remove(old) {
+                        objects.insert(new.clone(), object);
+                    }
+                }
+            }
+        }
+
+        let mut api: DefaultApiRaw = serde_json::from_value(value)?;
+        api.spec_format = spec_format;
+        Ok(api)
+    }
+}
+
+/// Returns the base path shared by all the given specs (if any).
+fn common_base_path(inputs: &[MergeInput]) -> Option<String> {
+    let mut iter = inputs
+        .iter()
+        .map(|i| path_segments(i.api.base_path.as_deref()));
+    let mut common = iter.next()?;
+    for segments in iter {
+        let len = common
+            .iter()
+            .zip(&segments)
+            .take_while(|(a, b)| a == b)
+            .count();
+        common.truncate(len);
+    }
+
+    if common.is_empty() {
+        return None;
+    }
+
+    Some(common.iter().map(|s| format!("/{}", s)).collect())
+}
+
+/// Returns the (non-empty) segments of the given path.
+fn path_segments(path: Option<&str>) -> Vec<&str> {
+    path.unwrap_or_default()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Moves the parameters shared by the operations in the given path
+/// to the operations (unless they've been overridden by the operation).
+fn push_down_parameters(
+    item: &mut DefaultPathItemRaw,
+    params: &BTreeMap<String, DefaultParameterRaw>,
+) {
+    let shared = mem::take(&mut item.parameters);
+    for op in item.methods.values_mut() {
+        for param in &shared {
+            let key = parameter_key(param, params);
+            if key.is_none()
+                || !op
+                    .parameters
+                    .iter()
+                    .any(|p| parameter_key(p, params) == key)
+            {
+                op.parameters.push(param.clone());
+            }
+        }
+    }
+}
+
+/// Name and location of the given parameter (which uniquely identify a parameter in an operation).
+fn parameter_key<'a>(
+    param: &'a Either<Reference, DefaultParameterRaw>,
+    params: &'a BTreeMap<String, DefaultParameterRaw>,
+) -> Option<(&'a str, ParameterIn)> {
+    let param = match param {
+        Either::Left(r) => params.get(r.reference.strip_prefix("#/parameters/")?)?,
+        Either::Right(p) => p,
+    };
+
+    Some((&param.name, param.in_))
+}
+
+/// Rewrites references to renamed objects in the given value.
+fn rewrite_refs(value: &mut Value, renames: &HashMap<(&'static str, String), String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                match v {
+                    Value::String(uri) if k == "$ref" => {
+                        if let Some(new_uri) = renamed_ref(uri, renames) {
+                            *uri = new_uri;
+                        }
+                    }
+                    _ => rewrite_refs(v, renames),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| rewrite_refs(v, renames)),
+        _ => (),
+    }
+}
+
+/// Returns the new reference (if the referenced object has been renamed).
+fn renamed_ref(uri: &str, renames: &HashMap<(&'static str, String), String>) -> Option<String> {
+    let pointer = uri.strip_prefix("#/")?;
+    let mut parts = pointer.splitn(3, '/');
+    let section = parts.next()?;
+    let section = SECTIONS.iter().map(|(s, _)| *s).find(|s| *s == section)?;
+    let name = pointer::unescape(parts.next()?);
+    let new_name = renames.get(&(section, name))?;
+
+    let mut new_uri = format!("#/{}/{}", section, pointer::escape(new_name));
+    if let Some(rest) = parts.next() {
+        new_uri.push('/');
+        new_uri.push_str(rest);
+    }
+
+    Some(new_uri)
+}
@@ -0,0 +1,961 @@
+//! Mock servers for v2 specs.
+//!
+//! [`MockServer`](struct.MockServer.html) routes every operation in a
+//! (resolved) spec in an actix-web app. Requests are validated against the
+//! parameters of the operation and the responses have the documented status
+//! codes and headers, along with bodies synthesized from their schemas (see
+//! [`example`](../example/index.html)).
+//!
+//! ```rust,no_run
+//! use actix_web4::{App, HttpServer};
+//! use paperclip::v2::{self, mock::MockServer, DefaultSchema, ResolvableApi};
+//!
+//! let api: ResolvableApi<DefaultSchema> = v2::from_path("pets.yaml").unwrap();
+//! let mock = MockServer::new(&api.resolve().unwrap()).unwrap();
+//! actix_web4::rt::System::new().block_on(async move {
+//!     HttpServer::new(move || {
+//!         let mock = mock.clone();
+//!         App::new().configure(move |c| mock.configure(c))
+//!     })
+//!     .bind(("127.0.0.1", 8080))?
+//!     .run()
+//!     .await
+//! }).unwrap();
+//! ```
+//!
+//! The response for a successful request has the lowest documented `2xx`
+//! status (or the `default` response). Clients can ask for some other
+//! documented status with the [`STATUS_HEADER`](constant.STATUS_HEADER.html)
+//! header. Invalid requests get a `400 Bad Request` response (or `415
+//! Unsupported Media Type` for unknown content types) with the list of
+//! errors.
+//!
+//! Response bodies have the first media type (in the order of preference in
+//! the `Accept` header) which the operation produces and which can be
+//! synthesized, i.e. JSON (`application/json` and `application/*+json`) or
+//! `text/plain`. Requests which don't accept any of them get a `406 Not
+//! Acceptable` response.
+//!
+//! Values are validated against the keywords of their schemas, along with
+//! the standard formats (`int32`, `int64`, `float`, `date`, `date-time`,
+//! `uuid`, `byte`, `ip`, `ipv4`, `ipv6` and `url`). Other formats are
+//! accepted as they are.
+//!
+//! Patterns in the spec are compiled when the mock server is created, so
+//! invalid patterns are reported upfront (instead of failing requests).
+
+use super::example::Synthesizer;
+use crate::error::PaperClipError;
+use actix_web4::{
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        Method, StatusCode,
+    },
+    web, HttpRequest, HttpResponse,
+};
+use paperclip_core::v2::{
+    models::{
+        CollectionFormat, DataType, DataTypeFormat, DefaultSchema, Either, HttpMethod, MediaRange,
+        ParameterIn, Reference, Resolvable, ResolvableApi, ResolvableOperation,
+        ResolvableParameter, ResolvableResponse,
+    },
+    schema::Schema,
+};
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, RwLock},
+};
+
+/// Request header for choosing the status code of the mock response.
+pub const STATUS_HEADER: &str = "x-mock-status";
+
+/// Keys of non-body parameters and headers which aren't schema keywords.
+const NON_SCHEMA_KEYS: &[&str] = &[
+    "name",
+    "in",
+    "required",
+    "description",
+    "collectionFormat",
+    "allowEmptyValue",
+];
+
+/// Mock server for a resolved v2 spec.
+#[derive(Clone)]
+pub struct MockServer {
+    base_path: String,
+    seed: u64,
+    paths: Arc<Vec<MockPath>>,
+    patterns: Arc<HashMap<String, Regex>>,
+}
+
+/// Path template along with the operations in it.
+struct MockPath {
+    template: String,
+    operations: Vec<(Method, Arc<MockOperation>)>,
+}
+
+impl MockServer {
+    /// Creates a mock server for the given (resolved) spec. This fails if
+    /// the parameters of some operation have an invalid pattern.
+    pub fn new(api: &ResolvableApi<DefaultSchema>) -> Result<Self, PaperClipError> {
+        let mut patterns = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut paths = vec![];
+        for (path, item) in &api.paths {
+            let mut operations = vec![];
+            for (&method, op) in &item.methods {
+                let op = MockOperation::new(api, &item.parameters, op);
+                let schemas = op
+                    .params
+                    .iter()
+                    .map(|p| &p.schema)
+                    .chain(op.body.as_ref().map(|(_, s)| s));
+                for schema in schemas {
+                    compile_patterns(schema, &mut patterns, &mut visited)?;
+                }
+
+                operations.push((actix_method(method), Arc::new(op)));
+            }
+
+            paths.push(MockPath {
+                template: path.clone(),
+                operations,
+            });
+        }
+
+        Ok(MockServer {
+            base_path: api
+                .base_path
+                .as_deref()
+                .unwrap_or_default()
+                .trim_end_matches('/')
+                .into(),
+            seed: 0,
+            paths: Arc::new(paths),
+            patterns: Arc::new(patterns),
+        })
+    }
+
+    /// Sets the seed used for synthesizing response bodies (defaults to 0).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Adds the routes for all operations in the spec to an actix-web app.
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        let seed = self.seed;
+        let mut scope = web::scope(&self.base_path);
+        for path in self.paths.iter() {
+            let mut resource = web::resource(path.template.as_str());
+            for (method, op) in &path.operations {
+                let op = op.clone();
+                let patterns = self.patterns.clone();
+                resource = resource.route(web::method(method.clone()).to(
+                    move |req: HttpRequest, body: web::Bytes| {
+                        let op = op.clone();
+                        let patterns = patterns.clone();
+                        async move { op.respond(&req, &body, seed, &patterns) }
+                    },
+                ));
+            }
+
+            scope = scope.service(resource);
+        }
+
+        cfg.service(scope);
+    }
+}
+
+/// Operation along with everything needed for mocking it.
+struct MockOperation {
+    params: Vec<MockParameter>,
+    body: Option<(bool, Resolvable<DefaultSchema>)>,
+    consumes: Vec<MediaRange>,
+    produces: Vec<MediaRange>,
+    responses: BTreeMap<String, ResolvableResponse<DefaultSchema>>,
+}
+
+/// Non-body parameter with its equivalent schema.
+struct MockParameter {
+    name: String,
+    in_: ParameterIn,
+    required: bool,
+    collection_format: CollectionFormat,
+    schema: Resolvable<DefaultSchema>,
+}
+
+impl MockOperation {
+    fn new(
+        api: &ResolvableApi<DefaultSchema>,
+        path_params: &[Either<Reference, ResolvableParameter<DefaultSchema>>],
+        op: &ResolvableOperation<DefaultSchema>,
+    ) -> Self {
+        let mut params = vec![];
+        let mut body = None;
+        // Operation parameters override the ones in the path item.
+        let all = op
+            .parameters
+            .iter()
+            .chain(path_params)
+            .filter_map(|p| p.right());
+        for param in all {
+            let p = param.read().unwrap();
+            if p.in_ == ParameterIn::Body {
+                if body.is_none() {
+                    body = p.schema.clone().map(|s| (p.required, s));
+                }
+
+                continue;
+            }
+
+            if params
+                .iter()
+                .any(|m: &MockParameter| m.name == p.name && m.in_ == p.in_)
+            {
+                continue;
+            }
+
+            params.push(MockParameter {
+                name: p.name.clone(),
+                in_: p.in_,
+                required: p.required,
+                collection_format: p.collection_format.unwrap_or_default(),
+                schema: simple_schema(&*p),
+            });
+        }
+
+        MockOperation {
+            params,
+            body,
+            consumes: op
+                .consumes
+                .as_ref()
+                .unwrap_or(&api.consumes)
+                .iter()
+                .cloned()
+                .collect(),
+            produces: op
+                .produces
+                .as_ref()
+                .unwrap_or(&api.produces)
+                .iter()
+                .cloned()
+                .collect(),
+            responses: op
+                .responses
+                .iter()
+                .filter_map(|(code, r)| r.right().map(|r| (code.clone(), r.clone())))
+                .collect(),
+        }
+    }
+
+    fn respond(
+        &self,
+        req: &HttpRequest,
+        body: &[u8],
+        seed: u64,
+        patterns: &HashMap<String, Regex>,
+    ) -> HttpResponse {
+        let (code, response) = match self.choose_response(req) {
+            Ok(r) => r,
+            Err(msg) => return error_response(StatusCode::BAD_REQUEST, vec![msg]),
+        };
+
+        if let Err((status, errors)) = self.validate(req, body, patterns) {
+            return error_response(status, errors);
+        }
+
+        let response = response.read().unwrap();
+        let schema = response
+            .schema
+            .as_ref()
+            .filter(|_| code != StatusCode::NO_CONTENT);
+        let media = match schema.map(|_| self.choose_media_type(req)) {
+            Some(None) => {
+                return error_response(
+                    StatusCode::NOT_ACCEPTABLE,
+                    vec!["None of the acceptable media types can be produced.".into()],
+                )
+            }
+            Some(media) => media,
+            None => None,
+        };
+
+        let mut builder = HttpResponse::build(code);
+        let mut synthesizer = Synthesizer::new(seed);
+        for (name, header) in &response.headers {
+            let value = serde_json::to_value(header)
+                .ok()
+                .and_then(schema_from_value)
+                .map(|s| synthesizer.generate(&s))
+                .unwrap_or(Value::Null);
+            if let Some(v) = header_value(&value) {
+                builder.insert_header((name.as_str(), v));
+            }
+        }
+
+        match (schema, media) {
+            (Some(schema), Some((ty, subtype))) => {
+                let value = synthesizer.generate(schema);
+                let body = match value {
+                    Value::String(s) if !is_json(&subtype) => s,
+                    v => v.to_string(),
+                };
+
+                builder
+                    .insert_header((CONTENT_TYPE, format!("{}/{}", ty, subtype)))
+                    .body(body)
+            }
+            _ => builder.finish(),
+        }
+    }
+
+    /// Picks the media type of the response body, which is the first one
+    /// (in the order of preference in the `Accept` header of the request)
+    /// produced by this operation that can be synthesized (JSON or plain text).
+    fn choose_media_type(&self, req: &HttpRequest) -> Option<(String, String)> {
+        let accepted = accepted_media_types(req);
+        let produced: Vec<(String, String)> = if self.produces.is_empty() {
+            vec![("application".into(), "json".into())]
+        } else {
+            self.produces
+                .iter()
+                .map(|r| {
+                    (
+                        r.0.type_().as_str().to_lowercase(),
+                        r.0.subtype().as_str().to_lowercase(),
+                    )
+                })
+                .collect()
+        };
+
+        accepted.iter().find_map(|accepted| {
+            produced.iter().find_map(|produced| {
+                let ty = media_part(&accepted.0, &produced.0)?;
+                let subtype = media_part(&accepted.1, &produced.1)?;
+                synthesized_media_type(ty, subtype)
+            })
+        })
+    }
+
+    /// Picks the response for this request.
+    fn choose_response(
+        &self,
+        req: &HttpRequest,
+    ) -> Result<(StatusCode, &ResolvableResponse<DefaultSchema>), String> {
+        if let Some(value) = req.headers().get(STATUS_HEADER) {
+            let code = value
+                .to_str()
+                .ok()
+                .and_then(|v| StatusCode::from_bytes(v.trim().as_bytes()).ok())
+                .ok_or_else(|| format!("Invalid status code in {:?} header.", STATUS_HEADER))?;
+            return self
+                .responses
+                .get(code.as_str())
+                .or_else(|| self.responses.get("default"))
+                .map(|r| (code, r))
+                .ok_or_else(|| format!("Status code {} is not documented.", code.as_str()));
+        }
+
+        let documented = self
+            .responses
+            .iter()
+            .filter_map(|(c, r)| StatusCode::from_bytes(c.as_bytes()).ok().map(|c| (c, r)));
+        let mut fallback = None;
+        for (code, resp) in documented {
+            if code.is_success() {
+                return Ok((code, resp));
+            }
+
+            fallback = fallback.or(Some((code, resp)));
+        }
+
+        self.responses
+            .get("default")
+            .map(|r| (StatusCode::OK, r))
+            .or(fallback)
+            .ok_or_else(|| "Operation doesn't have any responses.".into())
+    }
+
+    /// Validates the request against the parameters of this operation.
+    fn validate(
+        &self,
+        req: &HttpRequest,
+        body: &[u8],
+        patterns: &HashMap<String, Regex>,
+    ) -> Result<(), (StatusCode, Vec<String>)> {
+        let mut errors = vec![];
+        let query: Vec<(String, String)> =
+            url_dep::form_urlencoded::parse(req.query_string().as_bytes())
+                .into_owned()
+                .collect();
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(media_type);
+
+        if !body.is_empty() && !self.consumes.is_empty() {
+            let supported = content_type.as_ref().map(|(ty, subtype)| {
+                self.consumes.iter().any(|r| {
+                    r.0.type_().as_str() == ty
+                        && (r.0.subtype().as_str() == "*" || r.0.subtype().as_str() == subtype)
+                })
+            }) == Some(true);
+            if !supported {
+                return Err((
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    vec!["Content type of the request body is not supported.".into()],
+                ));
+            }
+        }
+
+        let is_form = matches!(&content_type, Some((_, s)) if s == "x-www-form-urlencoded");
+        let form: Vec<(String, String)> = if is_form {
+            url_dep::form_urlencoded::parse(body).into_owned().collect()
+        } else {
+            vec![]
+        };
+
+        for param in &self.params {
+            let values: Vec<&str> = match param.in_ {
+                ParameterIn::Path => req.match_info().get(&param.name).into_iter().collect(),
+                ParameterIn::Query => query
+                    .iter()
+                    .filter(|(k, _)| *k == param.name)
+                    .map(|(_, v)| v.as_str())
+                    .collect(),
+                ParameterIn::Header => req
+                    .headers()
+                    .get_all(param.name.as_str())
+                    .filter_map(|v| v.to_str().ok())
+                    .collect(),
+                // Multipart forms are not validated.
+                ParameterIn::FormData if !is_form => continue,
+                ParameterIn::FormData => form
+                    .iter()
+                    .filter(|(k, _)| *k == param.name)
+                    .map(|(_, v)| v.as_str())
+                    .collect(),
+                ParameterIn::Body => continue,
+            };
+
+            let path = format!("{}.{}", param_location(param.in_), param.name);
+            if values.is_empty() {
+                if param.required {
+                    errors.push(format!("{}: missing required parameter", path));
+                }
+
+                continue;
+            }
+
+            let value = param.coerce(&values);
+            validate(&value, &param.schema, &path, patterns, &mut errors);
+        }
+
+        if let Some((required, schema)) = &self.body {
+            if body.is_empty() {
+                if *required {
+                    errors.push("body: missing request body".into());
+                }
+            } else if content_type.as_ref().map(|(_, s)| is_json(s)) != Some(false) {
+                match serde_json::from_slice::<Value>(body) {
+                    Ok(value) => validate(&value, schema, "body", patterns, &mut errors),
+                    Err(e) => errors.push(format!("body: invalid JSON ({})", e)),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err((StatusCode::BAD_REQUEST, errors))
+        }
+    }
+}
+
+impl MockParameter {
+    /// Converts the raw values of this parameter to a JSON value based on its type.
+    fn coerce(&self, values: &[&str]) -> Value {
+        let schema = self.schema.read().unwrap();
+        if schema.data_type() != Some(DataType::Array) {
+            return coerce_value(values[0], schema.data_type());
+        }
+
+        let item_type = schema.items().and_then(|i| i.read().unwrap().data_type());
+        let items: Vec<&str> = match self.collection_format {
+            CollectionFormat::Multi => values.to_vec(),
+            CollectionFormat::Csv => values[0].split(',').collect(),
+            CollectionFormat::Ssv => values[0].split(' ').collect(),
+            CollectionFormat::Tsv => values[0].split('\t').collect(),
+            CollectionFormat::Pipes => values[0].split('|').collect(),
+        };
+
+        Value::Array(
+            items
+                .into_iter()
+                .map(|v| coerce_value(v, item_type))
+                .collect(),
+        )
+    }
+}
+
+/// Validates a JSON value against a schema, collecting the errors.
+fn validate<S: Schema>(
+    value: &Value,
+    schema: &Resolvable<S>,
+    path: &str,
+    patterns: &HashMap<String, Regex>,
+    errors: &mut Vec<String>,
+) {
+    let schema = schema.read().unwrap();
+    if let Some(variants) = schema.enum_variants() {
+        if !variants.contains(value) {
+            errors.push(format!("{}: value is not one of the enum variants", path));
+            return;
+        }
+    }
+
+    for member in schema.all_of().into_iter().flatten() {
+        validate(value, member, path, patterns, errors);
+    }
+
+    let expected = match schema.data_type() {
+        Some(DataType::Integer) => value.is_i64() || value.is_u64(),
+        Some(DataType::Number) => value.is_number(),
+        Some(DataType::String) => value.is_string(),
+        Some(DataType::Boolean) => value.is_boolean(),
+        Some(DataType::Array) => value.is_array(),
+        Some(DataType::Object) => value.is_object(),
+        Some(DataType::File) | None => true,
+    };
+    if !expected {
+        let ty = serde_json::to_value(schema.data_type()).unwrap_or_default();
+        errors.push(format!(
+            "{}: expected {}",
+            path,
+            ty.as_str().unwrap_or("value")
+        ));
+        return;
+    }
+
+    if let Some(format) = schema.format() {
+        validate_format(value, format, path, errors);
+    }
+
+    match value {
+        Value::Number(n) => validate_number(n.as_f64().unwrap_or_default(), &*schema, path, errors),
+        Value::String(s) => {
+            let len = s.chars().count() as u32;
+            if let Some(min) = schema.min_length().filter(|m| len < *m) {
+                errors.push(format!(
+                    "{}: string is shorter than {} characters",
+                    path, min
+                ));
+            }
+
+            if let Some(max) = schema.max_length().filter(|m| len > *m) {
+                errors.push(format!(
+                    "{}: string is longer than {} characters",
+                    path, max
+                ));
+            }
+
+            if let Some(pattern) = schema.pattern() {
+                if !matches!(patterns.get(pattern), Some(r) if r.is_match(s)) {
+                    errors.push(format!("{}: string doesn't match {:?}", path, pattern));
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u32;
+            if let Some(min) = schema.min_items().filter(|m| len < *m) {
+                errors.push(format!("{}: array has fewer than {} items", path, min));
+            }
+
+            if let Some(max) = schema.max_items().filter(|m| len > *m) {
+                errors.push(format!("{}: array has more than {} items", path, max));
+            }
+
+            if schema.unique_items()
+                && items
+                    .iter()
+                    .enumerate()
+                    .any(|(i, v)| items[..i].contains(v))
+            {
+                errors.push(format!("{}: array items are not unique", path));
+            }
+
+            if let Some(s) = schema.items() {
+                for (i, item) in items.iter().enumerate() {
+                    validate(item, s, &format!("{}[{}]", path, i), patterns, errors);
+                }
+            }
+        }
+        Value::Object(map) => validate_object(map, &*schema, path, patterns, errors),
+        _ => (),
+    }
+}
+
+fn validate_number<S: Schema>(n: f64, schema: &S, path: &str, errors: &mut Vec<String>) {
+    if let Some(min) = schema.minimum().map(f64::from) {
+        if n < min || (n == min && schema.exclusive_minimum()) {
+            errors.push(format!(
+                "{}: number is less than the minimum ({})",
+                path, min
+            ));
+        }
+    }
+
+    if let Some(max) = schema.maximum().map(f64::from) {
+        if n > max || (n == max && schema.exclusive_maximum()) {
+            errors.push(format!(
+                "{}: number is greater than the maximum ({})",
+                path, max
+            ));
+        }
+    }
+
+    if let Some(step) = schema.multiple_of().map(f64::from).filter(|s| *s > 0.0) {
+        let q = n / step;
+        if (q - q.round()).abs() > 1e-9 {
+            errors.push(format!("{}: number is not a multiple of {}", path, step));
+        }
+    }
+}
+
+/// Validates a value against the (standard) format of its schema. Other
+/// formats aren't validated.
+fn validate_format(value: &Value, format: &DataTypeFormat, path: &str, errors: &mut Vec<String>) {
+    let valid = match (format, value) {
+        (DataTypeFormat::Int32, Value::Number(n)) => {
+            matches!(n.as_i64().map(i32::try_from), Some(Ok(_)))
+        }
+        (DataTypeFormat::Int64, Value::Number(n)) => n.is_i64(),
+        (DataTypeFormat::Float, Value::Number(n)) => {
+            matches!(n.as_f64(), Some(n) if n.abs() <= f64::from(f32::MAX))
+        }
+        (DataTypeFormat::Date, Value::String(s)) => is_date(s),
+        (DataTypeFormat::DateTime, Value::String(s)) => is_date_time(s),
+        (DataTypeFormat::Uuid, Value::String(s)) => is_uuid(s),
+        (DataTypeFormat::Byte, Value::String(s)) => is_base64(s),
+        (DataTypeFormat::Ip, Value::String(s)) => s.parse::<IpAddr>().is_ok(),
+        (DataTypeFormat::IpV4, Value::String(s)) => s.parse::<Ipv4Addr>().is_ok(),
+        (DataTypeFormat::IpV6, Value::String(s)) => s.parse::<Ipv6Addr>().is_ok(),
+        (DataTypeFormat::Url, Value::String(s)) => url_dep::Url::parse(s).is_ok(),
+        _ => return,
+    };
+
+    if !valid {
+        let kind = if value.is_number() {
+            "number is out of range for"
+        } else {
+            "string is not a valid"
+        };
+        errors.push(format!("{}: {} {}", path, kind, format.as_str()));
+    }
+}
+
+/// Whether the string is a `full-date` (like `2021-02-28`) as per RFC 3339.
+fn is_date(s: &str) -> bool {
+    let fields = match digit_fields(s, '-', &[4, 2, 2]) {
+        Some(f) => f,
+        None => return false,
+    };
+
+    let (year, month, day) = (fields[0], fields[1], fields[2]);
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+
+    (1..=days).contains(&day)
+}
+
+/// Whether the string is a `date-time` (like `2021-02-28T16:30:00.5+05:30`)
+/// as per RFC 3339.
+fn is_date_time(s: &str) -> bool {
+    let (date, time) = match (s.get(..10), s.get(10..)) {
+        (Some(date), Some(time)) if time.starts_with(['T', 't']) => (date, &time[1..]),
+        _ => return false,
+    };
+
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => time.split_at(i),
+        None => return false,
+    };
+
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+
+    let valid_fraction = fraction
+        .into_iter()
+        .all(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit()));
+    let valid_time = matches!(
+        digit_fields(time, ':', &[2, 2, 2]).as_deref(),
+        // (the seconds can be 60 for leap seconds)
+        Some(&[h, m, s]) if h < 24 && m < 60 && s <= 60
+    );
+    let valid_offset = offset.eq_ignore_ascii_case("z")
+        || matches!(
+            digit_fields(&offset[1..], ':', &[2, 2]).as_deref(),
+            Some(&[h, m]) if h < 24 && m < 60
+        );
+
+    is_date(date) && valid_time && valid_fraction && valid_offset
+}
+
+/// Splits the string into fields of digits (with the given lengths) and
+/// parses them.
+fn digit_fields(s: &str, separator: char, lengths: &[usize]) -> Option<Vec<u32>> {
+    let fields: Vec<_> = s.split(separator).collect();
+    if fields.len() != lengths.len() {
+        return None;
+    }
+
+    fields
+        .iter()
+        .zip(lengths)
+        .map(|(f, len)| {
+            if f.len() == *len && f.bytes().all(|b| b.is_ascii_digit()) {
+                f.parse().ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Whether the string is a UUID (like `8a7f4b6e-0c63-4b8e-9a53-3c2f3b1d4e5f`).
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Whether the string is (padded) base64 encoded data.
+fn is_base64(s: &str) -> bool {
+    let data = s.trim_end_matches('=');
+    s.len().is_multiple_of(4)
+        && s.len() - data.len() <= 2
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+}
+
+fn validate_object<S: Schema>(
+    map: &Map<String, Value>,
+    schema: &S,
+    path: &str,
+    patterns: &HashMap<String, Regex>,
+    errors: &mut Vec<String>,
+) {
+    for name in schema.required_properties().into_iter().flatten() {
+        if !map.contains_key(name) {
+            errors.push(format!("{}: missing required property {:?}", path, name));
+        }
+    }
+
+    let props = schema.properties();
+    for (key, value) in map {
+        let prop_path = format!("{}.{}", path, key);
+        match props.and_then(|p| p.get(key)) {
+            Some(prop) => validate(value, prop, &prop_path, patterns, errors),
+            None => match schema.additional_properties() {
+                Some(Either::Left(false)) => {
+                    errors.push(format!("{}: unknown property", prop_path))
+                }
+                Some(Either::Right(extra)) => validate(value, extra, &prop_path, patterns, errors),
+                _ => (),
+            },
+        }
+    }
+}
+
+/// Compiles the patterns in the given schema (and the schemas in it).
+fn compile_patterns(
+    schema: &Resolvable<DefaultSchema>,
+    patterns: &mut HashMap<String, Regex>,
+    visited: &mut HashSet<*const RwLock<DefaultSchema>>,
+) -> Result<(), PaperClipError> {
+    if !visited.insert(Arc::as_ptr(schema)) {
+        return Ok(());
+    }
+
+    let s = schema.read().unwrap();
+    if let Some(pattern) = s.pattern().filter(|p| !patterns.contains_key(*p)) {
+        let regex = Regex::new(pattern)
+            .map_err(|e| PaperClipError::InvalidPattern(pattern.into(), e.to_string()))?;
+        patterns.insert(pattern.into(), regex);
+    }
+
+    let children = s
+        .items()
+        .into_iter()
+        .chain(s.properties().into_iter().flat_map(|p| p.values()))
+        .chain(s.all_of().into_iter().flatten())
+        .chain(s.additional_properties().and_then(|e| e.right()));
+    for child in children {
+        compile_patterns(child, patterns, visited)?;
+    }
+
+    Ok(())
+}
+
+/// Converts a non-body parameter (or header) to an equivalent schema.
+fn simple_schema<T: serde::Serialize>(param: &T) -> Resolvable<DefaultSchema> {
+    serde_json::to_value(param)
+        .ok()
+        .and_then(schema_from_value)
+        .unwrap_or_default()
+}
+
+fn schema_from_value(mut value: Value) -> Option<Resolvable<DefaultSchema>> {
+    if let Some(map) = value.as_object_mut() {
+        for key in NON_SCHEMA_KEYS {
+            map.remove(*key);
+        }
+    }
+
+    serde_json::from_value::<DefaultSchema>(value)
+        .ok()
+        .map(Resolvable::from)
+}
+
+fn coerce_value(raw: &str, data_type: Option<DataType>) -> Value {
+    let value = match data_type {
+        Some(DataType::Integer) => raw.parse::<i64>().ok().map(Value::from),
+        Some(DataType::Number) => raw.parse::<f64>().ok().map(Value::from),
+        Some(DataType::Boolean) => raw.parse::<bool>().ok().map(Value::from),
+        _ => None,
+    };
+
+    value.unwrap_or_else(|| Value::String(raw.into()))
+}
+
+/// Converts a synthesized value to a header value.
+fn header_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(header_value)
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        v => Some(v.to_string()),
+    }
+}
+
+/// Returns the (lowercase) type and subtype of a media type.
+fn media_type(value: &str) -> Option<(String, String)> {
+    let essence = value.split(';').next()?.trim().to_lowercase();
+    let (ty, subtype) = essence.split_once('/')?;
+    Some((ty.into(), subtype.into()))
+}
+
+/// Returns the (lowercase) media ranges in the `Accept` header of the request,
+/// ordered by their quality (and leaving out the unacceptable ones). Requests
+/// without the header accept anything.
+fn accepted_media_types(req: &HttpRequest) -> Vec<(String, String)> {
+    let mut ranges = vec![];
+    for value in req.headers().get_all(ACCEPT) {
+        for range in value.to_str().unwrap_or_default().split(',') {
+            let mut params = range.split(';');
+            let media = match params.next().and_then(media_type) {
+                Some(m) => m,
+                None => continue,
+            };
+
+            let quality = params
+                .filter_map(|p| p.split_once('='))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, q)| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 {
+                ranges.push((quality, media));
+            }
+        }
+    }
+
+    if ranges.is_empty() && req.headers().get(ACCEPT).is_none() {
+        return vec![("*".into(), "*".into())];
+    }
+
+    // (the sort is stable, so ranges with the same quality keep their order)
+    ranges.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    ranges.into_iter().map(|(_, m)| m).collect()
+}
+
+/// Returns the (possibly wildcard) part of a media type which matches both
+/// the accepted and the produced part.
+fn media_part<'a>(accepted: &'a str, produced: &'a str) -> Option<&'a str> {
+    match (accepted, produced) {
+        ("*", p) => Some(p),
+        (a, "*") => Some(a),
+        (a, p) if a == p => Some(a),
+        _ => None,
+    }
+}
+
+/// Returns the media type (for the given type and subtype, which can be
+/// wildcards) of the response bodies which can be synthesized.
+fn synthesized_media_type(ty: &str, subtype: &str) -> Option<(String, String)> {
+    let (ty, subtype) = match (ty, subtype) {
+        ("*", "*") | ("application", "*") => ("application", "json"),
+        ("text", "*") => ("text", "plain"),
+        (ty, subtype) => (ty, subtype),
+    };
+
+    if (ty == "application" && is_json(subtype)) || (ty, subtype) == ("text", "plain") {
+        Some((ty.into(), subtype.into()))
+    } else {
+        None
+    }
+}
+
+fn is_json(subtype: &str) -> bool {
+    subtype == "json" || subtype.ends_with("+json")
+}
+
+fn param_location(in_: ParameterIn) -> &'static str {
+    match in_ {
+        ParameterIn::Path => "path",
+        ParameterIn::Query => "query",
+        ParameterIn::Header => "header",
+        ParameterIn::FormData => "formData",
+        ParameterIn::Body => "body",
+    }
+}
+
+fn error_response(status: StatusCode, errors: Vec<String>) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "errors": errors }))
+}
+
+fn actix_method(method: HttpMethod) -> Method {
+    match method {
+        HttpMethod::Get => Method::GET,
+        HttpMethod::Put => Method::PUT,
+        HttpMethod::Post => Method::POST,
+        HttpMethod::Delete => Method::DELETE,
+        HttpMethod::Options => Method::OPTIONS,
+        HttpMethod::Head => Method::HEAD,
+        HttpMethod::Patch => Method::PATCH,
+    }
+}
@@ -16,6 +16,16 @@
 //! assert_eq!(api.swagger, Version::V2);
 //! ```
 //!
+//! If your spec is split across multiple files (i.e., it has references like
+//! `common.yaml#/definitions/Error` or `./models/pet.json`), then use
+//! [`from_path`](fn.from_path.html) instead, which follows relative file
+//! references and merges the referenced objects into the root spec.
+//!
+//! ```rust,no_run
+//! # use paperclip::v2::{self, ResolvableApi, DefaultSchema};
+//! let api: ResolvableApi<DefaultSchema> = v2::from_path("my_spec.yaml").unwrap();
+//! ```
+//!
 //! On the other hand, if your schema does have custom properties which you'd
 //! like to parse, then use the `#[api_v2_schema]` proc macro.
 //!
@@ -86,53 +96,148 @@
 //! }
 //! ```
 
+pub mod bundle;
 #[cfg(feature = "codegen")]
 pub mod codegen;
+pub mod diff;
+pub mod example;
+pub mod filter;
+pub mod json_schema;
+pub mod lint;
+mod loader;
+pub mod merge;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod overlay;
+mod writer;
 
+use self::overlay::Overlay;
 use crate::error::PaperClipError;
-use paperclip_core::v2::models::SpecFormat;
+use paperclip_core::v2::models::{Either, Resolvable, SpecFormat};
 use serde::Deserialize;
 
-use std::io::Read;
+use std::{
+    collections::HashSet,
+    io::Read,
+    sync::{Arc, RwLock},
+};
 
 #[cfg(feature = "codegen")]
 pub use self::codegen::{DefaultEmitter, Emitter, EmitterState};
+pub use self::loader::{from_path, from_path_with_overlays};
+pub use self::writer::{to_string, to_writer};
 pub use paperclip_core::{
     im,
     v2::{
+        graph,
         models::{self, DefaultSchema, ResolvableApi},
         schema::{self, Schema},
         serde_json,
+        source_map::{self, SourceMap},
     },
+    Span,
 };
 
+pub(crate) use paperclip_core::v2::source_map::json_path;
+
 /// Deserialize the schema from the given reader. Currently, this only supports
 /// JSON and YAML formats.
-pub fn from_reader<R, S>(mut reader: R) -> Result<ResolvableApi<S>, PaperClipError>
+///
+/// The returned spec carries the source map of the document, so that errors
+/// (in parsing or resolving the spec) point to their location in the document.
+pub fn from_reader<R, S>(reader: R) -> Result<ResolvableApi<S>, PaperClipError>
 where
     R: Read,
     for<'de> S: Deserialize<'de> + Schema,
 {
-    let mut buf = [b' '];
-    while buf[0].is_ascii_whitespace() {
-        reader.read_exact(&mut buf)?;
+    from_reader_with_overlays(reader, &[]).map(|(api, _)| api)
+}
+
+/// Same as [`from_reader`](fn.from_reader.html), but the given
+/// [overlays](overlay/index.html) are applied (in order) to the document
+/// before it's deserialized. This also returns the overlay targets which
+/// didn't match anything.
+///
+/// **NOTE:** Locations in the document change with overlays, so the spec
+/// doesn't have a [source map](source_map/index.html) in that case.
+pub fn from_reader_with_overlays<R, S>(
+    mut reader: R,
+    overlays: &[Overlay],
+) -> Result<(ResolvableApi<S>, Vec<String>), PaperClipError>
+where
+    R: Read,
+    for<'de> S: Deserialize<'de> + Schema,
+{
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+
+    let fmt = spec_format(&text);
+    let map = SourceMap::new(&text, fmt);
+    if overlays.is_empty() {
+        let mut api: ResolvableApi<S> = deserialize(&text, fmt, &map)?;
+        api.spec_format = fmt;
+        api.source_map = Some(Arc::new(map));
+        return Ok((api, vec![]));
     }
-    let reader = buf.as_ref().chain(reader);
 
-    let (mut api, fmt) = if buf[0] == b'{' {
-        (
-            serde_json::from_reader::<_, ResolvableApi<S>>(reader)?,
-            SpecFormat::Json,
-        )
+    let mut doc: serde_json::Value = deserialize(&text, fmt, &map)?;
+    let unmatched = apply_overlays(&mut doc, overlays)?;
+    let mut api: ResolvableApi<S> = serde_json::from_value(doc)?;
+    api.spec_format = fmt;
+    Ok((api, unmatched))
+}
+
+/// Applies the given overlays (in order) to the raw document, and returns
+/// the targets which didn't match anything.
+pub(crate) fn apply_overlays(
+    doc: &mut serde_json::Value,
+    overlays: &[Overlay],
+) -> Result<Vec<String>, PaperClipError> {
+    let mut unmatched = vec![];
+    for overlay in overlays {
+        unmatched.extend(overlay.apply(doc)?.into_iter().map(String::from));
+    }
+
+    Ok(unmatched)
+}
+
+/// Returns the format of the given document (JSON documents begin with `{`).
+pub(crate) fn spec_format(text: &str) -> SpecFormat {
+    if text.trim_start().starts_with('{') {
+        SpecFormat::Json
     } else {
-        (
-            serde_yaml::from_reader::<_, ResolvableApi<S>>(reader)?,
-            SpecFormat::Yaml,
-        )
-    };
+        SpecFormat::Yaml
+    }
+}
 
-    api.spec_format = fmt;
-    Ok(api)
+/// Deserialize the given document. Parse errors are located using the given
+/// source map (of the same document).
+pub(crate) fn deserialize<T>(
+    text: &str,
+    fmt: SpecFormat,
+    map: &SourceMap,
+) -> Result<T, PaperClipError>
+where
+    for<'de> T: Deserialize<'de>,
+{
+    match fmt {
+        SpecFormat::Json => serde_json::from_str(text).map_err(|e| {
+            let span = Some((e.line(), e.column())).filter(|&(line, _)| line > 0);
+            located(e.into(), span, map)
+        }),
+        SpecFormat::Yaml => serde_yaml::from_str(text).map_err(|e| {
+            let span = e.location().map(|l| (l.line(), l.column()));
+            located(e.into(), span, map)
+        }),
+    }
+}
+
+/// Attaches the span at the given line and column (if any) to the error.
+fn located(err: PaperClipError, pos: Option<(usize, usize)>, map: &SourceMap) -> PaperClipError {
+    match pos {
+        Some((line, column)) => PaperClipError::Located(Box::new(err), map.span_at(line, column)),
+        None => err,
+    }
 }
 
 #[cfg(feature = "cli-ng")]
@@ -162,3 +267,46 @@ where
 
     Ok(api)
 }
+
+/// Visits the given schema and its children (without following references)
+/// and collects the references found along the way.
+pub(crate) fn visit_schema<S: Schema>(
+    schema: &Resolvable<S>,
+    visited: &mut HashSet<*const RwLock<S>>,
+    refs: &mut Vec<String>,
+) {
+    // Resolved schemas are references - visit the referencing schema instead.
+    let node = match schema {
+        Resolvable::Raw(s) => s,
+        Resolvable::Resolved { old, .. } => old,
+    };
+
+    if !visited.insert(&**node as *const _) {
+        return;
+    }
+
+    let s = node.read().unwrap();
+    if let Some(uri) = s.reference() {
+        refs.push(uri.into());
+    }
+
+    if let Some(inner) = s.items() {
+        visit_schema(inner, visited, refs);
+    }
+
+    if let Some(props) = s.properties() {
+        for prop in props.values() {
+            visit_schema(prop, visited, refs);
+        }
+    }
+
+    if let Some(members) = s.all_of() {
+        for member in members {
+            visit_schema(member, visited, refs);
+        }
+    }
+
+    if let Some(Either::Right(extra)) = s.additional_properties() {
+        visit_schema(extra, visited, refs);
+    }
+}
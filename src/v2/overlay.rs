@@ -0,0 +1,685 @@
+//! Support for [OpenAPI Overlay](https://github.com/OAI/Overlay-Specification) documents.
+//!
+//! An overlay is a list of actions, each of which targets some nodes in the spec
+//! (using a [JSONPath](https://www.rfc-editor.org/rfc/rfc9535) expression) and
+//! either updates or removes them. This is useful for patching specs which
+//! can't be fixed upstream.
+//!
+//! ```yaml
+//! overlay: 1.0.0
+//! info:
+//!   title: Fixes for the pet store
+//!   version: 1.0.0
+//! actions:
+//!   - target: $.paths['/pets'].get
+//!     update:
+//!       operationId: listPets
+//!   - target: $.definitions.Pet.properties.id
+//!     update:
+//!       type: integer
+//!   - target: $.paths.*.*.parameters[?@.in == 'header']
+//!     remove: true
+//! ```
+//!
+//! Overlays are applied to the raw document (in the given order) before the
+//! spec is deserialized.
+//!
+//! ```rust,no_run
+//! use paperclip::v2::{self, overlay::Overlay, DefaultSchema, ResolvableApi};
+//!
+//! let overlay = Overlay::from_path("fixes.yaml").unwrap();
+//! let (api, unmatched): (ResolvableApi<DefaultSchema>, _) =
+//!     v2::from_path_with_overlays("my_spec.yaml", &[overlay]).unwrap();
+//! for target in unmatched {
+//!     eprintln!("{} didn't match anything", target);
+//! }
+//! ```
+//!
+//! Updates are merged into the targeted objects - nested objects are merged
+//! recursively, while other values (including arrays) are replaced. If the
+//! target is an array, then the update is appended to it. Replacing a value
+//! with an object (or removing a key) needs a `remove` action first.
+//!
+//! JSONPath support includes names (`.name`, `['name']`), indices (`[0]`, `[-1]`),
+//! wildcards (`.*`, `[*]`), recursive descent (`..name`), unions (`['a','b']`) and
+//! filters with comparisons, existence checks and logical operators
+//! (`[?@.in == 'query' && !@.required]`).
+
+use crate::error::PaperClipError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::{cmp::Ordering, fs, io::Read, path::Path};
+
+/// An OpenAPI Overlay document.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Overlay {
+    /// Version of the overlay specification (only `1.x` is supported).
+    pub overlay: String,
+    /// Metadata about the overlay.
+    pub info: OverlayInfo,
+    /// URL of the spec this overlay is meant for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Actions to be applied (in order) to the spec.
+    pub actions: Vec<OverlayAction>,
+}
+
+/// Metadata about an overlay.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OverlayInfo {
+    pub title: String,
+    pub version: String,
+}
+
+/// An action in an overlay.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OverlayAction {
+    /// JSONPath expression for the targeted nodes.
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value to be merged into the targeted nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<Value>,
+    /// Whether the targeted nodes should be removed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remove: bool,
+}
+
+impl Overlay {
+    /// Deserialize the overlay from the given reader (JSON or YAML).
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, PaperClipError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        // YAML is a superset of JSON.
+        let overlay: Overlay = serde_yaml::from_str(&text)?;
+        if !overlay.overlay.starts_with("1.") {
+            return Err(PaperClipError::UnsupportedOverlayVersion(overlay.overlay));
+        }
+
+        for action in &overlay.actions {
+            JsonPath::parse(&action.target)?;
+        }
+
+        Ok(overlay)
+    }
+
+    /// Load the overlay from the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, PaperClipError> {
+        Self::from_reader(fs::File::open(path)?)
+    }
+
+    /// Applies the actions in this overlay to the given (raw) document and
+    /// returns the targets which didn't match any nodes.
+    pub fn apply(&self, doc: &mut Value) -> Result<Vec<&str>, PaperClipError> {
+        let mut unmatched = vec![];
+        for action in &self.actions {
+            let path = JsonPath::parse(&action.target)?;
+            let mut targets = path.select(doc);
+            if targets.is_empty() {
+                unmatched.push(action.target.as_str());
+                continue;
+            }
+
+            // Unions can select the same node more than once.
+            targets.sort();
+            targets.dedup();
+            if action.remove {
+                // Remove the last nodes first, so that array indices of
+                // the remaining targets stay valid.
+                for target in targets.iter().rev() {
+                    remove(doc, target);
+                }
+            } else if let Some(update) = &action.update {
+                for target in &targets {
+                    if let Some(node) = node_mut(doc, target) {
+                        match node {
+                            Value::Array(items) => items.push(update.clone()),
+                            node => merge(node, update),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(unmatched)
+    }
+}
+
+/// Merges the given value into the node.
+fn merge(node: &mut Value, update: &Value) {
+    match (node, update) {
+        (Value::Object(map), Value::Object(fields)) => {
+            for (key, value) in fields {
+                match map.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        map.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (node, value) => *node = value.clone(),
+    }
+}
+
+/// Removes the node at the given location (the root can't be removed).
+fn remove(doc: &mut Value, location: &[Step]) {
+    let (last, parent) = match location.split_last() {
+        Some(l) => l,
+        None => return,
+    };
+
+    match (node_mut(doc, parent), last) {
+        (Some(Value::Object(map)), Step::Key(k)) => {
+            map.remove(k);
+        }
+        (Some(Value::Array(items)), &Step::Index(i)) if i < items.len() => {
+            items.remove(i);
+        }
+        _ => (),
+    }
+}
+
+fn node_mut<'a>(doc: &'a mut Value, location: &[Step]) -> Option<&'a mut Value> {
+    location
+        .iter()
+        .try_fold(doc, |node, step| match (node, step) {
+            (Value::Object(map), Step::Key(k)) => map.get_mut(k),
+            (Value::Array(items), &Step::Index(i)) => items.get_mut(i),
+            _ => None,
+        })
+}
+
+/* JSONPath */
+
+/// A step in the location of a node.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// A parsed JSONPath query.
+#[derive(Debug)]
+struct JsonPath {
+    /// Whether this query is relative to the current node (`@`).
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+    Filter(Filter),
+}
+
+#[derive(Debug)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Exists(JsonPath),
+    Compare(Operand, Comparison, Operand),
+}
+
+#[derive(Debug)]
+enum Operand {
+    Literal(Value),
+    Query(JsonPath),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+type Node<'v> = (Vec<Step>, &'v Value);
+
+impl JsonPath {
+    fn parse(path: &str) -> Result<Self, PaperClipError> {
+        let mut parser = Parser {
+            chars: path.chars().collect(),
+            pos: 0,
+        };
+
+        parser
+            .query()
+            .and_then(|query| match parser.peek() {
+                None if !query.relative => Ok(query),
+                None => Err("query must begin with '$'".into()),
+                Some(c) => Err(format!("unexpected {:?} at {}", c, parser.pos)),
+            })
+            .map_err(|e| PaperClipError::InvalidJsonPath(path.into(), e))
+    }
+
+    /// Returns the locations of the nodes matching this query.
+    fn select(&self, doc: &Value) -> Vec<Vec<Step>> {
+        self.nodes(doc, doc)
+            .into_iter()
+            .map(|(location, _)| location)
+            .collect()
+    }
+
+    fn nodes<'v>(&self, root: &'v Value, current: &'v Value) -> Vec<Node<'v>> {
+        let start = if self.relative { current } else { root };
+        let mut nodes = vec![(vec![], start)];
+        for segment in &self.segments {
+            let mut next = vec![];
+            for node in nodes {
+                match segment {
+                    Segment::Child(selectors) => select_children(root, &node, selectors, &mut next),
+                    Segment::Descendant(selectors) => {
+                        let mut descendants = vec![];
+                        collect_descendants(node, &mut descendants);
+                        for node in &descendants {
+                            select_children(root, node, selectors, &mut next);
+                        }
+                    }
+                }
+            }
+
+            nodes = next;
+        }
+
+        nodes
+    }
+}
+
+/// Collects the given node and all its descendants (in document order).
+fn collect_descendants<'v>(node: Node<'v>, nodes: &mut Vec<Node<'v>>) {
+    let (location, value) = node;
+    nodes.push((location.clone(), value));
+    for child in children(&location, value) {
+        collect_descendants(child, nodes);
+    }
+}
+
+fn children<'v>(location: &[Step], value: &'v Value) -> Vec<Node<'v>> {
+    let child = |step| {
+        let mut l = location.to_vec();
+        l.push(step);
+        l
+    };
+
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (child(Step::Key(k.clone())), v))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (child(Step::Index(i)), v))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn select_children<'v>(
+    root: &'v Value,
+    node: &Node<'v>,
+    selectors: &[Selector],
+    out: &mut Vec<Node<'v>>,
+) {
+    let (location, value) = node;
+    for selector in selectors {
+        match (selector, value) {
+            (Selector::Name(name), Value::Object(map)) => {
+                if let Some(v) = map.get(name) {
+                    let mut l = location.clone();
+                    l.push(Step::Key(name.clone()));
+                    out.push((l, v));
+                }
+            }
+            (&Selector::Index(i), Value::Array(items)) => {
+                let i = if i < 0 { items.len() as i64 + i } else { i };
+                if i >= 0 && (i as usize) < items.len() {
+                    let mut l = location.clone();
+                    l.push(Step::Index(i as usize));
+                    out.push((l, &items[i as usize]));
+                }
+            }
+            (Selector::Wildcard, v) => out.extend(children(location, v)),
+            (Selector::Filter(filter), v) => out.extend(
+                children(location, v)
+                    .into_iter()
+                    .filter(|(_, child)| filter.test(root, child)),
+            ),
+            _ => (),
+        }
+    }
+}
+
+impl Filter {
+    fn test(&self, root: &Value, current: &Value) -> bool {
+        match self {
+            Filter::Or(a, b) => a.test(root, current) || b.test(root, current),
+            Filter::And(a, b) => a.test(root, current) && b.test(root, current),
+            Filter::Not(f) => !f.test(root, current),
+            Filter::Exists(query) => !query.nodes(root, current).is_empty(),
+            Filter::Compare(a, op, b) => {
+                let (a, b) = (a.value(root, current), b.value(root, current));
+                match op {
+                    Comparison::Eq => equal(a, b),
+                    Comparison::Ne => !equal(a, b),
+                    Comparison::Lt => compare(a, b) == Some(Ordering::Less),
+                    Comparison::Gt => compare(a, b) == Some(Ordering::Greater),
+                    Comparison::Le => equal(a, b) || compare(a, b) == Some(Ordering::Less),
+                    Comparison::Ge => equal(a, b) || compare(a, b) == Some(Ordering::Greater),
+                }
+            }
+        }
+    }
+}
+
+impl Operand {
+    /// Returns the value of this operand. Queries which don't match
+    /// exactly one node don't have a value.
+    fn value<'v>(&'v self, root: &'v Value, current: &'v Value) -> Option<&'v Value> {
+        match self {
+            Operand::Literal(v) => Some(v),
+            Operand::Query(query) => {
+                let nodes = query.nodes(root, current);
+                match &nodes[..] {
+                    [(_, v)] => Some(*v),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+fn equal(a: Option<&Value>, b: Option<&Value>) -> bool {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64() == b.as_f64(),
+        (a, b) => a == b,
+    }
+}
+
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Option<Ordering> {
+    match (a?, b?) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().filter(|c| c.is_whitespace()).is_some() {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let matches = s
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c));
+        if matches {
+            self.pos += s.chars().count();
+        }
+
+        matches
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?} at {}", s, self.pos))
+        }
+    }
+
+    fn query(&mut self) -> Result<JsonPath, String> {
+        let relative = match self.peek() {
+            Some('$') => false,
+            Some('@') => true,
+            _ => return Err(format!("expected '$' or '@' at {}", self.pos)),
+        };
+
+        self.pos += 1;
+        let mut segments = vec![];
+        loop {
+            if self.eat("..") {
+                let selectors = match self.peek() {
+                    Some('[') => self.bracket()?,
+                    Some('*') => {
+                        self.pos += 1;
+                        vec![Selector::Wildcard]
+                    }
+                    _ => vec![Selector::Name(self.name()?)],
+                };
+
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat(".") {
+                let selector = if self.eat("*") {
+                    Selector::Wildcard
+                } else {
+                    Selector::Name(self.name()?)
+                };
+
+                segments.push(Segment::Child(vec![selector]));
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                break;
+            }
+        }
+
+        Ok(JsonPath { relative, segments })
+    }
+
+    /// Member name shorthand (after `.` or `..`).
+    fn name(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .filter(|&c| c.is_alphanumeric() || c == '_' || c == '-' || !c.is_ascii())
+            .is_some()
+        {
+            self.pos += 1;
+        }
+
+        if start == self.pos {
+            return Err(format!("expected name at {}", self.pos));
+        }
+
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn bracket(&mut self) -> Result<Vec<Selector>, String> {
+        self.expect("[")?;
+        let mut selectors = vec![];
+        loop {
+            self.skip_whitespace();
+            let selector = match self.peek() {
+                Some('\'') | Some('"') => Selector::Name(self.string()?),
+                Some('*') => {
+                    self.pos += 1;
+                    Selector::Wildcard
+                }
+                Some('?') => {
+                    self.pos += 1;
+                    Selector::Filter(self.or()?)
+                }
+                Some(c) if c == '-' || c.is_ascii_digit() => match self.number()? {
+                    Value::Number(n) if n.is_i64() => Selector::Index(n.as_i64().unwrap()),
+                    _ => return Err(format!("invalid index before {}", self.pos)),
+                },
+                _ => return Err(format!("invalid selector at {}", self.pos)),
+            };
+
+            selectors.push(selector);
+            self.skip_whitespace();
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        self.expect("]")?;
+        Ok(selectors)
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.eat("||") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.unary()?;
+        while self.eat("&&") {
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        self.skip_whitespace();
+        let filter = if self.eat("!") {
+            Filter::Not(Box::new(self.unary()?))
+        } else if self.eat("(") {
+            let filter = self.or()?;
+            self.skip_whitespace();
+            self.expect(")")?;
+            filter
+        } else {
+            let start = self.pos;
+            let lhs = self.operand()?;
+            self.skip_whitespace();
+            match self.comparison() {
+                Some(op) => {
+                    self.skip_whitespace();
+                    Filter::Compare(lhs, op, self.operand()?)
+                }
+                None => match lhs {
+                    Operand::Query(query) => Filter::Exists(query),
+                    Operand::Literal(_) => {
+                        return Err(format!("expected comparison after literal at {}", start))
+                    }
+                },
+            }
+        };
+
+        self.skip_whitespace();
+        Ok(filter)
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        // Longer operators go first.
+        let ops = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+
+        ops.iter().find(|(s, _)| self.eat(s)).map(|&(_, op)| op)
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        let literal = match self.peek() {
+            Some('$') | Some('@') => return self.query().map(Operand::Query),
+            Some('\'') | Some('"') => Value::String(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number()?,
+            _ if self.eat("true") => Value::Bool(true),
+            _ if self.eat("false") => Value::Bool(false),
+            _ if self.eat("null") => Value::Null,
+            _ => return Err(format!("expected operand at {}", self.pos)),
+        };
+
+        Ok(Operand::Literal(literal))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .filter(|&c| c.is_ascii_digit() || "-+.eE".contains(c))
+            .is_some()
+        {
+            self.pos += 1;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str::<serde_json::Number>(&text)
+            .map(Value::Number)
+            .map_err(|_| format!("invalid number {:?} at {}", text, start))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.peek();
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| "unterminated string".to_owned())?;
+            self.pos += 1;
+            if Some(c) == quote {
+                return Ok(s);
+            }
+
+            if c != '\\' {
+                s.push(c);
+                continue;
+            }
+
+            let escaped = self
+                .peek()
+                .ok_or_else(|| "unterminated string".to_owned())?;
+            self.pos += 1;
+            s.push(match escaped {
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                    self.pos += 4;
+                    u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape \\u{}", hex))?
+                }
+                c => c,
+            });
+        }
+    }
+}
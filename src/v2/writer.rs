@@ -0,0 +1,119 @@
+//! Writer for v2 specs.
+//!
+//! Specs are written in a canonical form, so that reading and writing a spec
+//! (in the same format) gives the same output regardless of how the original
+//! document was laid out:
+//!
+//! - Top-level keys follow the order in which they're listed in the spec
+//!   (`swagger`, `info`, `host`, `basePath`, ..., `paths`, `definitions`, ...)
+//!   and vendor extensions come last.
+//! - Paths, definitions, parameters, responses and security definitions
+//!   are sorted by their keys.
+//! - Paths are written as-is (actix path patterns like `{id:\d+}` are not stripped).
+//! - JSON output is pretty printed and ends with a newline.
+
+use crate::error::PaperClipError;
+use paperclip_core::v2::models::{Api, SpecFormat};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use std::io::Write;
+
+/// Serialize the spec into the given writer in the format (JSON or YAML)
+/// it was originally provided in (i.e., `Api.spec_format`).
+pub fn to_writer<W, P, R, S>(api: &Api<P, R, S>, mut writer: W) -> Result<(), PaperClipError>
+where
+    W: Write,
+    P: Serialize,
+    R: Serialize,
+    S: Serialize,
+{
+    match api.spec_format {
+        SpecFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &Canonical(api))?;
+            writer.write_all(b"\n")?;
+        }
+        SpecFormat::Yaml => serde_yaml::to_writer(writer, &Canonical(api))?,
+    }
+
+    Ok(())
+}
+
+/// Serialize the spec into a string in the format (JSON or YAML) it was
+/// originally provided in (i.e., `Api.spec_format`).
+pub fn to_string<P, R, S>(api: &Api<P, R, S>) -> Result<String, PaperClipError>
+where
+    P: Serialize,
+    R: Serialize,
+    S: Serialize,
+{
+    let mut buf = vec![];
+    to_writer(api, &mut buf)?;
+    Ok(String::from_utf8(buf).expect("serializers emit UTF-8"))
+}
+
+/// Wrapper for serializing the spec in canonical form.
+struct Canonical<'a, P, R, S>(&'a Api<P, R, S>);
+
+impl<P, R, S> Serialize for Canonical<'_, P, R, S>
+where
+    P: Serialize,
+    R: Serialize,
+    S: Serialize,
+{
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        let api = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("swagger", &api.swagger)?;
+        map.serialize_entry("info", &api.info)?;
+        if let Some(host) = &api.host {
+            map.serialize_entry("host", host)?;
+        }
+        if let Some(path) = &api.base_path {
+            map.serialize_entry("basePath", path)?;
+        }
+        if !api.schemes.is_empty() {
+            map.serialize_entry("schemes", &api.schemes)?;
+        }
+        if !api.consumes.is_empty() {
+            map.serialize_entry("consumes", &api.consumes)?;
+        }
+        if !api.produces.is_empty() {
+            map.serialize_entry("produces", &api.produces)?;
+        }
+
+        map.serialize_entry("paths", &api.paths)?;
+        if !api.definitions.is_empty() {
+            map.serialize_entry("definitions", &api.definitions)?;
+        }
+        if !api.parameters.is_empty() {
+            map.serialize_entry("parameters", &api.parameters)?;
+        }
+        if !api.responses.is_empty() {
+            map.serialize_entry("responses", &api.responses)?;
+        }
+        if !api.security_definitions.is_empty() {
+            map.serialize_entry("securityDefinitions", &api.security_definitions)?;
+        }
+        if !api.security.is_empty() {
+            map.serialize_entry("security", &api.security)?;
+        }
+        if !api.tags.is_empty() {
+            map.serialize_entry("tags", &api.tags)?;
+        }
+        if let Some(docs) = &api.external_docs {
+            map.serialize_entry("externalDocs", docs)?;
+        }
+
+        if !api.coders.is_empty() {
+            map.serialize_entry("x-rust-coders", &api.coders)?;
+        }
+        if !api.support_crates.is_empty() {
+            map.serialize_entry("x-rust-dependencies", &api.support_crates)?;
+        }
+        for (key, value) in &api.extensions {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
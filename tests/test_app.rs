@@ -2245,6 +2245,124 @@ fn test_serde_skip() {
     );
 }
 
+#[test]
+fn test_serde_tagged_enum() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[serde(tag = "kind", rename_all = "lowercase")]
+    enum Shape {
+        Circle {
+            radius: f64,
+        },
+        Square {
+            side: f64,
+        },
+        #[serde(rename = "dot")]
+        Point,
+        #[serde(skip)]
+        #[allow(dead_code)]
+        Unknown,
+    }
+
+    #[post("/v0/shapes")]
+    #[api_v2_operation]
+    fn post_shape(
+        shape: web::Json<Shape>,
+    ) -> impl Future<Output = Result<web::Json<Shape>, Error>> {
+        futures::future::ready(Ok(shape))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(post_shape)
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                    "definitions": {
+                        "Shape": {
+                            "discriminator": "kind",
+                            "properties": {
+                                "kind": {
+                                    "enum": ["circle", "square", "dot"],
+                                    "type": "string"
+                                }
+                            },
+                            "required": ["kind"],
+                            "type": "object"
+                        },
+                        "circle": {
+                            "allOf": [
+                                {"$ref": "#/definitions/Shape"},
+                                {
+                                    "properties": {
+                                        "radius": {"format": "double", "type": "number"}
+                                    },
+                                    "required": ["radius"],
+                                    "type": "object"
+                                }
+                            ]
+                        },
+                        "dot": {
+                            "allOf": [{"$ref": "#/definitions/Shape"}]
+                        },
+                        "square": {
+                            "allOf": [
+                                {"$ref": "#/definitions/Shape"},
+                                {
+                                    "properties": {
+                                        "side": {"format": "double", "type": "number"}
+                                    },
+                                    "required": ["side"],
+                                    "type": "object"
+                                }
+                            ]
+                        }
+                    },
+                    "info": {
+                        "title": "",
+                        "version": ""
+                    },
+                    "paths": {
+                        "/v0/shapes": {
+                            "post": {
+                                "parameters": [
+                                    {
+                                        "in": "body",
+                                        "name": "body",
+                                        "required": true,
+                                        "schema": {
+                                            "$ref": "#/definitions/Shape"
+                                        }
+                                    }
+                                ],
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "$ref": "#/definitions/Shape"
+                                        }
+                                    }
+                                },
+                            }
+                        },
+                    },
+                    "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
 #[test]
 fn test_list_in_out() {
     #[derive(Serialize, Deserialize, Apiv2Schema)]
@@ -4792,6 +4910,81 @@ fn test_rename() {
     );
 }
 
+#[test]
+fn test_yaml_spec() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Pet {
+        name: String,
+    }
+
+    #[api_v2_operation]
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/pets").route(web::post().to(echo_pet)))
+                .with_yaml_spec_at("/api/spec.yaml")
+                .with_spec_at("/api/spec")
+                .build()
+        },
+        |addr| {
+            let get = |path: &str, accept: Option<&str>| {
+                let req = CLIENT.get(&format!("http://{}{}", addr, path));
+                let req = match accept {
+                    Some(accept) => req.header("Accept", accept),
+                    None => req,
+                };
+                let resp = req.send().expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 200);
+                let content_type = resp.headers()["content-type"].to_str().unwrap().to_owned();
+                (content_type, resp.text().unwrap())
+            };
+
+            let (content_type, body) = get("/api/spec.yaml", None);
+            assert_eq!(content_type, "application/yaml");
+            let spec: serde_json::Value = serde_yaml::from_str(&body).expect("yaml error");
+            assert_eq!(spec["swagger"], "2.0");
+            assert_eq!(
+                spec["definitions"]["Pet"],
+                json!({
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"],
+                    "type": "object"
+                })
+            );
+
+            for (accept, expected) in &[
+                (None, "application/json"),
+                (Some("application/json"), "application/json"),
+                (Some("*/*"), "application/json"),
+                (Some("application/yaml"), "application/yaml"),
+                (Some("text/html, application/x-yaml"), "application/yaml"),
+                (
+                    Some("application/json;q=0.5, application/yaml"),
+                    "application/yaml",
+                ),
+                (
+                    Some("application/yaml;q=0.5, application/json"),
+                    "application/json",
+                ),
+            ] {
+                let (content_type, negotiated) = get("/api/spec", *accept);
+                assert_eq!(content_type, *expected, "Accept: {:?}", accept);
+                let negotiated: serde_json::Value = if content_type == "application/yaml" {
+                    serde_yaml::from_str(&negotiated).unwrap()
+                } else {
+                    serde_json::from_str(&negotiated).unwrap()
+                };
+                assert_eq!(negotiated, spec);
+            }
+        },
+    );
+}
+
 #[test]
 fn test_example() {
     #[derive(Deserialize, Serialize, Apiv2Schema)]
@@ -0,0 +1,394 @@
+#[macro_use]
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+
+extern crate actix_web4 as actix_web;
+
+use actix_web::{rt::System, test, App};
+use paperclip::actix::{
+    api_v2_operation, api_v3_operation, web, Apiv2Schema, Apiv2Security, Apiv3Schema, OpenApiExt,
+};
+
+/// A pet.
+#[derive(Deserialize, Serialize, Apiv3Schema)]
+struct Pet {
+    /// Pick a good one.
+    name: String,
+    nickname: Option<String>,
+    class: PetClass,
+    #[openapi(nullable)]
+    owner: Owner,
+}
+
+#[derive(Deserialize, Serialize, Apiv3Schema)]
+#[serde(rename_all = "lowercase")]
+enum PetClass {
+    Dog,
+    Cat,
+}
+
+#[derive(Deserialize, Serialize, Apiv3Schema)]
+struct Owner {
+    name: String,
+}
+
+#[derive(Deserialize, Serialize, Apiv3Schema)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Event {
+    Adopted { by: String },
+    Lost,
+}
+
+#[derive(Deserialize, Serialize, Apiv3Schema)]
+#[serde(untagged)]
+enum PetOrName {
+    Pet(Pet),
+    Name(String),
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, Apiv3Schema)]
+struct PetFilter {
+    /// Filter by class.
+    class: Option<PetClass>,
+    limit: u32,
+}
+
+#[derive(Apiv2Security)]
+#[openapi(apiKey, in = "header", name = "Authorization")]
+struct AccessToken;
+
+impl actix_web::FromRequest for AccessToken {
+    type Error = actix_web::Error;
+    type Future = futures::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(_: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        futures::future::ok(AccessToken)
+    }
+}
+
+#[derive(Deserialize, Serialize, Apiv2Schema)]
+struct Legacy {
+    id: u64,
+}
+
+/// Adds a pet.
+#[api_v3_operation(
+    operation_id = "createPet",
+    consumes = "application/json, application/yaml",
+    request_body_description = "The pet to add",
+    servers("https://pets.example.com"),
+    cookie(name = "session", description = "Session ID", required),
+    link(
+        status = 200,
+        name = "GetPet",
+        operation_id = "getPet",
+        parameters = "id=$response.body#/name"
+    )
+)]
+async fn create_pet(_token: AccessToken, body: web::Json<Pet>) -> web::Json<Pet> {
+    body
+}
+
+#[api_v3_operation(operation_id = "getPet", tags(Pets))]
+async fn get_pet(_id: web::Path<u64>) -> web::Json<PetOrName> {
+    unimplemented!()
+}
+
+#[api_v3_operation]
+async fn list_events(_filter: web::Query<PetFilter>) -> web::Json<Vec<Event>> {
+    web::Json(vec![])
+}
+
+#[api_v2_operation]
+async fn legacy() -> web::Json<Legacy> {
+    web::Json(Legacy { id: 0 })
+}
+
+#[test]
+fn test_native_v3_spec() {
+    let _ = App::new()
+        .wrap_api()
+        .service(web::resource("/pets").route(web::post().to(create_pet)))
+        .service(
+            web::scope("/api")
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .route("/events", web::get().to(list_events)),
+        )
+        .route("/legacy", web::get().to(legacy))
+        .with_raw_json_spec_v3(|app, spec| {
+            assert_eq!(
+                spec["paths"]["/pets"]["post"],
+                json!({
+                    "operationId": "createPet",
+                    "summary": "Adds a pet.",
+                    "parameters": [{
+                        "description": "Session ID",
+                        "in": "cookie",
+                        "name": "session",
+                        "required": true,
+                        "schema": { "type": "string" },
+                        "style": "form"
+                    }],
+                    "requestBody": {
+                        "content": {
+                            "application/json": {
+                                "schema": { "$ref": "#/components/schemas/Pet" }
+                            },
+                            "application/yaml": {
+                                "schema": { "$ref": "#/components/schemas/Pet" }
+                            }
+                        },
+                        "description": "The pet to add",
+                        "required": true
+                    },
+                    "responses": {
+                        "200": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Pet" }
+                                }
+                            },
+                            "description": "OK",
+                            "links": {
+                                "GetPet": {
+                                    "operationId": "getPet",
+                                    "parameters": { "id": "$response.body#/name" }
+                                }
+                            }
+                        }
+                    },
+                    "security": [{ "apiKey": [] }],
+                    "servers": [{ "url": "https://pets.example.com" }]
+                })
+            );
+
+            assert_eq!(
+                spec["paths"]["/api/pets/{id}"]["get"]["parameters"],
+                json!([{
+                    "in": "path",
+                    "name": "id",
+                    "required": true,
+                    "schema": { "format": "int64", "type": "integer" },
+                    "style": "simple"
+                }])
+            );
+            assert_eq!(
+                spec["paths"]["/api/events"]["get"]["parameters"],
+                json!([{
+                    "description": "Filter by class.",
+                    "in": "query",
+                    "name": "class",
+                    "schema": {
+                        "allOf": [{ "$ref": "#/components/schemas/PetClass" }],
+                        "nullable": true
+                    },
+                    "style": "form"
+                }, {
+                    "in": "query",
+                    "name": "limit",
+                    "required": true,
+                    "schema": { "format": "int32", "type": "integer" },
+                    "style": "form"
+                }])
+            );
+
+            // v2 operations are still converted.
+            assert_eq!(
+                spec["paths"]["/legacy"]["get"]["responses"]["200"]["content"]["application/json"]
+                    ["schema"],
+                json!({ "$ref": "#/components/schemas/Legacy" })
+            );
+
+            let schemas = &spec["components"]["schemas"];
+            assert_eq!(
+                schemas["Pet"],
+                json!({
+                    "description": "A pet.",
+                    "properties": {
+                        "class": { "$ref": "#/components/schemas/PetClass" },
+                        "name": { "description": "Pick a good one.", "type": "string" },
+                        "nickname": { "nullable": true, "type": "string" },
+                        "owner": {
+                            "allOf": [{ "$ref": "#/components/schemas/Owner" }],
+                            "nullable": true
+                        }
+                    },
+                    "required": ["name", "class", "owner"],
+                    "type": "object"
+                })
+            );
+            assert_eq!(
+                schemas["PetClass"],
+                json!({ "enum": ["dog", "cat"], "type": "string" })
+            );
+            assert_eq!(
+                schemas["PetOrName"],
+                json!({
+                    "oneOf": [
+                        { "$ref": "#/components/schemas/Pet" },
+                        { "type": "string" }
+                    ]
+                })
+            );
+            assert_eq!(
+                schemas["Event"],
+                json!({
+                    "discriminator": { "propertyName": "kind" },
+                    "oneOf": [{
+                        "properties": {
+                            "by": { "type": "string" },
+                            "kind": { "enum": ["adopted"], "type": "string" }
+                        },
+                        "required": ["kind", "by"],
+                        "type": "object"
+                    }, {
+                        "properties": {
+                            "kind": { "enum": ["lost"], "type": "string" }
+                        },
+                        "required": ["kind"],
+                        "type": "object"
+                    }]
+                })
+            );
+            assert!(schemas["Owner"].is_object());
+            assert_eq!(
+                spec["components"]["securitySchemes"]["apiKey"],
+                json!({ "in": "header", "name": "Authorization", "type": "apiKey" })
+            );
+
+            app
+        })
+        .with_raw_json_spec(|app, spec| {
+            // Native operations are downgraded for the v2 spec.
+            let op = &spec["paths"]["/pets"]["post"];
+            assert_eq!(
+                op["consumes"],
+                json!(["application/json", "application/yaml"])
+            );
+            assert_eq!(
+                op["parameters"],
+                json!([{
+                    "description": "The pet to add",
+                    "in": "body",
+                    "name": "body",
+                    "required": true,
+                    "schema": { "$ref": "#/definitions/Pet" }
+                }])
+            );
+            assert_eq!(
+                spec["definitions"]["Pet"]["required"],
+                json!(["class", "name", "owner"])
+            );
+
+            app
+        })
+        .build();
+}
+
+#[test]
+fn test_v31_spec() {
+    let spec: serde_json::Value = System::new().block_on(async {
+        let app = test::init_service(
+            App::new()
+                .wrap_api()
+                .service(web::resource("/pets").route(web::post().to(create_pet)))
+                .route("/legacy", web::get().to(legacy))
+                .with_json_spec_v31_at("/api/spec/v3.1")
+                .build(),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/api/spec/v3.1").to_request();
+        test::call_and_read_body_json(&app, req).await
+    });
+
+    assert_eq!(spec["openapi"], "3.1.0");
+    let pet = &spec["components"]["schemas"]["Pet"];
+    assert_eq!(
+        pet["properties"]["nickname"],
+        json!({ "type": ["string", "null"] })
+    );
+    assert_eq!(
+        pet["properties"]["owner"],
+        json!({
+            "anyOf": [
+                { "$ref": "#/components/schemas/Owner" },
+                { "type": "null" }
+            ]
+        })
+    );
+    assert_eq!(
+        spec["paths"]["/legacy"]["get"]["responses"]["200"]["content"]["application/json"]
+            ["schema"],
+        json!({ "$ref": "#/components/schemas/Legacy" })
+    );
+    assert_eq!(
+        spec["paths"]["/pets"]["post"]["requestBody"]["description"],
+        "The pet to add"
+    );
+}
+
+#[test]
+fn test_yaml_spec_v3() {
+    System::new().block_on(async {
+        let app = test::init_service(
+            App::new()
+                .wrap_api()
+                .service(web::resource("/pets").route(web::post().to(create_pet)))
+                .with_json_spec_v3_at("/api/spec/v3.json")
+                .with_yaml_spec_v3_at("/api/spec/v3.yaml")
+                .with_spec_v3_at("/api/spec/v3")
+                .build(),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/spec/v3.json")
+            .to_request();
+        let spec: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(spec["openapi"], "3.0.0");
+        assert!(spec["components"]["schemas"]["Pet"].is_object());
+
+        let req = test::TestRequest::get()
+            .uri("/api/spec/v3.yaml")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/yaml"
+        );
+        assert!(resp.headers().get("vary").is_none());
+        let body = test::read_body(resp).await;
+        let yaml: serde_json::Value = serde_yaml::from_slice(&body).unwrap();
+        assert_eq!(yaml, spec);
+
+        let req = test::TestRequest::get()
+            .uri("/api/spec/v3")
+            .insert_header(("Accept", "application/yaml, application/json;q=0.9"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/yaml"
+        );
+        assert_eq!(resp.headers().get("vary").unwrap(), "Accept");
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            serde_yaml::from_slice::<serde_json::Value>(&body).unwrap(),
+            spec
+        );
+
+        let req = test::TestRequest::get().uri("/api/spec/v3").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            spec
+        );
+    });
+}
@@ -0,0 +1,201 @@
+use paperclip::v2::{
+    self,
+    bundle::{self, BundleMode},
+    models::{DefaultSchema, ResolvableApi, SpecFormat},
+};
+use serde_json::json;
+
+use std::io::Cursor;
+
+const DEEP_REFS_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Pods
+  version: \"1.0.0\"
+paths:
+  /pods:
+    post:
+      parameters:
+      - name: body
+        in: body
+        schema:
+          $ref: \"#/definitions/Pod/properties/spec\"
+      responses:
+        \"200\":
+          description: Containers
+          schema:
+            type: array
+            items:
+              $ref: \"#/definitions/Pod/properties/spec/properties/containers/items\"
+definitions:
+  Pod:
+    type: object
+    properties:
+      spec:
+        type: object
+        properties:
+          containers:
+            type: array
+            items:
+              type: object
+              properties:
+                name:
+                  type: string
+  PodSpec:
+    type: string
+";
+
+fn raw_api(spec: &[u8]) -> ResolvableApi<DefaultSchema> {
+    v2::from_reader(Cursor::new(spec)).expect("deserializing spec")
+}
+
+#[test]
+fn test_hoist_deep_refs() {
+    let api = bundle::bundle(&raw_api(DEEP_REFS_SPEC), BundleMode::Hoist).expect("bundling");
+    let value = serde_json::to_value(&api).unwrap();
+    let defs = &value["definitions"];
+
+    // `PodSpec` is taken, so the hoisted schema gets a suffix.
+    assert_eq!(
+        defs.as_object().unwrap().keys().collect::<Vec<_>>(),
+        vec!["Pod", "PodSpec", "PodSpec2", "PodSpec2ContainersItems"]
+    );
+    assert_eq!(
+        defs["Pod"]["properties"]["spec"],
+        json!({"$ref": "#/definitions/PodSpec2"})
+    );
+    assert_eq!(
+        defs["PodSpec2"]["properties"]["containers"]["items"],
+        json!({"$ref": "#/definitions/PodSpec2ContainersItems"})
+    );
+    assert_eq!(
+        defs["PodSpec2ContainersItems"]["properties"]["name"]["type"],
+        "string"
+    );
+
+    let op = &value["paths"]["/pods"]["post"];
+    assert_eq!(
+        op["parameters"][0]["schema"],
+        json!({"$ref": "#/definitions/PodSpec2"})
+    );
+    assert_eq!(
+        op["responses"]["200"]["schema"]["items"],
+        json!({"$ref": "#/definitions/PodSpec2ContainersItems"})
+    );
+
+    // Bundled spec can be resolved.
+    let api: ResolvableApi<DefaultSchema> = serde_json::from_value(value).unwrap();
+    api.resolve().expect("resolution");
+}
+
+#[test]
+fn test_hoist_external_refs() {
+    let api =
+        bundle::bundle_path("tests/multi-file/api.yaml", BundleMode::Hoist).expect("bundling");
+    assert_eq!(api.spec_format, SpecFormat::Yaml);
+    let value = serde_json::to_value(&api).unwrap();
+
+    assert_eq!(
+        value["definitions"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec!["CommonError", "Error", "Owner", "Pet"]
+    );
+    assert_eq!(
+        value["paths"]["/pets"]["get"]["parameters"][0],
+        json!({"$ref": "#/parameters/Limit"})
+    );
+    assert_eq!(
+        value["definitions"]["Owner"]["properties"]["pets"]["items"],
+        json!({"$ref": "#/definitions/Pet"})
+    );
+}
+
+#[test]
+fn test_inline_refs() {
+    let api = bundle::bundle(&raw_api(DEEP_REFS_SPEC), BundleMode::Inline).expect("bundling");
+    let value = serde_json::to_value(&api).unwrap();
+
+    let op = &value["paths"]["/pods"]["post"];
+    assert_eq!(
+        op["parameters"][0]["schema"],
+        value["definitions"]["Pod"]["properties"]["spec"]
+    );
+    assert_eq!(
+        op["responses"]["200"]["schema"]["items"]["properties"]["name"]["type"],
+        "string"
+    );
+}
+
+#[test]
+fn test_inline_cyclic_refs() {
+    let api =
+        bundle::bundle_path("tests/multi-file/api.yaml", BundleMode::Inline).expect("bundling");
+    let value = serde_json::to_value(&api).unwrap();
+
+    // Pet -> Owner -> Pet is a cycle, so we stop at the second Pet.
+    let pet = &value["paths"]["/pets"]["post"]["parameters"][0]["schema"];
+    let owner = &pet["properties"]["owner"];
+    assert_eq!(owner["properties"]["pets"]["type"], "array");
+    assert_eq!(
+        owner["properties"]["pets"]["items"],
+        json!({"$ref": "#/definitions/Pet"})
+    );
+
+    // Definitions themselves are the starting points.
+    assert_eq!(
+        value["definitions"]["Pet"]["properties"]["owner"]["properties"]["pets"]["items"],
+        json!({"$ref": "#/definitions/Pet"})
+    );
+
+    // Other references are gone.
+    assert!(value["paths"]["/pets"]["get"]["parameters"][0]["$ref"].is_null());
+    assert_eq!(
+        value["paths"]["/pets"]["get"]["parameters"][0]["name"],
+        "limit"
+    );
+    assert!(value["paths"]["/pets"]["get"]["responses"]["default"]["$ref"].is_null());
+}
+
+#[test]
+fn test_bundle_keeps_spec_format() {
+    let api = bundle::bundle(&raw_api(DEEP_REFS_SPEC), BundleMode::Hoist).expect("bundling");
+    let mut yaml = vec![];
+    v2::to_writer(&api, &mut yaml).unwrap();
+    assert!(yaml.starts_with(b"swagger: '2.0'\n"));
+
+    let api = bundle::bundle_raw(&api, BundleMode::Hoist).expect("bundling");
+    let mut out = vec![];
+    v2::to_writer(&api, &mut out).unwrap();
+    assert_eq!(out, yaml);
+
+    let json_spec = serde_json::to_vec(&serde_json::to_value(&api).unwrap()).unwrap();
+    let api = bundle::bundle(&raw_api(&json_spec), BundleMode::Hoist).expect("bundling");
+    assert_eq!(api.spec_format, SpecFormat::Json);
+    let mut out = vec![];
+    v2::to_writer(&api, &mut out).unwrap();
+    assert!(out.starts_with(b"{\n  \"swagger\": \"2.0\""));
+}
+
+#[test]
+fn test_bundle_external_ref_without_path() {
+    let api: ResolvableApi<DefaultSchema> = serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Pets", "version": "1.0"},
+        "paths": {},
+        "definitions": {
+            "Pet": {"$ref": "models/pet.json"}
+        }
+    }))
+    .unwrap();
+
+    let err = bundle::bundle(&api, BundleMode::Hoist)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        err,
+        "Invalid $ref URI \"models/pet.json\". Only relative URIs are supported."
+    );
+}
@@ -0,0 +1,310 @@
+use paperclip::v2::{
+    self,
+    diff::{self, ChangeKind},
+    models::{DefaultApiRaw, DefaultSchema, ResolvableApi},
+};
+
+use std::io::Cursor;
+
+const OLD_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Petstore
+  version: \"1.0.0\"
+paths:
+  /pets:
+    get:
+      parameters:
+      - name: status
+        in: query
+        type: string
+        enum: [available, pending, sold]
+      responses:
+        \"200\":
+          description: Pets
+          schema:
+            type: array
+            items:
+              $ref: \"#/definitions/Pet\"
+    post:
+      parameters:
+      - name: body
+        in: body
+        required: true
+        schema:
+          $ref: \"#/definitions/Pet\"
+      responses:
+        \"201\":
+          description: Created
+  /pets/{id}:
+    delete:
+      parameters:
+      - name: id
+        in: path
+        type: integer
+        required: true
+      responses:
+        \"204\":
+          description: Deleted
+  /stores/{storeId}:
+    get:
+      parameters:
+      - name: storeId
+        in: path
+        type: string
+        required: true
+      responses:
+        \"200\":
+          description: Store
+          schema:
+            type: string
+definitions:
+  Pet:
+    type: object
+    required: [name]
+    properties:
+      name:
+        type: string
+      tag:
+        type: string
+      kind:
+        type: string
+        enum: [cat, dog]
+";
+
+const NEW_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Petstore
+  version: \"2.0.0\"
+paths:
+  /pets:
+    get:
+      parameters:
+      - name: status
+        in: query
+        type: string
+        enum: [available, sold]
+      - name: limit
+        in: query
+        type: integer
+        required: true
+      responses:
+        \"200\":
+          description: Pets
+          schema:
+            type: array
+            items:
+              $ref: \"#/definitions/Pet\"
+    post:
+      parameters:
+      - name: body
+        in: body
+        required: true
+        schema:
+          $ref: \"#/definitions/Pet\"
+      - name: dryRun
+        in: query
+        type: boolean
+      responses:
+        \"201\":
+          description: Created
+  /stores/{store}:
+    get:
+      parameters:
+      - name: store
+        in: path
+        type: string
+        required: true
+      responses:
+        \"200\":
+          description: Store
+          schema:
+            type: object
+  /owners:
+    get:
+      responses:
+        \"200\":
+          description: Owners
+definitions:
+  Pet:
+    type: object
+    required: [name, tag]
+    properties:
+      name:
+        type: string
+      tag:
+        type: string
+      kind:
+        type: string
+        enum: [cat, dog, bird]
+";
+
+fn parse(spec: &'static [u8]) -> ResolvableApi<DefaultSchema> {
+    v2::from_reader(Cursor::new(spec)).expect("deserializing spec")
+}
+
+#[test]
+fn test_diff_classifies_changes() {
+    let report = diff::diff(&parse(OLD_SPEC), &parse(NEW_SPEC));
+    let changes = report
+        .changes
+        .iter()
+        .map(|c| (c.kind, c.breaking, c.path.as_str()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        changes,
+        vec![
+            (
+                ChangeKind::EnumValuesRemoved,
+                true,
+                "$.paths['/pets'].get.parameters[0]"
+            ),
+            (
+                ChangeKind::ParameterAdded,
+                true,
+                "$.paths['/pets'].get.parameters[1]"
+            ),
+            (
+                ChangeKind::EnumValuesAdded,
+                true,
+                "$.paths['/pets'].get.responses['200'].schema.items.properties.kind"
+            ),
+            (
+                ChangeKind::PropertyRequired,
+                false,
+                "$.paths['/pets'].get.responses['200'].schema.items.properties.tag"
+            ),
+            (
+                ChangeKind::EnumValuesAdded,
+                false,
+                "$.paths['/pets'].post.parameters[0].schema.properties.kind"
+            ),
+            (
+                ChangeKind::PropertyRequired,
+                true,
+                "$.paths['/pets'].post.parameters[0].schema.properties.tag"
+            ),
+            (
+                ChangeKind::ParameterAdded,
+                false,
+                "$.paths['/pets'].post.parameters[1]"
+            ),
+            (
+                ChangeKind::OperationRemoved,
+                true,
+                "$.paths['/pets/{id}'].delete"
+            ),
+            (
+                ChangeKind::TypeChanged,
+                true,
+                "$.paths['/stores/{store}'].get.responses['200'].schema"
+            ),
+            (ChangeKind::OperationAdded, false, "$.paths['/owners'].get"),
+        ]
+    );
+
+    assert!(report.has_breaking_changes());
+    assert_eq!(
+        report.changes[8].message,
+        "Type has changed from string to object"
+    );
+
+    let json = serde_json::to_value(&report.changes[0]).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "kind": "enum-values-removed",
+            "breaking": true,
+            "path": "$.paths['/pets'].get.parameters[0]",
+            "message": "Enum values \"pending\" were removed",
+        })
+    );
+}
+
+#[test]
+fn test_diff_same_spec() {
+    let report = diff::diff(&parse(OLD_SPEC), &parse(OLD_SPEC));
+    assert!(report.changes.is_empty());
+    assert!(!report.has_breaking_changes());
+}
+
+#[test]
+fn test_diff_raw_specs() {
+    let old: DefaultApiRaw = serde_yaml::from_slice(OLD_SPEC).unwrap();
+    let new: DefaultApiRaw = serde_yaml::from_slice(NEW_SPEC).unwrap();
+    let report = diff::diff_raw(&old, &new).expect("diff");
+    assert_eq!(report.breaking_changes().count(), 6);
+}
+
+const OLD_SHARED_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Petstore
+  version: \"1.0.0\"
+paths:
+  /pets/{id}:
+    get:
+      parameters:
+      - name: id
+        in: path
+        required: true
+        type: string
+      responses:
+        \"200\":
+          description: Pet
+          schema:
+            $ref: \"#/definitions/Pet\"
+  /pets/{id}/parent:
+    get:
+      parameters:
+      - name: id
+        in: path
+        required: true
+        type: string
+      responses:
+        \"200\":
+          description: Parent of the pet
+          schema:
+            $ref: \"#/definitions/Pet\"
+definitions:
+  Pet:
+    type: object
+    properties:
+      name:
+        type: string
+      tag:
+        type: string
+      parent:
+        $ref: \"#/definitions/Pet\"
+";
+
+#[test]
+fn test_diff_shared_schemas() {
+    let new_spec =
+        String::from_utf8_lossy(OLD_SHARED_SPEC).replace("      tag:\n        type: string\n", "");
+    let report = diff::diff(
+        &parse(OLD_SHARED_SPEC),
+        &v2::from_reader(Cursor::new(new_spec.into_bytes())).unwrap(),
+    );
+    let changes = report
+        .changes
+        .iter()
+        .map(|c| (c.kind, c.path.as_str()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        changes,
+        vec![
+            (
+                ChangeKind::PropertyRemoved,
+                "$.paths['/pets/{id}'].get.responses['200'].schema.properties.tag"
+            ),
+            (
+                ChangeKind::PropertyRemoved,
+                "$.paths['/pets/{id}/parent'].get.responses['200'].schema.properties.tag"
+            ),
+        ]
+    );
+}
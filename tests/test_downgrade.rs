@@ -0,0 +1,388 @@
+use paperclip::v3::{self, ConversionLoss};
+use serde_json::{json, Value};
+
+const SPEC: &str = r##"
+openapi: 3.0.3
+info:
+  title: Pet store
+  version: "1.0"
+x-owner: pets-team
+servers:
+  - url: https://{region}.example.com/api/v1/
+    variables:
+      region:
+        default: eu
+  - url: http://eu.example.com/api/v1
+  - url: https://staging.example.com/api
+security:
+  - bearer: []
+  - session: []
+paths:
+  /pets:
+    parameters:
+      - $ref: "#/components/parameters/Session"
+    get:
+      operationId: listPets
+      parameters:
+        - $ref: "#/components/parameters/Limit"
+        - name: tags
+          in: query
+          schema:
+            type: array
+            items:
+              type: string
+        - name: ids
+          in: query
+          style: pipeDelimited
+          explode: false
+          schema:
+            type: array
+            items:
+              type: integer
+      responses:
+        "200":
+          description: Pets
+          headers:
+            X-Rate-Limit:
+              schema:
+                type: integer
+          content:
+            application/json:
+              schema:
+                type: array
+                items:
+                  $ref: "#/components/schemas/Pet"
+            application/xml:
+              schema:
+                type: string
+        "4XX":
+          $ref: "#/components/responses/Error"
+    post:
+      operationId: addPet
+      x-codegen-request-body-name: pet
+      requestBody:
+        $ref: "#/components/requestBodies/Pet"
+      responses:
+        default:
+          $ref: "#/components/responses/Error"
+  /pets/{id}/photo:
+    put:
+      operationId: uploadPhoto
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      requestBody:
+        content:
+          multipart/form-data:
+            schema:
+              type: object
+              required: [file]
+              properties:
+                file:
+                  type: string
+                  format: binary
+                caption:
+                  type: string
+                  description: Photo caption
+      responses:
+        "204":
+          description: Uploaded
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      discriminator:
+        propertyName: kind
+        mapping:
+          dog: "#/components/schemas/Dog"
+      properties:
+        name:
+          type: string
+          nullable: true
+        kind:
+          type: string
+        password:
+          type: string
+          writeOnly: true
+        id:
+          oneOf:
+            - type: string
+              enum: [a, b]
+            - type: string
+              enum: [b, c]
+        owner:
+          anyOf:
+            - $ref: "#/components/schemas/Person"
+            - type: string
+    Dog:
+      allOf:
+        - $ref: "#/components/schemas/Pet"
+        - type: object
+          properties:
+            bark:
+              type: boolean
+    Person:
+      type: object
+      properties:
+        name:
+          type: string
+    Error:
+      type: object
+      properties:
+        message:
+          type: string
+  parameters:
+    Limit:
+      name: limit
+      in: query
+      schema:
+        type: integer
+        maximum: 100
+    Session:
+      name: session
+      in: cookie
+      schema:
+        type: string
+  responses:
+    Error:
+      description: Error
+      content:
+        application/json:
+          schema:
+            $ref: "#/components/schemas/Error"
+  requestBodies:
+    Pet:
+      required: true
+      content:
+        application/json:
+          schema:
+            $ref: "#/components/schemas/Pet"
+  securitySchemes:
+    bearer:
+      type: http
+      scheme: bearer
+    session:
+      type: apiKey
+      in: cookie
+      name: session
+    oauth:
+      type: oauth2
+      flows:
+        clientCredentials:
+          tokenUrl: https://example.com/token
+          scopes:
+            read: Read pets
+        authorizationCode:
+          authorizationUrl: https://example.com/auth
+          tokenUrl: https://example.com/token
+          scopes: {}
+"##;
+
+fn downgrade() -> (Value, v3::ConversionReport) {
+    let spec: openapiv3::OpenAPI = serde_yaml::from_str(SPEC).expect("parsing v3 spec");
+    let (api, report) = v3::openapiv3_to_v2(&spec).expect("downgrading spec");
+    (serde_json::to_value(&api).unwrap(), report)
+}
+
+#[test]
+fn test_servers_and_security() {
+    let (api, report) = downgrade();
+    assert_eq!(api["swagger"], "2.0");
+    assert_eq!(api["host"], "eu.example.com");
+    assert_eq!(api["basePath"], "/api/v1");
+    assert_eq!(api["schemes"], json!(["http", "https"]));
+    assert_eq!(api["x-owner"], "pets-team");
+
+    assert_eq!(
+        api["securityDefinitions"],
+        json!({
+            "bearer": {"type": "apiKey", "name": "Authorization", "in": "header"},
+            "oauth": {
+                "type": "oauth2",
+                "flow": "application",
+                "tokenUrl": "https://example.com/token",
+                "scopes": {"read": "Read pets"}
+            }
+        })
+    );
+    // Requirements for the (dropped) cookie scheme are left out.
+    assert_eq!(api["security"], json!([{"bearer": []}]));
+
+    let dropped: Vec<_> = report.dropped().map(|i| i.path.as_str()).collect();
+    assert!(dropped.contains(&"$.servers[2]"));
+    assert!(dropped.contains(&"$.components.securitySchemes.session"));
+    assert!(dropped.contains(&"$.components.securitySchemes.oauth.flows"));
+}
+
+#[test]
+fn test_schemas() {
+    let (api, _) = downgrade();
+    let pet = &api["definitions"]["Pet"];
+    assert_eq!(pet["discriminator"], "kind");
+    assert_eq!(
+        pet["x-discriminator-mapping"],
+        json!({"dog": "#/definitions/Dog"})
+    );
+
+    let props = &pet["properties"];
+    assert_eq!(props["name"]["x-nullable"], true);
+    assert_eq!(props["password"]["x-writeOnly"], true);
+    assert_eq!(props["id"]["type"], "string");
+    assert_eq!(props["id"]["enum"], json!(["a", "b", "c"]));
+    assert_eq!(props["id"]["x-oneOf"].as_array().unwrap().len(), 2);
+    assert!(props["owner"].get("type").is_none());
+    assert_eq!(
+        props["owner"]["x-anyOf"][0],
+        json!({"$ref": "#/definitions/Person"})
+    );
+    assert_eq!(
+        api["definitions"]["Dog"]["allOf"][0],
+        json!({"$ref": "#/definitions/Pet"})
+    );
+}
+
+#[test]
+fn test_operations() {
+    let (api, report) = downgrade();
+    assert_eq!(
+        api["parameters"],
+        json!({"Limit": {"name": "limit", "in": "query", "type": "integer", "maximum": 100.0}})
+    );
+
+    let pets = &api["paths"]["/pets"];
+    // Cookie parameters (and references to them) are dropped.
+    assert!(pets.get("parameters").is_none());
+
+    let list = &pets["get"];
+    assert_eq!(
+        list["parameters"],
+        json!([
+            {"$ref": "#/parameters/Limit"},
+            {"name": "tags", "in": "query", "type": "array", "items": {"type": "string"}, "collectionFormat": "multi"},
+            {"name": "ids", "in": "query", "type": "array", "items": {"type": "integer"}, "collectionFormat": "pipes"}
+        ])
+    );
+    assert_eq!(
+        list["produces"],
+        json!(["application/json", "application/xml"])
+    );
+    assert_eq!(
+        list["responses"]["200"]["headers"]["X-Rate-Limit"],
+        json!({"type": "integer"})
+    );
+    assert_eq!(
+        list["responses"]["200"]["schema"]["items"],
+        json!({"$ref": "#/definitions/Pet"})
+    );
+    assert!(list["responses"].get("4XX").is_none());
+
+    let add = &pets["post"];
+    assert_eq!(
+        add["parameters"],
+        json!([{
+            "name": "pet",
+            "in": "body",
+            "required": true,
+            "schema": {"$ref": "#/definitions/Pet"}
+        }])
+    );
+    assert_eq!(add["consumes"], json!(["application/json"]));
+    assert_eq!(
+        add["responses"]["default"],
+        json!({"$ref": "#/responses/Error"})
+    );
+
+    let upload = &api["paths"]["/pets/{id}/photo"]["put"];
+    assert_eq!(
+        upload["parameters"],
+        json!([
+            {"name": "id", "in": "path", "required": true, "type": "string"},
+            {"name": "caption", "in": "formData", "description": "Photo caption", "type": "string"},
+            {"name": "file", "in": "formData", "required": true, "type": "file"}
+        ])
+    );
+    assert_eq!(upload["consumes"], json!(["multipart/form-data"]));
+
+    let issue = report
+        .issues
+        .iter()
+        .find(|i| i.path == "$.paths['/pets'].get.responses['4XX']")
+        .unwrap();
+    assert_eq!(issue.kind, ConversionLoss::Dropped);
+    assert_eq!(
+        issue.to_string(),
+        "dropped: status code range \"4XX\" isn't supported in v2 (at $.paths['/pets'].get.responses['4XX'])"
+    );
+    assert!(report.approximated().any(|i| i.path
+        == "$.paths['/pets'].get.responses['200'].content"
+        && i.message.contains("application/xml")));
+}
+
+#[test]
+fn test_lossless() {
+    let spec: openapiv3::OpenAPI = serde_yaml::from_str(
+        r##"
+openapi: 3.0.0
+info:
+  title: Ping
+  version: "1.0"
+servers:
+  - url: https://example.com/v1
+paths:
+  /ping:
+    get:
+      responses:
+        "200":
+          description: Pong
+          content:
+            application/json:
+              schema:
+                type: string
+"##,
+    )
+    .unwrap();
+
+    let (api, report) = v3::openapiv3_to_v2(&spec).unwrap();
+    assert!(report.is_lossless(), "{:?}", report);
+    assert_eq!(api.host.as_deref(), Some("example.com"));
+    assert_eq!(api.base_path.as_deref(), Some("/v1"));
+}
+
+#[test]
+fn test_percent_encoded_references() {
+    let spec: openapiv3::OpenAPI = serde_yaml::from_str(
+        r##"
+openapi: 3.0.3
+info:
+  title: Pet store
+  version: "1.0"
+paths:
+  /pets:
+    post:
+      requestBody:
+        $ref: "#/components/requestBodies/New%20Pet"
+      responses:
+        "204":
+          description: Created
+components:
+  requestBodies:
+    New Pet:
+      required: true
+      content:
+        application/json:
+          schema:
+            type: string
+"##,
+    )
+    .expect("parsing v3 spec");
+    let (api, _) = v3::openapiv3_to_v2(&spec).expect("downgrading spec");
+    let api = serde_json::to_value(&api).unwrap();
+
+    let params = &api["paths"]["/pets"]["post"]["parameters"];
+    assert_eq!(params[0]["in"], "body");
+    assert_eq!(params[0]["schema"], json!({"type": "string"}));
+}
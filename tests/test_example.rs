@@ -0,0 +1,185 @@
+use paperclip::v2::{
+    self,
+    example::Synthesizer,
+    models::{DefaultSchema, DefaultSchemaRaw, ResolvableApi},
+};
+use serde_json::{json, Value};
+
+const SPEC: &str = r##"swagger: "2.0"
+info:
+  title: Pets
+  version: "1.0"
+paths: {}
+definitions:
+  Pet:
+    type: object
+    discriminator: kind
+    required: [id, kind, name]
+    properties:
+      id:
+        type: string
+        format: uuid
+      kind:
+        type: string
+      name:
+        type: string
+        minLength: 12
+        maxLength: 16
+      status:
+        type: string
+        enum: [available, sold]
+      age:
+        type: integer
+        format: int32
+        minimum: 1
+        maximum: 5
+        exclusiveMaximum: true
+      weight:
+        type: number
+        minimum: 1.5
+        maximum: 2.5
+        multipleOf: 0.5
+      born:
+        type: string
+        format: date-time
+      address:
+        type: string
+        format: ipv4
+      tags:
+        type: array
+        minItems: 2
+        maxItems: 3
+        uniqueItems: true
+        items:
+          type: string
+      note:
+        type: string
+        example: Good boy
+      owner:
+        $ref: "#/definitions/Person"
+  Person:
+    type: object
+    required: [name, pets]
+    properties:
+      name:
+        type: string
+      friend:
+        $ref: "#/definitions/Person"
+      pets:
+        type: array
+        items:
+          $ref: "#/definitions/Pet"
+  Dog:
+    allOf:
+    - $ref: "#/definitions/Pet"
+    - type: object
+      properties:
+        barks:
+          type: boolean
+"##;
+
+fn api() -> ResolvableApi<DefaultSchema> {
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_reader(SPEC.as_bytes()).expect("deserializing spec");
+    api.resolve().expect("resolution")
+}
+
+#[test]
+fn test_example_honors_schema() {
+    let api = api();
+    for seed in 0..32 {
+        let pet = Synthesizer::new(seed).generate(&api.definitions["Pet"]);
+
+        let id = pet["id"].as_str().unwrap();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert_eq!(pet["kind"], "Pet");
+        let name = pet["name"].as_str().unwrap();
+        assert!(name.len() >= 12 && name.len() <= 16, "{}", name);
+        assert!(pet["status"] == "available" || pet["status"] == "sold");
+
+        let age = pet["age"].as_i64().unwrap();
+        assert!((1..5).contains(&age), "{}", age);
+        let weight = pet["weight"].as_f64().unwrap();
+        assert!([1.5, 2.0, 2.5].contains(&weight), "{}", weight);
+
+        let born = pet["born"].as_str().unwrap();
+        assert_eq!(born.len(), 20);
+        assert!(born.starts_with("202") && born.ends_with('Z'), "{}", born);
+        let octets: Vec<u8> = pet["address"]
+            .as_str()
+            .unwrap()
+            .split('.')
+            .map(|o| o.parse().unwrap())
+            .collect();
+        assert_eq!(octets.len(), 4);
+
+        let tags = pet["tags"].as_array().unwrap();
+        assert!(tags.len() >= 2 && tags.len() <= 3);
+        assert!(tags.iter().skip(1).all(|t| t != &tags[0]));
+        assert_eq!(pet["note"], "Good boy");
+        assert!(pet["owner"]["name"].is_string());
+    }
+}
+
+#[test]
+fn test_example_is_deterministic() {
+    let api = api();
+    let generate = |seed| Synthesizer::new(seed).generate(&api.definitions["Pet"]);
+
+    assert_eq!(generate(7), generate(7));
+    assert!((0..8).any(|seed| generate(seed) != generate(7)));
+}
+
+#[test]
+fn test_example_stops_at_cycles() {
+    let api = api();
+    let person = Synthesizer::new(1).generate(&api.definitions["Person"]);
+
+    // Optional cyclic properties are skipped and required ones are emptied.
+    assert!(person.get("friend").is_none());
+    let pets = person["pets"].as_array().unwrap();
+    assert!(!pets.is_empty());
+    assert!(pets[0].get("owner").is_none());
+
+    let pet = Synthesizer::new(1)
+        .optional_properties(false)
+        .generate(&api.definitions["Pet"]);
+    let mut keys: Vec<_> = pet.as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["id", "kind", "name"]);
+}
+
+#[test]
+fn test_example_subtypes() {
+    let api = api();
+    let dog = Synthesizer::new(3).generate(&api.definitions["Dog"]);
+
+    assert_eq!(dog["kind"], "Dog");
+    assert!(dog["barks"].is_boolean());
+    assert!(dog["id"].is_string());
+}
+
+#[test]
+fn test_example_raw_schema() {
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "type": "object",
+        "required": ["count"],
+        "properties": {
+            "count": {"type": "integer", "minimum": 10, "maximum": 10},
+            "ratio": {"type": "number", "default": 0.5},
+            "labels": {
+                "type": "object",
+                "additionalProperties": {"type": "boolean"}
+            },
+            "parent": {"$ref": "#/definitions/Node"}
+        }
+    }))
+    .unwrap();
+    let value = Synthesizer::new(0).generate_raw(&schema).unwrap();
+
+    assert_eq!(value["count"], 10);
+    assert_eq!(value["ratio"], 0.5);
+    assert!(value["labels"]["additionalProp1"].is_boolean());
+    assert_eq!(value.get("parent"), None::<&Value>);
+}
@@ -0,0 +1,248 @@
+use paperclip::v2::{
+    filter::{self, Filter},
+    models::{DefaultSchema, ResolvableApi},
+};
+use serde_json::json;
+
+fn cluster_spec() -> ResolvableApi<DefaultSchema> {
+    serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Cluster", "version": "1.0"},
+        "paths": {
+            "/api/v1/namespaces/{namespace}/pods": {
+                "parameters": [{"$ref": "#/parameters/namespace"}],
+                "get": {
+                    "operationId": "listNamespacedPod",
+                    "tags": ["core_v1"],
+                    "responses": {
+                        "200": {"description": "Pods", "schema": {"$ref": "#/definitions/PodList"}}
+                    }
+                },
+                "post": {
+                    "operationId": "createNamespacedPod",
+                    "tags": ["core_v1"],
+                    "parameters": [{"$ref": "#/parameters/body"}],
+                    "responses": {"default": {"$ref": "#/responses/Status"}}
+                },
+                "delete": {
+                    "operationId": "deleteCollectionNamespacedPod",
+                    "tags": ["core_v1"],
+                    "responses": {"default": {"$ref": "#/responses/Status"}}
+                }
+            },
+            "/api/v1/namespaces/{namespace}/pods/{name}/log": {
+                "get": {
+                    "operationId": "readNamespacedPodLog",
+                    "tags": ["core_v1"],
+                    "responses": {"200": {"description": "Log", "schema": {"type": "string"}}}
+                }
+            },
+            "/apis/apps/v1/deployments": {
+                "get": {
+                    "operationId": "listDeployment",
+                    "tags": ["apps_v1"],
+                    "responses": {
+                        "200": {
+                            "description": "Deployments",
+                            "schema": {
+                                "type": "array",
+                                "items": {"$ref": "#/definitions/Deployment/properties/spec"}
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "parameters": {
+            "body": {"name": "body", "in": "body", "schema": {"$ref": "#/definitions/Pod"}},
+            "namespace": {"name": "namespace", "in": "path", "required": true, "type": "string"},
+            "pretty": {"name": "pretty", "in": "query", "type": "string"}
+        },
+        "responses": {
+            "Status": {"description": "Status", "schema": {"$ref": "#/definitions/Status"}},
+            "NotFound": {"description": "Not found"}
+        },
+        "definitions": {
+            "Container": {"type": "object", "properties": {"name": {"type": "string"}}},
+            "Deployment": {
+                "type": "object",
+                "properties": {
+                    "spec": {
+                        "type": "object",
+                        "properties": {"template": {"$ref": "#/definitions/PodTemplate"}}
+                    }
+                }
+            },
+            "Pod": {
+                "type": "object",
+                "properties": {
+                    "containers": {"type": "array", "items": {"$ref": "#/definitions/Container"}},
+                    "owner": {"$ref": "#/definitions/Pod"}
+                }
+            },
+            "PodList": {
+                "type": "object",
+                "properties": {"items": {"type": "array", "items": {"$ref": "#/definitions/Pod"}}}
+            },
+            "PodTemplate": {"type": "object", "properties": {"name": {"type": "string"}}},
+            "Status": {"type": "object", "properties": {"message": {"type": "string"}}}
+        }
+    }))
+    .unwrap()
+}
+
+fn keys(value: &serde_json::Value) -> Vec<&String> {
+    value
+        .as_object()
+        .map(|o| o.keys().collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_filter_by_tag() {
+    let mut api = cluster_spec();
+    filter::filter(&mut api, Filter::default().include_tag("core_v1"));
+    let value = serde_json::to_value(&api).unwrap();
+
+    assert_eq!(
+        keys(&value["paths"]),
+        vec![
+            "/api/v1/namespaces/{namespace}/pods",
+            "/api/v1/namespaces/{namespace}/pods/{name}/log"
+        ]
+    );
+    assert_eq!(
+        keys(&value["definitions"]),
+        vec!["Container", "Pod", "PodList", "Status"]
+    );
+    assert_eq!(keys(&value["parameters"]), vec!["body", "namespace"]);
+    assert_eq!(keys(&value["responses"]), vec!["Status"]);
+
+    // Filtered spec can be resolved.
+    api.resolve().expect("resolution");
+}
+
+#[test]
+fn test_filter_by_path_and_operation() {
+    let mut api = cluster_spec();
+    filter::filter(
+        &mut api,
+        Filter::default()
+            .include_path("/api/v1/namespaces/*/pods")
+            .include_operation("list*")
+            .exclude_operation("*Collection*")
+            .exclude_tag("ignored"),
+    );
+    let value = serde_json::to_value(&api).unwrap();
+
+    let pods = &value["paths"]["/api/v1/namespaces/{namespace}/pods"];
+    assert_eq!(keys(pods), vec!["get", "parameters", "post"]);
+    assert_eq!(
+        keys(&value["paths"]),
+        vec![
+            "/api/v1/namespaces/{namespace}/pods",
+            "/apis/apps/v1/deployments"
+        ]
+    );
+
+    // Deep references keep the root definition and whatever it refers to.
+    assert_eq!(
+        keys(&value["definitions"]),
+        vec![
+            "Container",
+            "Deployment",
+            "Pod",
+            "PodList",
+            "PodTemplate",
+            "Status"
+        ]
+    );
+}
+
+#[test]
+fn test_filter_path_globs() {
+    let mut api = cluster_spec();
+    filter::filter(
+        &mut api,
+        Filter::default()
+            .include_path("/api/**/l?g")
+            .exclude_path("/apis/**"),
+    );
+    let value = serde_json::to_value(&api).unwrap();
+
+    assert_eq!(
+        keys(&value["paths"]),
+        vec!["/api/v1/namespaces/{namespace}/pods/{name}/log"]
+    );
+    assert!(keys(&value["definitions"]).is_empty());
+    assert!(keys(&value["parameters"]).is_empty());
+    assert!(keys(&value["responses"]).is_empty());
+
+    let mut api = cluster_spec();
+    filter::filter(&mut api, Filter::default().include_path("/api/*"));
+    let value = serde_json::to_value(&api).unwrap();
+    assert!(keys(&value["paths"]).is_empty());
+}
+
+#[test]
+fn test_filter_keeps_discriminator_subtypes() {
+    let mut api: ResolvableApi<DefaultSchema> = serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Zoo", "version": "1.0"},
+        "paths": {
+            "/pets": {
+                "get": {
+                    "responses": {
+                        "200": {"description": "Pet", "schema": {"$ref": "#/definitions/Pet"}}
+                    }
+                }
+            }
+        },
+        "definitions": {
+            "Cat": {
+                "allOf": [
+                    {"$ref": "#/definitions/Pet"},
+                    {"properties": {"toy": {"$ref": "#/definitions/Toy"}}}
+                ]
+            },
+            "Dog": {"allOf": [{"$ref": "#/definitions/Pet"}]},
+            "Pet": {
+                "discriminator": "kind",
+                "required": ["kind"],
+                "properties": {"kind": {"type": "string"}}
+            },
+            "Robot": {"allOf": [{"$ref": "#/definitions/Machine"}]},
+            "Machine": {"properties": {"model": {"type": "string"}}},
+            "Toy": {"type": "string"}
+        }
+    }))
+    .unwrap();
+
+    filter::filter(&mut api, Filter::default().include_path("/pets"));
+    let value = serde_json::to_value(&api).unwrap();
+    assert_eq!(
+        keys(&value["definitions"]),
+        vec!["Cat", "Dog", "Pet", "Toy"]
+    );
+}
+
+#[test]
+fn test_filter_glob_does_not_backtrack() {
+    let mut api = cluster_spec();
+    let id = "a".repeat(64);
+    let pattern = "*a*a*a*a*a*a*a*a*a*a*a*a*b";
+    api.paths
+        .values_mut()
+        .flat_map(|map| map.methods.values_mut())
+        .for_each(|op| op.operation_id = Some(id.clone()));
+
+    let start = std::time::Instant::now();
+    filter::filter(
+        &mut api,
+        Filter::default()
+            .include_operation(pattern)
+            .include_path(format!("/**{}", pattern)),
+    );
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    assert!(api.paths.is_empty());
+}
@@ -0,0 +1,154 @@
+use paperclip::v2::{
+    self,
+    graph::SchemaGraph,
+    models::{DefaultSchema, ResolvableApi},
+    Schema,
+};
+
+use std::io::Cursor;
+
+const SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Tree
+  version: \"1.0.0\"
+paths:
+  /nodes:
+    get:
+      responses:
+        \"200\":
+          description: Nodes
+          schema:
+            type: array
+            items:
+              $ref: \"#/definitions/Node\"
+definitions:
+  Node:
+    type: object
+    properties:
+      children:
+        type: array
+        items:
+          $ref: \"#/definitions/Node\"
+      owner:
+        $ref: \"#/definitions/Owner\"
+  Owner:
+    type: object
+    properties:
+      name:
+        type: string
+      pets:
+        type: array
+        items:
+          $ref: \"#/definitions/Pet\"
+  Pet:
+    type: object
+    properties:
+      owner:
+        $ref: \"#/definitions/Owner\"
+      extra:
+        type: object
+        additionalProperties: true
+  Label:
+    type: object
+    properties:
+      value:
+        type: string
+";
+
+#[paperclip::api_v2_schema]
+#[allow(dead_code)]
+#[derive(Debug, serde::Deserialize)]
+struct CustomSchema {
+    #[serde(rename = "x-kubernetes-patch-strategy")]
+    patch_strategy: Option<String>,
+}
+
+fn resolved<S>(spec: &[u8]) -> ResolvableApi<S>
+where
+    for<'de> S: serde::Deserialize<'de> + Schema + Default,
+{
+    let api: ResolvableApi<S> = v2::from_reader(Cursor::new(spec)).expect("deserializing spec");
+    api.resolve().expect("resolution")
+}
+
+#[test]
+fn test_graph_nodes_and_cycles() {
+    let api = resolved::<DefaultSchema>(SPEC);
+    let graph = SchemaGraph::new(&api);
+
+    let node = graph.definition("Node").unwrap();
+    let owner = graph.definition("Owner").unwrap();
+    let pet = graph.definition("Pet").unwrap();
+    let label = graph.definition("Label").unwrap();
+
+    // References point to the definitions' nodes.
+    let children = graph.node(node).properties["children"];
+    assert_eq!(graph.node(children).items, Some(node));
+    assert_eq!(graph.node(node).properties["owner"], owner);
+    assert_eq!(graph.node(pet).name.as_deref(), Some("Pet"));
+
+    // Self references and mutual references are cycles.
+    assert!(graph.in_cycle(node));
+    assert!(graph.in_cycle(owner));
+    assert!(graph.in_cycle(pet));
+    assert!(!graph.in_cycle(label));
+    assert!(!graph.in_cycle(graph.node(owner).properties["name"]));
+
+    // Each schema is visited once.
+    assert_eq!(graph.nodes().count(), graph.len());
+    assert_eq!(
+        graph.nodes().filter(|(_, n)| n.name.is_some()).count(),
+        graph.definitions().len()
+    );
+}
+
+#[test]
+fn test_graph_contains_any() {
+    let api = resolved::<DefaultSchema>(SPEC);
+    let graph = SchemaGraph::new(&api);
+
+    for (name, lock) in &api.definitions {
+        let schema = lock.read().unwrap();
+        let id = graph.id_of(&*schema).unwrap();
+        assert_eq!(graph.definition(name), Some(id));
+        assert!(std::sync::Arc::ptr_eq(graph.schema(id), lock));
+        assert_eq!(graph.contains_any(id), schema.contains_any(), "{}", name);
+    }
+
+    assert!(graph.contains_any(graph.definition("Pet").unwrap()));
+    assert!(graph.contains_any(graph.definition("Node").unwrap()));
+    assert!(!graph.contains_any(graph.definition("Label").unwrap()));
+}
+
+#[test]
+fn test_graph_custom_schema() {
+    let spec = String::from_utf8(SPEC.to_vec()).unwrap().replace(
+        "  Label:\n    type: object\n",
+        "  Label:\n    type: object\n    x-kubernetes-patch-strategy: merge\n",
+    );
+    let api = resolved::<CustomSchema>(spec.as_bytes());
+    let graph = SchemaGraph::new(&api);
+
+    let label = api.definitions["Label"].read().unwrap();
+    assert_eq!(label.patch_strategy.as_deref(), Some("merge"));
+    let id = graph.id_of(&*label).unwrap();
+    assert_eq!(graph.node(id).name.as_deref(), Some("Label"));
+    assert!(graph.in_cycle(graph.definition("Node").unwrap()));
+}
+
+#[test]
+fn test_graph_outlives_spec() {
+    let api = resolved::<DefaultSchema>(SPEC);
+    let graph = SchemaGraph::new(&api);
+    let len = graph.len();
+    drop(api);
+
+    // Schemas allocated afterwards can't take the place of the graph's schemas.
+    let schemas = (0..4 * len)
+        .map(|_| std::sync::Arc::new(std::sync::RwLock::new(DefaultSchema::default())))
+        .collect::<Vec<_>>();
+    for schema in &schemas {
+        assert_eq!(graph.id_of(&*schema.read().unwrap()), None);
+    }
+}
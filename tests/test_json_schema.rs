@@ -0,0 +1,218 @@
+use paperclip::v2::{
+    json_schema::{self, Exporter, DRAFT_2020_12},
+    models::{DefaultApiRaw, DefaultParameterRaw, DefaultSchemaRaw},
+};
+use serde_json::json;
+
+fn api() -> DefaultApiRaw {
+    serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Pets", "version": "1.0"},
+        "paths": {},
+        "definitions": {
+            "Pet": {
+                "type": "object",
+                "description": "A pet",
+                "discriminator": "kind",
+                "required": ["kind", "name"],
+                "properties": {
+                    "kind": {"type": "string"},
+                    "name": {"type": "string", "example": "Milo", "x-order": 1},
+                    "age": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 30,
+                        "exclusiveMaximum": true,
+                        "exclusiveMinimum": false
+                    },
+                    "photo": {"type": "file"},
+                    "nickname": {"type": "string", "x-nullable": true},
+                    "owner": {"$ref": "#/definitions/Person"},
+                    "parent": {"$ref": "#/definitions/Pet"}
+                },
+                "xml": {"name": "pet"}
+            },
+            "Person": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "address": {"$ref": "#/definitions/Address"}
+                }
+            },
+            "Address": {
+                "type": "object",
+                "additionalProperties": {"type": "string", "format": "byte"}
+            }
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_definition_documents() {
+    let api = api();
+    let exporter = Exporter::from_api(&api).base_uri("https://example.com/schemas/");
+    let docs = exporter.definitions().unwrap();
+    assert_eq!(docs.len(), 3);
+
+    assert_eq!(
+        docs["Pet"],
+        json!({
+            "$schema": DRAFT_2020_12,
+            "$id": "https://example.com/schemas/Pet.json",
+            "title": "Pet",
+            "type": "object",
+            "description": "A pet",
+            "required": ["kind", "name"],
+            "properties": {
+                "kind": {"type": "string"},
+                "name": {"type": "string", "examples": ["Milo"]},
+                "age": {"type": "integer", "minimum": 0.0, "exclusiveMaximum": 30.0},
+                "photo": {"type": "string", "contentMediaType": "application/octet-stream"},
+                "nickname": {"type": ["string", "null"]},
+                "owner": {"$ref": "#/$defs/Person"},
+                "parent": {"$ref": "#"}
+            },
+            "$defs": {
+                "Person": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "address": {"$ref": "#/$defs/Address"}
+                    }
+                },
+                "Address": {
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "string",
+                        "format": "byte",
+                        "contentEncoding": "base64"
+                    }
+                }
+            }
+        })
+    );
+    assert!(docs["Address"].get("$defs").is_none());
+}
+
+#[test]
+fn test_bundle_and_extensions() {
+    let api = api();
+    let bundle = Exporter::from_api(&api)
+        .keep_extensions(true)
+        .bundle()
+        .unwrap();
+
+    assert_eq!(bundle["$schema"], DRAFT_2020_12);
+    assert_eq!(bundle.get("$id"), None);
+    let defs = bundle["$defs"].as_object().unwrap();
+    assert_eq!(defs.len(), 3);
+    assert_eq!(defs["Pet"]["properties"]["parent"]["$ref"], "#/$defs/Pet");
+    assert_eq!(defs["Pet"]["properties"]["name"]["x-order"], 1);
+    assert_eq!(
+        defs["Pet"]["properties"]["nickname"].get("x-nullable"),
+        None
+    );
+}
+
+#[test]
+fn test_schema_and_parameter_documents() {
+    let api = api();
+    let exporter = Exporter::from_api(&api);
+
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "type": "array",
+        "items": {"$ref": "#/definitions/Address"}
+    }))
+    .unwrap();
+    let doc = exporter.schema(&schema).unwrap();
+    assert_eq!(doc["items"]["$ref"], "#/$defs/Address");
+    assert_eq!(doc["$defs"]["Address"]["type"], "object");
+
+    let param: DefaultParameterRaw = serde_json::from_value(json!({
+        "name": "tags",
+        "in": "query",
+        "required": true,
+        "description": "Tags to filter by",
+        "type": "array",
+        "collectionFormat": "csv",
+        "items": {"type": "string", "enum": ["cat", "dog"]}
+    }))
+    .unwrap();
+    assert_eq!(
+        exporter.parameter(&param).unwrap(),
+        json!({
+            "$schema": DRAFT_2020_12,
+            "description": "Tags to filter by",
+            "type": "array",
+            "items": {"type": "string", "enum": ["cat", "dog"]}
+        })
+    );
+
+    let missing: DefaultSchemaRaw =
+        serde_json::from_value(json!({"$ref": "#/definitions/Tag"})).unwrap();
+    assert_eq!(
+        exporter.schema(&missing).unwrap_err().to_string(),
+        "Reference missing in spec: Tag"
+    );
+    assert_eq!(
+        json_schema::file_name("io.k8s/Pod v1"),
+        "io.k8s_Pod_v1.json"
+    );
+}
+
+#[test]
+fn test_percent_encoded_references() {
+    let api: DefaultApiRaw = serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Pets", "version": "1.0"},
+        "paths": {},
+        "definitions": {
+            "Pet Owner": {"type": "object", "properties": {"name": {"type": "string"}}}
+        }
+    }))
+    .unwrap();
+    let exporter = Exporter::from_api(&api);
+
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "type": "array",
+        "items": {"$ref": "#/definitions/Pet%20Owner"}
+    }))
+    .unwrap();
+    let doc = exporter.schema(&schema).unwrap();
+    assert_eq!(doc["items"]["$ref"], "#/$defs/Pet%20Owner");
+    assert_eq!(doc["$defs"]["Pet Owner"]["type"], "object");
+}
+
+#[test]
+fn test_file_name_collisions() {
+    let api: DefaultApiRaw = serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Pets", "version": "1.0"},
+        "paths": {},
+        "definitions": {
+            "Page<Pet>": {"type": "object"},
+            "Page_Pet_": {"type": "object"},
+            "Pet": {"type": "object"}
+        }
+    }))
+    .unwrap();
+
+    let exporter = Exporter::from_api(&api);
+    assert_eq!(
+        exporter.definitions().unwrap_err().to_string(),
+        "Definitions \"Page<Pet>\" and \"Page_Pet_\" have the same file name \"Page_Pet_.json\""
+    );
+    // Without a base URI, the documents of individual definitions don't have IDs.
+    assert!(exporter.definition("Page_Pet_").is_ok());
+
+    let exporter = exporter.base_uri("https://example.com/");
+    assert_eq!(
+        exporter.definition("Page_Pet_").unwrap_err().to_string(),
+        "Definitions \"Page_Pet_\" and \"Page<Pet>\" have the same file name \"Page_Pet_.json\""
+    );
+    assert_eq!(
+        exporter.definition("Pet").unwrap()["$id"],
+        "https://example.com/Pet.json"
+    );
+}
@@ -0,0 +1,200 @@
+use paperclip::v2::{
+    self,
+    lint::{self, Diagnostic, LintConfig, Rule, RuleLevel, Severity},
+    models::{DefaultSchema, ResolvableApi},
+};
+
+use std::io::Cursor;
+
+const SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Petstore
+  version: \"1.0.0\"
+paths:
+  /pets/{petId}:
+    get:
+      operationId: getPet
+      description: Get a pet
+      parameters:
+      - name: id
+        in: path
+        description: ID of pet
+        type: string
+        required: true
+      - name: body
+        in: body
+        description: Filter
+        schema:
+          $ref: \"#/definitions/Filter\"
+      responses:
+        \"200\":
+          description: Pet
+          schema:
+            $ref: \"#/definitions/Pet\"
+  /pets:
+    post:
+      operationId: getPet
+      summary: Add a pet
+      parameters:
+      - $ref: \"#/parameters/Tags\"
+      responses:
+        \"201\":
+          description: Added
+          headers:
+            X-Ids:
+              type: string
+              collectionFormat: pipes
+parameters:
+  Tags:
+    name: tags
+    in: header
+    description: Pet tags
+    type: array
+    collectionFormat: multi
+    items:
+      type: string
+definitions:
+  Pet:
+    description: A pet
+    type: object
+    properties:
+      owner:
+        $ref: \"#/definitions/Owner\"
+  Owner:
+    type: object
+  Filter:
+    description: A filter
+    type: object
+  Unused:
+    description: Not used anywhere
+    type: object
+";
+
+fn parse() -> ResolvableApi<DefaultSchema> {
+    v2::from_reader(Cursor::new(SPEC)).expect("deserializing spec")
+}
+
+fn diagnostic(severity: Severity, rule: Rule, path: &str, message: &str) -> Diagnostic {
+    Diagnostic {
+        severity,
+        rule,
+        path: path.into(),
+        message: message.into(),
+    }
+}
+
+#[test]
+fn test_lint_default_rules() {
+    let diagnostics = lint::lint(&parse(), &LintConfig::default());
+    assert_eq!(
+        diagnostics,
+        vec![
+            diagnostic(
+                Severity::Error,
+                Rule::InvalidCollectionFormat,
+                "$.paths['/pets'].post.responses['201'].headers['X-Ids'].collectionFormat",
+                "Header \"X-Ids\" has collectionFormat, but it's not an array",
+            ),
+            diagnostic(
+                Severity::Error,
+                Rule::DuplicateOperationId,
+                "$.paths['/pets/{petId}'].get.operationId",
+                "Operation ID \"getPet\" is already used by $.paths['/pets'].post",
+            ),
+            diagnostic(
+                Severity::Error,
+                Rule::UnknownPathParameter,
+                "$.paths['/pets/{petId}'].get.parameters[0]",
+                "Path parameter \"id\" is not in the path template",
+            ),
+            diagnostic(
+                Severity::Warning,
+                Rule::UnexpectedBody,
+                "$.paths['/pets/{petId}'].get.parameters[1]",
+                "Body parameter in GET operation",
+            ),
+            diagnostic(
+                Severity::Error,
+                Rule::MissingPathParameter,
+                "$.paths['/pets/{petId}'].get",
+                "Parameter \"petId\" in path template isn't defined as a path parameter",
+            ),
+            diagnostic(
+                Severity::Error,
+                Rule::InvalidCollectionFormat,
+                "$.parameters.Tags.collectionFormat",
+                "Parameter \"tags\" uses 'multi' collectionFormat, which is only valid for query and formData parameters",
+            ),
+            diagnostic(
+                Severity::Info,
+                Rule::MissingDescription,
+                "$.definitions.Owner",
+                "Definition \"Owner\" doesn't have a description",
+            ),
+            diagnostic(
+                Severity::Warning,
+                Rule::UnusedDefinition,
+                "$.definitions.Unused",
+                "Definition \"Unused\" is not used by any operation",
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_lint_configured_rules() {
+    let config: LintConfig = serde_yaml::from_str(
+        "
+missing-description: off
+unused-definition: error
+unexpected-body: off
+",
+    )
+    .expect("config");
+    assert_eq!(config.severity(Rule::MissingDescription), None);
+    assert_eq!(
+        config.severity(Rule::DuplicateOperationId),
+        Some(Severity::Error)
+    );
+
+    let mut config = config;
+    config.set_level(Rule::InvalidCollectionFormat, RuleLevel::Warning);
+
+    let diagnostics = lint::lint(&parse().resolve().expect("resolution"), &config);
+    let found = diagnostics
+        .iter()
+        .map(|d| (d.severity, d.rule.id(), d.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            (
+                Severity::Warning,
+                "invalid-collection-format",
+                "$.paths['/pets'].post.responses['201'].headers['X-Ids'].collectionFormat"
+            ),
+            (
+                Severity::Error,
+                "duplicate-operation-id",
+                "$.paths['/pets/{petId}'].get.operationId"
+            ),
+            (
+                Severity::Error,
+                "unknown-path-parameter",
+                "$.paths['/pets/{petId}'].get.parameters[0]"
+            ),
+            (
+                Severity::Error,
+                "missing-path-parameter",
+                "$.paths['/pets/{petId}'].get"
+            ),
+            (
+                Severity::Warning,
+                "invalid-collection-format",
+                "$.parameters.Tags.collectionFormat"
+            ),
+            (Severity::Error, "unused-definition", "$.definitions.Unused"),
+        ]
+    );
+}
@@ -0,0 +1,72 @@
+use paperclip::v2::{
+    self,
+    models::{DefaultSchema, ResolvableApi},
+    Schema,
+};
+
+#[test]
+fn test_external_refs_are_hoisted() {
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_path("tests/multi-file/api.yaml").expect("loading spec");
+
+    assert_eq!(
+        api.definitions.keys().collect::<Vec<_>>(),
+        vec!["CommonError", "Error", "Owner", "Pet"]
+    );
+    assert_eq!(api.parameters.keys().collect::<Vec<_>>(), vec!["Limit"]);
+    assert_eq!(
+        api.responses.keys().collect::<Vec<_>>(),
+        vec!["ErrorResponse"]
+    );
+
+    let pet = api.definitions["Pet"].read().unwrap();
+    let owner = pet.properties().unwrap()["owner"].read().unwrap();
+    assert_eq!(owner.reference(), Some("#/definitions/Owner"));
+
+    let owner = api.definitions["Owner"].read().unwrap();
+    let pets = owner.properties().unwrap()["pets"].read().unwrap();
+    let item = pets.items().unwrap().read().unwrap();
+    assert_eq!(item.reference(), Some("#/definitions/Pet"));
+
+    let resp = api.responses["ErrorResponse"].read().unwrap();
+    let schema = resp.schema.as_ref().unwrap().read().unwrap();
+    assert_eq!(schema.reference(), Some("#/definitions/CommonError"));
+}
+
+#[test]
+fn test_external_refs_resolve() {
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_path("tests/multi-file/api.yaml").expect("loading spec");
+    let api = api.resolve().expect("resolution");
+
+    let op = &api.paths["/pets"].methods[&v2::models::HttpMethod::Get];
+    let param = op.parameters[0].read().unwrap();
+    assert_eq!(param.name, "limit");
+
+    let resp = op.responses["200"].read().unwrap();
+    let schema = resp.schema.as_ref().unwrap().read().unwrap();
+    let item = schema.items().unwrap().read().unwrap();
+    assert_eq!(item.name(), Some("Pet"));
+
+    // Cyclic references across files end up on the same definition.
+    let owner = item.properties().unwrap()["owner"].read().unwrap();
+    assert_eq!(owner.name(), Some("Owner"));
+    let pets = owner.properties().unwrap()["pets"].read().unwrap();
+    let pet = pets.items().unwrap().read().unwrap();
+    assert_eq!(pet.name(), Some("Pet"));
+}
+
+#[test]
+fn test_missing_external_ref() {
+    let err = v2::from_path::<_, DefaultSchema>("tests/multi-file/broken.yaml")
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        err,
+        "Reference missing in spec: common.yaml#/definitions/Owner
+  --> tests/multi-file/broken.yaml:11:9
+   |
+11 |         $ref: \"common.yaml#/definitions/Owner\"
+   |         ^"
+    );
+}
@@ -0,0 +1,397 @@
+use paperclip::v2::{
+    merge::{self, ConflictKind, MergeInput},
+    models::DefaultApiRaw,
+};
+use serde_json::json;
+
+fn store_spec() -> DefaultApiRaw {
+    serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Store", "version": "1.0"},
+        "basePath": "/api",
+        "securityDefinitions": {
+            "oauth": {
+                "type": "oauth2",
+                "flow": "implicit",
+                "authorizationUrl": "https://example.com/auth",
+                "scopes": {"read": "Read stuff"}
+            }
+        },
+        "tags": [{"name": "pets"}],
+        "paths": {
+            "/pets": {
+                "get": {
+                    "operationId": "listPets",
+                    "responses": {
+                        "200": {
+                            "description": "Pets",
+                            "schema": {"type": "array", "items": {"$ref": "#/definitions/Pet"}}
+                        }
+                    }
+                }
+            },
+            "/health": {
+                "parameters": [{"name": "verbose", "in": "query", "type": "boolean"}],
+                "get": {
+                    "operationId": "storeHealth",
+                    "responses": {"200": {"description": "OK"}}
+                }
+            }
+        },
+        "definitions": {
+            "Error": {"type": "object", "properties": {"reason": {"type": "string"}}},
+            "Pet": {"type": "object", "properties": {"name": {"type": "string"}}}
+        }
+    }))
+    .unwrap()
+}
+
+fn users_spec() -> DefaultApiRaw {
+    serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": {"title": "Users", "version": "2.0"},
+        "securityDefinitions": {
+            "oauth": {
+                "type": "oauth2",
+                "flow": "implicit",
+                "authorizationUrl": "https://example.com/auth",
+                "scopes": {"write": "Write stuff"}
+            }
+        },
+        "tags": [{"name": "pets"}, {"name": "users"}],
+        "paths": {
+            "/users": {
+                "get": {
+                    "operationId": "listUsers",
+                    "responses": {
+                        "200": {
+                            "description": "Users",
+                            "schema": {"type": "array", "items": {"$ref": "#/definitions/User"}}
+                        },
+                        "default": {
+                            "description": "Error",
+                            "schema": {"$ref": "#/definitions/Error"}
+                        }
+                    }
+                }
+            },
+            "/health": {
+                "get": {
+                    "operationId": "usersHealth",
+                    "responses": {"200": {"description": "OK"}}
+                },
+                "head": {
+                    "operationId": "usersHealthHead",
+                    "responses": {"200": {"description": "OK"}}
+                }
+            }
+        },
+        "definitions": {
+            "Error": {"type": "object", "properties": {"reason": {"type": "string"}}},
+            "Pet": {"type": "object", "properties": {"id": {"type": "integer"}}},
+            "User": {
+                "type": "object",
+                "properties": {"pets": {"type": "array", "items": {"$ref": "#/definitions/Pet"}}}
+            }
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_merge_with_prefixes() {
+    let report = merge::merge(vec![
+        MergeInput::new(store_spec()).path_prefix("/store"),
+        MergeInput::new(users_spec()).path_prefix("/users/"),
+    ])
+    .unwrap();
+    let value = serde_json::to_value(&report.api).unwrap();
+
+    assert_eq!(value["info"]["title"], "Store");
+    // Users spec doesn't have a base path, so the base path goes into the paths.
+    assert!(value["basePath"].is_null());
+    assert_eq!(
+        value["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec![
+            "/api/store/health",
+            "/api/store/pets",
+            "/users/health",
+            "/users/users"
+        ]
+    );
+    assert_eq!(value["tags"], json!([{"name": "pets"}, {"name": "users"}]));
+    assert_eq!(
+        value["securityDefinitions"]["oauth"]["scopes"],
+        json!({"read": "Read stuff", "write": "Write stuff"})
+    );
+
+    // Identical definitions are shared and different ones are renamed.
+    assert_eq!(
+        value["definitions"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec!["Error", "Pet", "User", "UsersPet"]
+    );
+    assert_eq!(
+        value["definitions"]["User"]["properties"]["pets"]["items"],
+        json!({"$ref": "#/definitions/UsersPet"})
+    );
+    assert_eq!(
+        value["paths"]["/users/users"]["get"]["responses"]["default"]["schema"],
+        json!({"$ref": "#/definitions/Error"})
+    );
+
+    assert_eq!(report.conflicts.len(), 1);
+    let conflict = &report.conflicts[0];
+    assert_eq!(conflict.kind, ConflictKind::Definition);
+    assert_eq!(conflict.input, 1);
+    assert_eq!(conflict.path, "$.definitions.Pet");
+    assert_eq!(conflict.renamed_to.as_deref(), Some("UsersPet"));
+    assert_eq!(report.unresolved_conflicts().count(), 0);
+}
+
+#[test]
+fn test_merge_conflicting_operations() {
+    let mut users = users_spec();
+    users.base_path = Some("/api/".into());
+    let report = merge::merge(vec![store_spec().into(), users.into()]).unwrap();
+    let value = serde_json::to_value(&report.api).unwrap();
+    assert_eq!(value["basePath"], "/api");
+
+    // Operation from the first spec wins.
+    let health = &value["paths"]["/health"];
+    assert_eq!(health["get"]["operationId"], "storeHealth");
+    assert_eq!(health["head"]["operationId"], "usersHealthHead");
+
+    // Shared parameters only belong to the operations which had them.
+    assert!(health["parameters"].is_null());
+    assert_eq!(health["get"]["parameters"][0]["name"], "verbose");
+    assert!(health["head"]["parameters"].is_null());
+
+    let conflicts = report.unresolved_conflicts().collect::<Vec<_>>();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].kind, ConflictKind::Operation);
+    assert_eq!(conflicts[0].path, "$.paths['/health'].get");
+    assert_eq!(
+        conflicts[0].to_string(),
+        "input 1: Operation GET \"/health\" already exists (at $.paths['/health'].get)"
+    );
+
+    // Without a prefix, the title is used for renaming.
+    assert!(value["definitions"]["UsersPet"].is_object());
+}
+
+#[test]
+fn test_merge_renames_dependent_definitions() {
+    let mut other = store_spec();
+    other.definitions.insert(
+        "Owner".into(),
+        serde_json::from_value(json!({
+            "type": "object",
+            "properties": {"pet": {"$ref": "#/definitions/Pet"}}
+        }))
+        .unwrap(),
+    );
+    let mut spec = users_spec();
+    spec.definitions
+        .insert("Owner".into(), other.definitions["Owner"].clone());
+
+    // `Owner` looks the same in both specs, but it refers to different pets.
+    let report = merge::merge(vec![other.into(), spec.into()]).unwrap();
+    let value = serde_json::to_value(&report.api).unwrap();
+
+    assert_eq!(
+        value["definitions"]["UsersOwner"]["properties"]["pet"],
+        json!({"$ref": "#/definitions/UsersPet"})
+    );
+    assert_eq!(
+        value["definitions"]["Owner"]["properties"]["pet"],
+        json!({"$ref": "#/definitions/Pet"})
+    );
+    assert_eq!(
+        report
+            .conflicts
+            .iter()
+            .filter_map(|c| c.renamed_to.as_deref())
+            .collect::<Vec<_>>(),
+        vec!["UsersPet", "UsersOwner"]
+    );
+}
+
+#[test]
+fn test_merge_renames_parameters_and_responses() {
+    let with_globals = |title: &str, limit: u64, error: &str| -> DefaultApiRaw {
+        serde_json::from_value(json!({
+            "swagger": "2.0",
+            "info": {"title": title, "version": "1.0"},
+            "parameters": {
+                "Limit": {"name": "limit", "in": "query", "type": "integer", "maximum": limit}
+            },
+            "responses": {
+                "Error": {"description": error}
+            },
+            "paths": {
+                format!("/{}", title.to_lowercase()): {
+                    "get": {
+                        "operationId": format!("list{}", title),
+                        "parameters": [{"$ref": "#/parameters/Limit"}],
+                        "responses": {"default": {"$ref": "#/responses/Error"}}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    };
+
+    let report = merge::merge(vec![
+        with_globals("Pets", 100, "Pet error").into(),
+        with_globals("Users", 10, "User error").into(),
+    ])
+    .unwrap();
+    let value = serde_json::to_value(&report.api).unwrap();
+
+    assert_eq!(value["parameters"]["UsersLimit"]["maximum"], 10.0);
+    assert_eq!(
+        value["responses"]["UsersError"]["description"],
+        "User error"
+    );
+    let users = &value["paths"]["/users"]["get"];
+    assert_eq!(
+        users["parameters"],
+        json!([{"$ref": "#/parameters/UsersLimit"}])
+    );
+    assert_eq!(
+        users["responses"]["default"],
+        json!({"$ref": "#/responses/UsersError"})
+    );
+
+    // References in the first spec still point to the original objects.
+    let pets = &value["paths"]["/pets"]["get"];
+    assert_eq!(pets["parameters"], json!([{"$ref": "#/parameters/Limit"}]));
+    assert_eq!(
+        pets["responses"]["default"],
+        json!({"$ref": "#/responses/Error"})
+    );
+
+    let kinds: Vec<_> = report
+        .conflicts
+        .iter()
+        .map(|c| (c.kind, c.renamed_to.as_deref()))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (ConflictKind::Parameter, Some("UsersLimit")),
+            (ConflictKind::Response, Some("UsersError")),
+        ]
+    );
+}
+
+#[test]
+fn test_merge_base_paths_and_globals() {
+    let spec = |title: &str, base: &str, host: &str, media: &str, scheme: &str| -> DefaultApiRaw {
+        serde_json::from_value(json!({
+            "swagger": "2.0",
+            "info": {"title": title, "version": "1.0"},
+            "host": host,
+            "basePath": base,
+            "consumes": [media],
+            "produces": [media],
+            "schemes": [scheme],
+            "security": [{title.to_lowercase(): []}],
+            "paths": {
+                "/items": {
+                    "get": {
+                        "operationId": format!("list{}", title),
+                        "responses": {"200": {"description": "OK"}}
+                    },
+                    "post": {
+                        "operationId": format!("add{}", title),
+                        "consumes": ["text/plain"],
+                        "security": [],
+                        "responses": {"200": {"description": "OK"}}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    };
+
+    let report = merge::merge(vec![
+        spec(
+            "Store",
+            "/api/v1",
+            "example.com",
+            "application/json",
+            "https",
+        )
+        .into(),
+        spec(
+            "Users",
+            "/api/v2/",
+            "example.com",
+            "application/json",
+            "https",
+        )
+        .into(),
+        MergeInput::new(spec(
+            "Orders",
+            "/api",
+            "example.org",
+            "application/xml",
+            "http",
+        ))
+        .path_prefix("orders"),
+    ])
+    .unwrap();
+    let value = serde_json::to_value(&report.api).unwrap();
+
+    // The common base path is kept and the rest goes before the prefix.
+    assert_eq!(value["basePath"], "/api");
+    assert_eq!(
+        value["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec!["/orders/items", "/v1/items", "/v2/items"]
+    );
+
+    // Globals are taken from the first spec.
+    assert_eq!(value["consumes"], json!(["application/json"]));
+    assert_eq!(value["produces"], json!(["application/json"]));
+    assert_eq!(value["schemes"], json!(["https"]));
+    assert_eq!(value["security"], json!([{"store": []}]));
+
+    // Same media types and schemes, but different security requirements.
+    let users = &value["paths"]["/v2/items"];
+    assert!(users["get"]["consumes"].is_null());
+    assert!(users["get"]["schemes"].is_null());
+    assert_eq!(users["get"]["security"], json!([{"users": []}]));
+    // Operations can override them.
+    assert_eq!(users["post"]["security"], json!([]));
+
+    let orders = &value["paths"]["/orders/items"];
+    assert_eq!(orders["get"]["consumes"], json!(["application/xml"]));
+    assert_eq!(orders["get"]["produces"], json!(["application/xml"]));
+    assert_eq!(orders["get"]["schemes"], json!(["http"]));
+    assert_eq!(orders["get"]["security"], json!([{"orders": []}]));
+    assert_eq!(orders["post"]["consumes"], json!(["text/plain"]));
+    assert_eq!(orders["post"]["produces"], json!(["application/xml"]));
+
+    let store = &value["paths"]["/v1/items"];
+    assert!(store["get"]["consumes"].is_null());
+    assert!(store["get"]["security"].is_null());
+
+    let conflicts = report.unresolved_conflicts().collect::<Vec<_>>();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].kind, ConflictKind::Host);
+    assert_eq!(conflicts[0].input, 2);
+    assert_eq!(conflicts[0].path, "$.host");
+}
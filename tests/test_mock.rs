@@ -0,0 +1,353 @@
+use actix_web4::{
+    http::StatusCode,
+    rt::System,
+    test::{self, TestRequest},
+    App,
+};
+use paperclip::v2::{
+    self,
+    mock::{MockServer, STATUS_HEADER},
+    models::{DefaultSchema, ResolvableApi},
+};
+use serde_json::{json, Value};
+
+const SPEC: &str = r##"swagger: "2.0"
+info:
+  title: Pets
+  version: "1.0"
+basePath: /api
+consumes: [application/json]
+produces: [application/json]
+paths:
+  /pets:
+    get:
+      parameters:
+      - name: limit
+        in: query
+        type: integer
+        minimum: 1
+        maximum: 50
+      - name: tags
+        in: query
+        type: array
+        items:
+          type: string
+          enum: [cat, dog]
+      - name: since
+        in: query
+        type: string
+        format: date-time
+      - name: born
+        in: query
+        type: string
+        format: date
+      - name: page
+        in: query
+        type: integer
+        format: int32
+      responses:
+        '200':
+          description: Pets.
+          headers:
+            X-Total:
+              type: integer
+              minimum: 10
+              maximum: 10
+          schema:
+            type: array
+            items:
+              $ref: "#/definitions/Pet"
+    post:
+      parameters:
+      - name: X-Request-Id
+        in: header
+        type: string
+        format: uuid
+        required: true
+      - name: body
+        in: body
+        required: true
+        schema:
+          $ref: "#/definitions/Pet"
+      responses:
+        '201':
+          description: Created pet.
+          schema:
+            $ref: "#/definitions/Pet"
+        '409':
+          description: Conflict.
+          schema:
+            $ref: "#/definitions/Error"
+  /pets/{id}/name:
+    get:
+      produces: [application/xml, text/plain]
+      responses:
+        '200':
+          description: Name of the pet.
+          schema:
+            type: string
+            example: Milo
+  /pets/{id}:
+    parameters:
+    - name: id
+      in: path
+      type: integer
+      required: true
+    delete:
+      responses:
+        '204':
+          description: Deleted.
+        default:
+          description: Error.
+          schema:
+            $ref: "#/definitions/Error"
+definitions:
+  Pet:
+    type: object
+    required: [name]
+    additionalProperties: false
+    properties:
+      id:
+        type: integer
+        format: int64
+      name:
+        type: string
+        minLength: 1
+        pattern: "^[A-Za-z]*$"
+        example: Milo
+  Error:
+    type: object
+    required: [message]
+    properties:
+      message:
+        type: string
+"##;
+
+fn mock() -> MockServer {
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_reader(SPEC.as_bytes()).expect("deserializing spec");
+    MockServer::new(&api.resolve().expect("resolution"))
+        .expect("mock server")
+        .seed(5)
+}
+
+/// Sends the requests to the mock and returns the status, headers and bodies.
+fn call(requests: Vec<TestRequest>) -> Vec<(StatusCode, Option<String>, Value, Option<String>)> {
+    let mock = mock();
+    System::new().block_on(async move {
+        let app = test::init_service(App::new().configure(move |c| mock.configure(c))).await;
+        let mut results = vec![];
+        for req in requests {
+            let resp = test::call_service(&app, req.to_request()).await;
+            let status = resp.status();
+            let header = |name| {
+                resp.headers()
+                    .get(name)
+                    .map(|v| v.to_str().unwrap().to_owned())
+            };
+            let (total, content_type) = (header("x-total"), header("content-type"));
+            let body = test::read_body(resp).await;
+            // (plain text bodies are kept as strings)
+            let body = match serde_json::from_slice(&body) {
+                Ok(value) => value,
+                Err(_) if body.is_empty() => Value::Null,
+                Err(_) => Value::String(String::from_utf8_lossy(&body).into()),
+            };
+            results.push((status, total, body, content_type));
+        }
+
+        results
+    })
+}
+
+#[test]
+fn test_mock_responses() {
+    let results = call(vec![
+        TestRequest::get().uri("/api/pets?limit=5&tags=cat,dog"),
+        TestRequest::get().uri("/api/pets?limit=5&tags=cat,dog"),
+        TestRequest::post()
+            .uri("/api/pets")
+            .insert_header(("X-Request-Id", "8a7f4b6e-0c63-4b8e-9a53-3c2f3b1d4e5f"))
+            .set_json(json!({"name": "Milo"})),
+        TestRequest::delete().uri("/api/pets/1"),
+    ]);
+
+    let (status, total, body, _) = &results[0];
+    assert_eq!(*status, StatusCode::OK);
+    assert_eq!(total.as_deref(), Some("10"));
+    let pets = body.as_array().unwrap();
+    assert!(!pets.is_empty());
+    assert!(pets.iter().all(|p| p["name"] == "Milo" && p["id"].is_i64()));
+    // Responses are deterministic.
+    assert_eq!(results[1], results[0]);
+
+    assert_eq!(results[2].0, StatusCode::CREATED);
+    assert_eq!(results[2].2["name"], "Milo");
+    assert_eq!(results[3].0, StatusCode::NO_CONTENT);
+    assert_eq!(results[3].2, Value::Null);
+}
+
+#[test]
+fn test_mock_alternate_status() {
+    let results = call(vec![
+        TestRequest::post()
+            .uri("/api/pets")
+            .insert_header(("X-Request-Id", "8a7f4b6e-0c63-4b8e-9a53-3c2f3b1d4e5f"))
+            .insert_header((STATUS_HEADER, "409"))
+            .set_json(json!({"name": "Milo"})),
+        // Falls back to the default response.
+        TestRequest::delete()
+            .uri("/api/pets/1")
+            .insert_header((STATUS_HEADER, "404")),
+        TestRequest::get()
+            .uri("/api/pets")
+            .insert_header((STATUS_HEADER, "500")),
+    ]);
+
+    assert_eq!(results[0].0, StatusCode::CONFLICT);
+    assert!(results[0].2["message"].is_string());
+    assert_eq!(results[1].0, StatusCode::NOT_FOUND);
+    assert!(results[1].2["message"].is_string());
+    assert_eq!(results[2].0, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        results[2].2,
+        json!({"errors": ["Status code 500 is not documented."]})
+    );
+}
+
+#[test]
+fn test_mock_validation() {
+    let results = call(vec![
+        TestRequest::get().uri("/api/pets?limit=100&tags=cat,fish"),
+        TestRequest::get().uri("/api/pets?limit=many"),
+        TestRequest::delete().uri("/api/pets/abc"),
+        TestRequest::post()
+            .uri("/api/pets")
+            .set_json(json!({"name": "", "age": 2})),
+        TestRequest::post()
+            .uri("/api/pets")
+            .insert_header(("X-Request-Id", "8a7f4b6e-0c63-4b8e-9a53-3c2f3b1d4e5f"))
+            .insert_header(("Content-Type", "text/plain"))
+            .set_payload("Milo"),
+        TestRequest::put().uri("/api/pets"),
+        TestRequest::get().uri("/pets"),
+        TestRequest::post()
+            .uri("/api/pets")
+            .insert_header(("X-Request-Id", "8a7f4b6e-0c63-4b8e-9a53-3c2f3b1d4e5f"))
+            .set_json(json!({"name": "M1lo"})),
+    ]);
+
+    let errors = |i: usize| results[i].2["errors"].clone();
+    assert_eq!(results[0].0, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        errors(0),
+        json!([
+            "query.limit: number is greater than the maximum (50)",
+            "query.tags[1]: value is not one of the enum variants"
+        ])
+    );
+    assert_eq!(errors(1), json!(["query.limit: expected integer"]));
+    assert_eq!(errors(2), json!(["path.id: expected integer"]));
+    assert_eq!(
+        errors(3),
+        json!([
+            "header.X-Request-Id: missing required parameter",
+            "body.age: unknown property",
+            "body.name: string is shorter than 1 characters"
+        ])
+    );
+    assert_eq!(results[4].0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(results[5].0, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(results[6].0, StatusCode::NOT_FOUND);
+    assert_eq!(
+        errors(7),
+        json!(["body.name: string doesn't match \"^[A-Za-z]*$\""])
+    );
+}
+
+#[test]
+fn test_mock_formats() {
+    let results = call(vec![
+        TestRequest::get()
+            .uri("/api/pets?since=2020-02-29T10:00:00.5%2B05:30&born=2020-02-29&page=2147483647"),
+        TestRequest::get()
+            .uri("/api/pets?since=2021-02-28T25:00:00Z&born=2021-02-29&page=2147483648"),
+        TestRequest::get().uri("/api/pets?since=2021-02-28&born=2021-1-1"),
+        TestRequest::post()
+            .uri("/api/pets")
+            .insert_header(("X-Request-Id", "8a7f4b6e-0c63-4b8e-9a53"))
+            .set_json(json!({"name": "Milo", "id": 9223372036854775808u64})),
+    ]);
+
+    let errors = |i: usize| results[i].2["errors"].clone();
+    assert_eq!(results[0].0, StatusCode::OK);
+    assert_eq!(
+        errors(1),
+        json!([
+            "query.since: string is not a valid date-time",
+            "query.born: string is not a valid date",
+            "query.page: number is out of range for int32"
+        ])
+    );
+    assert_eq!(
+        errors(2),
+        json!([
+            "query.since: string is not a valid date-time",
+            "query.born: string is not a valid date"
+        ])
+    );
+    assert_eq!(
+        errors(3),
+        json!([
+            "header.X-Request-Id: string is not a valid uuid",
+            "body.id: number is out of range for int64"
+        ])
+    );
+}
+
+#[test]
+fn test_mock_media_types() {
+    let results = call(vec![
+        TestRequest::get().uri("/api/pets"),
+        TestRequest::get()
+            .uri("/api/pets")
+            .insert_header(("Accept", "text/html, application/*;q=0.5")),
+        TestRequest::get()
+            .uri("/api/pets")
+            .insert_header(("Accept", "application/xml, application/json;q=0")),
+        // Only the plain text can be synthesized.
+        TestRequest::get().uri("/api/pets/1/name"),
+        TestRequest::get()
+            .uri("/api/pets/1/name")
+            .insert_header(("Accept", "text/*, application/xml;q=0.9")),
+        TestRequest::get()
+            .uri("/api/pets/1/name")
+            .insert_header(("Accept", "application/xml")),
+    ]);
+
+    assert_eq!(results[0].0, StatusCode::OK);
+    assert_eq!(results[0].3.as_deref(), Some("application/json"));
+    assert_eq!(results[1].0, StatusCode::OK);
+    assert_eq!(results[1].3.as_deref(), Some("application/json"));
+    assert_eq!(results[2].0, StatusCode::NOT_ACCEPTABLE);
+    assert_eq!(
+        results[2].2,
+        json!({"errors": ["None of the acceptable media types can be produced."]})
+    );
+    assert_eq!(results[3].0, StatusCode::OK);
+    assert_eq!(results[3].3.as_deref(), Some("text/plain"));
+    assert_eq!(results[3].2, "Milo");
+    assert_eq!(results[4].3.as_deref(), Some("text/plain"));
+    assert_eq!(results[5].0, StatusCode::NOT_ACCEPTABLE);
+}
+
+#[test]
+fn test_mock_invalid_pattern() {
+    let spec = SPEC.replace("^[A-Za-z]*$", "^[A-Z");
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_reader(spec.as_bytes()).expect("deserializing spec");
+    let err = MockServer::new(&api.resolve().expect("resolution"))
+        .err()
+        .expect("invalid pattern");
+    assert!(err.to_string().starts_with("Invalid pattern \"^[A-Z\""));
+}
@@ -0,0 +1,243 @@
+use paperclip::{
+    v2::{
+        self,
+        models::{DataType, DefaultSchema, HttpMethod, ResolvableApi},
+        overlay::Overlay,
+        Schema,
+    },
+    PaperClipError,
+};
+use serde_json::json;
+
+fn overlay(actions: &str) -> Overlay {
+    let doc = format!(
+        "overlay: 1.0.0\ninfo:\n  title: Fixes\n  version: 1.0.0\nactions:\n{}",
+        actions
+    );
+    Overlay::from_reader(doc.as_bytes()).expect("parsing overlay")
+}
+
+#[test]
+fn test_update_and_remove() {
+    let mut doc = json!({
+        "paths": {
+            "/pets": {
+                "get": {
+                    "parameters": [
+                        {"name": "limit", "in": "query", "type": "string"},
+                        {"name": "X-Trace", "in": "header", "type": "string"},
+                        {"name": "X-Debug", "in": "header", "type": "boolean"}
+                    ]
+                }
+            }
+        },
+        "definitions": {
+            "Pet": {
+                "properties": {
+                    "status": {"type": "string", "enum": ["available", "sold"]}
+                }
+            }
+        }
+    });
+
+    let overlay = overlay(
+        r#"
+  - target: $.paths['/pets'].get
+    update:
+      operationId: listPets
+  - target: $.paths.*.*.parameters[?@.name == 'limit']
+    update:
+      type: integer
+  - target: "$.paths.*.*.parameters[?(@.in == 'header')]"
+    remove: true
+  - target: $.definitions.Pet.properties.status
+    update:
+      enum: [available, pending, sold]
+  - target: $.definitions.Pet.properties.status.enum
+    update: unknown
+  - target: $.definitions.Order
+    remove: true
+"#,
+    );
+
+    let unmatched = overlay.apply(&mut doc).unwrap();
+    assert_eq!(unmatched, vec!["$.definitions.Order"]);
+    assert_eq!(
+        doc,
+        json!({
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "operationId": "listPets",
+                        "parameters": [
+                            {"name": "limit", "in": "query", "type": "integer"}
+                        ]
+                    }
+                }
+            },
+            "definitions": {
+                "Pet": {
+                    "properties": {
+                        "status": {
+                            "type": "string",
+                            "enum": ["available", "pending", "sold", "unknown"]
+                        }
+                    }
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn test_selectors() {
+    let doc = json!({
+        "a": {"b": [1, 2, 3], "c": {"b": [4]}},
+        "d": [
+            {"id": 1, "tags": ["x"]},
+            {"id": 2},
+            {"id": 3, "tags": []}
+        ]
+    });
+    let (a, d) = (&doc["a"], &doc["d"]);
+
+    let cases = vec![
+        (
+            "$..b[-1]",
+            json!({"a": {"b": [1, 2], "c": {"b": []}}, "d": d}),
+        ),
+        ("$.a[\"b\", \"c\"]", json!({"a": {}, "d": d})),
+        (
+            "$.a.b[0,2,0]",
+            json!({"a": {"b": [2], "c": {"b": [4]}}, "d": d}),
+        ),
+        (
+            "$.d[?@.id > 1 && @.tags]",
+            json!({"a": a, "d": [{"id": 1, "tags": ["x"]}, {"id": 2}]}),
+        ),
+        (
+            "$.d[?!@.tags || @.id <= 1]",
+            json!({"a": a, "d": [{"id": 3, "tags": []}]}),
+        ),
+        (
+            "$.d[?@.tags[0] == \"x\"]",
+            json!({"a": a, "d": [d[1], d[2]]}),
+        ),
+        ("$.d[*]", json!({"a": a, "d": []})),
+        ("$.*", json!({})),
+    ];
+
+    for (target, expected) in cases {
+        let mut doc = doc.clone();
+        let overlay = overlay(&format!("  - target: '{}'\n    remove: true\n", target));
+        assert!(overlay.apply(&mut doc).unwrap().is_empty());
+        assert_eq!(doc, expected, "target: {}", target);
+    }
+}
+
+#[test]
+fn test_from_reader_with_overlays() {
+    let spec = r##"
+swagger: "2.0"
+info:
+  title: Store
+  version: "1.0"
+paths:
+  /orders/{id}:
+    get:
+      parameters:
+        - name: id
+          in: path
+          required: true
+          type: string
+      responses:
+        200:
+          description: Order
+          schema:
+            $ref: "#/definitions/Order"
+definitions:
+  Order:
+    type: object
+    properties:
+      quantity:
+        type: string
+"##;
+
+    let overlay = overlay(
+        r#"
+  - target: $.paths['/orders/{id}'].get
+    update:
+      operationId: getOrder
+  - target: $.definitions.Order.properties.quantity
+    update:
+      type: integer
+      format: int32
+  - target: $.definitions.Customer
+    remove: true
+"#,
+    );
+
+    let (api, unmatched): (ResolvableApi<DefaultSchema>, _) =
+        v2::from_reader_with_overlays(spec.as_bytes(), &[overlay]).unwrap();
+    assert_eq!(unmatched, vec!["$.definitions.Customer"]);
+    assert!(api.source_map.is_none());
+    let op = &api.paths["/orders/{id}"].methods[&HttpMethod::Get];
+    assert_eq!(op.operation_id.as_deref(), Some("getOrder"));
+
+    let order = api.definitions["Order"].read().unwrap();
+    let quantity = order.properties().unwrap()["quantity"].read().unwrap();
+    assert_eq!(quantity.data_type(), Some(DataType::Integer));
+}
+
+#[test]
+fn test_from_path_with_overlays() {
+    // Overlays see hoisted objects from other documents.
+    let overlay = overlay(
+        r#"
+  - target: $.definitions.Pet.properties
+    update:
+      nickname:
+        type: string
+  - target: $.paths['/pets'].post
+    remove: true
+"#,
+    );
+
+    let (api, unmatched): (ResolvableApi<DefaultSchema>, _) =
+        v2::from_path_with_overlays("tests/multi-file/api.yaml", &[overlay]).unwrap();
+    assert!(unmatched.is_empty());
+    assert!(api.source_map.is_none());
+    let methods: Vec<_> = api.paths["/pets"].methods.keys().collect();
+    assert_eq!(methods, vec![&HttpMethod::Get]);
+
+    let pet = api.definitions["Pet"].read().unwrap();
+    let props: Vec<_> = pet.properties().unwrap().keys().collect();
+    assert_eq!(props, vec!["name", "nickname", "owner"]);
+}
+
+#[test]
+fn test_invalid_overlays() {
+    let err = Overlay::from_reader(
+        "overlay: 2.0.0\ninfo: {title: Fixes, version: 1.0.0}\nactions: []".as_bytes(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, PaperClipError::UnsupportedOverlayVersion(v) if v == "2.0.0"));
+
+    for (target, reason) in &[
+        ("paths", "expected '$' or '@' at 0"),
+        ("$.paths[", "invalid selector at 8"),
+        ("$.paths['/pets'", "expected \"]\" at 15"),
+        ("@.paths", "query must begin with '$'"),
+        ("$.d[?1]", "expected comparison after literal at 5"),
+    ] {
+        let doc = format!(
+            "overlay: 1.0.0\ninfo: {{title: Fixes, version: 1.0.0}}\nactions:\n  - target: \"{}\"\n    remove: true",
+            target
+        );
+        let err = Overlay::from_reader(doc.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Invalid JSONPath {:?}: {}", target, reason)
+        );
+    }
+}
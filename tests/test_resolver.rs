@@ -0,0 +1,521 @@
+use paperclip::v2::{
+    self,
+    codegen::{DefaultEmitter, Emitter, EmitterState},
+    models::{DefaultSchema, Resolvable, ResolvableApi},
+    Schema,
+};
+
+use std::{fs, io::Cursor, sync::Arc};
+
+const POINTER_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Pods
+  version: \"1.0.0\"
+paths:
+  /pods/{name}:
+    get:
+      parameters:
+      - name: name
+        in: path
+        type: string
+        required: true
+      responses:
+        \"200\":
+          schema:
+            type: object
+            properties:
+              status:
+                type: object
+                properties:
+                  phase:
+                    type: string
+  /pods:
+    post:
+      parameters:
+      - name: body
+        in: body
+        schema:
+          $ref: \"#/definitions/Pod/properties/spec\"
+      responses:
+        \"200\":
+          schema:
+            $ref: \"#/paths/~1pods~1%7Bname%7D/get/responses/200/schema/properties/status\"
+definitions:
+  Pod:
+    type: object
+    properties:
+      spec:
+        type: object
+        properties:
+          containers:
+            type: array
+            items:
+              type: object
+              properties:
+                image:
+                  type: string
+  Deployment:
+    type: object
+    properties:
+      template:
+        $ref: \"#/definitions/Pod/properties/spec\"
+      container:
+        $ref: \"#/definitions/Pod/properties/spec/properties/containers/items\"
+  a/b~c:
+    type: object
+    properties:
+      pod:
+        $ref: \"#/definitions/Pod\"
+  Escaped:
+    type: object
+    properties:
+      other:
+        $ref: \"#/definitions/a~1b~0c\"
+";
+
+fn resolve_pointer_spec() -> ResolvableApi<DefaultSchema> {
+    let raw: ResolvableApi<DefaultSchema> =
+        v2::from_reader(Cursor::new(POINTER_SPEC)).expect("deserializing spec");
+    raw.resolve().expect("resolution")
+}
+
+#[test]
+fn test_json_pointer_refs_share_schema() {
+    let api = resolve_pointer_spec();
+
+    let pod = api.definitions["Pod"].read().unwrap();
+    let spec = &pod.properties().unwrap()["spec"];
+    let deployment = api.definitions["Deployment"].read().unwrap();
+    let template = &deployment.properties().unwrap()["template"];
+    assert!(Arc::ptr_eq(spec, template));
+    assert!(Arc::ptr_eq(spec, &api.definitions["PodSpec"]));
+    assert_eq!(spec.read().unwrap().name(), Some("PodSpec"));
+
+    let container = &deployment.properties().unwrap()["container"];
+    assert!(Arc::ptr_eq(
+        container,
+        &api.definitions["PodSpecContainersItems"]
+    ));
+
+    let body = api.paths["/pods"].methods[&v2::models::HttpMethod::Post].parameters[0]
+        .read()
+        .unwrap();
+    assert!(Arc::ptr_eq(body.schema.as_ref().unwrap(), spec));
+
+    let get_resp = api.definitions["GetPodsNameResponse"].read().unwrap();
+    let status = &get_resp.properties().unwrap()["status"];
+    let resp = api.paths["/pods"].methods[&v2::models::HttpMethod::Post].responses["200"]
+        .read()
+        .unwrap();
+    assert!(Arc::ptr_eq(resp.schema.as_ref().unwrap(), status));
+
+    let escaped = api.definitions["Escaped"].read().unwrap();
+    let other = &escaped.properties().unwrap()["other"];
+    assert!(Arc::ptr_eq(other, &api.definitions["a/b~c"]));
+}
+
+#[test]
+fn test_json_pointer_refs_emit_single_type() {
+    let api = resolve_pointer_spec();
+    let dir = std::env::temp_dir().join("paperclip_test_pointer_refs");
+    let _ = fs::remove_dir_all(&dir);
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    let emitter = DefaultEmitter::from(state);
+    emitter.generate(&api).expect("codegen");
+
+    let pod = fs::read_to_string(dir.join("pod.rs")).expect("pod module");
+    assert!(pod.contains("pub spec: Option<crate::pod_spec::PodSpec>"));
+    assert!(!pod.contains("pub struct PodSpec"));
+
+    let deployment = fs::read_to_string(dir.join("deployment.rs")).expect("deployment module");
+    assert!(deployment.contains("pub template: Option<crate::pod_spec::PodSpec>"));
+
+    let spec = fs::read_to_string(dir.join("pod_spec.rs")).expect("spec module");
+    assert!(spec.contains("pub struct PodSpec"));
+}
+
+#[test]
+fn test_json_pointer_missing_target() {
+    let spec = Cursor::new(
+        b"
+swagger: \"2.0\"
+info:
+  title: Pods
+  version: \"1.0.0\"
+paths: {}
+definitions:
+  Pod:
+    type: object
+    properties:
+      spec:
+        $ref: \"#/definitions/Pod/properties/status\"
+" as &[_],
+    );
+
+    let raw: ResolvableApi<DefaultSchema> = v2::from_reader(spec).expect("deserializing spec");
+    let err = raw.resolve().unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "Reference missing in spec: #/definitions/Pod/properties/status
+  --> 12:9
+   |
+12 |         $ref: \"#/definitions/Pod/properties/status\"
+   |         ^"
+    );
+}
+
+#[test]
+fn test_json_pointer_unsupported_target() {
+    for pointer in &[
+        "#/info",
+        "#/definitions/Pod/x-kind",
+        "#/parameters/Page/type",
+    ] {
+        let spec = format!(
+            "
+swagger: \"2.0\"
+info:
+  title: Pods
+  version: \"1.0.0\"
+paths: {{}}
+parameters:
+  Page:
+    name: page
+    in: query
+    type: integer
+definitions:
+  Pod:
+    type: object
+    x-kind: Pod
+    properties:
+      spec:
+        $ref: \"{}\"
+",
+            pointer
+        );
+
+        let raw: ResolvableApi<DefaultSchema> =
+            v2::from_reader(Cursor::new(spec)).expect("deserializing spec");
+        let err = raw.resolve().unwrap_err();
+        assert_eq!(
+            err.kind().to_string(),
+            format!(
+                "JSON pointer {:?} is not supported. Only pointers to schemas are supported.",
+                pointer
+            )
+        );
+        assert_eq!(err.span().map(|s| s.line), Some(18));
+    }
+}
+
+#[test]
+fn test_json_pointer_to_aliased_schema() {
+    // The alias inside the shared schema is visited through both `Pod` and
+    // `Deployment`, and it should be resolved only once.
+    let spec = Cursor::new(
+        b"
+swagger: \"2.0\"
+info:
+  title: Pods
+  version: \"1.0.0\"
+paths: {}
+definitions:
+  Label:
+    type: string
+  Name:
+    $ref: \"#/definitions/Label\"
+  Pod:
+    type: object
+    properties:
+      spec:
+        type: object
+        properties:
+          name:
+            $ref: \"#/definitions/Name\"
+  Deployment:
+    type: object
+    properties:
+      template:
+        $ref: \"#/definitions/Pod/properties/spec\"
+" as &[_],
+    );
+
+    let raw: ResolvableApi<DefaultSchema> = v2::from_reader(spec).expect("deserializing spec");
+    let api = raw.resolve().expect("resolution");
+    let spec = api.definitions["PodSpec"].read().unwrap();
+    match &spec.properties["name"] {
+        Resolvable::Resolved { old, .. } => {
+            assert_eq!(old.read().unwrap().reference(), Some("#/definitions/Name"))
+        }
+        Resolvable::Raw(_) => panic!("expected name to be resolved"),
+    }
+}
+
+const ALL_OF_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Pets
+  version: \"1.0.0\"
+paths:
+  /dogs:
+    post:
+      parameters:
+      - name: body
+        in: body
+        schema:
+          $ref: \"#/definitions/Dog\"
+      responses:
+        \"200\":
+          schema:
+            $ref: \"#/definitions/Dog\"
+definitions:
+  Pet:
+    type: object
+    required: [name]
+    properties:
+      id:
+        type: integer
+        format: int64
+      name:
+        type: string
+  Dog:
+    description: A good boy.
+    allOf:
+    - $ref: \"#/definitions/Pet\"
+    - required: [bark]
+      properties:
+        bark:
+          type: boolean
+        owner:
+          type: object
+          properties:
+            name:
+              type: string
+";
+
+fn resolve_all_of_spec() -> ResolvableApi<DefaultSchema> {
+    let raw: ResolvableApi<DefaultSchema> =
+        v2::from_reader(Cursor::new(ALL_OF_SPEC)).expect("deserializing spec");
+    raw.resolve().expect("resolution")
+}
+
+#[test]
+fn test_all_of_members_resolved() {
+    let api = resolve_all_of_spec();
+
+    let dog = api.definitions["Dog"].read().unwrap();
+    let members = dog.all_of().expect("allOf members");
+    assert_eq!(members.len(), 2);
+    assert!(Arc::ptr_eq(&members[0], &api.definitions["Pet"]));
+    assert!(members[1].read().unwrap().reference().is_none());
+    assert!(!dog.contains_any());
+}
+
+#[test]
+fn test_all_of_emits_merged_struct() {
+    let api = resolve_all_of_spec();
+    let dir = std::env::temp_dir().join("paperclip_test_all_of");
+    let _ = fs::remove_dir_all(&dir);
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    let emitter = DefaultEmitter::from(state);
+    emitter.generate(&api).expect("codegen");
+
+    let dog = fs::read_to_string(dir.join("dog.rs")).expect("dog module");
+    assert!(dog.contains("pub struct Dog {"));
+    assert!(dog.contains("pub bark: bool,"));
+    assert!(dog.contains("pub id: Option<i64>,"));
+    assert!(dog.contains("pub name: String,"));
+    assert!(dog.contains("pub owner: Option<crate::dog::DogOwner>,"));
+    assert!(dog.contains("pub struct DogOwner {"));
+    // Required properties from the base and the member are both enforced.
+    assert!(dog.contains(
+        "pub fn builder() -> DogBuilder<crate::generics::MissingBark, crate::generics::MissingName>"
+    ));
+}
+
+const DISCRIMINATOR_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Pets
+  version: \"1.0.0\"
+paths: {}
+definitions:
+  Pet:
+    type: object
+    discriminator: petType
+    required: [name, petType]
+    properties:
+      name:
+        type: string
+      petType:
+        type: string
+  Dog:
+    allOf:
+    - $ref: \"#/definitions/Pet\"
+    - type: object
+      properties:
+        friend:
+          $ref: \"#/definitions/Pet\"
+  Cat:
+    allOf:
+    - $ref: \"#/definitions/Pet\"
+    - type: object
+      properties:
+        lives:
+          type: integer
+          format: int32
+  Lion:
+    allOf:
+    - $ref: \"#/definitions/Cat\"
+    - type: object
+      properties:
+        pride:
+          type: string
+";
+
+fn resolve_discriminator_spec() -> ResolvableApi<DefaultSchema> {
+    let raw: ResolvableApi<DefaultSchema> =
+        v2::from_reader(Cursor::new(DISCRIMINATOR_SPEC)).expect("deserializing spec");
+    raw.resolve().expect("resolution")
+}
+
+#[test]
+fn test_discriminator_subtypes() {
+    let api = resolve_discriminator_spec();
+
+    let pet = api.definitions["Pet"].read().unwrap();
+    assert_eq!(pet.discriminator(), Some("petType"));
+    let subtypes = pet.subtypes();
+    assert_eq!(subtypes.len(), 3);
+    assert!(Arc::ptr_eq(&subtypes[0], &api.definitions["Cat"]));
+    assert!(Arc::ptr_eq(&subtypes[1], &api.definitions["Dog"]));
+    // Subtypes of subtypes are also collected.
+    assert!(Arc::ptr_eq(&subtypes[2], &api.definitions["Lion"]));
+    assert!(api.definitions["Cat"].read().unwrap().subtypes().is_empty());
+    assert!(api.definitions["Dog"].read().unwrap().subtypes().is_empty());
+}
+
+#[test]
+fn test_discriminator_emits_tagged_enum() {
+    let api = resolve_discriminator_spec();
+    let dir = std::env::temp_dir().join("paperclip_test_discriminator");
+    let _ = fs::remove_dir_all(&dir);
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    let emitter = DefaultEmitter::from(state);
+    emitter.generate(&api).expect("codegen");
+
+    let pet = fs::read_to_string(dir.join("pet.rs")).expect("pet module");
+    assert!(pet.contains(
+        "#[derive(Debug, Clone)]
+pub enum Pet {
+    Pet(Box<crate::pet::PetBase>),
+    Cat(Box<crate::cat::Cat>),
+    Dog(Box<crate::dog::Dog>),
+    Lion(Box<crate::lion::Lion>),
+}"
+    ));
+    // The base itself is a variant (for values tagged with its own name).
+    assert!(pet.contains(
+        "pub struct PetBase {
+    pub name: String,
+    #[serde(rename = \"petType\")]
+    pub pet_type: String,
+}"
+    ));
+    assert!(pet.contains("Pet::Lion(v) => (\"Lion\", serde_json::to_value(v)),"));
+    assert!(pet.contains("map.insert(\"petType\".into(), tag.into());"));
+    assert!(pet.contains("let tag = match value.get(\"petType\") {"));
+    assert!(
+        pet.contains("\"Pet\" => serde_json::from_value(value).map(|v| Pet::Pet(Box::new(v))),")
+    );
+    assert!(
+        pet.contains("\"Lion\" => serde_json::from_value(value).map(|v| Pet::Lion(Box::new(v))),")
+    );
+    assert!(pet.contains("Pet::Pet(Default::default())"));
+}
+
+#[test]
+fn test_discriminator_subtypes_keep_tag() {
+    let api = resolve_discriminator_spec();
+    let dir = std::env::temp_dir().join("paperclip_test_discriminator_subtypes");
+    let _ = fs::remove_dir_all(&dir);
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    let emitter = DefaultEmitter::from(state);
+    emitter.generate(&api).expect("codegen");
+
+    // Subtypes can be used on their own, so they have the discriminator property.
+    let dog = fs::read_to_string(dir.join("dog.rs")).expect("dog module");
+    assert!(dog.contains(
+        "pub struct Dog {
+    pub friend: Option<crate::pet::Pet>,
+    pub name: String,
+    #[serde(rename = \"petType\")]
+    pub pet_type: String,
+}"
+    ));
+
+    let lion = fs::read_to_string(dir.join("lion.rs")).expect("lion module");
+    assert!(lion.contains(
+        "pub struct Lion {
+    pub lives: Option<i32>,
+    pub name: String,
+    #[serde(rename = \"petType\")]
+    pub pet_type: String,
+    pub pride: Option<String>,
+}"
+    ));
+}
+
+const FORMATS_SPEC: &[u8] = b"
+swagger: \"2.0\"
+info:
+  title: Orders
+  version: \"1.0.0\"
+paths: {}
+definitions:
+  Order:
+    type: object
+    required: [price]
+    properties:
+      price:
+        type: string
+        format: decimal
+      email:
+        type: string
+        format: email
+      quantity:
+        type: integer
+        format: int32
+";
+
+#[test]
+fn test_format_types_in_codegen() {
+    let raw: ResolvableApi<DefaultSchema> =
+        v2::from_reader(Cursor::new(FORMATS_SPEC)).expect("deserializing spec");
+    let api = raw.resolve().expect("resolution");
+    let dir = std::env::temp_dir().join("paperclip_test_format_types");
+    let _ = fs::remove_dir_all(&dir);
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    state
+        .format_types
+        .insert("decimal".into(), "rust_decimal::Decimal".into());
+    state.format_types.insert("int32".into(), "u16".into());
+    let emitter = DefaultEmitter::from(state);
+    emitter.generate(&api).expect("codegen");
+
+    // Unmapped formats fall back to the type.
+    let order = fs::read_to_string(dir.join("order.rs")).expect("order module");
+    assert!(order.contains(
+        "pub struct Order {
+    pub email: Option<String>,
+    pub price: rust_decimal::Decimal,
+    pub quantity: Option<u16>,
+}"
+    ));
+}
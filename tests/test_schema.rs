@@ -0,0 +1,254 @@
+use paperclip::v2::models::{DataTypeFormat, DefaultSchemaRaw};
+use serde_json::json;
+
+fn schema_json() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "description": "A pet",
+        "readOnly": true,
+        "default": {"name": "Milo"},
+        "externalDocs": {"url": "https://example.com/pets"},
+        "minProperties": 1,
+        "maxProperties": 4,
+        "required": ["name"],
+        "properties": {
+            "name": {
+                "type": "string",
+                "pattern": "^[a-z]+$",
+                "minLength": 1,
+                "maxLength": 32
+            },
+            "age": {
+                "type": "integer",
+                "format": "int32",
+                "minimum": 0.0,
+                "maximum": 100.0,
+                "exclusiveMaximum": true,
+                "multipleOf": 1.0
+            },
+            "tags": {
+                "type": "array",
+                "minItems": 1,
+                "maxItems": 8,
+                "uniqueItems": true,
+                "items": {"type": "string"}
+            }
+        }
+    })
+}
+
+#[test]
+fn test_schema_keywords_roundtrip() {
+    let value = schema_json();
+    let schema: DefaultSchemaRaw = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(schema.read_only, Some(true));
+    assert_eq!(schema.min_properties, Some(1));
+    assert_eq!(
+        schema.properties["name"].pattern.as_deref(),
+        Some("^[a-z]+$")
+    );
+    assert_eq!(schema.properties["age"].exclusive_maximum, Some(true));
+    assert_eq!(schema.properties["tags"].unique_items, Some(true));
+
+    assert_eq!(serde_json::to_value(&schema).unwrap(), value);
+}
+
+#[test]
+fn test_schema_all_of_roundtrip() {
+    let value = json!({
+        "allOf": [
+            {"$ref": "#/definitions/Base"},
+            {"type": "object", "properties": {"id": {"type": "string"}}}
+        ]
+    });
+
+    let schema: DefaultSchemaRaw = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(schema.all_of.len(), 2);
+    assert_eq!(
+        schema.all_of[0].reference.as_deref(),
+        Some("#/definitions/Base")
+    );
+    assert_eq!(serde_json::to_value(&schema).unwrap(), value);
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_schema_keywords_v3() {
+    let schema: DefaultSchemaRaw = serde_json::from_value(schema_json()).unwrap();
+    let schema: openapiv3::ReferenceOr<openapiv3::Schema> = schema.into();
+    let value = serde_json::to_value(&schema).unwrap();
+
+    assert_eq!(value["readOnly"], true);
+    assert_eq!(value["default"], json!({"name": "Milo"}));
+    assert_eq!(value["externalDocs"]["url"], "https://example.com/pets");
+    assert_eq!(value["minProperties"], 1);
+    assert_eq!(value["maxProperties"], 4);
+    assert_eq!(value["properties"]["name"]["pattern"], "^[a-z]+$");
+    assert_eq!(value["properties"]["name"]["maxLength"], 32);
+    assert_eq!(value["properties"]["age"]["maximum"], 100);
+    assert_eq!(value["properties"]["age"]["exclusiveMaximum"], true);
+    assert_eq!(value["properties"]["tags"]["minItems"], 1);
+    assert_eq!(value["properties"]["tags"]["uniqueItems"], true);
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_schema_all_of_v3() {
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "description": "A dog",
+        "allOf": [
+            {"$ref": "#/definitions/Pet"},
+            {"type": "object", "properties": {"bark": {"type": "boolean"}}}
+        ]
+    }))
+    .unwrap();
+    let schema: openapiv3::ReferenceOr<openapiv3::Schema> = schema.into();
+    let value = serde_json::to_value(&schema).unwrap();
+
+    assert_eq!(value["description"], "A dog");
+    assert_eq!(value["allOf"][0]["$ref"], "#/components/schemas/Pet");
+    assert_eq!(value["allOf"][1]["properties"]["bark"]["type"], "boolean");
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_schema_discriminator_v3() {
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "type": "object",
+        "discriminator": "petType",
+        "properties": {"petType": {"type": "string"}}
+    }))
+    .unwrap();
+    assert_eq!(schema.discriminator.as_deref(), Some("petType"));
+    let schema: openapiv3::ReferenceOr<openapiv3::Schema> = schema.into();
+    let value = serde_json::to_value(&schema).unwrap();
+
+    assert_eq!(value["discriminator"]["propertyName"], "petType");
+}
+
+#[test]
+fn test_schema_unknown_format_roundtrip() {
+    let value = json!({
+        "type": "object",
+        "properties": {
+            "id": {"type": "string", "format": "uuid"},
+            "email": {"type": "string", "format": "email"},
+            "price": {"type": "string", "format": "decimal"},
+            "flags": {"type": "integer", "format": "int8"},
+            "createdAt": {"type": "string", "format": "date-time"}
+        }
+    });
+
+    let schema: DefaultSchemaRaw = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(schema.properties["id"].format, Some(DataTypeFormat::Uuid));
+    assert_eq!(
+        schema.properties["email"].format,
+        Some(DataTypeFormat::Other("email".into()))
+    );
+    assert_eq!(
+        schema.properties["flags"].format,
+        Some(DataTypeFormat::Other("int8".into()))
+    );
+    assert_eq!(
+        schema.properties["createdAt"].format,
+        Some(DataTypeFormat::DateTime)
+    );
+    assert_eq!(serde_json::to_value(&schema).unwrap(), value);
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_schema_unknown_format_v3() {
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "type": "object",
+        "properties": {
+            "price": {"type": "string", "format": "decimal"},
+            "flags": {"type": "integer", "format": "int8"},
+            "ratio": {"type": "number", "format": "decimal128"}
+        }
+    }))
+    .unwrap();
+    let schema: openapiv3::ReferenceOr<openapiv3::Schema> = schema.into();
+    let value = serde_json::to_value(&schema).unwrap();
+
+    assert_eq!(value["properties"]["price"]["format"], "decimal");
+    assert_eq!(value["properties"]["flags"]["format"], "int8");
+    assert_eq!(value["properties"]["ratio"]["format"], "decimal128");
+}
+
+#[test]
+fn test_schema_xml_roundtrip() {
+    let value = json!({
+        "type": "object",
+        "xml": {"name": "pet", "prefix": "p", "namespace": "https://example.com/pets"},
+        "properties": {
+            "id": {"type": "integer", "xml": {"attribute": true}},
+            "tags": {
+                "type": "array",
+                "xml": {"name": "tags", "wrapped": true},
+                "items": {"type": "string", "xml": {"name": "tag"}}
+            }
+        }
+    });
+
+    let schema: DefaultSchemaRaw = serde_json::from_value(value.clone()).unwrap();
+    let xml = schema.xml.as_ref().unwrap();
+    assert_eq!(xml.qualified_name().as_deref(), Some("p:pet"));
+    assert!(schema.properties["id"].xml.as_ref().unwrap().is_attribute());
+    assert!(schema.properties["tags"].xml.as_ref().unwrap().is_wrapped());
+    assert_eq!(serde_json::to_value(&schema).unwrap(), value);
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_schema_xml_v3() {
+    let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+        "type": "object",
+        "xml": {"name": "pet"},
+        "properties": {"id": {"type": "integer", "xml": {"attribute": true}}}
+    }))
+    .unwrap();
+    let schema: openapiv3::ReferenceOr<openapiv3::Schema> = schema.into();
+    let value = serde_json::to_value(&schema).unwrap();
+
+    assert_eq!(value["xml"], json!({"name": "pet"}));
+    assert_eq!(value["properties"]["id"]["xml"], json!({"attribute": true}));
+}
+
+#[cfg(feature = "jiff01")]
+#[test]
+fn test_schema_jiff_civil_datetime_format() {
+    use paperclip::v2::schema::Apiv2Schema;
+
+    // Civil datetimes don't have an offset, so they're not `date-time`.
+    let schema = <jiff_dev::civil::DateTime as Apiv2Schema>::raw_schema();
+    assert_eq!(
+        schema.format,
+        Some(DataTypeFormat::Other("date-time-local".into()))
+    );
+    assert_eq!(
+        serde_json::to_value(&schema).unwrap(),
+        json!({"type": "string", "format": "date-time-local"})
+    );
+}
+
+#[cfg(feature = "jiff01")]
+#[test]
+fn test_schema_jiff_zoned_format() {
+    use paperclip::v2::schema::Apiv2Schema;
+
+    // Zoned datetimes have a time zone annotation, so they're not `date-time` either.
+    let schema = <jiff_dev::Zoned as Apiv2Schema>::raw_schema();
+    assert_eq!(
+        schema.format,
+        Some(DataTypeFormat::Other("zoned-date-time".into()))
+    );
+    assert_eq!(
+        serde_json::to_value(&schema).unwrap(),
+        json!({"type": "string", "format": "zoned-date-time"})
+    );
+    assert_eq!(
+        <jiff_dev::Timestamp as Apiv2Schema>::raw_schema().format,
+        Some(DataTypeFormat::DateTime)
+    );
+}
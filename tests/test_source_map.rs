@@ -0,0 +1,181 @@
+use paperclip::v2::{
+    self,
+    codegen::{DefaultEmitter, Emitter, EmitterState},
+    models::{DefaultSchema, ResolvableApi, SpecFormat},
+    SourceMap,
+};
+use paperclip::PaperClipError;
+
+const YAML_SPEC: &str = r##"swagger: "2.0"
+info:
+  title: Pets
+  version: "1.0"
+paths:
+  /pets/{id}:
+    get:
+      parameters:
+      - name: id
+        in: path
+        type: integer
+        required: true
+      - $ref: "#/parameters/Limit"
+      responses:
+        '200':
+          description: |
+            Pet with the given ID.
+            description: this isn't a key
+          schema: {$ref: "#/definitions/Pet"}
+definitions:
+  Pet:
+    type: object
+    required: [id,
+      name]
+    properties:
+      # Names are unique.
+      name:
+        type: string
+      tags:
+        type: array
+        items:
+          $ref: '#/definitions/Tag'
+"##;
+
+fn position(map: &SourceMap, path: &str) -> (usize, usize) {
+    let span = map.span(path).unwrap();
+    (span.line, span.column)
+}
+
+#[test]
+fn test_yaml_source_map() {
+    let map = SourceMap::new(YAML_SPEC, SpecFormat::Yaml);
+
+    assert_eq!(position(&map, "$.swagger"), (1, 1));
+    assert_eq!(position(&map, "$.paths['/pets/{id}'].get"), (7, 5));
+    let params = "$.paths['/pets/{id}'].get.parameters";
+    assert_eq!(position(&map, &format!("{}[0]", params)), (9, 7));
+    assert_eq!(position(&map, &format!("{}[0].required", params)), (12, 9));
+    assert_eq!(position(&map, &format!("{}[1]['$ref']", params)), (13, 9));
+
+    let resp = "$.paths['/pets/{id}'].get.responses['200']";
+    assert_eq!(position(&map, resp), (15, 9));
+    // Block scalars are skipped and flow collections are scanned.
+    assert_eq!(position(&map, &format!("{}.schema", resp)), (19, 11));
+    assert_eq!(
+        position(&map, &format!("{}.schema['$ref']", resp)),
+        (19, 20)
+    );
+    assert_eq!(
+        position(&map, &format!("{}.description.description", resp)),
+        (16, 11)
+    );
+
+    assert_eq!(position(&map, "$.definitions.Pet.required[1]"), (24, 7));
+    assert_eq!(position(&map, "$.definitions.Pet.properties.name"), (27, 7));
+    assert_eq!(
+        position(&map, "$.definitions.Pet.properties.tags.items['$ref']"),
+        (32, 11)
+    );
+    // Missing nodes fall back to their ancestors.
+    assert_eq!(
+        position(&map, "$.definitions.Pet.properties.age.type"),
+        (25, 5)
+    );
+    assert!(map.span("$.unknown").is_none());
+
+    let span = map.span("$.definitions.Pet").unwrap();
+    assert_eq!(span.text, "  Pet:");
+    assert_eq!(span.to_string(), "21:3");
+    assert_eq!(
+        span.snippet(),
+        "  --> 21:3
+   |
+21 |   Pet:
+   |   ^"
+    );
+}
+
+#[test]
+fn test_json_source_map() {
+    let value: serde_json::Value = serde_yaml::from_str(YAML_SPEC).unwrap();
+    let text = serde_json::to_string_pretty(&value).unwrap();
+    let map = SourceMap::new(&text, SpecFormat::Json).with_file("pets.json");
+
+    for (path, pattern) in &[
+        ("$.swagger", "  \"swagger\""),
+        ("$.paths['/pets/{id}'].get", "      \"get\""),
+        (
+            "$.paths['/pets/{id}'].get.parameters[1]['$ref']",
+            "            \"$ref\": \"#/parameters/Limit\"",
+        ),
+        ("$.definitions.Pet.required[1]", "        \"name\""),
+    ] {
+        let span = map.span(path).unwrap();
+        let line = text.lines().nth(span.line - 1).unwrap();
+        assert_eq!(span.text, line);
+        assert!(line.starts_with(pattern), "{}: {:?}", path, line);
+        let indent = line.len() - line.trim_start().len();
+        assert_eq!(span.column, indent + 1, "{}", path);
+        assert!(span.to_string().starts_with("pets.json:"));
+    }
+
+    // Flow collections on a single line.
+    let map = SourceMap::new(r#"{"a": [1, {"b": true}], "c": null}"#, SpecFormat::Json);
+    assert_eq!(position(&map, "$.a[1].b"), (1, 12));
+    assert_eq!(position(&map, "$.c"), (1, 25));
+}
+
+#[test]
+fn test_parse_errors_have_spans() {
+    let spec = YAML_SPEC.replace("    type: object\n", "    type: objekt\n");
+    let err = v2::from_reader::<_, DefaultSchema>(spec.as_bytes()).unwrap_err();
+    // Schemas are buffered (for their extensions), so this points to the schema.
+    let span = err.span().unwrap();
+    assert_eq!((span.line, span.column), (21, 3));
+    assert!(
+        matches!(err, PaperClipError::Located(ref e, _) if matches!(**e, PaperClipError::Yaml(_)))
+    );
+
+    let spec = "{\n  \"swagger\": \"2.0\",\n  \"info\": {\"title\": 5 \"x\"}\n}";
+    let err = v2::from_reader::<_, DefaultSchema>(spec.as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "JSON error: invalid type: integer `5`, expected a string at line 3 column 21
+ --> 3:21
+  |
+3 |   \"info\": {\"title\": 5 \"x\"}
+  |                     ^"
+    );
+}
+
+#[test]
+fn test_validation_errors_have_spans() {
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_reader(YAML_SPEC.as_bytes()).expect("deserializing spec");
+    let err = api.resolve().unwrap_err();
+    let span = err.span().unwrap();
+    assert_eq!((span.line, span.column), (13, 9));
+    assert_eq!(err.kind().to_string(), "Reference missing in spec: Limit");
+
+    // Errors found by the emitter are located too.
+    let spec = YAML_SPEC
+        .replace("      - $ref: \"#/parameters/Limit\"\n", "")
+        .replace(
+            "        type: integer\n",
+            "        schema:\n          type: string\n",
+        )
+        .replace("'#/definitions/Tag'", "'#/definitions/Pet'");
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_reader(spec.as_bytes()).expect("deserializing spec");
+    let api = api.resolve().expect("resolution");
+
+    let dir = std::env::temp_dir().join("paperclip_source_map");
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    let err = DefaultEmitter::from(state)
+        .generate(&api)
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("Parameter \"id\" in path \"/pets/{id}\" has specified None type"));
+    assert!(err.ends_with(" --> 9:7\n  |\n9 |       - name: id\n  |       ^"));
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,556 @@
+use paperclip::{
+    v2::models::DefaultApiRaw,
+    v3::{self, ConversionLoss},
+};
+use serde_json::{json, Value};
+
+const SPEC: &str = r##"
+swagger: "2.0"
+info:
+  title: Pet store
+  version: "1.0"
+x-owner: pets-team
+host: example.com
+basePath: /api
+schemes: [https]
+consumes: [application/json, application/xml]
+produces: [application/json]
+security:
+  - key: []
+securityDefinitions:
+  key:
+    type: apiKey
+    in: header
+    name: X-Key
+parameters:
+  Limit:
+    name: limit
+    in: query
+    type: integer
+    default: 20
+    minimum: 0.5
+    maximum: 100.5
+    exclusiveMaximum: true
+    multipleOf: 2.5
+  PetBody:
+    name: pet
+    in: body
+    required: true
+    schema:
+      $ref: "#/definitions/Pet"
+responses:
+  Error:
+    description: Error
+    schema:
+      $ref: "#/definitions/Error"
+paths:
+  /pets:
+    x-internal: false
+    get:
+      operationId: listPets
+      x-rate-limited: true
+      schemes: [http]
+      parameters:
+        - $ref: "#/parameters/Limit"
+        - name: tags
+          in: query
+          type: array
+          items:
+            type: string
+        - name: ids
+          in: query
+          type: array
+          collectionFormat: pipes
+          items:
+            type: integer
+        - name: X-Trace
+          in: header
+          type: string
+          x-example: abc
+          x-deprecated: true
+      responses:
+        "200":
+          description: Pets
+          x-cache: 60
+          headers:
+            X-Total:
+              type: integer
+              default: 0
+          schema:
+            type: array
+            items:
+              $ref: "#/definitions/Pet"
+        default:
+          $ref: "#/responses/Error"
+    post:
+      operationId: addPet
+      parameters:
+        - $ref: "#/parameters/PetBody"
+      responses:
+        "201":
+          description: Created
+  /pets/{id}/photo:
+    put:
+      operationId: uploadPhoto
+      parameters:
+        - name: id
+          in: path
+          required: true
+          type: string
+        - name: file
+          in: formData
+          required: true
+          type: file
+        - name: caption
+          in: formData
+          type: string
+          x-hint: short
+      responses:
+        "204":
+          description: Uploaded
+definitions:
+  Pet:
+    type: object
+    discriminator: kind
+    x-discriminator-mapping:
+      dog: "#/definitions/Dog"
+    x-entity: true
+    required: [name, kind]
+    properties:
+      name:
+        type: string
+        x-nullable: true
+        default: Milo
+      kind:
+        type: string
+      password:
+        type: string
+        x-writeOnly: true
+      id:
+        type: string
+        x-oneOf:
+          - type: string
+            format: uuid
+          - type: string
+            pattern: "^[0-9]+$"
+      extra: {}
+  Dog:
+    allOf:
+      - $ref: "#/definitions/Pet"
+      - properties:
+          bark:
+            type: boolean
+  Error:
+    type: object
+    properties:
+      message:
+        type: string
+"##;
+
+fn upgrade() -> (Value, v3::ConversionReport) {
+    let api: DefaultApiRaw = serde_yaml::from_str(SPEC).unwrap();
+    let (spec, report) = v3::openapiv2_to_v3_with_report(api);
+    (serde_json::to_value(&spec).unwrap(), report)
+}
+
+#[test]
+fn test_schemas() {
+    let (spec, _) = upgrade();
+    let schemas = &spec["components"]["schemas"];
+    let pet = &schemas["Pet"];
+    assert_eq!(
+        pet["discriminator"],
+        json!({"propertyName": "kind", "mapping": {"dog": "#/components/schemas/Dog"}})
+    );
+    assert_eq!(pet["x-entity"], true);
+    assert!(pet.get("x-discriminator-mapping").is_none());
+
+    let props = &pet["properties"];
+    assert_eq!(
+        props["name"],
+        json!({"type": "string", "nullable": true, "default": "Milo"})
+    );
+    assert_eq!(props["password"]["writeOnly"], true);
+    assert_eq!(
+        props["id"],
+        json!({"oneOf": [
+            {"type": "string", "format": "uuid"},
+            {"type": "string", "pattern": "^[0-9]+$"}
+        ]})
+    );
+    // Schemas without a type accept anything.
+    assert_eq!(props["extra"], json!({}));
+    // ... unless they have properties.
+    assert_eq!(
+        schemas["Dog"]["allOf"][1],
+        json!({"type": "object", "properties": {"bark": {"type": "boolean"}}})
+    );
+}
+
+#[test]
+fn test_untyped_schemas() {
+    let api: DefaultApiRaw = serde_yaml::from_str(
+        r#"
+swagger: "2.0"
+info:
+  title: Untyped
+  version: "1.0"
+paths: {}
+definitions:
+  Anything: {}
+  Described:
+    description: Anything at all
+  Named:
+    required: [name]
+  Map:
+    additionalProperties:
+      type: string
+"#,
+    )
+    .unwrap();
+
+    let spec = serde_json::to_value(v3::openapiv2_to_v3(api)).unwrap();
+    let schemas = &spec["components"]["schemas"];
+    // No type means any value (and not just objects).
+    assert_eq!(schemas["Anything"], json!({}));
+    assert_eq!(
+        schemas["Described"],
+        json!({"description": "Anything at all"})
+    );
+    // Object keywords still imply an object.
+    assert_eq!(
+        schemas["Named"],
+        json!({"type": "object", "required": ["name"]})
+    );
+    assert_eq!(
+        schemas["Map"],
+        json!({"type": "object", "additionalProperties": {"type": "string"}})
+    );
+}
+
+#[test]
+fn test_operations() {
+    let (spec, _) = upgrade();
+    assert_eq!(spec["x-owner"], "pets-team");
+    assert_eq!(spec["security"], json!([{"key": []}]));
+    assert_eq!(
+        spec["components"]["parameters"],
+        json!({"Limit": {
+            "name": "limit",
+            "in": "query",
+            // Integers in range (and their multiples) stay the same.
+            "schema": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": 100,
+                "multipleOf": 5,
+                "default": 20
+            },
+            "style": "form"
+        }})
+    );
+    assert_eq!(
+        spec["components"]["responses"]["Error"]["content"],
+        json!({"application/json": {"schema": {"$ref": "#/components/schemas/Error"}}})
+    );
+
+    let pets = &spec["paths"]["/pets"];
+    assert_eq!(pets["x-internal"], false);
+
+    let list = &pets["get"];
+    assert_eq!(list["x-rate-limited"], true);
+    let params = &list["parameters"];
+    assert_eq!(params[0], json!({"$ref": "#/components/parameters/Limit"}));
+    assert_eq!(params[1]["style"], "form");
+    assert_eq!(params[1]["explode"], false);
+    assert_eq!(params[2]["style"], "pipeDelimited");
+    assert_eq!(
+        params[3],
+        json!({
+            "name": "X-Trace",
+            "in": "header",
+            "deprecated": true,
+            "example": "abc",
+            "schema": {"type": "string"},
+            "style": "simple"
+        })
+    );
+
+    let ok = &list["responses"]["200"];
+    assert_eq!(ok["x-cache"], 60);
+    assert_eq!(
+        ok["headers"]["X-Total"]["schema"],
+        json!({"type": "integer", "default": 0})
+    );
+    assert_eq!(
+        list["responses"]["default"],
+        json!({"$ref": "#/components/responses/Error"})
+    );
+
+    // References to global body parameters are inlined.
+    let add = &pets["post"];
+    assert_eq!(add["x-codegen-request-body-name"], "pet");
+    assert_eq!(add["requestBody"]["required"], true);
+    let content: Vec<_> = add["requestBody"]["content"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect();
+    assert_eq!(content, vec!["application/json", "application/xml"]);
+
+    let upload = &spec["paths"]["/pets/{id}/photo"]["put"];
+    assert_eq!(
+        upload["requestBody"]["content"]["multipart/form-data"]["schema"],
+        json!({
+            "type": "object",
+            "required": ["file"],
+            "properties": {
+                "file": {"type": "string", "format": "binary"},
+                "caption": {"type": "string"}
+            }
+        })
+    );
+}
+
+#[test]
+fn test_report() {
+    let (_, report) = upgrade();
+    let issues: Vec<_> = report
+        .issues
+        .iter()
+        .map(|i| (i.kind, i.path.as_str()))
+        .collect();
+    assert_eq!(
+        issues,
+        vec![
+            (
+                ConversionLoss::Approximated,
+                "$.parameters.Limit.multipleOf"
+            ),
+            (ConversionLoss::Dropped, "$.paths['/pets'].get.schemes"),
+            (
+                ConversionLoss::Dropped,
+                "$.paths['/pets/{id}/photo'].put.parameters[2]"
+            ),
+        ]
+    );
+    assert_eq!(
+        report.issues[0].message,
+        "fractional `multipleOf` is replaced by its smallest integral multiple (5) for integers"
+    );
+    assert_eq!(
+        report.issues[2].to_string(),
+        "dropped: extensions of form parameters aren't supported in v3 (at $.paths['/pets/{id}/photo'].put.parameters[2])"
+    );
+}
+
+#[test]
+fn test_report_inherited_parameters() {
+    // Inherited parameters are reported once, where they're defined.
+    let api: DefaultApiRaw = serde_yaml::from_str(
+        r##"
+swagger: "2.0"
+info:
+  title: Pets
+  version: "1.0"
+parameters:
+  Note:
+    name: note
+    in: formData
+    type: string
+    allowEmptyValue: true
+paths:
+  /pets:
+    parameters:
+      - name: tag
+        in: formData
+        type: string
+      - $ref: "#/parameters/Note"
+    post:
+      parameters:
+        - name: pet
+          in: body
+          schema:
+            type: object
+      responses:
+        "201":
+          description: Created
+          headers:
+            X-Ids:
+              type: array
+              collectionFormat: pipes
+              items:
+                type: array
+                collectionFormat: ssv
+                items:
+                  type: integer
+        "2XX":
+          description: Success
+    put:
+      parameters:
+        - name: pet
+          in: body
+          schema:
+            type: object
+      responses:
+        "204":
+          description: Updated
+"##,
+    )
+    .unwrap();
+
+    let (_, report) = v3::openapiv2_to_v3_with_report(api);
+    let issues: Vec<_> = report
+        .issues
+        .iter()
+        .map(|i| (i.kind, i.path.as_str()))
+        .collect();
+    assert_eq!(
+        issues,
+        vec![
+            (ConversionLoss::Dropped, "$.parameters.Note.allowEmptyValue"),
+            (ConversionLoss::Dropped, "$.paths['/pets'].parameters[0]"),
+            (ConversionLoss::Dropped, "$.parameters.Note"),
+            (
+                ConversionLoss::Approximated,
+                "$.paths['/pets'].post.responses['201'].headers['X-Ids'].collectionFormat"
+            ),
+            (
+                ConversionLoss::Dropped,
+                "$.paths['/pets'].post.responses['201'].headers['X-Ids'].items.collectionFormat"
+            ),
+            (
+                ConversionLoss::Dropped,
+                "$.paths['/pets'].post.responses['2XX']"
+            ),
+        ]
+    );
+    assert_eq!(
+        report.issues[1].message,
+        "form parameters can't be combined with a body in v3"
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    // Constructs approximated when downgrading v3 specs are restored.
+    let original = json!({
+        "openapi": "3.0.0",
+        "info": {"title": "Pets", "version": "1.0"},
+        "paths": {},
+        "components": {"schemas": {"Pet": {
+            "type": "object",
+            "discriminator": {"propertyName": "kind", "mapping": {"cat": "#/components/schemas/Cat"}},
+            "properties": {
+                "name": {"type": "string", "nullable": true},
+                "secret": {"type": "string", "writeOnly": true, "deprecated": true},
+                "owner": {"anyOf": [
+                    {"$ref": "#/components/schemas/Person"},
+                    {"type": "string"}
+                ]}
+            }
+        }}}
+    });
+
+    let spec: openapiv3::OpenAPI = serde_json::from_value(original.clone()).unwrap();
+    let (api, _) = v3::openapiv3_to_v2(&spec).unwrap();
+    let (spec, report) = v3::openapiv2_to_v3_with_report(api);
+    assert!(report.is_lossless(), "{:?}", report);
+    assert_eq!(
+        serde_json::to_value(&spec).unwrap()["components"]["schemas"],
+        original["components"]["schemas"]
+    );
+}
+
+fn upgrade_petstore(path: &str) -> openapiv3::OpenAPI {
+    let fd = std::fs::File::open(path).unwrap();
+    let api: DefaultApiRaw = serde_yaml::from_reader(fd).unwrap();
+    api.into()
+}
+
+#[test]
+fn test_petstore_security() {
+    let spec = serde_json::to_value(upgrade_petstore("tests/pet-security-v2.yaml")).unwrap();
+    assert_eq!(
+        spec["servers"],
+        json!([{"url": "http://pets.com:8888/api"}, {"url": "https://pets.com:8888/api"}])
+    );
+
+    let schemes = &spec["components"]["securitySchemes"];
+    assert_eq!(
+        schemes["petstore_implicit"]["flows"],
+        json!({"implicit": {
+            "authorizationUrl": "https://pets.com/oauth/authorize",
+            "scopes": {"read:pets": "Read pets"}
+        }})
+    );
+    assert_eq!(
+        schemes["petstore_password"]["flows"],
+        json!({"password": {
+            "tokenUrl": "https://pets.com/oauth/token",
+            "scopes": {"read:pets": "Read pets"}
+        }})
+    );
+    assert_eq!(
+        schemes["petstore_application"]["flows"]["clientCredentials"]["tokenUrl"],
+        "https://pets.com/oauth/token"
+    );
+    assert_eq!(
+        schemes["petstore_access_code"]["flows"],
+        json!({"authorizationCode": {
+            "authorizationUrl": "https://pets.com/oauth/authorize",
+            "tokenUrl": "https://pets.com/oauth/token",
+            "scopes": {"read:pets": "Read pets", "write:pets": "Modify pets"}
+        }})
+    );
+    assert_eq!(
+        schemes["basic"],
+        json!({"type": "http", "scheme": "basic", "description": "Store admins"})
+    );
+
+    assert_eq!(
+        spec["security"],
+        json!([{"api_key": []}, {"petstore_password": ["read:pets"]}])
+    );
+    let paths = &spec["paths"];
+    // Operations can opt out of the global requirements.
+    assert_eq!(paths["/pets/{petId}"]["get"]["security"], json!([]));
+    assert!(paths["/pets/{petId}"]["delete"].get("security").is_none());
+    assert_eq!(
+        paths["/pets"]["post"]["security"],
+        json!([{"petstore_access_code": ["read:pets", "write:pets"]}, {"basic": []}])
+    );
+}
+
+#[test]
+fn test_petstore_snapshot() {
+    let spec = serde_yaml::to_string(&upgrade_petstore("tests/pet-v2.yaml")).unwrap();
+    insta::assert_snapshot!("pet-v3.yaml", spec);
+
+    let spec = serde_yaml::to_string(&upgrade_petstore("tests/pet-security-v2.yaml")).unwrap();
+    insta::assert_snapshot!("pet-security-v3.yaml", spec);
+}
+
+#[test]
+fn test_roundtrip_not() {
+    let original = json!({
+        "openapi": "3.0.0",
+        "info": {"title": "Pets", "version": "1.0"},
+        "paths": {},
+        "components": {"schemas": {"Name": {
+            "type": "object",
+            "properties": {
+                "name": {"not": {"type": "string", "enum": ["admin"]}}
+            }
+        }}}
+    });
+
+    let spec: openapiv3::OpenAPI = serde_json::from_value(original.clone()).unwrap();
+    let (api, _) = v3::openapiv3_to_v2(&spec).unwrap();
+    let (spec, report) = v3::openapiv2_to_v3_with_report(api);
+    assert!(report.is_lossless(), "{:?}", report);
+    assert_eq!(
+        serde_json::to_value(&spec).unwrap()["components"]["schemas"],
+        original["components"]["schemas"]
+    );
+}
@@ -0,0 +1,311 @@
+use paperclip::v3::{self, v31};
+use serde_json::{json, Value};
+
+const V30_SPEC: &str = r##"
+openapi: 3.0.3
+info:
+  title: Pet store
+  version: "1.0"
+paths:
+  /pets:
+    get:
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+            minimum: 0
+            exclusiveMinimum: true
+            example: 10
+      responses:
+        "200":
+          description: Pets
+          content:
+            application/json:
+              schema:
+                type: array
+                items:
+                  $ref: "#/components/schemas/Pet"
+  /pets/{id}/photo:
+    put:
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      requestBody:
+        content:
+          multipart/form-data:
+            schema:
+              type: object
+              properties:
+                photo:
+                  type: string
+                  format: binary
+      responses:
+        "204":
+          description: Uploaded
+components:
+  schemas:
+    Pet:
+      type: object
+      required:
+        - name
+      properties:
+        name:
+          type: string
+          nullable: true
+        class:
+          type: string
+          enum: [dog, cat]
+          nullable: true
+        owner:
+          allOf:
+            - $ref: "#/components/schemas/Person"
+          nullable: true
+        vet:
+          allOf:
+            - $ref: "#/components/schemas/Person"
+          description: Usual vet.
+        friend:
+          oneOf:
+            - $ref: "#/components/schemas/Pet"
+            - $ref: "#/components/schemas/Person"
+          nullable: true
+    Person:
+      type: object
+      properties:
+        name:
+          type: string
+"##;
+
+const V31_SPEC: &str = r##"
+openapi: 3.1.0
+info:
+  title: Pet store
+  summary: Pets and their owners.
+  version: "1.0"
+  license:
+    name: MIT
+    identifier: MIT
+jsonSchemaDialect: https://spec.openapis.org/oas/3.1/dialect/base
+webhooks:
+  newPet:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/Pet"
+security:
+  - apiKey: []
+  - mtls: []
+paths:
+  /pets:
+    get:
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+            exclusiveMinimum: 0
+            examples: [10, 20]
+      responses:
+        "200":
+          description: Pets
+          content:
+            application/json:
+              schema:
+                type: array
+                items:
+                  $ref: "#/components/schemas/Pet"
+    delete: {}
+components:
+  securitySchemes:
+    apiKey:
+      type: apiKey
+      in: header
+      name: X-API-Key
+    mtls:
+      type: mutualTLS
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        name:
+          type: [string, "null"]
+        kind:
+          const: pet
+        owner:
+          anyOf:
+            - $ref: "#/components/schemas/Person"
+            - type: "null"
+        vet:
+          $ref: "#/components/schemas/Person"
+          description: Usual vet.
+        tags:
+          type: array
+          prefixItems:
+            - type: string
+          items: false
+        id:
+          type: [string, integer]
+      $defs:
+        Tag:
+          type: string
+    Person:
+      type: object
+      properties:
+        name:
+          type: string
+"##;
+
+fn upgrade() -> Value {
+    let spec: openapiv3::OpenAPI = serde_yaml::from_str(V30_SPEC).expect("parsing v3 spec");
+    let spec = v3::openapiv3_to_v31(&spec).expect("upgrading spec");
+    serde_json::to_value(&spec).unwrap()
+}
+
+fn downgrade() -> (Value, v3::ConversionReport) {
+    let spec: v31::OpenApi = serde_yaml::from_str(V31_SPEC).expect("parsing v3.1 spec");
+    let (api, report) = v3::openapiv31_to_v3(&spec).expect("downgrading spec");
+    (serde_json::to_value(&api).unwrap(), report)
+}
+
+#[test]
+fn test_upgrade_schemas() {
+    let api = upgrade();
+    assert_eq!(api["openapi"], "3.1.0");
+
+    let props = &api["components"]["schemas"]["Pet"]["properties"];
+    assert_eq!(props["name"], json!({"type": ["string", "null"]}));
+    assert_eq!(
+        props["class"],
+        json!({"type": ["string", "null"], "enum": ["dog", "cat", null]})
+    );
+    assert_eq!(
+        props["owner"],
+        json!({"anyOf": [{"$ref": "#/components/schemas/Person"}, {"type": "null"}]})
+    );
+    // References can have siblings in v3.1.
+    assert_eq!(
+        props["vet"],
+        json!({"$ref": "#/components/schemas/Person", "description": "Usual vet."})
+    );
+    assert_eq!(props["friend"]["oneOf"][2], json!({"type": "null"}));
+}
+
+#[test]
+fn test_upgrade_keywords() {
+    let api = upgrade();
+    assert_eq!(
+        api["paths"]["/pets"]["get"]["parameters"][0]["schema"],
+        json!({"type": "integer", "exclusiveMinimum": 0, "examples": [10]})
+    );
+    assert_eq!(
+        api["paths"]["/pets/{id}/photo"]["put"]["requestBody"]["content"]["multipart/form-data"]
+            ["schema"]["properties"]["photo"],
+        json!({"type": "string", "contentMediaType": "application/octet-stream"})
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let original: openapiv3::OpenAPI = serde_yaml::from_str(V30_SPEC).unwrap();
+    let upgraded = v3::openapiv3_to_v31(&original).unwrap();
+    let (mut downgraded, report) = v3::openapiv31_to_v3(&upgraded).unwrap();
+    assert!(report.is_lossless(), "{:?}", report);
+
+    downgraded.openapi = original.openapi.clone();
+    assert_eq!(
+        serde_json::to_value(&downgraded).unwrap(),
+        serde_json::to_value(&original).unwrap()
+    );
+}
+
+#[test]
+fn test_downgrade_schemas() {
+    let (api, report) = downgrade();
+    assert_eq!(api["openapi"], "3.0.3");
+
+    let pet = &api["components"]["schemas"]["Pet"];
+    assert!(pet.get("$defs").is_none());
+    let props = &pet["properties"];
+    assert_eq!(props["name"], json!({"type": "string", "nullable": true}));
+    assert_eq!(props["kind"], json!({"enum": ["pet"]}));
+    assert_eq!(
+        props["owner"],
+        json!({"allOf": [{"$ref": "#/components/schemas/Person"}], "nullable": true})
+    );
+    assert_eq!(
+        props["vet"],
+        json!({"allOf": [{"$ref": "#/components/schemas/Person"}], "description": "Usual vet."})
+    );
+    assert_eq!(
+        props["tags"],
+        json!({"type": "array", "items": {"not": {}}})
+    );
+    assert_eq!(
+        props["id"],
+        json!({"oneOf": [{"type": "string"}, {"type": "integer"}]})
+    );
+
+    let dropped: Vec<_> = report.dropped().map(|i| i.path.as_str()).collect();
+    assert!(dropped.contains(&"$.components.schemas.Pet['$defs']"));
+    assert!(dropped.contains(&"$.components.schemas.Pet.properties.tags.prefixItems"));
+    let approximated: Vec<_> = report.approximated().map(|i| i.path.as_str()).collect();
+    assert!(approximated.contains(&"$.components.schemas.Pet.properties.id.type"));
+}
+
+#[test]
+fn test_downgrade_document() {
+    let (api, report) = downgrade();
+    assert!(api.get("webhooks").is_none());
+    assert!(api["info"].get("summary").is_none());
+    assert_eq!(api["info"]["license"], json!({"name": "MIT"}));
+    assert_eq!(
+        api["paths"]["/pets"]["get"]["parameters"][0]["schema"],
+        json!({
+            "type": "integer",
+            "minimum": 0,
+            "exclusiveMinimum": true,
+            "example": 10
+        })
+    );
+    // Responses are required in v3.0.
+    assert_eq!(api["paths"]["/pets"]["delete"], json!({"responses": {}}));
+
+    // Requirements for the (dropped) mutual TLS scheme are left out.
+    assert_eq!(api["security"], json!([{"apiKey": []}]));
+    assert!(api["components"]["securitySchemes"].get("mtls").is_none());
+
+    let dropped: Vec<_> = report.dropped().map(|i| i.path.as_str()).collect();
+    for path in &[
+        "$.webhooks",
+        "$.jsonSchemaDialect",
+        "$.info.summary",
+        "$.info.license.identifier",
+        "$.components.securitySchemes.mtls",
+    ] {
+        assert!(dropped.contains(path), "{} in {:?}", path, dropped);
+    }
+
+    assert!(report.approximated().any(|i| i.path
+        == "$.paths['/pets'].get.parameters[0].schema.examples"
+        && i.message.contains("first of 2")));
+}
+
+#[test]
+fn test_from_value() {
+    let doc: Value = serde_yaml::from_str(V31_SPEC).unwrap();
+    let (api, report) = v3::openapiv3_from_value(doc).unwrap();
+    assert_eq!(api.openapi, "3.0.3");
+    assert!(!report.is_lossless());
+
+    let doc: Value = serde_yaml::from_str(V30_SPEC).unwrap();
+    let (api, report) = v3::openapiv3_from_value(doc).unwrap();
+    assert_eq!(api.openapi, "3.0.3");
+    assert!(report.is_lossless());
+}
@@ -0,0 +1,96 @@
+use paperclip::v2::{
+    self,
+    models::{DefaultSchema, ResolvableApi, SpecFormat},
+};
+
+const SPEC: &str = r##"{
+  "x-origin": "tests",
+  "definitions": {
+    "Pet": {
+      "type": "object",
+      "x-kubernetes-group-version-kind": [{"group": "", "kind": "Pet", "version": "v1"}],
+      "properties": {
+        "weight": {"type": "number", "format": "decimal"}
+      }
+    },
+    "Error": {"type": "string"}
+  },
+  "paths": {
+    "/pets/{id:\\d+}": {
+      "x-path-owner": "pets-team",
+      "get": {
+        "x-kubernetes-action": "get",
+        "parameters": [{"name": "id", "in": "path", "required": true, "type": "integer", "x-example": 1}],
+        "responses": {"200": {"description": "Pet", "x-cache": true, "schema": {"$ref": "#/definitions/Pet"}}}
+      }
+    },
+    "/errors": {
+      "get": {"responses": {"default": {"description": "Error"}}}
+    }
+  },
+  "tags": [{"name": "pets", "description": "Pets"}],
+  "info": {"title": "Pets", "version": "1.0", "x-logo": "pets.png"},
+  "basePath": "/api",
+  "swagger": "2.0"
+}"##;
+
+fn parse(spec: &str) -> ResolvableApi<DefaultSchema> {
+    v2::from_reader(spec.as_bytes()).expect("deserializing spec")
+}
+
+#[test]
+fn test_canonical_json() {
+    let out = v2::to_string(&parse(SPEC)).unwrap();
+    assert!(out.ends_with("}\n"));
+
+    let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+    let original: serde_json::Value = serde_json::from_str(SPEC).unwrap();
+    // Nothing is lost (including vendor extensions, formats and path templates).
+    assert_eq!(value, original);
+
+    // Top-level keys are in canonical order.
+    let keys = [
+        "swagger",
+        "info",
+        "basePath",
+        "paths",
+        "definitions",
+        "tags",
+        "x-origin",
+    ]
+    .iter()
+    .map(|k| out.find(&format!("\n  \"{}\":", k)).unwrap())
+    .collect::<Vec<_>>();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+    // Paths and definitions are sorted.
+    assert!(out.find("\"/errors\"").unwrap() < out.find("\"/pets/{id:\\\\d+}\"").unwrap());
+    assert!(out.find("\"Error\"").unwrap() < out.find("\"Pet\"").unwrap());
+}
+
+#[test]
+fn test_canonical_output_is_stable() {
+    let out = v2::to_string(&parse(SPEC)).unwrap();
+    assert_eq!(v2::to_string(&parse(&out)).unwrap(), out);
+
+    // Same spec with a different layout gives the same output.
+    let value: serde_json::Value = serde_json::from_str(SPEC).unwrap();
+    let compact = serde_json::to_string(&value).unwrap();
+    assert_eq!(v2::to_string(&parse(&compact)).unwrap(), out);
+}
+
+#[test]
+fn test_canonical_yaml() {
+    let mut api = parse(SPEC);
+    api.spec_format = SpecFormat::Yaml;
+    let out = v2::to_string(&api).unwrap();
+    assert!(out.starts_with("swagger: '2.0'\ninfo:\n"));
+
+    let api = parse(&out);
+    assert_eq!(api.spec_format, SpecFormat::Yaml);
+    assert_eq!(v2::to_string(&api).unwrap(), out);
+
+    let value: serde_json::Value = serde_yaml::from_str(&out).unwrap();
+    let original: serde_json::Value = serde_json::from_str(SPEC).unwrap();
+    assert_eq!(value, original);
+}
@@ -0,0 +1,107 @@
+use paperclip::v2::{
+    self,
+    codegen::{CrateMeta, DefaultEmitter, EmitMode, Emitter, EmitterState},
+    models::{DefaultSchema, ResolvableApi},
+};
+
+use std::fs;
+
+const SPEC: &str = r##"swagger: "2.0"
+info:
+  title: Pets
+  version: "1.0"
+consumes: [application/xml]
+produces: [application/xml]
+paths:
+  /pets:
+    get:
+      responses:
+        '200':
+          description: All pets.
+          schema:
+            $ref: "#/definitions/Pet"
+definitions:
+  Pet:
+    type: object
+    xml:
+      name: pet
+    required: [id]
+    properties:
+      id:
+        type: integer
+        format: int64
+        xml:
+          attribute: true
+      name:
+        type: string
+        xml:
+          name: petName
+      tags:
+        type: array
+        xml:
+          wrapped: true
+        items:
+          type: string
+          xml:
+            name: tag
+      photos:
+        type: array
+        items:
+          type: string
+          xml:
+            name: photo
+"##;
+
+fn generate(spec: &str, name: &str) -> (String, Option<String>) {
+    let api: ResolvableApi<DefaultSchema> =
+        v2::from_reader(spec.as_bytes()).expect("deserializing spec");
+    let api = api.resolve().expect("resolution");
+
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    let mut state = EmitterState::default();
+    state.working_dir = dir.clone();
+    let mut meta = CrateMeta::default();
+    meta.authors = Some(vec!["Me <me@example.com>".into()]);
+    meta.mode = EmitMode::Crate;
+    state.set_meta(meta);
+    DefaultEmitter::from(state).generate(&api).expect("codegen");
+
+    let pet = fs::read_to_string(dir.join("pet.rs")).unwrap();
+    // Manifests are generated only with the CLI.
+    let manifest = fs::read_to_string(dir.join("Cargo.toml")).ok();
+    let _ = fs::remove_dir_all(dir);
+    (pet, manifest)
+}
+
+#[test]
+fn test_xml_names() {
+    let (pet, manifest) = generate(SPEC, "paperclip_xml_names");
+
+    assert!(pet.contains("#[serde(rename = \"pet\")]\npub struct Pet {"));
+    assert!(pet.contains("#[serde(rename = \"@id\")]\n    pub id: i64,"));
+    assert!(pet.contains("#[serde(rename = \"petName\")]\n    pub name: Option<String>,"));
+    assert!(pet.contains(
+        "#[serde(rename = \"tags\", default, with = \"pet_tags_xml\")]\n    pub tags: Option<Vec<String>>,"
+    ));
+    assert!(pet.contains("#[serde(rename = \"photo\")]\n    pub photos: Option<Vec<String>>,"));
+    assert!(pet.contains("mod pet_tags_xml {"));
+    assert!(pet.contains("#[serde(rename = \"tag\")]"));
+    if cfg!(feature = "cli") {
+        assert!(manifest.unwrap().contains("serde-xml-rs = "));
+    }
+}
+
+#[test]
+fn test_xml_names_with_json() {
+    // Mixed specs keep the JSON names.
+    let spec = SPEC.replace("[application/xml]", "[application/json, application/xml]");
+    let (pet, manifest) = generate(&spec, "paperclip_xml_json");
+
+    assert!(!pet.contains("@id"));
+    assert!(!pet.contains("petName"));
+    assert!(!pet.contains("pet_tags_xml"));
+    if cfg!(feature = "cli") {
+        assert!(manifest.unwrap().contains("serde-xml-rs = "));
+    }
+}